@@ -3,13 +3,11 @@
 static GLOBAL: tracy_client::ProfiledAllocator<std::alloc::System> =
     tracy_client::ProfiledAllocator::new(std::alloc::System, 100);
 
-use itertools::izip;
 use rand::{distributions as distr, distributions::Distribution};
 
+use sf::math::ConvertPrecision;
 use starframe as sf;
 
-mod mousegrab;
-use mousegrab::MouseGrabber;
 mod player;
 mod recipes;
 use recipes::Recipe;
@@ -38,6 +36,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
             fps: 60,
             use_vsync: std::env::var("NO_VSYNC").is_err(),
             lighting_quality: sf::LightingQualityConfig::default(),
+            ..Default::default()
         },
     })?;
 
@@ -68,11 +67,12 @@ pub struct State {
     scene: Scene,
     state: StateEnum,
     // gameplay
-    mouse_grabber: MouseGrabber,
+    mouse_spring: sf::MouseSpring,
     // graphics
     camera: sf::Camera,
     env_map: EnvironmentMapState,
     light_quality: sf::LightingQualityConfig,
+    tonemap: sf::Tonemap,
     gen_assets: GeneratedAssets,
     camera_ctl: sf::MouseDragCameraController,
     // egui stuff
@@ -88,6 +88,9 @@ pub struct State {
 impl State {
     fn init(game: &mut sf::Game) -> Self {
         let gen_assets = load_common_assets(game);
+        // sky colors follow the environment map as it changes
+        game.renderer
+            .set_background(sf::Background::EnvironmentGradient);
 
         let egui_context = egui::Context::default();
         let viewport_id = egui_context.viewport_id();
@@ -95,12 +98,13 @@ impl State {
             scenes_available: read_available_scenes().expect("Failed to read scenes directory"),
             scene: Scene::default(),
             state: StateEnum::Playing,
-            mouse_grabber: MouseGrabber::new(),
+            mouse_spring: sf::MouseSpring::new(),
             camera: sf::Camera::default(),
             // default environment map simulates a soft moonlight
             // so that dynamic lights inside of the scene look bright
             env_map: EnvironmentMapState::Static(sf::EnvironmentMap::preset_night()),
             light_quality: sf::LightingQualityConfig::default(),
+            tonemap: sf::Tonemap::default(),
             gen_assets,
             camera_ctl: sf::MouseDragCameraController {
                 activate_button: sf::MouseButton::Middle.into(),
@@ -118,7 +122,7 @@ impl State {
                 sf::Renderer::device(),
                 game.renderer.swapchain_format(),
                 Some(game.renderer.depth_format()),
-                sf::graphics::renderer::MSAA_SAMPLES,
+                sf::Renderer::msaa_samples(),
             ),
             last_egui_output: Default::default(),
             spawner_circle_r: 0.0,
@@ -147,9 +151,8 @@ pub struct GeneratedAssets {
 
 /// Load assets referenced by name elsewhere.
 ///
-/// Currently, this must be called after [`State::reset`] before loading a level.
-/// It would be nice to have a form of garbage collection for `GraphicsManager`
-/// that doesn't remove these, but that's not a top priority right now
+/// These are protected from being unloaded when switching levels,
+/// so this only needs to be called once.
 fn load_common_assets(game: &mut sf::Game) -> GeneratedAssets {
     game.graphics
         .load_gltf("examples/sandbox/assets/library.glb")
@@ -196,6 +199,8 @@ fn load_common_assets(game: &mut sf::Game) -> GeneratedAssets {
         ..Default::default()
     });
 
+    game.graphics.protect_all();
+
     GeneratedAssets {
         player,
         light_palette,
@@ -344,6 +349,7 @@ impl sf::GameState for State {
         let mut step_one = false;
         let mut shape_to_spawn: Option<sf::ColliderPolygon> = None;
         let mut light_quality = self.light_quality;
+        let mut tonemap = self.tonemap;
         let current_env_map = match &self.env_map {
             EnvironmentMapState::Static(m) => m,
             EnvironmentMapState::Interpolating { end, .. } => end,
@@ -351,8 +357,11 @@ impl sf::GameState for State {
         let mut next_env_map = current_env_map.clone();
 
         egui::Window::new("Controls").show(self.egui_state.egui_ctx(), |ui| {
-            let framerate = game.get_framerate();
-            ui.label(format!("{framerate:.1} ms/frame"));
+            let timings = game.frame_timings();
+            ui.label(format!(
+                "{:.1} ms/frame ({:.0} fps), 1% low {:.1} ms",
+                timings.avg_ms, timings.fps, timings.p99_ms
+            ));
 
             ui.separator();
 
@@ -444,6 +453,24 @@ impl sf::GameState for State {
                     .step_by(0.5)
                     .text("Mip bias"),
             );
+            ui.checkbox(&mut light_quality.skip_final_cascade, "Skip final cascade");
+            ui.checkbox(
+                &mut light_quality.skip_upper_cascades,
+                "Skip upper cascades",
+            );
+            ui.checkbox(&mut light_quality.skip_light_mips, "Skip light mips");
+
+            ui.horizontal(|ui| {
+                ui.label("Tonemap");
+                ui.radio_value(&mut tonemap.mode, sf::TonemapMode::None, "None");
+                ui.radio_value(&mut tonemap.mode, sf::TonemapMode::Reinhard, "Reinhard");
+                ui.radio_value(&mut tonemap.mode, sf::TonemapMode::Aces, "ACES");
+            });
+            ui.add(
+                egui::Slider::new(&mut tonemap.exposure, 0.1..=10.0)
+                    .logarithmic(true)
+                    .text("Exposure"),
+            );
 
             ui.horizontal(|ui| {
                 ui.label("Presets");
@@ -457,26 +484,16 @@ impl sf::GameState for State {
                     next_env_map = sf::EnvironmentMap::preset_day();
                 }
                 if ui.button("none").clicked() {
-                    next_env_map = sf::EnvironmentMap {
-                        // add a light with zero so that interpolation still works
-                        lights: vec![sf::DirectionalLight {
-                            color: [0.; 3],
-                            ..Default::default()
-                        }],
-                        ..Default::default()
-                    };
+                    next_env_map = sf::EnvironmentMap::default();
                 }
             });
 
+            // egui's rgb color pickers edit linear colors, same as the renderer uses
             ui.horizontal(|ui| {
                 ui.vertical(|ui| {
                     ui.label("Ambient");
                     ui.color_edit_button_rgb(&mut next_env_map.ambient);
                 });
-                ui.vertical(|ui| {
-                    ui.label("Sun");
-                    ui.color_edit_button_rgb(&mut next_env_map.lights[0].color);
-                });
                 ui.vertical(|ui| {
                     ui.label("Zenith");
                     ui.color_edit_button_rgb(&mut next_env_map.zenith);
@@ -490,14 +507,27 @@ impl sf::GameState for State {
                     ui.color_edit_button_rgb(&mut next_env_map.ground);
                 });
             });
-            ui.add(
-                egui::Slider::new(&mut next_env_map.lights[0].direction.x, -1.0..=1.0)
-                    .text("Sun direction x"),
-            );
-            ui.add(
-                egui::Slider::new(&mut next_env_map.lights[0].direction.y, -1.0..=1.0)
-                    .text("Sun direction y"),
-            );
+            let mut removed_light = None;
+            for (light_idx, light) in next_env_map.lights.iter_mut().enumerate() {
+                ui.horizontal(|ui| {
+                    ui.label(format!("Light {light_idx}"));
+                    ui.color_edit_button_rgb(&mut light.color);
+                    ui.add(egui::Slider::new(&mut light.direction.x, -1.0..=1.0).text("x"));
+                    ui.add(egui::Slider::new(&mut light.direction.y, -1.0..=1.0).text("y"));
+                    if ui.button("Remove").clicked() {
+                        removed_light = Some(light_idx);
+                    }
+                });
+            }
+            if let Some(light_idx) = removed_light {
+                next_env_map.lights.remove(light_idx);
+            }
+            if ui.button("Add light").clicked() {
+                next_env_map.lights.push(sf::DirectionalLight {
+                    color: [0.2; 3],
+                    ..Default::default()
+                });
+            }
 
             ui.separator();
 
@@ -533,6 +563,17 @@ impl sf::GameState for State {
                     .text("Physics substeps"),
             );
             ui.add(egui::Slider::new(&mut self.time_scale, 0.05..=2.0).text("Time scale"));
+            let mut vsync = matches!(
+                game.renderer.present_mode(),
+                sf::wgpu::PresentMode::AutoVsync | sf::wgpu::PresentMode::Fifo
+            );
+            if ui.checkbox(&mut vsync, "Vsync").changed() {
+                game.renderer.set_present_mode(if vsync {
+                    sf::wgpu::PresentMode::AutoVsync
+                } else {
+                    sf::wgpu::PresentMode::AutoNoVsync
+                });
+            }
 
             ui.separator();
 
@@ -544,8 +585,11 @@ impl sf::GameState for State {
             return None;
         }
         if reload {
-            game.clear_state();
-            self.gen_assets = load_common_assets(game);
+            game.world.clear();
+            game.physics.clear();
+            game.hecs_sync.clear();
+            // common assets are protected, so this only unloads ones loaded by the previous scene
+            game.graphics.collect_unused(&game.world);
             self.scene.instantiate(game, &self.gen_assets);
         }
 
@@ -553,6 +597,10 @@ impl sf::GameState for State {
             game.renderer.set_lighting_quality(light_quality);
             self.light_quality = light_quality;
         }
+        if tonemap != self.tonemap {
+            game.renderer.set_tonemap(tonemap);
+            self.tonemap = tonemap;
+        }
 
         if next_env_map != *current_env_map {
             self.env_map = EnvironmentMapState::Interpolating {
@@ -566,8 +614,12 @@ impl sf::GameState for State {
 
         // mouse controls
 
-        self.mouse_grabber
-            .update(&game.input, &self.camera, &mut game.physics);
+        self.mouse_spring.update(
+            &mut game.physics,
+            game.input.cursor_position_world(&self.camera).conv_p(),
+            game.input
+                .button(sf::ButtonQuery::mouse(sf::MouseButton::Left).held()),
+        );
         self.camera_ctl.update(&mut self.camera, &game.input);
 
         // spawn stuff even when paused
@@ -657,22 +709,7 @@ impl sf::GameState for State {
                 fn smoothstep(t: f32) -> f32 {
                     t * t * (3.0 - 2.0 * t)
                 }
-                let t = smoothstep(*t);
-                let lerp_color = |start: [f32; 3], end: [f32; 3]| -> [f32; 3] {
-                    std::array::from_fn(|i| (1. - t) * start[i] + t * end[i])
-                };
-                sf::EnvironmentMap {
-                    ambient: lerp_color(start.ambient, end.ambient),
-                    horizon: lerp_color(start.horizon, end.horizon),
-                    zenith: lerp_color(start.zenith, end.zenith),
-                    ground: lerp_color(start.ground, end.ground),
-                    lights: izip!(&start.lights, &end.lights)
-                        .map(|(s, e)| sf::DirectionalLight {
-                            color: lerp_color(s.color, e.color),
-                            direction: (1. - t) * s.direction + t * e.direction,
-                        })
-                        .collect(),
-                }
+                start.lerp(end, smoothstep(*t))
             }
         };
 
@@ -681,7 +718,6 @@ impl sf::GameState for State {
         // scene rendering
 
         let mut frame = game.renderer.begin_frame();
-        frame.set_clear_color([0.00802, 0.0137, 0.02732, 1.]);
         frame.draw_meshes(&mut game.graphics, &mut game.world, &self.camera);
 
         // egui
@@ -708,16 +744,17 @@ impl sf::GameState for State {
             size_in_pixels: [window_size.width, window_size.height],
             pixels_per_point: self.egui_state.egui_ctx().pixels_per_point(),
         };
+        let mut egui_pass = frame.custom_pass();
         self.egui_renderer.update_buffers(
             device,
             queue,
-            frame.encoder_mut(),
+            egui_pass.encoder(),
             &paint_jobs,
             &screen_desc,
         );
 
         {
-            let mut pass = frame.pass();
+            let mut pass = egui_pass.begin_render_pass(Some("egui"));
             self.egui_renderer
                 .render(&mut pass, &paint_jobs, &screen_desc);
         }
@@ -26,8 +26,8 @@ const SNAP_THRESHOLD: u128 = 200_000;
 
 const MAX_ACC_VALUE: u128 = 1_000_000_000 / 8;
 
-/// How many frames to store for the moving average frame time
-const STORED_FRAME_TIME_COUNT: usize = 10;
+/// How many frames to store for frame time statistics by default
+const DEFAULT_FRAME_TIMING_WINDOW: usize = 120;
 
 fn should_snap(dt: u128, target: u128) -> bool {
     if dt < target {
@@ -46,10 +46,34 @@ pub trait GameState: Sized + 'static {
     /// It is done this way due to async functions involved in the creation of the renderer,
     /// which is easiest to handle within a single encompassing async function (especially in wasm).
     fn init(game: &mut Game) -> Self;
-    /// Advance the game forward by a timestep of `Game::dt_fixed` seconds. Return None to exit the game.
+    /// Advance the game forward by a timestep of [`Game::dt_fixed`] seconds. Return None to exit the game.
+    ///
+    /// Use `game.dt_fixed` for time-based gameplay logic here;
+    /// it's always the same value regardless of how fast the game is actually running.
     fn tick(&mut self, game: &mut Game) -> Option<()>;
-    /// Render the game onto the screen. `dt` is the time in seconds since last draw.
-    fn draw(&mut self, game: &mut Game, dt: f32);
+    /// Render the game onto the screen.
+    ///
+    /// `dt` is the real time in seconds since last draw, useful for purely visual effects.
+    /// To smooth out motion between fixed ticks, see [`Game::interpolation_alpha`].
+    ///
+    /// By default this only clears the screen to black,
+    /// which is enough for states that don't need to show anything, e.g. in tests and tools.
+    fn draw(&mut self, game: &mut Game, _dt: f32) {
+        let mut frame = game.renderer.begin_frame();
+        // beginning a pass with the default clear color is all it takes to clear
+        drop(frame.pass());
+    }
+    /// React to a window or device event before the game loop handles it.
+    ///
+    /// Called right after [`GameParams::on_event`], and does nothing by default.
+    fn on_event(&mut self, _event: &Event<()>) {}
+    /// React to the window changing size, e.g. to adjust the camera's view area.
+    ///
+    /// Called after the renderer has resized its render targets,
+    /// so `game.renderer` already reports the new size.
+    /// Not called when the window is minimized to a size of zero.
+    /// Does nothing by default.
+    fn on_resize(&mut self, _game: &mut Game, _new_size: winit::dpi::PhysicalSize<u32>) {}
 }
 
 #[derive(Clone, Debug)]
@@ -64,6 +88,12 @@ pub struct GraphicsConfig {
     pub fps: u32,
     pub use_vsync: bool,
     pub lighting_quality: crate::LightingQualityConfig,
+    /// Number of samples per pixel for multisample antialiasing, 1 to disable it.
+    /// Default: 4
+    ///
+    /// If the GPU doesn't support the requested count, the closest supported one is used.
+    /// The count actually in use can be checked with [`Renderer::msaa_samples`][crate::Renderer::msaa_samples].
+    pub msaa_samples: u32,
 }
 
 impl<State: GameState> Default for GameParams<State> {
@@ -87,6 +117,7 @@ impl Default for GraphicsConfig {
             fps: 60,
             use_vsync: true,
             lighting_quality: crate::LightingQualityConfig::default(),
+            msaa_samples: 4,
         }
     }
 }
@@ -108,10 +139,85 @@ pub struct Game {
     pub hecs_sync: HecsSyncManager,
     /// Fixed delta-time between frames.
     pub dt_fixed: f64,
+    /// Fraction of a tick accumulated but not yet simulated at the time of drawing.
+    interpolation_alpha: f32,
+    /// Whether calls to `GameState::tick` are currently skipped.
+    paused: bool,
+    /// Multiplier for the speed of physics, see `set_time_scale`.
+    time_scale: f64,
     /// Duration of a frame in nanoseconds.
     nanos_per_frame: u128,
-    /// Durations of the last N frames to allow displaying a moving average frame time.
-    last_frame_times: VecDeque<f32>,
+    /// Durations of the last N frames to allow displaying frame time statistics.
+    frame_times: FrameTimeHistory,
+}
+
+/// Statistics of how long recent frames took to run, from [`Game::frame_timings`].
+///
+/// Frame times are measured from the start of one frame to the start of the next,
+/// so they include ticks, drawing and waiting for vsync.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct FrameTimings {
+    /// Duration of the most recent frame in milliseconds.
+    pub last_ms: f32,
+    /// Average frame duration in milliseconds.
+    pub avg_ms: f32,
+    /// 99th percentile frame duration in milliseconds,
+    /// i.e. the frame time of the "1% lows".
+    ///
+    /// Occasional stutters barely move the average but show up clearly here.
+    pub p99_ms: f32,
+    /// Average frames per second.
+    pub fps: f32,
+}
+
+/// Ring buffer of recent frame durations in seconds, oldest first.
+#[derive(Clone, Debug)]
+struct FrameTimeHistory {
+    times: VecDeque<f32>,
+    window: usize,
+}
+
+impl FrameTimeHistory {
+    /// Create a history filled with the given frame time
+    /// so that statistics are sensible before any frames have run.
+    fn new(window: usize, initial_secs: f32) -> Self {
+        Self {
+            times: VecDeque::from(vec![initial_secs; window]),
+            window,
+        }
+    }
+
+    fn push(&mut self, dt_secs: f32) {
+        while self.times.len() >= self.window {
+            self.times.pop_front();
+        }
+        self.times.push_back(dt_secs);
+    }
+
+    fn set_window(&mut self, window: usize) {
+        assert!(
+            window > 0,
+            "Frame timing window must contain at least one frame"
+        );
+        self.window = window;
+        while self.times.len() > window {
+            self.times.pop_front();
+        }
+    }
+
+    fn timings(&self) -> FrameTimings {
+        let avg_secs = self.times.iter().sum::<f32>() / self.times.len() as f32;
+        let mut sorted: Vec<f32> = self.times.iter().copied().collect();
+        sorted.sort_unstable_by(f32::total_cmp);
+        // nearest-rank percentile
+        let p99_idx = (sorted.len() * 99).div_ceil(100) - 1;
+        FrameTimings {
+            last_ms: 1000. * self.times.back().copied().unwrap_or_default(),
+            avg_ms: 1000. * avg_secs,
+            p99_ms: 1000. * sorted[p99_idx],
+            fps: 1. / avg_secs,
+        }
+    }
 }
 
 /// An error that occurred during in the initialization
@@ -190,9 +296,13 @@ impl Game {
             hecs_sync: HecsSyncManager::new_autosync(crate::HecsSyncOptions::both_ways()),
             nanos_per_frame: 1_000_000_000 / u128::from(graphics_conf.fps),
             dt_fixed: 1.0 / graphics_conf.fps as f64,
-            last_frame_times: [1. / graphics_conf.fps as f32; STORED_FRAME_TIME_COUNT]
-                .into_iter()
-                .collect(),
+            interpolation_alpha: 0.0,
+            paused: false,
+            time_scale: 1.0,
+            frame_times: FrameTimeHistory::new(
+                DEFAULT_FRAME_TIMING_WINDOW,
+                1. / graphics_conf.fps as f32,
+            ),
         };
         let mut state = State::init(&mut game);
 
@@ -206,6 +316,7 @@ impl Game {
         let mut acc = game.nanos_per_frame;
         events.run(move |event, elwt| {
             (on_event)(&mut state, &event);
+            state.on_event(&event);
 
             elwt.set_control_flow(ControlFlow::Poll);
 
@@ -242,11 +353,15 @@ impl Game {
                     // run gameplay ticks at a constant rate
 
                     while acc >= game.nanos_per_frame {
-                        let _frame = tracy_client::non_continuous_frame!("tick");
-
-                        if state.tick(&mut game).is_none() {
-                            elwt.exit();
-                            return;
+                        // input keeps updating while paused
+                        // so that the game can still react to e.g. an unpause button
+                        if !game.paused {
+                            let _frame = tracy_client::non_continuous_frame!("tick");
+
+                            if state.tick(&mut game).is_none() {
+                                elwt.exit();
+                                return;
+                            }
                         }
                         game.input.tick();
                         acc -= game.nanos_per_frame;
@@ -254,22 +369,19 @@ impl Game {
 
                     // draw as fast as we can
 
+                    game.interpolation_alpha = acc as f32 / game.nanos_per_frame as f32;
                     let dt_secs = dt.as_secs_f32();
-                    {
+                    // nothing to draw to while minimized
+                    if !game.renderer.is_minimized() {
                         let _draw_span = tracy_client::span!("draw");
 
                         state.draw(&mut game, dt_secs);
                     }
 
-                    game.last_frame_times.pop_front();
-                    game.last_frame_times.push_back(dt_secs);
+                    game.frame_times.push(dt_secs);
 
-                    game.renderer.profiler.end_frame().unwrap();
+                    game.renderer.end_profiler_frame();
                     tracy_client::frame_mark();
-
-                    game.renderer
-                        .profiler
-                        .process_finished_frame(crate::Renderer::queue().get_timestamp_period());
                 }
                 Event::WindowEvent { event, .. } => {
                     game.input.track_window_event(&event);
@@ -277,8 +389,16 @@ impl Game {
                         WindowEvent::CloseRequested => {
                             elwt.exit();
                         }
-                        WindowEvent::Resized(new_size) => {
+                        // zero size means the window was minimized, nothing to resize to
+                        WindowEvent::Resized(new_size)
+                            if new_size.width > 0 && new_size.height > 0 =>
+                        {
                             game.renderer.resize_swap_chain(new_size);
+                            state.on_resize(&mut game, new_size);
+                        }
+                        WindowEvent::ScaleFactorChanged { scale_factor, .. } => {
+                            // a Resized event follows if the physical size changes too
+                            game.renderer.set_window_scale_factor(scale_factor);
                         }
                         _ => (),
                     }
@@ -294,9 +414,17 @@ impl Game {
     ///
     /// Convenience method that calls [`HecsSyncManager::sync_hecs_to_physics`],
     /// [`PhysicsWorld::tick`], and [`HecsSyncManager::sync_physics_to_hecs`].
+    ///
+    /// The given `time_scale` is multiplied by the one set with
+    /// [`set_time_scale`][Self::set_time_scale].
     pub fn physics_tick(&mut self, ff: &impl ForceField, time_scale: Option<f64>) {
         self.hecs_sync
             .sync_hecs_to_physics(&mut self.physics, &mut self.world);
+        let time_scale = match time_scale {
+            Some(scale) => Some(scale * self.time_scale),
+            None if self.time_scale != 1.0 => Some(self.time_scale),
+            None => None,
+        };
         self.physics.tick(self.dt_fixed, time_scale, ff);
         self.hecs_sync
             .sync_physics_to_hecs(&self.physics, &mut self.world);
@@ -311,9 +439,111 @@ impl Game {
         self.hecs_sync.clear();
     }
 
+    /// Stop calling [`GameState::tick`] until unpaused.
+    ///
+    /// Drawing, events and input keep going while paused,
+    /// so unpausing needs to happen in [`GameState::draw`] or [`GameState::on_event`].
+    #[inline]
+    pub fn set_paused(&mut self, paused: bool) {
+        self.paused = paused;
+    }
+
+    /// Check whether the game is paused with [`set_paused`][Self::set_paused].
+    #[inline]
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    /// Speed up or slow down physics simulated with [`physics_tick`][Self::physics_tick],
+    /// e.g. 0.5 for slow motion. Defaults to 1.
+    ///
+    /// Ticks still happen at the same rate and [`dt_fixed`][Self::dt_fixed] doesn't change,
+    /// so for other time-based things like animations
+    /// multiply their timestep by [`time_scale`][Self::time_scale].
+    #[inline]
+    pub fn set_time_scale(&mut self, scale: f64) {
+        self.time_scale = scale;
+    }
+
+    /// Get the time scale set with [`set_time_scale`][Self::set_time_scale].
+    #[inline]
+    pub fn time_scale(&self) -> f64 {
+        self.time_scale
+    }
+
+    /// How far the game is between the last tick and the next one when drawing,
+    /// from 0 to 1.
+    ///
+    /// Ticks happen at a fixed rate while drawing happens as fast as possible,
+    /// so there's usually some time left over that hasn't been simulated yet.
+    /// Drawing objects at a position interpolated between their previous and current tick
+    /// using this value gives smooth motion even when the two rates don't match.
+    #[inline]
+    pub fn interpolation_alpha(&self) -> f32 {
+        self.interpolation_alpha
+    }
+
     /// Get the average recent framerate as ms/frame.
+    ///
+    /// This is the same as [`frame_timings().avg_ms`][Self::frame_timings].
     pub fn get_framerate(&self) -> f32 {
-        1000. * self.last_frame_times.iter().fold(0., |acc, x| acc + x)
-            / self.last_frame_times.len() as f32
+        self.frame_times.timings().avg_ms
+    }
+
+    /// Get statistics of how long recent frames took,
+    /// e.g. for a performance overlay.
+    ///
+    /// Statistics are computed over the last 120 frames by default,
+    /// change this with [`set_frame_timing_window`][Self::set_frame_timing_window].
+    pub fn frame_timings(&self) -> FrameTimings {
+        self.frame_times.timings()
+    }
+
+    /// Set how many recent frames [`frame_timings`][Self::frame_timings] are computed over.
+    ///
+    /// A longer window gives smoother averages
+    /// and catches rarer stutters in the percentile,
+    /// a shorter one reacts faster to changes.
+    ///
+    /// Panics if `frames` is zero.
+    #[inline]
+    pub fn set_frame_timing_window(&mut self, frames: usize) {
+        self.frame_times.set_window(frames);
+    }
+
+    /// Get the number of frames set with
+    /// [`set_frame_timing_window`][Self::set_frame_timing_window].
+    #[inline]
+    pub fn frame_timing_window(&self) -> usize {
+        self.frame_times.window
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn frame_timings_catch_stutter() {
+        let mut history = FrameTimeHistory::new(100, 0.010);
+        let timings = history.timings();
+        assert!((timings.avg_ms - 10.).abs() < 1e-3);
+        assert!((timings.fps - 100.).abs() < 1e-2);
+
+        // two stutters in a hundred frames barely move the average
+        history.push(0.050);
+        history.push(0.050);
+        let timings = history.timings();
+        assert_eq!(timings.last_ms, 50.);
+        assert!(timings.avg_ms < 11.);
+        assert_eq!(timings.p99_ms, 50.);
+
+        // shrinking the window drops the oldest frames first
+        history.set_window(2);
+        let timings = history.timings();
+        assert_eq!(timings.avg_ms, 50.);
+        history.push(0.010);
+        history.push(0.010);
+        assert_eq!(history.timings().p99_ms, 10.);
     }
 }
@@ -2,13 +2,15 @@ mod animation;
 pub use animation::animator::Animator;
 
 mod manager;
-pub use manager::{AnimationId, GraphicsManager, MaterialId, MeshId};
+pub use manager::{AnimationId, AnimatorId, GraphicsManager, LightAnimatorId, MaterialId, MeshId};
+#[cfg(feature = "gltf")]
+pub use manager::{GltfLoadHandle, LoadError};
 
 mod scene;
 pub use scene::Scene;
 
 pub mod renderer;
-pub use renderer::Renderer;
+pub use renderer::{Background, CustomPass, FrameStats, MeshDrawStage, Renderer, ScissorRect};
 
 pub(crate) mod gi;
 
@@ -18,10 +20,20 @@ pub mod camera;
 pub use camera::{Camera, MouseDragCameraController};
 
 pub(super) mod mesh;
-pub use mesh::{ConvexMeshShape, Mesh, MeshRenderer, Skin, Vertex as MeshVertex};
+pub use mesh::{ConvexMeshShape, Mesh, MeshRenderer, Skin, Tint, Vertex as MeshVertex};
 
 mod line_renderer;
 pub use line_renderer::{LineStrip, LineVertex};
 
+mod blob_renderer;
+pub use blob_renderer::{BlobSet, BlobShape};
+
+mod debug_text;
+
 pub mod material;
 pub use material::Texture;
+
+pub mod color;
+
+pub mod atlas;
+pub use atlas::{TextureAtlas, UvRect};
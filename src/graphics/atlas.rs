@@ -0,0 +1,255 @@
+//! Packing many small textures into one, so that they can share a single material.
+
+use super::material::TextureData;
+
+/// Largest width or height an atlas is allowed to grow to.
+///
+/// This is the texture size limit wgpu guarantees on all platforms by default.
+const MAX_ATLAS_SIZE: u32 = 8192;
+/// Empty pixels left between packed textures
+/// so that linear filtering at the edge of one doesn't pick up color from its neighbour.
+const PADDING: u32 = 1;
+
+/// The area of an atlas occupied by one of the textures packed into it,
+/// in texture coordinates from 0 to 1.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct UvRect {
+    /// The top left corner.
+    pub min: [f32; 2],
+    /// The bottom right corner.
+    pub max: [f32; 2],
+}
+
+/// Error when packing textures into an atlas.
+#[derive(thiserror::Error, Debug)]
+pub enum AtlasError {
+    #[error("No textures were given to pack")]
+    Empty,
+    #[error("All textures in an atlas must have the same format")]
+    FormatMismatch,
+    #[error("Textures with the format {0:?} can't be packed")]
+    UnsupportedFormat(wgpu::TextureFormat),
+    #[error("Pixel data of texture {0} doesn't match its dimensions")]
+    InvalidData(usize),
+    #[error("Textures don't fit in an atlas of size {MAX_ATLAS_SIZE}x{MAX_ATLAS_SIZE}")]
+    TooLarge,
+}
+
+/// A texture built by packing several smaller textures next to each other.
+///
+/// Create one with [`pack`][Self::pack] and upload it with [`texture_data`][Self::texture_data],
+/// or use [`GraphicsManager::create_atlas`][super::GraphicsManager::create_atlas]
+/// to do both and get a material with the atlas as its diffuse texture.
+#[derive(Clone, Debug)]
+pub struct TextureAtlas {
+    pixels: Vec<u8>,
+    format: wgpu::TextureFormat,
+    dimensions: (u32, u32),
+    uv_rects: Vec<UvRect>,
+}
+
+impl TextureAtlas {
+    /// Pack the given textures into one, growing it until they all fit.
+    ///
+    /// Textures are placed in rows ordered by height,
+    /// which wastes little space when the textures are similar in size
+    /// (e.g. sprites or tiles) but isn't optimal for very different ones.
+    /// All textures must have the same uncompressed format.
+    pub fn pack(textures: &[TextureData<'_>]) -> Result<Self, AtlasError> {
+        let format = textures.first().ok_or(AtlasError::Empty)?.format;
+        if textures.iter().any(|tex| tex.format != format) {
+            return Err(AtlasError::FormatMismatch);
+        }
+        let bytes_per_pixel = match (format.block_dimensions(), format.block_copy_size(None)) {
+            ((1, 1), Some(size)) => size as usize,
+            _ => return Err(AtlasError::UnsupportedFormat(format)),
+        };
+        for (idx, tex) in textures.iter().enumerate() {
+            let (w, h) = tex.dimensions;
+            if tex.pixels.len() != w as usize * h as usize * bytes_per_pixel {
+                return Err(AtlasError::InvalidData(idx));
+            }
+        }
+
+        let sizes: Vec<(u32, u32)> = textures.iter().map(|tex| tex.dimensions).collect();
+        let mut order: Vec<usize> = (0..sizes.len()).collect();
+        order.sort_by_key(|&idx| std::cmp::Reverse(sizes[idx].1));
+
+        // start from a size that could fit everything if there was no wasted space
+        let padded_area: u64 = sizes
+            .iter()
+            .map(|&(w, h)| (w + PADDING) as u64 * (h + PADDING) as u64)
+            .sum();
+        let max_width = sizes.iter().map(|&(w, _)| w).max().unwrap_or(1);
+        let max_height = sizes.iter().map(|&(_, h)| h).max().unwrap_or(1);
+        let mut width = max_width
+            .max((padded_area as f64).sqrt() as u32)
+            .next_power_of_two();
+        let mut height = max_height.next_power_of_two();
+        let positions = loop {
+            if width > MAX_ATLAS_SIZE || height > MAX_ATLAS_SIZE {
+                return Err(AtlasError::TooLarge);
+            }
+            if let Some(positions) = shelf_pack(&sizes, &order, (width, height)) {
+                break positions;
+            }
+            if height < width {
+                height *= 2;
+            } else {
+                width *= 2;
+            }
+        };
+
+        let row_len = width as usize * bytes_per_pixel;
+        let mut pixels = vec![0; row_len * height as usize];
+        for (tex, &(x, y)) in textures.iter().zip(&positions) {
+            let tex_row_len = tex.dimensions.0 as usize * bytes_per_pixel;
+            for (row_idx, row) in tex.pixels.chunks_exact(tex_row_len).enumerate() {
+                let start = (y as usize + row_idx) * row_len + x as usize * bytes_per_pixel;
+                pixels[start..start + tex_row_len].copy_from_slice(row);
+            }
+        }
+
+        let uv_rects = sizes
+            .iter()
+            .zip(&positions)
+            .map(|(&(w, h), &(x, y))| UvRect {
+                min: [x as f32 / width as f32, y as f32 / height as f32],
+                max: [
+                    (x + w) as f32 / width as f32,
+                    (y + h) as f32 / height as f32,
+                ],
+            })
+            .collect();
+
+        Ok(Self {
+            pixels,
+            format,
+            dimensions: (width, height),
+            uv_rects,
+        })
+    }
+
+    /// Where each texture ended up in the atlas,
+    /// in the same order they were given to [`pack`][Self::pack].
+    #[inline]
+    pub fn uv_rects(&self) -> &[UvRect] {
+        &self.uv_rects
+    }
+
+    /// Width and height of the atlas in pixels.
+    #[inline]
+    pub fn dimensions(&self) -> (u32, u32) {
+        self.dimensions
+    }
+
+    /// Get the atlas as texture data that can be uploaded to the GPU
+    /// or given to a material in [`MaterialParams`][super::material::MaterialParams].
+    pub fn texture_data(&self) -> TextureData<'_> {
+        TextureData {
+            label: Some("texture atlas".to_string()),
+            pixels: &self.pixels,
+            format: self.format,
+            dimensions: self.dimensions,
+        }
+    }
+}
+
+/// Place rectangles left to right in rows as tall as the tallest rectangle in them,
+/// starting a new row when one fills up.
+/// Returns the top left corner of each rectangle, or None if they don't fit.
+fn shelf_pack(
+    sizes: &[(u32, u32)],
+    order: &[usize],
+    atlas_size: (u32, u32),
+) -> Option<Vec<(u32, u32)>> {
+    let mut positions = vec![(0, 0); sizes.len()];
+    let mut x = 0;
+    let mut shelf_y = 0;
+    let mut shelf_height = 0;
+    for &idx in order {
+        let (w, h) = sizes[idx];
+        if x + w > atlas_size.0 {
+            x = 0;
+            shelf_y += shelf_height;
+            shelf_height = 0;
+        }
+        if x + w > atlas_size.0 || shelf_y + h > atlas_size.1 {
+            return None;
+        }
+        positions[idx] = (x, shelf_y);
+        x += w + PADDING;
+        shelf_height = shelf_height.max(h + PADDING);
+    }
+    Some(positions)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn solid_texture(pixels: &[u8], dimensions: (u32, u32)) -> TextureData<'_> {
+        TextureData {
+            label: None,
+            pixels,
+            format: wgpu::TextureFormat::R8Unorm,
+            dimensions,
+        }
+    }
+
+    #[test]
+    fn packed_textures_dont_overlap() {
+        let pixel_data: Vec<Vec<u8>> = (0..20u8)
+            .map(|i| {
+                let side = 3 + (i as usize % 5) * 4;
+                vec![i + 1; side * side]
+            })
+            .collect();
+        let textures: Vec<TextureData<'_>> = pixel_data
+            .iter()
+            .map(|pixels| {
+                let side = (pixels.len() as f64).sqrt() as u32;
+                solid_texture(pixels, (side, side))
+            })
+            .collect();
+        let atlas = TextureAtlas::pack(&textures).unwrap();
+        let (width, height) = atlas.dimensions();
+        assert_eq!(atlas.uv_rects().len(), textures.len());
+
+        for (idx, (rect, tex)) in atlas.uv_rects().iter().zip(&textures).enumerate() {
+            let x0 = (rect.min[0] * width as f32).round() as usize;
+            let y0 = (rect.min[1] * height as f32).round() as usize;
+            let x1 = (rect.max[0] * width as f32).round() as usize;
+            let y1 = (rect.max[1] * height as f32).round() as usize;
+            assert_eq!(
+                (x1 - x0, y1 - y0),
+                (tex.dimensions.0 as usize, tex.dimensions.1 as usize)
+            );
+            // every pixel inside the rect belongs to this texture,
+            // so nothing was written over it
+            for y in y0..y1 {
+                for x in x0..x1 {
+                    assert_eq!(atlas.pixels[y * width as usize + x], idx as u8 + 1);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn invalid_inputs_are_rejected() {
+        assert!(matches!(TextureAtlas::pack(&[]), Err(AtlasError::Empty)));
+
+        let pixels = [0; 16];
+        assert!(matches!(
+            TextureAtlas::pack(&[solid_texture(&pixels, (4, 3))]),
+            Err(AtlasError::InvalidData(0))
+        ));
+
+        let huge = vec![0; 5000 * 5000];
+        let huge_textures = vec![solid_texture(&huge, (5000, 5000)); 4];
+        assert!(matches!(
+            TextureAtlas::pack(&huge_textures),
+            Err(AtlasError::TooLarge)
+        ));
+    }
+}
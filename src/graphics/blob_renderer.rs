@@ -0,0 +1,330 @@
+use crate::{
+    graphics::renderer::DEPTH_FORMAT,
+    math::{uv, PhysicsPose},
+    physics::{ColliderPolygon, ColliderShape},
+    MaterialId,
+};
+
+use std::{borrow::Cow, mem::size_of, sync::OnceLock};
+use zerocopy::{AsBytes, FromBytes};
+
+/// Bind group layout for blob parameters and shapes,
+/// created when the first blob set is made
+static BIND_GROUP_LAYOUT: OnceLock<wgpu::BindGroupLayout> = OnceLock::new();
+
+/// A circle or capsule to be drawn as part of a [`BlobSet`].
+#[derive(Clone, Copy, Debug)]
+pub struct BlobShape {
+    /// One end of the capsule's center line.
+    pub start: uv::Vec2,
+    /// The other end of the capsule's center line.
+    /// Set this equal to `start` for a circle.
+    pub end: uv::Vec2,
+    pub radius: f32,
+}
+
+impl BlobShape {
+    /// A circle centered at the given point.
+    pub fn circle(center: uv::Vec2, radius: f32) -> Self {
+        Self {
+            start: center,
+            end: center,
+            radius,
+        }
+    }
+
+    /// Create a blob shape matching a circle or capsule collider in world space.
+    ///
+    /// Returns None for other shapes, which don't have an equivalent blob shape.
+    pub fn from_collider(pose: PhysicsPose, shape: ColliderShape) -> Option<Self> {
+        let (start, end) = match shape.polygon {
+            ColliderPolygon::Point => (uv::DVec2::zero(), uv::DVec2::zero()),
+            ColliderPolygon::LineSegment { hl } => {
+                (uv::DVec2::new(-hl, 0.0), uv::DVec2::new(hl, 0.0))
+            }
+            _ => return None,
+        };
+        let to_f32 = |p: uv::DVec2| {
+            let p = pose * p;
+            uv::Vec2::new(p.x as f32, p.y as f32)
+        };
+        Some(Self {
+            start: to_f32(start),
+            end: to_f32(end),
+            radius: shape.circle_r as f32,
+        })
+    }
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, AsBytes, FromBytes)]
+struct GpuShape {
+    start: [f32; 2],
+    end: [f32; 2],
+    radius: f32,
+    _pad: f32,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, AsBytes, FromBytes)]
+struct GpuParams {
+    bounds_min: [f32; 2],
+    bounds_max: [f32; 2],
+    depth: f32,
+    smoothness: f32,
+    shape_count: u32,
+    _pad: u32,
+}
+
+/// A group of circles and capsules drawn as a single shape
+/// where nearby shapes smoothly merge together, like metaballs or slime.
+///
+/// Drawn with [`Frame::draw_blobs`][super::renderer::Frame::draw_blobs].
+/// Every pixel in the bounding box of the shapes is checked against every shape,
+/// so this is best suited for sets of at most a few hundred shapes.
+/// Use multiple sets for blobs that are far apart.
+pub struct BlobSet {
+    params_buf: wgpu::Buffer,
+    shape_buf: wgpu::Buffer,
+    shape_capacity: usize,
+    bind_group: wgpu::BindGroup,
+    shape_count: u32,
+    smoothness: f32,
+    depth: f32,
+    material_id: Option<MaterialId>,
+}
+
+impl BlobSet {
+    /// Create a new set of blobs.
+    ///
+    /// `smoothness` is the distance over which shapes blend together;
+    /// zero gives sharp edges where shapes overlap.
+    /// If a material id is not given, the default material is used.
+    /// The material's diffuse texture is stretched over the bounding box of the shapes.
+    pub fn new(
+        shapes: &[BlobShape],
+        smoothness: f32,
+        depth: f32,
+        material_id: Option<MaterialId>,
+    ) -> Self {
+        let device = crate::Renderer::device();
+        let params_buf = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("blob params"),
+            size: size_of::<GpuParams>() as u64,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let shape_capacity = shapes.len().max(1);
+        let shape_buf = Self::create_shape_buf(shape_capacity);
+        let bind_group = Self::create_bind_group(&params_buf, &shape_buf);
+        let mut set = Self {
+            params_buf,
+            shape_buf,
+            shape_capacity,
+            bind_group,
+            shape_count: 0,
+            smoothness,
+            depth,
+            material_id,
+        };
+        set.overwrite(shapes);
+        set
+    }
+
+    /// Rewrite the shapes in this set.
+    ///
+    /// This is more efficient than dropping and making a new one,
+    /// especially if the number of shapes does not grow.
+    pub fn overwrite(&mut self, shapes: &[BlobShape]) {
+        if shapes.len() > self.shape_capacity {
+            self.shape_capacity = shapes.len().next_power_of_two();
+            self.shape_buf = Self::create_shape_buf(self.shape_capacity);
+            self.bind_group = Self::create_bind_group(&self.params_buf, &self.shape_buf);
+        }
+        self.shape_count = shapes.len() as u32;
+
+        let gpu_shapes: Vec<GpuShape> = shapes
+            .iter()
+            .map(|s| GpuShape {
+                start: *s.start.as_array(),
+                end: *s.end.as_array(),
+                radius: s.radius,
+                _pad: 0.0,
+            })
+            .collect();
+
+        // bounds padded by the smoothing distance
+        // because smooth minimum can make the merged shape slightly larger than its parts
+        let (bounds_min, bounds_max) = shapes.iter().fold(
+            (uv::Vec2::broadcast(f32::MAX), uv::Vec2::broadcast(f32::MIN)),
+            |(min, max), s| {
+                let r = uv::Vec2::broadcast(s.radius + self.smoothness);
+                (
+                    min.min_by_component(s.start.min_by_component(s.end) - r),
+                    max.max_by_component(s.start.max_by_component(s.end) + r),
+                )
+            },
+        );
+        let params = GpuParams {
+            bounds_min: *bounds_min.as_array(),
+            bounds_max: *bounds_max.as_array(),
+            depth: self.depth,
+            smoothness: self.smoothness,
+            shape_count: self.shape_count,
+            _pad: 0,
+        };
+
+        let queue = crate::Renderer::queue();
+        queue.write_buffer(&self.params_buf, 0, params.as_bytes());
+        if !gpu_shapes.is_empty() {
+            queue.write_buffer(&self.shape_buf, 0, gpu_shapes.as_bytes());
+        }
+    }
+
+    fn create_shape_buf(capacity: usize) -> wgpu::Buffer {
+        crate::Renderer::device().create_buffer(&wgpu::BufferDescriptor {
+            label: Some("blob shapes"),
+            size: (capacity * size_of::<GpuShape>()) as u64,
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        })
+    }
+
+    fn create_bind_group(params_buf: &wgpu::Buffer, shape_buf: &wgpu::Buffer) -> wgpu::BindGroup {
+        crate::Renderer::device().create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("blobs"),
+            layout: Self::bind_group_layout(),
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: params_buf.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: shape_buf.as_entire_binding(),
+                },
+            ],
+        })
+    }
+
+    fn bind_group_layout<'a>() -> &'a wgpu::BindGroupLayout {
+        BIND_GROUP_LAYOUT.get_or_init(|| {
+            let device = crate::Renderer::device();
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: Some("blobs"),
+                entries: &[
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 0,
+                        visibility: wgpu::ShaderStages::VERTEX_FRAGMENT,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Uniform,
+                            has_dynamic_offset: false,
+                            min_binding_size: wgpu::BufferSize::new(size_of::<GpuParams>() as _),
+                        },
+                        count: None,
+                    },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 1,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Storage { read_only: true },
+                            has_dynamic_offset: false,
+                            min_binding_size: wgpu::BufferSize::new(size_of::<GpuShape>() as _),
+                        },
+                        count: None,
+                    },
+                ],
+            })
+        })
+    }
+}
+
+/// Renderer that draws [`BlobSet`]s as a smooth minimum of signed distance fields,
+/// evaluated over a quad covering each set.
+pub struct BlobRenderer {
+    pipeline: wgpu::RenderPipeline,
+}
+
+impl BlobRenderer {
+    pub(crate) fn new() -> Self {
+        let device = crate::Renderer::device();
+
+        let label = Some("blob");
+
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label,
+            source: wgpu::ShaderSource::Wgsl(Cow::Borrowed(include_str!("shaders/blob.wgsl"))),
+        });
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label,
+            bind_group_layouts: &[
+                crate::Camera::bind_group_layout(),
+                crate::Material::bind_group_layout(),
+                BlobSet::bind_group_layout(),
+            ],
+            push_constant_ranges: &[],
+        });
+
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label,
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: "vs_main",
+                // quad corners are generated from the vertex index
+                buffers: &[],
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: "fs_main",
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: super::renderer::SWAPCHAIN_FORMAT,
+                    blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                    write_mask: wgpu::ColorWrites::COLOR,
+                })],
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+            }),
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleList,
+                front_face: wgpu::FrontFace::Ccw,
+                cull_mode: None,
+                ..Default::default()
+            },
+            depth_stencil: Some(wgpu::DepthStencilState {
+                format: DEPTH_FORMAT,
+                depth_write_enabled: true,
+                depth_compare: wgpu::CompareFunction::Less,
+                stencil: wgpu::StencilState::default(),
+                bias: wgpu::DepthBiasState::default(),
+            }),
+            multisample: crate::Renderer::default_multisample_state(),
+            multiview: None,
+        });
+
+        Self { pipeline }
+    }
+
+    pub fn draw<'pass>(
+        &'pass self,
+        pass: &mut wgpu::RenderPass<'pass>,
+        manager: &'pass crate::GraphicsManager,
+        camera: &'pass crate::Camera,
+        blobs: &'pass BlobSet,
+    ) {
+        if blobs.shape_count == 0 {
+            return;
+        }
+        let material = if let Some(mid) = blobs.material_id {
+            manager.get_material(mid)
+        } else {
+            crate::Material::get_default()
+        };
+        pass.set_pipeline(&self.pipeline);
+        pass.set_bind_group(0, &camera.bind_group, &[]);
+        pass.set_bind_group(1, &material.bind_group, &[]);
+        pass.set_bind_group(2, &blobs.bind_group, &[]);
+        pass.draw(0..6, 0..1);
+    }
+}
@@ -213,7 +213,7 @@ impl Camera {
         Some(point_screen)
     }
 
-    fn pose_as_2d(&self) -> uv::Isometry2 {
+    pub(crate) fn pose_as_2d(&self) -> uv::Isometry2 {
         uv::Isometry2::new(
             uv::Vec2::new(self.pose.translation.x, self.pose.translation.y),
             uv::Rotor2::new(
@@ -0,0 +1,64 @@
+//! Conversions between color spaces.
+//!
+//! Every color given to the renderer as numbers,
+//! i.e. material colors, light colors and environment map colors,
+//! is in linear RGB, which is the space lighting is computed in.
+//! The final image is converted to sRGB by the GPU when it's written to the screen
+//! (see [`SWAPCHAIN_FORMAT`][super::renderer::SWAPCHAIN_FORMAT]).
+//! Diffuse textures in an sRGB [texture format][super::material::TextureData::format]
+//! are also converted to linear automatically when sampled.
+//!
+//! Colors from image editors, hex codes like `#ff8800` and most color pickers are sRGB.
+//! Convert them with [`srgb_to_linear`] or [`srgba_to_linear`] before use,
+//! otherwise they come out too bright and washed out.
+
+/// Convert an sRGB color to linear RGB.
+pub fn srgb_to_linear(srgb: [f32; 3]) -> [f32; 3] {
+    srgb.map(channel_srgb_to_linear)
+}
+
+/// Convert an sRGB color with alpha to linear RGB.
+/// Alpha is already linear and is left as is.
+pub fn srgba_to_linear(srgba: [f32; 4]) -> [f32; 4] {
+    let [r, g, b] = srgb_to_linear([srgba[0], srgba[1], srgba[2]]);
+    [r, g, b, srgba[3]]
+}
+
+/// Convert a linear RGB color to sRGB, e.g. to show it in a color picker that expects sRGB.
+pub fn linear_to_srgb(linear: [f32; 3]) -> [f32; 3] {
+    linear.map(channel_linear_to_srgb)
+}
+
+fn channel_srgb_to_linear(c: f32) -> f32 {
+    if c <= 0.04045 {
+        c / 12.92
+    } else {
+        ((c + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+fn channel_linear_to_srgb(c: f32) -> f32 {
+    if c <= 0.0031308 {
+        c * 12.92
+    } else {
+        1.055 * c.powf(1. / 2.4) - 0.055
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn conversions_round_trip() {
+        assert_eq!(srgb_to_linear([0., 1., 1.]), [0., 1., 1.]);
+        let mid = srgba_to_linear([0.5, 0.5, 0.5, 0.5]);
+        assert!((mid[0] - 0.214).abs() < 1e-3);
+        assert_eq!(mid[3], 0.5);
+
+        for c in [0.0, 0.002, 0.04, 0.3, 0.75, 1.0] {
+            let back = linear_to_srgb(srgb_to_linear([c; 3]));
+            assert!((back[0] - c).abs() < 1e-5, "{c} became {}", back[0]);
+        }
+    }
+}
@@ -0,0 +1,233 @@
+//! Minimal text overlay for debug information,
+//! drawn with a tiny built-in bitmap font.
+
+use crate::{graphics::renderer::DEPTH_FORMAT, math as m};
+
+use std::borrow::Cow;
+use zerocopy::{AsBytes, FromBytes};
+
+/// Width of a glyph in font pixels.
+const GLYPH_WIDTH: usize = 3;
+/// Height of a glyph in font pixels.
+const GLYPH_HEIGHT: usize = 5;
+/// Horizontal distance between the starts of consecutive glyphs in font pixels.
+const ADVANCE: f32 = 4.;
+/// Vertical distance between the tops of consecutive lines in font pixels.
+const LINE_HEIGHT: f32 = 7.;
+/// Size of a font pixel in physical screen pixels at a window scale factor of 1.
+const BASE_PIXEL_SIZE: f64 = 2.;
+
+const TEXT_COLOR: [f32; 4] = [1., 1., 1., 1.];
+// drawn offset by one font pixel behind the text
+// to keep it readable on top of bright backgrounds
+const SHADOW_COLOR: [f32; 4] = [0., 0., 0., 1.];
+
+/// A single lit pixel of a glyph in clip space.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, AsBytes, FromBytes)]
+pub(crate) struct GpuPixel {
+    min: [f32; 2],
+    size: [f32; 2],
+    color: [f32; 4],
+}
+
+/// Lay out a string starting from `screen_pos` (top left corner of the text),
+/// producing a pixel instance for each lit pixel of each glyph and its shadow.
+pub(crate) fn layout_text(
+    text: &str,
+    screen_pos: m::Vec2,
+    viewport_size: (u32, u32),
+    scale_factor: f64,
+) -> Vec<GpuPixel> {
+    let pixel_size = (BASE_PIXEL_SIZE * scale_factor).round().max(1.) as f32;
+    let (vp_w, vp_h) = (viewport_size.0 as f32, viewport_size.1 as f32);
+    let size = [2. * pixel_size / vp_w, 2. * pixel_size / vp_h];
+    let to_clip = |x: f32, y: f32| [2. * x / vp_w - 1., 1. - 2. * (y + pixel_size) / vp_h];
+
+    let mut shadows = Vec::new();
+    let mut pixels = Vec::new();
+    for (line_idx, line) in text.lines().enumerate() {
+        let line_y = screen_pos.y + line_idx as f32 * LINE_HEIGHT * pixel_size;
+        for (char_idx, c) in line.chars().enumerate() {
+            let char_x = screen_pos.x + char_idx as f32 * ADVANCE * pixel_size;
+            for (row_idx, row) in glyph(c).into_iter().enumerate() {
+                for col_idx in 0..GLYPH_WIDTH {
+                    if row & (1 << (GLYPH_WIDTH - 1 - col_idx)) == 0 {
+                        continue;
+                    }
+                    let x = char_x + col_idx as f32 * pixel_size;
+                    let y = line_y + row_idx as f32 * pixel_size;
+                    shadows.push(GpuPixel {
+                        min: to_clip(x + pixel_size, y + pixel_size),
+                        size,
+                        color: SHADOW_COLOR,
+                    });
+                    pixels.push(GpuPixel {
+                        min: to_clip(x, y),
+                        size,
+                        color: TEXT_COLOR,
+                    });
+                }
+            }
+        }
+    }
+    // shadows first so that text is drawn on top of them
+    shadows.append(&mut pixels);
+    shadows
+}
+
+/// Get the rows of a glyph in the built-in font,
+/// three bits per row with the most significant bit on the left.
+///
+/// Lowercase letters are drawn as uppercase,
+/// and characters without a glyph are drawn as a question mark.
+fn glyph(c: char) -> [u8; GLYPH_HEIGHT] {
+    match c.to_ascii_uppercase() {
+        ' ' => [0b000, 0b000, 0b000, 0b000, 0b000],
+        '0' => [0b111, 0b101, 0b101, 0b101, 0b111],
+        '1' => [0b010, 0b110, 0b010, 0b010, 0b111],
+        '2' => [0b111, 0b001, 0b111, 0b100, 0b111],
+        '3' => [0b111, 0b001, 0b111, 0b001, 0b111],
+        '4' => [0b101, 0b101, 0b111, 0b001, 0b001],
+        '5' => [0b111, 0b100, 0b111, 0b001, 0b111],
+        '6' => [0b111, 0b100, 0b111, 0b101, 0b111],
+        '7' => [0b111, 0b001, 0b010, 0b010, 0b010],
+        '8' => [0b111, 0b101, 0b111, 0b101, 0b111],
+        '9' => [0b111, 0b101, 0b111, 0b001, 0b111],
+        'A' => [0b010, 0b101, 0b111, 0b101, 0b101],
+        'B' => [0b110, 0b101, 0b110, 0b101, 0b110],
+        'C' => [0b011, 0b100, 0b100, 0b100, 0b011],
+        'D' => [0b110, 0b101, 0b101, 0b101, 0b110],
+        'E' => [0b111, 0b100, 0b110, 0b100, 0b111],
+        'F' => [0b111, 0b100, 0b110, 0b100, 0b100],
+        'G' => [0b011, 0b100, 0b101, 0b101, 0b011],
+        'H' => [0b101, 0b101, 0b111, 0b101, 0b101],
+        'I' => [0b111, 0b010, 0b010, 0b010, 0b111],
+        'J' => [0b001, 0b001, 0b001, 0b101, 0b010],
+        'K' => [0b101, 0b101, 0b110, 0b101, 0b101],
+        'L' => [0b100, 0b100, 0b100, 0b100, 0b111],
+        'M' => [0b101, 0b111, 0b111, 0b101, 0b101],
+        'N' => [0b110, 0b101, 0b101, 0b101, 0b101],
+        'O' => [0b010, 0b101, 0b101, 0b101, 0b010],
+        'P' => [0b110, 0b101, 0b110, 0b100, 0b100],
+        'Q' => [0b010, 0b101, 0b101, 0b110, 0b011],
+        'R' => [0b110, 0b101, 0b110, 0b101, 0b101],
+        'S' => [0b011, 0b100, 0b010, 0b001, 0b110],
+        'T' => [0b111, 0b010, 0b010, 0b010, 0b010],
+        'U' => [0b101, 0b101, 0b101, 0b101, 0b111],
+        'V' => [0b101, 0b101, 0b101, 0b101, 0b010],
+        'W' => [0b101, 0b101, 0b111, 0b111, 0b101],
+        'X' => [0b101, 0b101, 0b010, 0b101, 0b101],
+        'Y' => [0b101, 0b101, 0b010, 0b010, 0b010],
+        'Z' => [0b111, 0b001, 0b010, 0b100, 0b111],
+        '.' => [0b000, 0b000, 0b000, 0b000, 0b010],
+        ',' => [0b000, 0b000, 0b000, 0b010, 0b100],
+        ':' => [0b000, 0b010, 0b000, 0b010, 0b000],
+        ';' => [0b000, 0b010, 0b000, 0b010, 0b100],
+        '-' => [0b000, 0b000, 0b111, 0b000, 0b000],
+        '+' => [0b000, 0b010, 0b111, 0b010, 0b000],
+        '=' => [0b000, 0b111, 0b000, 0b111, 0b000],
+        '*' => [0b000, 0b101, 0b010, 0b101, 0b000],
+        '/' => [0b001, 0b001, 0b010, 0b100, 0b100],
+        '%' => [0b101, 0b001, 0b010, 0b100, 0b101],
+        '(' => [0b010, 0b100, 0b100, 0b100, 0b010],
+        ')' => [0b010, 0b001, 0b001, 0b001, 0b010],
+        '[' => [0b110, 0b100, 0b100, 0b100, 0b110],
+        ']' => [0b011, 0b001, 0b001, 0b001, 0b011],
+        '<' => [0b001, 0b010, 0b100, 0b010, 0b001],
+        '>' => [0b100, 0b010, 0b001, 0b010, 0b100],
+        '!' => [0b010, 0b010, 0b010, 0b000, 0b010],
+        '_' => [0b000, 0b000, 0b000, 0b000, 0b111],
+        '#' => [0b101, 0b111, 0b101, 0b111, 0b101],
+        '|' => [0b010, 0b010, 0b010, 0b010, 0b010],
+        '\'' => [0b010, 0b010, 0b000, 0b000, 0b000],
+        '"' => [0b101, 0b101, 0b000, 0b000, 0b000],
+        _ => [0b110, 0b001, 0b010, 0b000, 0b010],
+    }
+}
+
+/// Renderer for [`Frame::debug_text`][super::renderer::Frame::debug_text].
+pub struct DebugTextRenderer {
+    pipeline: wgpu::RenderPipeline,
+}
+
+impl DebugTextRenderer {
+    pub(crate) fn new() -> Self {
+        let device = crate::Renderer::device();
+
+        let label = Some("debug text");
+
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label,
+            source: wgpu::ShaderSource::Wgsl(Cow::Borrowed(include_str!(
+                "shaders/debug_text.wgsl"
+            ))),
+        });
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label,
+            bind_group_layouts: &[],
+            push_constant_ranges: &[],
+        });
+
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label,
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: "vs_main",
+                // quad corners are generated from the vertex index,
+                // each instance is one pixel of a glyph
+                buffers: &[wgpu::VertexBufferLayout {
+                    array_stride: std::mem::size_of::<GpuPixel>() as u64,
+                    step_mode: wgpu::VertexStepMode::Instance,
+                    attributes: &wgpu::vertex_attr_array![
+                        0 => Float32x2,
+                        1 => Float32x2,
+                        2 => Float32x4,
+                    ],
+                }],
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: "fs_main",
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: super::renderer::SWAPCHAIN_FORMAT,
+                    blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                    write_mask: wgpu::ColorWrites::COLOR,
+                })],
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+            }),
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleList,
+                front_face: wgpu::FrontFace::Ccw,
+                cull_mode: None,
+                ..Default::default()
+            },
+            // overlay drawn on top of everything regardless of depth
+            depth_stencil: Some(wgpu::DepthStencilState {
+                format: DEPTH_FORMAT,
+                depth_write_enabled: false,
+                depth_compare: wgpu::CompareFunction::Always,
+                stencil: wgpu::StencilState::default(),
+                bias: wgpu::DepthBiasState::default(),
+            }),
+            multisample: crate::Renderer::default_multisample_state(),
+            multiview: None,
+        });
+
+        Self { pipeline }
+    }
+
+    pub fn draw<'pass>(
+        &'pass self,
+        pass: &mut wgpu::RenderPass<'pass>,
+        pixels: &'pass wgpu::Buffer,
+        pixel_count: u32,
+    ) {
+        pass.set_pipeline(&self.pipeline);
+        pass.set_vertex_buffer(0, pixels.slice(..));
+        pass.draw(0..6, 0..pixel_count);
+    }
+}
@@ -4,10 +4,13 @@ use wgpu::util::DeviceExt;
 use zerocopy::{AsBytes, FromBytes};
 
 use crate::math::uv;
+use thunderdome as td;
 use wgpu_profiler as wp;
 
 pub(crate) mod environment_map;
 pub use environment_map::EnvironmentMapData;
+pub(crate) mod spot_light;
+use spot_light::{SpotLight, SpotLightData, SpotLightKey};
 
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub struct LightingQualityConfig {
@@ -27,6 +30,18 @@ pub struct LightingQualityConfig {
     /// Mainly useful for squeezing out a bit of extra performance
     /// on extremely low settings.
     pub skip_final_cascade: bool,
+    /// Whether or not to compute the cascades above the final one.
+    /// Without them, only light from emitters within a few pixels
+    /// (the final cascade's range) and ambient light from the environment map
+    /// reach a surface.
+    /// Mainly useful for profiling the cost of the cascade computation.
+    pub skip_upper_cascades: bool,
+    /// Whether or not to generate the light texture mip chain
+    /// used to accelerate raymarching.
+    /// When skipped, all raymarching happens on the full-resolution light texture
+    /// and `mip_bias` has no effect.
+    /// Mainly useful for profiling the cost of the mip chain against the raymarch.
+    pub skip_light_mips: bool,
 }
 
 impl LightingQualityConfig {
@@ -34,28 +49,51 @@ impl LightingQualityConfig {
         probe_interval: 1.,
         mip_bias: 0.,
         skip_final_cascade: false,
+        skip_upper_cascades: false,
+        skip_light_mips: false,
     };
     pub const HIGH: Self = Self {
         probe_interval: 2.,
         mip_bias: 0.,
         skip_final_cascade: false,
+        skip_upper_cascades: false,
+        skip_light_mips: false,
     };
     pub const MEDIUM: Self = Self {
         probe_interval: 2.,
         mip_bias: 1.,
         skip_final_cascade: false,
+        skip_upper_cascades: false,
+        skip_light_mips: false,
     };
     pub const LOW: Self = Self {
         probe_interval: 4.,
         mip_bias: 1.,
         skip_final_cascade: false,
+        skip_upper_cascades: false,
+        skip_light_mips: false,
     };
     pub const LOWEST: Self = Self {
         probe_interval: 8.,
         mip_bias: 2.,
         skip_final_cascade: true,
+        skip_upper_cascades: false,
+        skip_light_mips: false,
     };
 
+    // Get the mip bias to use on a given cascade level,
+    // accounting for the mip chain possibly being skipped
+    #[inline]
+    fn effective_mip_bias(self, level: u32) -> f32 {
+        if self.skip_light_mips {
+            // cancels out the cascade level in the shader,
+            // sampling only the 0th mip level
+            -(level as f32)
+        } else {
+            self.mip_bias
+        }
+    }
+
     // Get the range of a c0 probe, which is half the diagonal of a square between probes
     #[inline]
     fn probe_range(self) -> f32 {
@@ -69,6 +107,43 @@ impl Default for LightingQualityConfig {
     }
 }
 
+/// How lit colors are brought into the range the screen can show,
+/// set with [`Renderer::set_tonemap`][crate::Renderer::set_tonemap].
+///
+/// This is applied when shading meshes, since they're the only thing lighting affects.
+/// Lines, blobs and anything drawn in custom passes are drawn as is.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Tonemap {
+    /// Curve used to map colors into the 0..=1 range. Default: [`TonemapMode::None`].
+    pub mode: TonemapMode,
+    /// Multiplier applied to lit colors before the curve,
+    /// e.g. to brighten up a dark night scene. Default: 1.
+    pub exposure: f32,
+}
+
+impl Default for Tonemap {
+    fn default() -> Self {
+        Self {
+            mode: TonemapMode::None,
+            exposure: 1.,
+        }
+    }
+}
+
+/// Curve used by [`Tonemap`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TonemapMode {
+    /// Colors are used as is and anything brighter than 1 is clipped to white.
+    #[default]
+    None,
+    /// `x / (1 + x)` on each color channel.
+    /// Never clips, but darkens and desaturates bright colors.
+    Reinhard,
+    /// An approximation of the filmic ACES curve.
+    /// Keeps more contrast than Reinhard but can shift very saturated colors.
+    Aces,
+}
+
 pub(crate) const CASCADE_TEX_FMT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba16Float;
 pub(crate) const LIGHT_TEX_FMT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba8Unorm;
 
@@ -78,6 +153,7 @@ const TILE_SIZE: u32 = 16;
 pub(crate) struct GlobalIlluminationPipeline {
     quality_conf: LightingQualityConfig,
     pub(super) env_map: EnvironmentMapData,
+    spot_lights: td::Arena<SpotLight>,
 
     pipelines: Pipelines,
     pub(super) textures: Textures,
@@ -92,6 +168,7 @@ pub(crate) struct GlobalIlluminationPipeline {
     last_screen_size: winit::dpi::PhysicalSize<u32>,
     cascade_count: usize,
     probe_count: [u32; 2],
+    tonemap: Tonemap,
 }
 
 //
@@ -104,6 +181,8 @@ struct Pipelines {
     light_mip: wgpu::ComputePipeline,
     // actual radiance cascade computation
     cascade: wgpu::RenderPipeline,
+    // sky gradient drawn behind everything for `Background::EnvironmentGradient`
+    background: wgpu::RenderPipeline,
 }
 
 pub(super) struct BindGroupLayouts {
@@ -140,6 +219,7 @@ pub(super) struct Textures {
 
 struct Buffers {
     frame_params: wgpu::Buffer,
+    spot_lights: wgpu::Buffer,
     cascade_params: wgpu::Buffer,
     render_params: wgpu::Buffer,
 }
@@ -201,7 +281,7 @@ impl CascadeParams {
             level,
             level_count: cascade_count,
             probe_count: probe_count_c0.map(|c| c / level_exp2),
-            mip_bias: config.mip_bias,
+            mip_bias: config.effective_mip_bias(level),
             rays_per_probe: level_exp4,
             linear_spacing: spacing_c0 * level_exp2 as f32,
             // each range is 4 times larger than the previous,
@@ -225,6 +305,28 @@ struct RenderParams {
     // this is actually a bool
     // but that doesn't work with AsBytes/FromBytes
     skip_raymarch: u32,
+    tonemap: TonemapParams,
+}
+
+/// Gpu-side representation of a [`Tonemap`].
+#[repr(C)]
+#[derive(Clone, Copy, Debug, AsBytes, FromBytes)]
+struct TonemapParams {
+    mode: u32,
+    exposure: f32,
+}
+
+impl From<Tonemap> for TonemapParams {
+    fn from(tonemap: Tonemap) -> Self {
+        Self {
+            mode: match tonemap.mode {
+                TonemapMode::None => 0,
+                TonemapMode::Reinhard => 1,
+                TonemapMode::Aces => 2,
+            },
+            exposure: tonemap.exposure,
+        }
+    }
 }
 
 impl GlobalIlluminationPipeline {
@@ -234,7 +336,8 @@ impl GlobalIlluminationPipeline {
         let bind_group_layouts = Self::create_bind_group_layouts();
 
         let window_size = crate::Renderer::window().inner_size();
-        let resizables = Self::create_resizables(window_size.into(), quality_conf);
+        let tonemap = Tonemap::default();
+        let resizables = Self::create_resizables(window_size.into(), quality_conf, tonemap);
         let cascade_count = resizables.cascade_params.len();
 
         let bilinear_samp = device.create_sampler(&wgpu::SamplerDescriptor {
@@ -255,6 +358,12 @@ impl GlobalIlluminationPipeline {
                 usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
                 mapped_at_creation: false,
             }),
+            spot_lights: device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some("spot lights"),
+                size: size_of::<SpotLightData>() as u64,
+                usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            }),
             cascade_params: device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
                 label: Some("cascade compute params"),
                 contents: resizables.cascade_params.as_bytes(),
@@ -283,6 +392,7 @@ impl GlobalIlluminationPipeline {
         Self {
             quality_conf,
             env_map,
+            spot_lights: td::Arena::new(),
             pipelines,
             textures: resizables.textures,
             bind_group_layouts,
@@ -293,6 +403,7 @@ impl GlobalIlluminationPipeline {
             last_screen_size: window_size,
             cascade_count,
             probe_count: resizables.cascade_params[0].probe_count,
+            tonemap,
         }
     }
 
@@ -355,7 +466,50 @@ impl GlobalIlluminationPipeline {
             multiview: None,
         });
 
-        Pipelines { light_mip, cascade }
+        let bg_shader =
+            device.create_shader_module(wgpu::include_wgsl!("./shaders/background.wgsl"));
+
+        let bg_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("background"),
+            bind_group_layouts: &[&bg_layouts.render],
+            push_constant_ranges: &[],
+        });
+
+        let background = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("background"),
+            layout: Some(&bg_layout),
+            vertex: wgpu::VertexState {
+                module: &bg_shader,
+                entry_point: "vs_main",
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+                buffers: &[],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &bg_shader,
+                entry_point: "fs_main",
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: super::renderer::SWAPCHAIN_FORMAT,
+                    blend: None,
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+            }),
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleList,
+                front_face: wgpu::FrontFace::Ccw,
+                cull_mode: None,
+                ..Default::default()
+            },
+            depth_stencil: None,
+            multisample: crate::Renderer::default_multisample_state(),
+            multiview: None,
+        });
+
+        Pipelines {
+            light_mip,
+            cascade,
+            background,
+        }
     }
 
     fn create_bind_group_layouts() -> BindGroupLayouts {
@@ -410,6 +564,7 @@ impl GlobalIlluminationPipeline {
                 float_tex(0, S::FRAGMENT, true, D::D2Array),
                 float_tex(1, S::FRAGMENT, true, D::D1),
                 uniform_buf(2, size_of::<FrameParams>(), false, S::FRAGMENT),
+                uniform_buf(3, size_of::<SpotLightData>(), false, S::FRAGMENT),
             ],
         });
 
@@ -491,6 +646,10 @@ impl GlobalIlluminationPipeline {
                     binding: 2,
                     resource: buffers.frame_params.as_entire_binding(),
                 },
+                wgpu::BindGroupEntry {
+                    binding: 3,
+                    resource: buffers.spot_lights.as_entire_binding(),
+                },
             ],
         });
 
@@ -575,7 +734,11 @@ impl GlobalIlluminationPipeline {
         }
     }
 
-    fn create_resizables(target_size: (u32, u32), config: LightingQualityConfig) -> ResizeResults {
+    fn create_resizables(
+        target_size: (u32, u32),
+        config: LightingQualityConfig,
+        tonemap: Tonemap,
+    ) -> ResizeResults {
         let device = crate::Renderer::device();
 
         let light_tex_size = wgpu::Extent3d {
@@ -667,8 +830,9 @@ impl GlobalIlluminationPipeline {
             probe_spacing: config.probe_interval,
             probe_range: range_c0,
             probe_count,
-            mip_bias: config.mip_bias,
+            mip_bias: config.effective_mip_bias(0),
             skip_raymarch: config.skip_final_cascade as u32,
+            tonemap: tonemap.into(),
         };
 
         ResizeResults {
@@ -690,7 +854,7 @@ impl GlobalIlluminationPipeline {
         let device = crate::Renderer::device();
         let queue = crate::Renderer::queue();
 
-        let res = Self::create_resizables(new_size.into(), self.quality_conf);
+        let res = Self::create_resizables(new_size.into(), self.quality_conf, self.tonemap);
         // make room in the params buffer if we need more cascades than before
         if res.cascade_params.len() > self.cascade_count {
             self.buffers.cascade_params =
@@ -729,10 +893,47 @@ impl GlobalIlluminationPipeline {
         }
     }
 
+    pub fn tonemap(&self) -> Tonemap {
+        self.tonemap
+    }
+
+    pub fn set_tonemap(&mut self, tonemap: Tonemap) {
+        self.tonemap = tonemap;
+        let params = TonemapParams::from(tonemap);
+        crate::Renderer::queue().write_buffer(
+            &self.buffers.render_params,
+            std::mem::offset_of!(RenderParams, tonemap) as u64,
+            params.as_bytes(),
+        );
+    }
+
+    /// Fill the pass with a vertical gradient of the environment map's sky colors.
+    pub fn draw_background<'pass>(&'pass self, pass: &mut wgpu::RenderPass<'pass>) {
+        pass.set_pipeline(&self.pipelines.background);
+        pass.set_bind_group(0, &self.bind_groups.render, &[]);
+        pass.draw(0..3, 0..1);
+    }
+
+    pub fn add_spot_light(&mut self, light: SpotLight) -> SpotLightKey {
+        SpotLightKey(self.spot_lights.insert(light))
+    }
+
+    pub fn remove_spot_light(&mut self, key: SpotLightKey) -> Option<SpotLight> {
+        self.spot_lights.remove(key.0)
+    }
+
+    pub fn get_spot_light_mut(&mut self, key: SpotLightKey) -> Option<&mut SpotLight> {
+        self.spot_lights.get_mut(key.0)
+    }
+
     pub fn compute_light_mips<'pass>(
         &'pass self,
         pass: &mut wp::OwningScope<'_, wgpu::ComputePass<'pass>>,
     ) {
+        if self.quality_conf.skip_light_mips {
+            return;
+        }
+
         let device = crate::Renderer::device();
         let mut pass = pass.scope("light mip chain", device);
 
@@ -751,20 +952,54 @@ impl GlobalIlluminationPipeline {
         }
     }
 
+    /// Compute global illumination for the view of the given camera,
+    /// returning the number of spot lights that were in view and included in it.
+    ///
+    /// `spot_light_intensity` gives a multiplier for each spot light's color.
     pub fn compute_gi(
         &self,
         scope: &mut wp::Scope<'_, wgpu::CommandEncoder>,
         camera: &crate::Camera,
-    ) {
+        spot_light_intensity: impl Fn(SpotLightKey) -> f32,
+    ) -> usize {
         let device = crate::Renderer::device();
         let queue = crate::Renderer::queue();
 
         let mut scope = scope.scope("radiance cascades", device);
 
+        if self.quality_conf.skip_upper_cascades {
+            // the final cascade reads from the texture cascade 1 would write to,
+            // clear it so nothing is merged from there
+            let _pass = scope.scoped_render_pass(
+                "clear cascades",
+                device,
+                wgpu::RenderPassDescriptor {
+                    color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                        view: &self.textures.cascades[0],
+                        resolve_target: None,
+                        ops: wgpu::Operations {
+                            load: wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT),
+                            store: wgpu::StoreOp::Store,
+                        },
+                    })],
+                    depth_stencil_attachment: None,
+                    ..Default::default()
+                },
+            );
+            return 0;
+        }
+
         let frame_params = FrameParams {
             pixel_size_world: 1. / camera.pixels_per_world_unit(self.light_tex_size),
         };
         queue.write_buffer(&self.buffers.frame_params, 0, frame_params.as_bytes());
+        let spot_lights = SpotLightData::new(
+            &self.spot_lights,
+            camera,
+            self.light_tex_size,
+            spot_light_intensity,
+        );
+        queue.write_buffer(&self.buffers.spot_lights, 0, spot_lights.as_bytes());
 
         // cascades starting with the last
         for casc_idx in (1..self.cascade_count).rev() {
@@ -794,5 +1029,7 @@ impl GlobalIlluminationPipeline {
             );
             pass.draw(0..3, 0..1);
         }
+
+        spot_lights.count()
     }
 }
@@ -2,14 +2,17 @@ use std::{f32::consts::TAU, mem::size_of};
 use zerocopy::{AsBytes, FromBytes};
 
 use crate::{
-    graphics::util::{GpuVec2, GpuVec4},
+    graphics::{
+        animation::interpolation::lerp,
+        util::{GpuVec2, GpuVec4},
+    },
     math::uv,
 };
 
 /// Width of the environment map texture.
 const MAP_RESOLUTION: u32 = 256;
 
-/// Maximum number of directional lights.
+/// Maximum number of directional lights uploaded for direct use in shaders.
 ///
 /// Usually there will be only one or two,
 /// but allocate space for a handful
@@ -17,12 +20,16 @@ const MAP_RESOLUTION: u32 = 256;
 ///
 /// (These are only used directly in the far-field shader
 /// that doesn't use the GI lighting;
-/// more lights can technically exist in GI)
+/// any number of lights can exist in GI
+/// since they're all baked into the environment map texture)
 const MAX_LIGHTS: usize = 10;
 
 /// Parameters for additional lighting from off-screen sources
 /// using a very simple gradient sky model.
 /// Apply with [Renderer::set_environment_map][crate::Renderer::set_environment_map].
+///
+/// Colors are in linear RGB.
+/// See the [`color`][crate::graphics::color] module for converting from sRGB.
 #[derive(Clone, Debug, PartialEq, Default)]
 pub struct EnvironmentMap {
     /// A constant ambient light applied everywhere in the scene.
@@ -35,6 +42,9 @@ pub struct EnvironmentMap {
     /// Color of the ground.
     pub ground: [f32; 3],
     /// Directional light sources (sun or moon, usually).
+    ///
+    /// Any number of lights can be used and their contributions are summed,
+    /// e.g. a warm sun together with a dimmer, cool fill light from the sky.
     pub lights: Vec<DirectionalLight>,
 }
 
@@ -80,6 +90,155 @@ impl EnvironmentMap {
             }],
         }
     }
+
+    /// Linearly interpolate between two environment maps.
+    ///
+    /// Lights are interpolated pairwise in order.
+    /// If one map has more lights than the other,
+    /// the extra lights fade in or out from a zero-color light in the same direction.
+    pub fn lerp(&self, other: &Self, t: f32) -> Self {
+        let lerp_color = |start: [f32; 3], end: [f32; 3]| -> [f32; 3] {
+            std::array::from_fn(|i| lerp(start[i], end[i], t))
+        };
+        Self {
+            ambient: lerp_color(self.ambient, other.ambient),
+            horizon: lerp_color(self.horizon, other.horizon),
+            zenith: lerp_color(self.zenith, other.zenith),
+            ground: lerp_color(self.ground, other.ground),
+            lights: (0..self.lights.len().max(other.lights.len()))
+                .map(|i| {
+                    let (s, e) = match (self.lights.get(i), other.lights.get(i)) {
+                        (Some(&s), Some(&e)) => (s, e),
+                        (Some(&s), None) => (s, s.with_zero_color()),
+                        (None, Some(&e)) => (e.with_zero_color(), e),
+                        (None, None) => unreachable!(),
+                    };
+                    DirectionalLight {
+                        color: lerp_color(s.color, e.color),
+                        direction: lerp(s.direction, e.direction, t),
+                    }
+                })
+                .collect(),
+        }
+    }
+}
+
+/// What an [`EnvironmentMapAnimator`] does after reaching its last keyframe.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum EnvironmentMapLoop {
+    /// Stop at the last keyframe.
+    Once,
+    /// Continue from the last keyframe back to the first,
+    /// e.g. for a day-night cycle.
+    #[default]
+    Repeat,
+    /// Play the keyframes backwards after reaching the last one,
+    /// then forwards again, and so on.
+    PingPong,
+}
+
+/// Smoothly animates through a sequence of environment maps over time.
+///
+/// Keyframes are spaced evenly over the duration of the animation
+/// and blended with a smoothstep curve so that
+/// each keyframe is held briefly instead of passing through it at a constant rate.
+/// Advance with [`advance`][Self::advance] once per frame and pass
+/// [`current`][Self::current] to [`Renderer::set_environment_map`][crate::Renderer::set_environment_map].
+#[derive(Clone, Debug)]
+pub struct EnvironmentMapAnimator {
+    keyframes: Vec<EnvironmentMap>,
+    duration: f32,
+    loop_mode: EnvironmentMapLoop,
+    time: f32,
+}
+
+impl EnvironmentMapAnimator {
+    /// Create an animator going through the given keyframes over `duration` seconds.
+    ///
+    /// With [`EnvironmentMapLoop::Repeat`] the duration includes
+    /// the transition from the last keyframe back to the first.
+    ///
+    /// Panics if there are no keyframes.
+    pub fn new(keyframes: Vec<EnvironmentMap>, duration: f32) -> Self {
+        assert!(
+            !keyframes.is_empty(),
+            "Environment map animation needs at least one keyframe"
+        );
+        Self {
+            keyframes,
+            duration,
+            loop_mode: EnvironmentMapLoop::default(),
+            time: 0.,
+        }
+    }
+
+    /// Set the behavior after reaching the last keyframe. Default: [`EnvironmentMapLoop::Repeat`].
+    #[inline]
+    pub fn with_loop_mode(mut self, loop_mode: EnvironmentMapLoop) -> Self {
+        self.loop_mode = loop_mode;
+        self
+    }
+
+    /// Move the animation forward by `dt` seconds.
+    pub fn advance(&mut self, dt: f32) {
+        self.time += dt;
+        if self.loop_mode == EnvironmentMapLoop::Once {
+            self.time = self.time.min(self.duration);
+        } else if self.duration > 0. {
+            // ping-pong takes twice the duration to return to the start
+            let period = match self.loop_mode {
+                EnvironmentMapLoop::PingPong => 2. * self.duration,
+                _ => self.duration,
+            };
+            self.time = self.time.rem_euclid(period);
+        }
+    }
+
+    /// Time elapsed in the current loop of the animation, in seconds.
+    #[inline]
+    pub fn time(&self) -> f32 {
+        self.time
+    }
+
+    /// Jump to a point in the animation, e.g. to set the time of day.
+    #[inline]
+    pub fn set_time(&mut self, time: f32) {
+        self.time = 0.;
+        self.advance(time);
+    }
+
+    /// Check if a non-looping animation has reached its last keyframe.
+    #[inline]
+    pub fn is_finished(&self) -> bool {
+        self.loop_mode == EnvironmentMapLoop::Once && self.time >= self.duration
+    }
+
+    /// Get the environment map at the current point in the animation.
+    pub fn current(&self) -> EnvironmentMap {
+        let key_count = self.keyframes.len();
+        if key_count == 1 || self.duration <= 0. {
+            return self.keyframes[0].clone();
+        }
+
+        let progress = match self.loop_mode {
+            EnvironmentMapLoop::PingPong if self.time > self.duration => {
+                2. - self.time / self.duration
+            }
+            _ => self.time / self.duration,
+        };
+        let segment_count = match self.loop_mode {
+            EnvironmentMapLoop::Repeat => key_count,
+            _ => key_count - 1,
+        };
+        let segment_pos = progress.clamp(0., 1.) * segment_count as f32;
+        let segment = (segment_pos as usize).min(segment_count - 1);
+        let t = segment_pos - segment as f32;
+        let t = t * t * (3. - 2. * t);
+
+        let start = &self.keyframes[segment];
+        let end = &self.keyframes[(segment + 1) % key_count];
+        start.lerp(end, t)
+    }
 }
 
 /// Map definining additional lighting from off-screen sources.
@@ -103,12 +262,21 @@ pub struct EnvironmentMapData {
 /// This emulates a distant, powerful point light source like the sun.
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub struct DirectionalLight {
-    /// Color of the light. Default: white.
+    /// Color of the light in linear RGB. Default: white.
     pub color: [f32; 3],
     /// Direction in which the light rays travel. Default: negative y-axis.
     pub direction: uv::Vec2,
 }
 
+impl DirectionalLight {
+    fn with_zero_color(self) -> Self {
+        Self {
+            color: [0.; 3],
+            ..self
+        }
+    }
+}
+
 impl Default for DirectionalLight {
     fn default() -> Self {
         Self {
@@ -139,6 +307,10 @@ impl From<DirectionalLight> for GpuDirectionalLight {
 #[derive(Clone, Copy, Debug, AsBytes, FromBytes)]
 pub(super) struct RenderData {
     ambient_color: GpuVec4,
+    // sky colors for drawing the background
+    zenith_color: GpuVec4,
+    horizon_color: GpuVec4,
+    ground_color: GpuVec4,
     light_count: u32,
     lights: [GpuDirectionalLight; MAX_LIGHTS],
 }
@@ -192,7 +364,10 @@ impl EnvironmentMapData {
 
         let render_data = RenderData {
             ambient_color: params.ambient.into(),
-            light_count: params.lights.len() as u32,
+            zenith_color: params.zenith.into(),
+            horizon_color: params.horizon.into(),
+            ground_color: params.ground.into(),
+            light_count: params.lights.len().min(MAX_LIGHTS) as u32,
             lights: std::array::from_fn(|i| {
                 if i < params.lights.len() {
                     params.lights[i].into()
@@ -265,3 +440,64 @@ impl EnvironmentMapData {
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn solid(ambient: f32) -> EnvironmentMap {
+        EnvironmentMap {
+            ambient: [ambient; 3],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn lerp_fades_unmatched_lights() {
+        let sun = DirectionalLight {
+            color: [1., 0.8, 0.6],
+            direction: uv::Vec2::new(0.6, -0.8),
+        };
+        let one_light = EnvironmentMap {
+            lights: vec![sun],
+            ..Default::default()
+        };
+        let no_lights = EnvironmentMap::default();
+
+        let fade_out = one_light.lerp(&no_lights, 0.5);
+        assert_eq!(fade_out.lights.len(), 1);
+        assert_eq!(fade_out.lights[0].color, [0.5, 0.4, 0.3]);
+        assert_eq!(fade_out.lights[0].direction, sun.direction);
+
+        let fade_in = no_lights.lerp(&one_light, 0.25);
+        assert_eq!(fade_in.lights.len(), 1);
+        assert_eq!(fade_in.lights[0].color, [0.25, 0.2, 0.15]);
+    }
+
+    #[test]
+    fn animator_passes_through_keyframes() {
+        let keyframes = vec![solid(0.), solid(1.), solid(2.)];
+
+        // repeat mode spends a third of the time going from the last keyframe back to the first
+        let mut anim = EnvironmentMapAnimator::new(keyframes.clone(), 3.);
+        for (time, ambient) in [(0., 0.), (1., 1.), (2., 2.), (2.5, 1.), (3., 0.), (4., 1.)] {
+            anim.set_time(time);
+            assert!((anim.current().ambient[0] - ambient).abs() < 1e-5);
+        }
+
+        let mut anim = EnvironmentMapAnimator::new(keyframes.clone(), 2.)
+            .with_loop_mode(EnvironmentMapLoop::PingPong);
+        for (time, ambient) in [(1., 1.), (2., 2.), (3., 1.), (4., 0.), (5., 1.)] {
+            anim.set_time(time);
+            assert!((anim.current().ambient[0] - ambient).abs() < 1e-5);
+        }
+
+        let mut anim =
+            EnvironmentMapAnimator::new(keyframes, 2.).with_loop_mode(EnvironmentMapLoop::Once);
+        anim.advance(1.5);
+        assert!(!anim.is_finished());
+        anim.advance(1.5);
+        assert!(anim.is_finished());
+        assert_eq!(anim.current().ambient, [2.; 3]);
+    }
+}
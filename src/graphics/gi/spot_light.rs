@@ -0,0 +1,351 @@
+use thunderdome as td;
+use zerocopy::{AsBytes, FromBytes};
+
+use crate::{graphics::util::GpuVec4, math::uv};
+
+/// Maximum number of spot lights taken into account in lighting.
+///
+/// Like directional lights, these are stored in a fixed-size uniform buffer
+/// so we don't have to do dynamic buffer resizes.
+/// Lights beyond this limit are ignored.
+/// Lights outside the view don't count towards it.
+const MAX_SPOT_LIGHTS: usize = 16;
+
+/// Key type to look up a spot light added to the renderer
+/// with [Renderer::add_spot_light][crate::Renderer::add_spot_light].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct SpotLightKey(pub(super) td::Index);
+
+/// Positioned light source that shines in a cone, like a flashlight.
+///
+/// The light is emitted from a small disk at `position`
+/// and travels through the global illumination pass like light from emissive materials,
+/// so it's blocked and tinted by occluders in the scene.
+/// Add to the renderer with [Renderer::add_spot_light][crate::Renderer::add_spot_light].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SpotLight {
+    /// Position of the light in world space. Default: origin.
+    pub position: uv::Vec2,
+    /// Direction the light shines in. Default: negative y-axis.
+    pub direction: uv::Vec2,
+    /// Angle between the direction and the edge of the cone, in radians.
+    /// Default: 30 degrees.
+    pub cone_angle: f32,
+    /// Width of the soft edge of the cone, in radians.
+    /// Light fades out smoothly from `cone_angle - falloff` to `cone_angle`.
+    /// Default: 10 degrees.
+    pub falloff: f32,
+    /// Color of the light in linear RGB. Default: white.
+    pub color: [f32; 3],
+    /// Rate at which light fades with distance, per world unit,
+    /// on top of the natural falloff from spreading out.
+    /// Zero means no extra attenuation. Default: 0.
+    ///
+    /// This also limits how far the light reaches,
+    /// which lets it be skipped when it's far enough off screen.
+    /// Lights without attenuation are never skipped.
+    pub attenuation: f32,
+    /// Radius of the emitting disk in world units.
+    /// Larger lights are brighter and cast softer shadows. Default: 0.1.
+    pub radius: f32,
+}
+
+impl Default for SpotLight {
+    fn default() -> Self {
+        Self {
+            position: uv::Vec2::zero(),
+            direction: -uv::Vec2::unit_y(),
+            cone_angle: 30f32.to_radians(),
+            falloff: 10f32.to_radians(),
+            color: [1.0, 1.0, 1.0],
+            attenuation: 0.0,
+            radius: 0.1,
+        }
+    }
+}
+
+/// Shape of the brightness curve of a [`LightAnimator`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum LightWaveform {
+    /// Smooth random variation, like a torch or a candle.
+    #[default]
+    Flicker,
+    /// Smooth sine wave between full and dimmed brightness.
+    Pulse,
+    /// Switching between full and dimmed brightness,
+    /// spending half of each cycle at each, like a faulty neon sign.
+    Blink,
+}
+
+/// Animates the brightness of a [`SpotLight`] over time,
+/// e.g. to make torches flicker.
+///
+/// The light's color is multiplied by a value between `1 - amplitude` and 1
+/// following the chosen [`LightWaveform`].
+/// The light itself isn't modified, so its color can still be changed freely.
+/// Add to a [`GraphicsManager`][crate::GraphicsManager] with
+/// [`insert_light_animator`][crate::GraphicsManager::insert_light_animator];
+/// it's advanced in [`update_animations`][crate::GraphicsManager::update_animations]
+/// and applied when meshes are drawn with that manager.
+#[derive(Clone, Copy, Debug)]
+pub struct LightAnimator {
+    /// The light whose brightness is animated.
+    pub light: SpotLightKey,
+    pub waveform: LightWaveform,
+    /// How much the brightness dips, from 0 (not at all) to 1 (all the way to black).
+    /// Default: 0.3.
+    pub amplitude: f32,
+    /// Cycles per second for pulse and blink,
+    /// or random changes per second for flicker. Default: 1.
+    pub frequency: f32,
+    /// Whether the animation is applied.
+    /// A disabled animator is paused and leaves the light at full brightness.
+    /// Default: true.
+    pub enabled: bool,
+    // time multiplied by frequency, wrapped to keep it in the precise range of f32
+    phase: f32,
+    // lights animated with the same parameters shouldn't flicker in sync
+    seed: u32,
+}
+
+impl LightAnimator {
+    /// Create an animator for the given light.
+    pub fn new(light: SpotLightKey, waveform: LightWaveform) -> Self {
+        Self {
+            light,
+            waveform,
+            amplitude: 0.3,
+            frequency: 1.,
+            enabled: true,
+            phase: 0.,
+            seed: light.0.slot(),
+        }
+    }
+
+    /// Set how much the brightness dips. Default: 0.3.
+    #[inline]
+    pub fn with_amplitude(mut self, amplitude: f32) -> Self {
+        self.amplitude = amplitude;
+        self
+    }
+
+    /// Set the speed of the animation. Default: 1.
+    #[inline]
+    pub fn with_frequency(mut self, frequency: f32) -> Self {
+        self.frequency = frequency;
+        self
+    }
+
+    /// Get the factor the light's color is currently multiplied by.
+    pub fn intensity(&self) -> f32 {
+        if !self.enabled {
+            return 1.;
+        }
+        let dip = match self.waveform {
+            LightWaveform::Flicker => value_noise(self.phase, self.seed),
+            LightWaveform::Pulse => 0.5 - 0.5 * (std::f32::consts::TAU * self.phase).cos(),
+            LightWaveform::Blink => {
+                if self.phase.fract() < 0.5 {
+                    0.
+                } else {
+                    1.
+                }
+            }
+        };
+        1. - self.amplitude.clamp(0., 1.) * dip
+    }
+
+    /// Step this animator forward `dt` seconds.
+    pub(crate) fn step_time(&mut self, dt: f32) {
+        if !self.enabled {
+            return;
+        }
+        // wrapping at a whole number keeps pulse and blink continuous
+        self.phase = (self.phase + dt * self.frequency).rem_euclid(65536.);
+    }
+}
+
+/// Smoothly interpolated random values between 0 and 1 at integer points.
+fn value_noise(x: f32, seed: u32) -> f32 {
+    let i = x.floor();
+    let t = x - i;
+    let t = t * t * (3. - 2. * t);
+    let a = hash_to_unit(i as u32, seed);
+    let b = hash_to_unit(i as u32 + 1, seed);
+    a + (b - a) * t
+}
+
+fn hash_to_unit(n: u32, seed: u32) -> f32 {
+    // murmur3 finalizer
+    let mut h = n ^ seed.wrapping_mul(0x9e3779b9);
+    h ^= h >> 16;
+    h = h.wrapping_mul(0x85ebca6b);
+    h ^= h >> 13;
+    h = h.wrapping_mul(0xc2b2ae35);
+    h ^= h >> 16;
+    h as f32 / u32::MAX as f32
+}
+
+/// Gpu-side representation of a spot light,
+/// transformed into the pixel space of the light texture.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, AsBytes, FromBytes)]
+struct GpuSpotLight {
+    position: [f32; 2],
+    direction: [f32; 2],
+    color: GpuVec4,
+    radius: f32,
+    // cosines of the cone angles to compare against dot products in the shader
+    cos_outer: f32,
+    cos_inner: f32,
+    attenuation: f32,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, AsBytes, FromBytes)]
+pub(super) struct SpotLightData {
+    count: u32,
+    _pad: [u32; 3],
+    lights: [GpuSpotLight; MAX_SPOT_LIGHTS],
+}
+
+impl SpotLightData {
+    /// Gather the lights that can affect the view into GPU format.
+    ///
+    /// Positions and directions are given in the pixel space of the light texture
+    /// (y pointing down), computed from world space with `camera`.
+    ///
+    /// Lights are culled if their emitting disk plus the distance their light reaches
+    /// (see [`attenuation_reach`]) is entirely outside of the texture.
+    ///
+    /// Each light's color is multiplied by `intensity`,
+    /// which is used to apply [`LightAnimator`]s.
+    pub(super) fn new(
+        lights: &td::Arena<SpotLight>,
+        camera: &crate::Camera,
+        light_tex_size: (u32, u32),
+        intensity: impl Fn(SpotLightKey) -> f32,
+    ) -> Self {
+        let ppwu = camera.pixels_per_world_unit(light_tex_size);
+        let half_size = uv::Vec2::new(light_tex_size.0 as f32, light_tex_size.1 as f32) / 2.;
+        let cam_inv = camera.pose_as_2d().inversed();
+
+        let tex_size = 2. * half_size;
+
+        let mut gpu_lights = [GpuSpotLight::default(); MAX_SPOT_LIGHTS];
+        let mut count = 0;
+        let visible_lights = lights.iter().filter_map(|(idx, light)| {
+            let pos = cam_inv * light.position;
+            let pos = uv::Vec2::new(half_size.x + ppwu * pos.x, half_size.y - ppwu * pos.y);
+            let radius = light.radius * ppwu;
+            let reach = radius + attenuation_reach(light.attenuation) * ppwu;
+            in_view(pos, reach, tex_size).then_some((SpotLightKey(idx), light, pos, radius))
+        });
+        for (gpu_light, (key, light, pos, radius)) in gpu_lights.iter_mut().zip(visible_lights) {
+            let dir = cam_inv.rotation * light.direction.normalized();
+            let inner_angle = (light.cone_angle - light.falloff).max(0.);
+            *gpu_light = GpuSpotLight {
+                position: [pos.x, pos.y],
+                direction: [dir.x, -dir.y],
+                color: light.color.map(|c| c * intensity(key)).into(),
+                radius,
+                cos_outer: light.cone_angle.cos(),
+                // keep the inner cosine strictly larger
+                // so the smoothstep in the shader doesn't divide by zero
+                cos_inner: inner_angle.cos().max(light.cone_angle.cos() + 1e-4),
+                attenuation: light.attenuation,
+            };
+            count += 1;
+        }
+
+        Self {
+            count,
+            _pad: [0; 3],
+            lights: gpu_lights,
+        }
+    }
+
+    /// Number of lights that were in view and included in the data.
+    #[inline]
+    pub(super) fn count(&self) -> usize {
+        self.count as usize
+    }
+}
+
+/// Brightness below which a light no longer visibly affects anything.
+const VISIBLE_BRIGHTNESS: f32 = 1. / 256.;
+
+/// Distance in world units after which light with the given attenuation
+/// has faded below [`VISIBLE_BRIGHTNESS`], infinite if there is no attenuation.
+fn attenuation_reach(attenuation: f32) -> f32 {
+    if attenuation > 0. {
+        -VISIBLE_BRIGHTNESS.ln() / attenuation
+    } else {
+        f32::INFINITY
+    }
+}
+
+/// Whether a circle at `pos` with `radius`, in the pixel space of the light texture,
+/// overlaps the texture.
+fn in_view(pos: uv::Vec2, radius: f32, tex_size: uv::Vec2) -> bool {
+    pos.x + radius >= 0.
+        && pos.x - radius <= tex_size.x
+        && pos.y + radius >= 0.
+        && pos.y - radius <= tex_size.y
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn light_animator_stays_in_range() {
+        let mut lights = td::Arena::new();
+        let light = SpotLightKey(lights.insert(SpotLight::default()));
+
+        for waveform in [
+            LightWaveform::Flicker,
+            LightWaveform::Pulse,
+            LightWaveform::Blink,
+        ] {
+            let mut anim = LightAnimator::new(light, waveform)
+                .with_amplitude(0.4)
+                .with_frequency(3.);
+            let mut lowest = 1f32;
+            for _ in 0..600 {
+                anim.step_time(1. / 60.);
+                let intensity = anim.intensity();
+                assert!(
+                    (0.6 - 1e-5..=1.).contains(&intensity),
+                    "{waveform:?} went to {intensity}"
+                );
+                lowest = lowest.min(intensity);
+            }
+            assert!(lowest < 0.8, "{waveform:?} never dimmed, lowest {lowest}");
+
+            anim.enabled = false;
+            assert_eq!(anim.intensity(), 1.);
+        }
+    }
+
+    #[test]
+    fn light_culling_with_reach() {
+        let tex_size = uv::Vec2::new(100., 50.);
+        let disk = 2.;
+        // on screen and right at the edge
+        assert!(in_view(uv::Vec2::new(50., 25.), disk, tex_size));
+        assert!(in_view(uv::Vec2::new(101., 25.), disk, tex_size));
+        assert!(in_view(uv::Vec2::new(50., -1.5), disk, tex_size));
+
+        // off screen but with light reaching into view
+        let off_screen = uv::Vec2::new(-10., 25.);
+        assert!(!in_view(off_screen, disk, tex_size));
+        assert!(in_view(off_screen, disk + attenuation_reach(0.5), tex_size));
+        // strongly attenuated light doesn't reach that far
+        assert!(!in_view(off_screen, disk + attenuation_reach(1.), tex_size));
+
+        // no attenuation reaches everywhere
+        let far_away = uv::Vec2::new(1e6, -1e6);
+        assert!(in_view(far_away, disk + attenuation_reach(0.), tex_size));
+    }
+}
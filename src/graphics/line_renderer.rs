@@ -1,8 +1,5 @@
 use crate::{
-    graphics::{
-        renderer::{DEFAULT_MULTISAMPLE_STATE, DEPTH_FORMAT},
-        util::DynamicBuffer,
-    },
+    graphics::{renderer::DEPTH_FORMAT, util::DynamicBuffer},
     math::uv,
     MaterialId,
 };
@@ -318,7 +315,7 @@ impl LineRenderer {
                     stencil: wgpu::StencilState::default(),
                     bias: wgpu::DepthBiasState::default(),
                 }),
-                multisample: DEFAULT_MULTISAMPLE_STATE,
+                multisample: crate::Renderer::default_multisample_state(),
                 multiview: None,
             })
         };
@@ -1,10 +1,12 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use thunderdome as td;
 
 use super::{
     animation::{animator::Animator, gltf_animation::GltfAnimation},
-    material::{Material, MaterialParams},
-    mesh::{Mesh, MeshParams},
+    atlas::{AtlasError, TextureAtlas, UvRect},
+    gi::spot_light::{LightAnimator, SpotLightKey},
+    material::{Material, MaterialParams, TextureData},
+    mesh::{ConvexMeshShape, Mesh, MeshData, MeshParams},
     scene::{Node, Scene},
     Skin,
 };
@@ -43,6 +45,10 @@ pub struct AnimationId(td::Index);
 #[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
 pub struct AnimatorId(td::Index);
 
+/// Identifier for a [`LightAnimator`] stored in a [`GraphicsManager`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct LightAnimatorId(td::Index);
+
 //
 // manager itself
 //
@@ -65,6 +71,18 @@ pub struct AnimatorId(td::Index);
 /// Not all features are available this way;
 /// notably, skins and animations cannot currently be created outside of glTF.
 ///
+/// # Unloading assets
+///
+/// Meshes and materials can be removed individually with
+/// [`unload_mesh`][Self::unload_mesh] and [`unload_material`][Self::unload_material],
+/// freeing their GPU resources.
+/// To unload everything no longer in use, e.g. when switching levels,
+/// mark shared assets with [`protect_all`][Self::protect_all] (or individually)
+/// and call [`collect_unused`][Self::collect_unused].
+/// Ids of unloaded assets remain safe to use;
+/// entities with an unloaded mesh aren't drawn,
+/// and meshes with an unloaded material are drawn with the default material.
+///
 /// # Animating meshes
 ///
 /// Animations are played by inserting [`Animator`]s into the [`GraphicsManager`]
@@ -104,9 +122,14 @@ pub struct GraphicsManager {
     /// map from animations to target skins
     anim_target_map: td::Arena<td::Index>,
     animators: td::Arena<Animator>,
+    light_animators: td::Arena<LightAnimator>,
 
     materials: td::Arena<Material>,
     material_name_map: HashMap<String, td::Index>,
+
+    /// assets that aren't removed by `collect_unused`
+    protected_meshes: HashSet<td::Index>,
+    protected_materials: HashSet<td::Index>,
 }
 
 /// Error when loading assets from a glTF document.
@@ -121,6 +144,129 @@ pub enum LoadError {
     GltfError(#[from] gltf::Error),
     #[error("Document doesn't have a scene in it")]
     NoScene,
+    #[cfg(target_arch = "wasm32")]
+    #[error("Failed to fetch the document: {0}")]
+    FetchError(String),
+}
+
+/// The output of decoding a glTF document, before any of it is uploaded to the GPU.
+#[cfg(feature = "gltf")]
+type ImportedGltf = (
+    gltf::Document,
+    Vec<gltf::buffer::Data>,
+    Vec<gltf::image::Data>,
+);
+
+/// A decoded glTF document along with the prefix to name its assets with.
+#[cfg(feature = "gltf")]
+type DecodeResult = Result<(String, ImportedGltf), LoadError>;
+
+/// A glTF document being loaded in the background,
+/// created with [`GraphicsManager::load_gltf_async`].
+#[cfg(feature = "gltf")]
+pub struct GltfLoadHandle {
+    // None after the result has been taken out with `poll`
+    #[cfg(not(target_arch = "wasm32"))]
+    thread: Option<std::thread::JoinHandle<DecodeResult>>,
+    // set by the fetch task when it's done,
+    // None before that and after the result has been taken out with `poll`
+    #[cfg(target_arch = "wasm32")]
+    result: std::rc::Rc<std::cell::RefCell<Option<DecodeResult>>>,
+}
+
+#[cfg(feature = "gltf")]
+impl GltfLoadHandle {
+    /// Check if the document has been decoded and is ready to be finished with
+    /// [`poll`][Self::poll].
+    ///
+    /// This is also true if loading failed, in which case `poll` returns the error.
+    #[inline]
+    pub fn is_ready(&self) -> bool {
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            self.thread
+                .as_ref()
+                .is_some_and(|thread| thread.is_finished())
+        }
+        #[cfg(target_arch = "wasm32")]
+        {
+            self.result.borrow().is_some()
+        }
+    }
+
+    /// If the document is ready, upload its contents to the GPU
+    /// and return the loaded scene, like [`GraphicsManager::load_gltf`] would.
+    ///
+    /// Returns None if the document is still being decoded,
+    /// or if the result has already been returned by an earlier call.
+    pub fn poll(&mut self, manager: &mut GraphicsManager) -> Option<Result<Scene, LoadError>> {
+        let decoded = self.take_decoded()?;
+        Some(decoded.and_then(|(prefix, imported)| manager.load_gltf_imported(&prefix, imported)))
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn take_decoded(&mut self) -> Option<DecodeResult> {
+        if !self.is_ready() {
+            return None;
+        }
+        let thread = self.thread.take()?;
+        match thread.join() {
+            Ok(decoded) => Some(decoded),
+            // the loading thread doesn't panic unless something is seriously wrong,
+            // in which case the panic should be visible on the main thread too
+            Err(panic) => std::panic::resume_unwind(panic),
+        }
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    fn take_decoded(&mut self) -> Option<DecodeResult> {
+        self.result.borrow_mut().take()
+    }
+}
+
+/// Get the file name of a glTF document without the extension,
+/// used to prefix the names of its assets.
+#[cfg(feature = "gltf")]
+fn gltf_file_stem(path: &std::path::Path) -> Result<&str, LoadError> {
+    path.file_stem()
+        .and_then(|stem| stem.to_str())
+        .ok_or(LoadError::InvalidFileName)
+}
+
+/// Download a glTF document relative to the current page and decode it.
+#[cfg(all(feature = "gltf", target_arch = "wasm32"))]
+async fn fetch_and_decode_gltf(path: &std::path::Path) -> DecodeResult {
+    let file_stem = gltf_file_stem(path)?.to_string();
+    let url = path.to_str().ok_or(LoadError::InvalidFileName)?;
+    let file_bytes = fetch_bytes(url).await?;
+    let imported = gltf::import_slice(file_bytes)?;
+    Ok((file_stem, imported))
+}
+
+/// Download a file relative to the current page.
+#[cfg(all(feature = "gltf", target_arch = "wasm32"))]
+async fn fetch_bytes(url: &str) -> Result<Vec<u8>, LoadError> {
+    use wasm_bindgen::JsCast;
+    use wasm_bindgen_futures::JsFuture;
+
+    let js_err = |err: wasm_bindgen::JsValue| LoadError::FetchError(format!("{err:?}"));
+    let window =
+        web_sys::window().ok_or_else(|| LoadError::FetchError("no window to fetch from".into()))?;
+    let response: web_sys::Response = JsFuture::from(window.fetch_with_str(url))
+        .await
+        .map_err(js_err)?
+        .dyn_into()
+        .map_err(js_err)?;
+    if !response.ok() {
+        return Err(LoadError::FetchError(format!(
+            "HTTP status {}",
+            response.status()
+        )));
+    }
+    let buffer = JsFuture::from(response.array_buffer().map_err(js_err)?)
+        .await
+        .map_err(js_err)?;
+    Ok(js_sys::Uint8Array::new(&buffer).to_vec())
 }
 
 impl GraphicsManager {
@@ -139,9 +285,13 @@ impl GraphicsManager {
             anim_name_map: HashMap::new(),
             anim_target_map: td::Arena::new(),
             animators: td::Arena::new(),
+            light_animators: td::Arena::new(),
 
             materials: td::Arena::new(),
             material_name_map: HashMap::new(),
+
+            protected_meshes: HashSet::new(),
+            protected_materials: HashSet::new(),
         }
     }
 
@@ -179,15 +329,56 @@ impl GraphicsManager {
     pub fn load_gltf(&mut self, path: impl AsRef<std::path::Path>) -> Result<Scene, LoadError> {
         let path = path.as_ref();
         let file_bytes = std::fs::read(path)?;
-
-        let file_stem = path
-            .file_stem()
-            .and_then(|stem| stem.to_str())
-            .ok_or(LoadError::InvalidFileName)?;
+        let file_stem = gltf_file_stem(path)?;
 
         self.load_gltf_bytes(file_stem, &file_bytes)
     }
 
+    /// Start loading a glTF document from a file in the background,
+    /// like [`load_gltf`][Self::load_gltf] but without stalling the game while doing so.
+    ///
+    /// Reading the file and decoding its contents (including images)
+    /// happens on a separate thread.
+    /// Once [`GltfLoadHandle::is_ready`] returns true, call [`GltfLoadHandle::poll`]
+    /// to upload the assets to the GPU and get the loaded scene.
+    /// In the meantime, you can e.g. show a loading screen
+    /// or draw the [placeholder mesh][Self::placeholder_mesh]
+    /// in place of the ones being loaded.
+    ///
+    /// On the web, where threads can't be spawned,
+    /// the path is instead fetched as a URL relative to the page
+    /// in an async task on the browser's event loop.
+    /// Decoding still happens on the main thread once the download finishes,
+    /// so large documents cause a short stall there.
+    #[cfg(feature = "gltf")]
+    pub fn load_gltf_async(&self, path: impl AsRef<std::path::Path>) -> GltfLoadHandle {
+        let path = path.as_ref().to_owned();
+
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            let thread = std::thread::spawn(move || {
+                let file_stem = gltf_file_stem(&path)?.to_string();
+                let file_bytes = std::fs::read(&path)?;
+                let imported = gltf::import_slice(file_bytes)?;
+                Ok((file_stem, imported))
+            });
+            GltfLoadHandle {
+                thread: Some(thread),
+            }
+        }
+
+        #[cfg(target_arch = "wasm32")]
+        {
+            let result = std::rc::Rc::new(std::cell::RefCell::new(None));
+            let task_result = result.clone();
+            wasm_bindgen_futures::spawn_local(async move {
+                let decoded = fetch_and_decode_gltf(&path).await;
+                *task_result.borrow_mut() = Some(decoded);
+            });
+            GltfLoadHandle { result }
+        }
+    }
+
     /// Load graphics from a glTF document which has already been read into your program.
     ///
     /// The given `prefix` parameter is prepended
@@ -195,11 +386,23 @@ impl GraphicsManager {
     /// See the "naming scheme" section of [`load_gltf`][Self::load_gltf] for details.
     #[cfg(feature = "gltf")]
     pub fn load_gltf_bytes(&mut self, prefix: &str, data: &[u8]) -> Result<Scene, LoadError> {
+        let imported = gltf::import_slice(data)?;
+        self.load_gltf_imported(prefix, imported)
+    }
+
+    /// Load graphics from a glTF document that has been read into memory and decoded
+    /// but not yet uploaded to the GPU.
+    /// This is the part of loading that needs to happen on the main thread.
+    #[cfg(feature = "gltf")]
+    fn load_gltf_imported(
+        &mut self,
+        prefix: &str,
+        (doc, bufs, images): ImportedGltf,
+    ) -> Result<Scene, LoadError> {
         // asset ids are names of the gltf node prefixed with file name
         // (the assumption being that names are unique, as is enforced by Blender)
         let name_to_id = |name: &str| format!("{prefix}.{name}");
 
-        let (doc, bufs, images) = gltf::import_slice(data)?;
         let bufs: Vec<&[u8]> = bufs.iter().map(|data| data.0.as_slice()).collect();
 
         // collect the global poses of nodes by traversing the hierarchy;
@@ -385,8 +588,145 @@ impl GraphicsManager {
         self.anim_name_map.clear();
         self.anim_target_map.clear();
         self.animators.clear();
+        self.light_animators.clear();
         self.materials.clear();
         self.material_name_map.clear();
+        self.protected_meshes.clear();
+        self.protected_materials.clear();
+    }
+
+    /// Remove a mesh and any skins created for it, freeing their GPU resources.
+    /// Animators targeting the mesh or any of its skins are removed as well.
+    ///
+    /// Returns the mesh if it still existed.
+    /// Entities that still have the mesh's id won't be drawn.
+    pub fn unload_mesh(&mut self, id: &MeshId) -> Option<Mesh> {
+        let mesh = self.meshes.remove(id.mesh)?;
+        self.mesh_name_map
+            .retain(|_, mesh_idx| *mesh_idx != id.mesh);
+        self.mesh_skin_map.remove(id.mesh);
+        self.mesh_material_map.remove(id.mesh);
+        self.protected_meshes.remove(&id.mesh);
+        let skin_mesh_map = &mut self.skin_mesh_map;
+        self.skins.retain(|skin_idx, _| {
+            if skin_mesh_map.get(skin_idx) == Some(&id.mesh) {
+                skin_mesh_map.remove(skin_idx);
+                false
+            } else {
+                true
+            }
+        });
+        let skins = &self.skins;
+        self.anim_target_map
+            .retain(|_, skin_idx| skins.contains(*skin_idx));
+        let anim_target_map = &self.anim_target_map;
+        self.animators.retain(|_, anim| match anim.target {
+            Some(target) => target.mesh != id.mesh,
+            None => anim_target_map.contains(anim.animation.0),
+        });
+        Some(mesh)
+    }
+
+    /// Remove a material, freeing its textures and other GPU resources.
+    ///
+    /// Returns the material if it still existed.
+    /// Meshes that were using it are drawn with the default material instead.
+    pub fn unload_material(&mut self, id: MaterialId) -> Option<Material> {
+        let material = self.materials.remove(id.0)?;
+        self.material_name_map.retain(|_, mat_idx| *mat_idx != id.0);
+        self.protected_materials.remove(&id.0);
+        Some(material)
+    }
+
+    /// Prevent a mesh from being unloaded by [`collect_unused`][Self::collect_unused].
+    #[inline]
+    pub fn protect_mesh(&mut self, id: &MeshId) {
+        self.protected_meshes.insert(id.mesh);
+    }
+
+    /// Prevent a material from being unloaded by [`collect_unused`][Self::collect_unused].
+    #[inline]
+    pub fn protect_material(&mut self, id: MaterialId) {
+        self.protected_materials.insert(id.0);
+    }
+
+    /// Protect every mesh and material currently loaded
+    /// from being unloaded by [`collect_unused`][Self::collect_unused].
+    ///
+    /// Useful to call after loading assets shared between all levels of a game.
+    pub fn protect_all(&mut self) {
+        self.protected_meshes
+            .extend(self.meshes.iter().map(|(idx, _)| idx));
+        self.protected_materials
+            .extend(self.materials.iter().map(|(idx, _)| idx));
+    }
+
+    /// Unload all meshes and materials that aren't in use or protected,
+    /// along with animation targets and animators that aren't in use.
+    ///
+    /// A mesh is in use if a [`MeshId`] referring to it exists in the given world,
+    /// and a material is in use if it's set as the material of a mesh that is kept.
+    /// See [`protect_mesh`][Self::protect_mesh], [`protect_material`][Self::protect_material]
+    /// and [`protect_all`][Self::protect_all] for keeping assets that aren't currently in use.
+    ///
+    /// Skins created with [`new_animation_target`][Self::new_animation_target]
+    /// and [`Animator`]s with a target set with [`Animator::with_target`]
+    /// are kept only if their target [`MeshId`] exists in the world.
+    /// Protecting a mesh doesn't protect its animation targets.
+    pub fn collect_unused(&mut self, world: &hecs::World) {
+        let mut live_meshes: HashSet<td::Index> = HashSet::new();
+        let mut live_skins: HashSet<td::Index> = HashSet::new();
+        for (_, id) in world.query::<&MeshId>().iter() {
+            live_meshes.insert(id.mesh);
+            live_skins.extend(id.skin);
+        }
+
+        let mut used_meshes = self.protected_meshes.clone();
+        used_meshes.extend(live_meshes.iter().copied());
+        let unused_meshes: Vec<td::Index> = self
+            .meshes
+            .iter()
+            .map(|(idx, _)| idx)
+            .filter(|idx| !used_meshes.contains(idx))
+            .collect();
+        for mesh in unused_meshes {
+            self.unload_mesh(&MeshId { mesh, skin: None });
+        }
+
+        let mut used_materials = self.protected_materials.clone();
+        used_materials.extend(self.mesh_material_map.iter().map(|(_, &mat_idx)| mat_idx));
+        let unused_materials: Vec<td::Index> = self
+            .materials
+            .iter()
+            .map(|(idx, _)| idx)
+            .filter(|idx| !used_materials.contains(idx))
+            .collect();
+        for material in unused_materials {
+            self.unload_material(MaterialId(material));
+        }
+
+        // skins loaded along with a mesh stay as long as the mesh does,
+        // extra ones created as animation targets only while something refers to them
+        let mut kept_skins = live_skins.clone();
+        kept_skins.extend(self.mesh_skin_map.iter().map(|(_, &skin_idx)| skin_idx));
+        kept_skins.extend(self.anim_target_map.iter().map(|(_, &skin_idx)| skin_idx));
+        let skin_mesh_map = &mut self.skin_mesh_map;
+        self.skins.retain(|skin_idx, _| {
+            if kept_skins.contains(&skin_idx) {
+                true
+            } else {
+                skin_mesh_map.remove(skin_idx);
+                false
+            }
+        });
+
+        self.animators.retain(|_, anim| match anim.target {
+            Some(target) => {
+                live_meshes.contains(&target.mesh)
+                    && target.skin.is_none_or(|skin| live_skins.contains(&skin))
+            }
+            None => true,
+        });
     }
 
     /// Add a mesh to the set of drawable assets.
@@ -412,6 +752,31 @@ impl GraphicsManager {
         }
     }
 
+    /// Get a mesh to draw in place of assets that are still loading,
+    /// e.g. with [`load_gltf_async`][Self::load_gltf_async].
+    ///
+    /// This is a magenta 1x1 square, created the first time it's needed
+    /// and protected from [`collect_unused`][Self::collect_unused].
+    pub fn placeholder_mesh(&mut self) -> MeshId {
+        const NAME: &str = "starframe.placeholder";
+        if let Some(id) = self.get_mesh_id(NAME) {
+            return id;
+        }
+        let id = self.create_mesh(MeshParams {
+            name: Some(NAME),
+            data: MeshData::from(ConvexMeshShape::Rect { w: 1., h: 1. }),
+            ..Default::default()
+        });
+        let material = self.create_material(MaterialParams {
+            base_color: Some([1., 0., 1., 1.]),
+            ..Default::default()
+        });
+        self.set_mesh_material(id, material);
+        self.protect_mesh(&id);
+        self.protect_material(material);
+        id
+    }
+
     /// Look up a mesh id by its name.
     ///
     /// See [`load_gltf`][Self::load_gltf] for naming of assets loaded from glTF.
@@ -453,6 +818,13 @@ impl GraphicsManager {
         self.meshes.get_mut(id.mesh)
     }
 
+    /// Get the local-space bounding box and bounding circle radius of a mesh,
+    /// if it still exists. See [`Mesh::bounds`] for details.
+    #[inline]
+    pub fn mesh_bounds(&self, id: &MeshId) -> Option<(crate::AABB, f64)> {
+        self.meshes.get(id.mesh).map(|mesh| mesh.bounds())
+    }
+
     /// Get the material associated with a mesh.
     ///
     /// If no material has been explicitly associated,
@@ -479,6 +851,26 @@ impl GraphicsManager {
         MaterialId(id)
     }
 
+    /// Pack several textures into one with [`TextureAtlas::pack`]
+    /// and create a material that uses it as its diffuse texture.
+    ///
+    /// Returns the material along with the area of the atlas
+    /// occupied by each of the given textures, in the same order.
+    /// Meshes drawing from different parts of the atlas can then share the material.
+    pub fn create_atlas(
+        &mut self,
+        name: Option<&str>,
+        textures: &[TextureData<'_>],
+    ) -> Result<(MaterialId, Vec<UvRect>), AtlasError> {
+        let atlas = TextureAtlas::pack(textures)?;
+        let material = self.create_material(MaterialParams {
+            name,
+            diffuse_tex: Some(atlas.texture_data()),
+            ..Default::default()
+        });
+        Ok((material, atlas.uv_rects().to_vec()))
+    }
+
     /// Look up a material id by its name.
     ///
     /// See [`load_gltf`][Self::load_gltf] for naming of assets loaded from glTF.
@@ -519,9 +911,51 @@ impl GraphicsManager {
         AnimatorId(self.animators.insert(anim))
     }
 
+    /// Remove an Animator, stopping the animation where it currently is.
+    ///
+    /// Returns the animator if it still existed.
+    #[inline]
+    pub fn remove_animator(&mut self, id: AnimatorId) -> Option<Animator> {
+        self.animators.remove(id.0)
+    }
+
+    /// Add a [`LightAnimator`] that varies the brightness of a spot light over time.
+    ///
+    /// Returns an id that can be used to modify or toggle the animation later.
+    #[inline]
+    pub fn insert_light_animator(&mut self, anim: LightAnimator) -> LightAnimatorId {
+        LightAnimatorId(self.light_animators.insert(anim))
+    }
+
+    /// Mutably access a light animator, e.g. to toggle it with its `enabled` flag.
+    #[inline]
+    pub fn get_light_animator_mut(&mut self, id: LightAnimatorId) -> Option<&mut LightAnimator> {
+        self.light_animators.get_mut(id.0)
+    }
+
+    /// Remove a light animator, returning the light to its full brightness.
+    #[inline]
+    pub fn remove_light_animator(&mut self, id: LightAnimatorId) -> Option<LightAnimator> {
+        self.light_animators.remove(id.0)
+    }
+
+    /// Get the factor a spot light's color is multiplied by
+    /// from all the light animators targeting it.
+    pub(crate) fn spot_light_intensity(&self, light: SpotLightKey) -> f32 {
+        self.light_animators
+            .iter()
+            .filter(|(_, anim)| anim.light == light)
+            .map(|(_, anim)| anim.intensity())
+            .product()
+    }
+
     /// Step all animations forward by `dt` seconds.
     /// Typically should be called once a frame.
     pub fn update_animations(&mut self, dt: f32) {
+        for (_, anim) in self.light_animators.iter_mut() {
+            anim.step_time(dt);
+        }
+
         for (_, animator) in self.animators.iter_mut() {
             let anim_id = animator.animation.0;
             let Some(animation) = self.animations.get(anim_id) else {
@@ -121,6 +121,7 @@ pub fn load_material<'doc>(
         attenuation,
         diffuse_tex,
         normal_tex,
+        transparent: material.alpha_mode() == gltf::material::AlphaMode::Blend,
     }
 }
 
@@ -101,6 +101,9 @@ impl MaterialResources {
 }
 
 /// Creation parameters for a material.
+///
+/// Colors are in linear RGB.
+/// See the [`color`][super::color] module for converting from sRGB.
 #[derive(Debug, Clone, Default)]
 pub struct MaterialParams<'a> {
     /// An optional name, used to look the material up
@@ -119,9 +122,18 @@ pub struct MaterialParams<'a> {
     pub diffuse_tex: Option<TextureData<'a>>,
     /// Texture data for the normal map.
     pub normal_tex: Option<TextureData<'a>>,
+    /// Whether the material is see-through.
+    ///
+    /// Transparent meshes are drawn after all opaque ones, from back to front,
+    /// and don't hide things behind them.
+    /// This is inferred automatically if `base_color` has an alpha value less than 1,
+    /// but must be set explicitly if only the diffuse texture has transparency.
+    pub transparent: bool,
 }
 
 /// Parameters controlling how a material absorbs light.
+///
+/// Like other material colors, `color` is in linear RGB.
 #[derive(Clone, Copy, Debug)]
 pub struct AttenuationParams {
     /// Color that white light will become
@@ -148,6 +160,7 @@ impl Default for AttenuationParams {
 /// A material determines the color and lighting properties of a mesh.
 pub struct Material {
     pub(crate) participates_in_lighting: bool,
+    pub(crate) is_transparent: bool,
     pub(crate) bind_group: wgpu::BindGroup,
     // textures and buffer stored to avoid dropping them
     _uniform_buf: wgpu::Buffer,
@@ -208,6 +221,8 @@ impl Material {
         Self {
             participates_in_lighting: params.emissive_color.is_some()
                 || params.attenuation.is_some(),
+            is_transparent: params.transparent
+                || params.base_color.is_some_and(|color| color[3] < 1.),
             bind_group,
             _uniform_buf: uniform_buf,
             _diffuse_tex: diffuse_tex,
@@ -224,6 +239,7 @@ impl Material {
                 attenuation: None,
                 diffuse_tex: None,
                 normal_tex: None,
+                transparent: false,
             })
         })
     }
@@ -4,6 +4,9 @@ pub use skin::Skin;
 mod mesh_renderer;
 pub use mesh_renderer::MeshRenderer;
 
+mod builder;
+pub use builder::MeshBuilder;
+
 //
 
 use crate::{
@@ -79,6 +82,8 @@ impl MeshParams<'_> {
             })
         });
 
+        let bounds = MeshBounds::from_vertices(&self.data.vertices);
+
         let gpu_data = GpuMeshData {
             vertex_buf,
             vertex_count: self.data.vertices.len() as u32,
@@ -89,20 +94,83 @@ impl MeshParams<'_> {
 
         Mesh {
             offset: self.offset,
+            bounds,
             gpu_data,
         }
     }
 }
 
+/// Color multiplier for a single mesh instance.
+///
+/// Add this as a component next to a [`MeshId`][crate::MeshId]
+/// to tint the mesh without creating a new material,
+/// e.g. to color many copies of the same mesh differently
+/// or to flash something white when it takes damage.
+/// The material's base color and emissive color are multiplied by this,
+/// so a material with a white base color takes on exactly the tint color.
+/// Like material colors, it's in linear RGB.
+///
+/// An alpha value less than 1 makes the mesh transparent.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Tint(pub [f32; 4]);
+
+impl Default for Tint {
+    fn default() -> Self {
+        Self([1.; 4])
+    }
+}
+
 /// Triangle mesh uploaded to the GPU and ready to be rendered.
 ///
 /// Public fields can be mutated and will have an effect on the next render.
 /// Vertex data only exists on the GPU at this point and is immutable.
 pub struct Mesh {
     pub offset: m::Pose,
+    bounds: MeshBounds,
     gpu_data: GpuMeshData,
 }
 
+/// Bounding volumes of a mesh's vertices in the xy plane,
+/// in the mesh's local space (not including its `offset`).
+#[derive(Clone, Copy, Debug)]
+struct MeshBounds {
+    aabb: phys::collision::AABB,
+    // radius of a circle centered at the center of the aabb
+    radius: f64,
+}
+
+impl MeshBounds {
+    fn from_vertices(vertices: &[Vertex]) -> Self {
+        let positions = || {
+            vertices
+                .iter()
+                .map(|v| m::DVec2::new(v.position.0[0] as f64, v.position.0[1] as f64))
+        };
+        let Some(first) = positions().next() else {
+            return Self {
+                aabb: phys::collision::AABB {
+                    min: m::DVec2::zero(),
+                    max: m::DVec2::zero(),
+                },
+                radius: 0.,
+            };
+        };
+        let aabb = positions().fold(
+            phys::collision::AABB {
+                min: first,
+                max: first,
+            },
+            |aabb, p| phys::collision::AABB {
+                min: aabb.min.min_by_component(p),
+                max: aabb.max.max_by_component(p),
+            },
+        );
+        let center = (aabb.min + aabb.max) / 2.;
+        let radius = positions().map(|p| (p - center).mag()).fold(0., f64::max);
+        Self { aabb, radius }
+    }
+}
+
 #[derive(Debug)]
 pub(crate) struct GpuMeshData {
     vertex_buf: wgpu::Buffer,
@@ -148,6 +216,18 @@ pub struct VertexJoints {
 }
 
 impl Mesh {
+    /// Get the bounding box of the mesh in its local space
+    /// (not including [`offset`][Self::offset])
+    /// and the radius of a bounding circle centered on the box.
+    ///
+    /// These are computed from the vertices when the mesh is created.
+    /// For skinned meshes they cover the mesh in its bind pose,
+    /// which animation may move parts of the mesh outside of.
+    #[inline]
+    pub fn bounds(&self) -> (phys::collision::AABB, f64) {
+        (self.bounds.aabb, self.bounds.radius)
+    }
+
     /// Replace the vertex data of this mesh.
     ///
     /// This is more efficient than creating an entirely new mesh.
@@ -156,6 +236,8 @@ impl Mesh {
     /// Note that this does not check if the number of vertices is the same as on initial upload.
     /// Fewer vertices will leave vertices past the end unchanged,
     /// and more vertices will panic.
+    ///
+    /// The mesh's [`bounds`][Self::bounds] are not updated.
     pub fn overwrite(&self, vertices: &[Vertex]) {
         let queue = crate::Renderer::queue();
         queue.write_buffer(&self.gpu_data.vertex_buf, 0, vertices.as_bytes());
@@ -185,6 +267,14 @@ pub enum ConvexMeshShape {
 }
 
 impl MeshData {
+    /// Generate a mesh matching the outline of a collider shape,
+    /// including the rounded corners of shapes with a nonzero `circle_r`.
+    ///
+    /// Circular arcs are approximated with straight segments
+    /// no longer than `max_circle_vert_distance`.
+    /// Texture coordinates are mapped to the shape's bounding box.
+    ///
+    /// `MeshData::from(collider)` uses this with a default distance of 0.1.
     pub fn from_collider_shape(shape: &phys::ColliderShape, max_circle_vert_distance: f32) -> Self {
         let mut vertices: Vec<m::Vec2> = Vec::new();
 
@@ -0,0 +1,439 @@
+use super::{MeshData, Vertex};
+use crate::math::{self as m, uv};
+
+/// Tool for generating meshes in code.
+///
+/// Each operation appends a piece of geometry to the mesh being built,
+/// so several shapes can be combined into one mesh.
+/// Shapes are generated in the xy plane (z = 0), facing the camera,
+/// with texture coordinates mapped to each shape's own bounding box
+/// starting from the top left.
+///
+/// The builder is consumed and returned by every operation to allow chaining,
+/// and [`build`][Self::build] hands over its buffers without copying.
+/// Use [`with_capacity`][Self::with_capacity] to avoid reallocating
+/// when the final size is known in advance.
+///
+/// Panics if the mesh gets more than [`u16::MAX`] vertices.
+///
+/// ```
+/// # use starframe::{MeshBuilder, Vec2};
+/// let data = MeshBuilder::new()
+///     .quad(Vec2::zero(), 2., 1.)
+///     .circle(Vec2::new(0., 1.), 0.5, 16)
+///     .build();
+/// assert_eq!(data.vertices.len(), 4 + 17);
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct MeshBuilder {
+    vertices: Vec<Vertex>,
+    indices: Vec<u16>,
+}
+
+impl MeshBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a builder with space for the given numbers of vertices and indices
+    /// allocated up front.
+    pub fn with_capacity(vertices: usize, indices: usize) -> Self {
+        Self {
+            vertices: Vec::with_capacity(vertices),
+            indices: Vec::with_capacity(indices),
+        }
+    }
+
+    /// Finish building and get the generated mesh data,
+    /// ready to be passed to [`GraphicsManager::create_mesh`][crate::GraphicsManager::create_mesh].
+    pub fn build(self) -> MeshData {
+        MeshData {
+            vertices: self.vertices,
+            indices: self.indices,
+            joints: None,
+        }
+    }
+
+    /// Add a `w` by `h` rectangle centered at `center`.
+    pub fn quad(mut self, center: m::Vec2, w: f32, h: f32) -> Self {
+        let half = m::Vec2::new(w / 2., h / 2.);
+        let bounds = Bounds::from_center(center, half);
+        let base = self.next_index();
+        for corner in [
+            m::Vec2::new(half.x, half.y),
+            m::Vec2::new(-half.x, half.y),
+            m::Vec2::new(-half.x, -half.y),
+            m::Vec2::new(half.x, -half.y),
+        ] {
+            self.vertices.push(bounds.flat_vertex(center + corner));
+        }
+        self.check_vertex_count();
+        self.indices
+            .extend([base, base + 1, base + 2, base, base + 2, base + 3]);
+        self
+    }
+
+    /// Add a circle of radius `r` centered at `center`,
+    /// approximated with a polygon of `segments` edges.
+    pub fn circle(mut self, center: m::Vec2, r: f32, segments: usize) -> Self {
+        let bounds = Bounds::from_center(center, m::Vec2::new(r, r));
+        let base = self.next_index();
+        self.vertices.push(bounds.flat_vertex(center));
+        for dir in circle_points(segments) {
+            self.vertices.push(bounds.flat_vertex(center + r * dir));
+        }
+        self.check_vertex_count();
+        let segments = segments as u16;
+        for i in 0..segments {
+            let next = (i + 1) % segments;
+            self.indices.extend([base, base + 1 + i, base + 1 + next]);
+        }
+        self
+    }
+
+    /// Add a flat ring between the radii `inner_r` and `outer_r` centered at `center`,
+    /// approximated with `segments` quads.
+    pub fn ring(mut self, center: m::Vec2, inner_r: f32, outer_r: f32, segments: usize) -> Self {
+        let bounds = Bounds::from_center(center, m::Vec2::new(outer_r, outer_r));
+        let base = self.next_index();
+        for dir in circle_points(segments) {
+            self.vertices
+                .push(bounds.flat_vertex(center + outer_r * dir));
+            self.vertices
+                .push(bounds.flat_vertex(center + inner_r * dir));
+        }
+        self.check_vertex_count();
+        let segments = segments as u16;
+        for i in 0..segments {
+            let outer = base + 2 * i;
+            let next_outer = base + 2 * ((i + 1) % segments);
+            self.indices.extend([
+                outer,
+                outer + 1,
+                next_outer,
+                next_outer,
+                outer + 1,
+                next_outer + 1,
+            ]);
+        }
+        self
+    }
+
+    /// Add a solid made by pushing the polygon outlined by `points`
+    /// `depth` units away from the camera, for 2.5D visuals.
+    ///
+    /// The polygon can be concave and wound in either direction,
+    /// but must not intersect itself.
+    /// The front face is at z = 0 and the back face at z = `depth`.
+    /// The side walls are textured with u going around the outline
+    /// and v going from the front to the back.
+    pub fn extrude_polygon(mut self, points: &[m::Vec2], depth: f32) -> Self {
+        if points.len() < 3 {
+            return self;
+        }
+        let triangles = triangulate(points);
+
+        // front and back faces
+        let bounds = Bounds::from_points(points);
+        for (z, normal) in [(0., -1.), (depth, 1.)] {
+            let base = self.next_index();
+            self.vertices.extend(points.iter().map(|&p| Vertex {
+                position: uv::Vec3::new(p.x, p.y, z).into(),
+                normal: [0., 0., normal].into(),
+                ..bounds.flat_vertex(p)
+            }));
+            self.check_vertex_count();
+            self.indices.extend(
+                triangles
+                    .iter()
+                    .flat_map(|tri| tri.map(|idx| base + idx as u16)),
+            );
+        }
+
+        // side walls, with separate vertices for each edge
+        // so that the corners are sharp
+        let orientation = signed_area(points).signum();
+        let perimeter: f32 = edges(points).map(|(a, b)| (b - a).mag()).sum();
+        let mut dist_along = 0.;
+        for (a, b) in edges(points) {
+            let edge = b - a;
+            let len = edge.mag();
+            let tangent = edge / len;
+            let normal = orientation * m::Vec2::new(tangent.y, -tangent.x);
+            let base = self.next_index();
+            for (p, u) in [(a, dist_along), (b, dist_along + len)] {
+                for (z, v) in [(0., 0.), (depth, 1.)] {
+                    self.vertices.push(Vertex {
+                        position: uv::Vec3::new(p.x, p.y, z).into(),
+                        tex_coords: [u / perimeter, v].into(),
+                        normal: normal.into(),
+                        tangent: tangent.into(),
+                    });
+                }
+            }
+            self.check_vertex_count();
+            self.indices
+                .extend([base, base + 1, base + 2, base + 2, base + 1, base + 3]);
+            dist_along += len;
+        }
+
+        self
+    }
+
+    /// Add a grid of vertices `cell_size` apart, centered at the origin,
+    /// displaced toward the camera by the given heights.
+    ///
+    /// `heights` is read in rows of `columns` values,
+    /// with the first row at the top of the grid.
+    /// Normals are computed from the slope of the surface,
+    /// so lighting follows the shape of the terrain.
+    /// Does nothing if there are fewer than two rows or columns.
+    pub fn heightmap(mut self, heights: &[f32], columns: usize, cell_size: f32) -> Self {
+        if columns < 2 || heights.len() / columns < 2 {
+            return self;
+        }
+        let rows = heights.len() / columns;
+        let height_at = |row: usize, col: usize| heights[row * columns + col];
+        // slope of the surface at a grid point with central differences,
+        // or one-sided differences at the edges
+        let slope =
+            |before: f32, after: f32, cells: usize| (after - before) / (cells as f32 * cell_size);
+
+        let top_left = m::Vec2::new(
+            -((columns - 1) as f32) * cell_size / 2.,
+            (rows - 1) as f32 * cell_size / 2.,
+        );
+        let base = self.next_index();
+        for row in 0..rows {
+            for col in 0..columns {
+                let (left, right) = (col.saturating_sub(1), (col + 1).min(columns - 1));
+                let (up, down) = (row.saturating_sub(1), (row + 1).min(rows - 1));
+                let dh_dx = slope(height_at(row, left), height_at(row, right), right - left);
+                // rows go downward, the y axis upward
+                let dh_dy = slope(height_at(down, col), height_at(up, col), down - up);
+
+                let h = height_at(row, col);
+                let xy = top_left + cell_size * m::Vec2::new(col as f32, -(row as f32));
+                self.vertices.push(Vertex {
+                    // -z is toward the camera
+                    position: uv::Vec3::new(xy.x, xy.y, -h).into(),
+                    tex_coords: [
+                        col as f32 / (columns - 1) as f32,
+                        row as f32 / (rows - 1) as f32,
+                    ]
+                    .into(),
+                    normal: uv::Vec3::new(-dh_dx, -dh_dy, -1.).normalized().into(),
+                    tangent: uv::Vec3::new(1., 0., -dh_dx).normalized().into(),
+                });
+            }
+        }
+        self.check_vertex_count();
+        for row in 0..rows - 1 {
+            for col in 0..columns - 1 {
+                let top_left = base + (row * columns + col) as u16;
+                let bottom_left = top_left + columns as u16;
+                self.indices.extend([
+                    top_left,
+                    bottom_left,
+                    top_left + 1,
+                    top_left + 1,
+                    bottom_left,
+                    bottom_left + 1,
+                ]);
+            }
+        }
+        self
+    }
+
+    /// Index of the next vertex to be added.
+    fn next_index(&self) -> u16 {
+        u16::try_from(self.vertices.len()).expect("Too many vertices in a single mesh")
+    }
+
+    /// Panic if the vertices added so far can't all be indexed with `u16`.
+    /// Operations call this before generating indices
+    /// so that the index arithmetic never wraps around.
+    fn check_vertex_count(&self) {
+        self.next_index();
+    }
+}
+
+/// Bounding box of a flat shape for generating texture coordinates.
+struct Bounds {
+    min: m::Vec2,
+    size: m::Vec2,
+}
+
+impl Bounds {
+    fn from_center(center: m::Vec2, half_size: m::Vec2) -> Self {
+        Self {
+            min: center - half_size,
+            size: 2. * half_size,
+        }
+    }
+
+    fn from_points(points: &[m::Vec2]) -> Self {
+        let min = points
+            .iter()
+            .fold(points[0], |acc, p| acc.min_by_component(*p));
+        let max = points
+            .iter()
+            .fold(points[0], |acc, p| acc.max_by_component(*p));
+        Self {
+            min,
+            size: max - min,
+        }
+    }
+
+    /// Create a vertex facing the camera
+    /// with texture coordinates starting from the top left of the bounds.
+    fn flat_vertex(&self, position: m::Vec2) -> Vertex {
+        let rel = position - self.min;
+        Vertex {
+            position: position.into(),
+            tex_coords: [rel.x / self.size.x, 1. - rel.y / self.size.y].into(),
+            ..Default::default()
+        }
+    }
+}
+
+/// Evenly spaced unit vectors going counterclockwise around a circle.
+fn circle_points(count: usize) -> impl Iterator<Item = m::Vec2> {
+    let angle_incr = std::f32::consts::TAU / count as f32;
+    (0..count).map(move |i| {
+        let angle = angle_incr * i as f32;
+        m::Vec2::new(angle.cos(), angle.sin())
+    })
+}
+
+/// Pairs of consecutive points, wrapping around at the end.
+fn edges(points: &[m::Vec2]) -> impl '_ + Iterator<Item = (m::Vec2, m::Vec2)> {
+    points
+        .iter()
+        .zip(points.iter().cycle().skip(1))
+        .map(|(a, b)| (*a, *b))
+}
+
+/// Area of a polygon, positive if it's wound counterclockwise.
+fn signed_area(points: &[m::Vec2]) -> f32 {
+    edges(points).map(|(a, b)| a.wedge(b).xy).sum::<f32>() / 2.
+}
+
+/// Split a simple polygon into triangles with ear clipping.
+/// Returns indices into `points` wound counterclockwise.
+fn triangulate(points: &[m::Vec2]) -> Vec<[usize; 3]> {
+    let turn =
+        |a: usize, b: usize, c: usize| (points[b] - points[a]).wedge(points[c] - points[a]).xy;
+
+    let mut remaining: Vec<usize> = (0..points.len()).collect();
+    if signed_area(points) < 0. {
+        remaining.reverse();
+    }
+    let mut triangles = Vec::with_capacity(points.len() - 2);
+    while remaining.len() > 3 {
+        let n = remaining.len();
+        let corner = |i: usize| {
+            [
+                remaining[(i + n - 1) % n],
+                remaining[i],
+                remaining[(i + 1) % n],
+            ]
+        };
+        let is_ear = |i: usize| {
+            let [a, b, c] = corner(i);
+            turn(a, b, c) > 0.
+                && !remaining.iter().any(|&p| {
+                    p != a
+                        && p != b
+                        && p != c
+                        && turn(a, b, p) >= 0.
+                        && turn(b, c, p) >= 0.
+                        && turn(c, a, p) >= 0.
+                })
+        };
+        // a valid simple polygon always has an ear,
+        // but fall back to any corner to avoid looping forever on bad input
+        let ear = (0..n).find(|&i| is_ear(i)).unwrap_or(0);
+        triangles.push(corner(ear));
+        remaining.remove(ear);
+    }
+    triangles.push([remaining[0], remaining[1], remaining[2]]);
+    triangles
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn triangle_area(data: &MeshData, tri: &[u16]) -> f32 {
+        let pos = |i: u16| {
+            let p = data.vertices[i as usize].position.0;
+            m::Vec2::new(p[0], p[1])
+        };
+        (pos(tri[1]) - pos(tri[0]))
+            .wedge(pos(tri[2]) - pos(tri[0]))
+            .xy
+            / 2.
+    }
+
+    #[test]
+    fn extrude_concave_polygon() {
+        // an L shape wound clockwise
+        let points = [
+            m::Vec2::new(0., 0.),
+            m::Vec2::new(0., 2.),
+            m::Vec2::new(1., 2.),
+            m::Vec2::new(1., 1.),
+            m::Vec2::new(2., 1.),
+            m::Vec2::new(2., 0.),
+        ];
+        let data = MeshBuilder::new().extrude_polygon(&points, 0.5).build();
+
+        // front and back faces plus four vertices per side wall
+        assert_eq!(data.vertices.len(), 2 * 6 + 4 * 6);
+        let cap_tris = 6 - 2;
+        assert_eq!(data.indices.len(), 3 * (2 * cap_tris + 2 * 6));
+
+        // front face triangles cover the shape exactly once without flipping
+        let front = &data.indices[..3 * cap_tris];
+        let areas: Vec<f32> = front
+            .chunks_exact(3)
+            .map(|tri| triangle_area(&data, tri))
+            .collect();
+        assert!(areas.iter().all(|&a| a > 0.), "{areas:?}");
+        assert!((areas.iter().sum::<f32>() - 3.).abs() < 1e-5);
+
+        // side walls face outward
+        let wall_normal = |edge: usize| {
+            let n = data.vertices[12 + 4 * edge].normal.0;
+            m::Vec2::new(n[0], n[1])
+        };
+        assert!((wall_normal(0) - m::Vec2::new(-1., 0.)).mag() < 1e-5);
+        assert!((wall_normal(3) - m::Vec2::new(0., 1.)).mag() < 1e-5);
+    }
+
+    #[test]
+    #[should_panic(expected = "Too many vertices")]
+    fn too_many_vertices_panics() {
+        // one grid that starts in range but ends past the u16 limit
+        let heights = vec![0.; 300 * 300];
+        MeshBuilder::new().heightmap(&heights, 300, 1.);
+    }
+
+    #[test]
+    fn heightmap_normals_follow_slope() {
+        // a ramp rising toward the camera to the right
+        let heights = [0., 1., 2., 0., 1., 2.];
+        let data = MeshBuilder::new().heightmap(&heights, 3, 1.).build();
+        assert_eq!(data.vertices.len(), 6);
+        assert_eq!(data.indices.len(), 2 * 2 * 3);
+
+        let expected = uv::Vec3::new(-1., 0., -1.).normalized();
+        for vert in &data.vertices {
+            let n = uv::Vec3::from(vert.normal.0);
+            assert!((n - expected).mag() < 1e-5, "{n:?}");
+        }
+        // first row is at the top
+        assert!(data.vertices[0].position.0[1] > data.vertices[3].position.0[1]);
+    }
+}
@@ -3,9 +3,9 @@ use crate::{
         gi::{GlobalIlluminationPipeline, LIGHT_TEX_FMT},
         manager::MeshId,
         material::Material,
-        renderer::{DEFAULT_MULTISAMPLE_STATE, DEPTH_FORMAT, SWAPCHAIN_FORMAT},
-        util::GpuMat4,
-        Camera, GraphicsManager,
+        renderer::{DEPTH_FORMAT, SWAPCHAIN_FORMAT},
+        util::{GpuMat4, GpuVec4},
+        Camera, GraphicsManager, Tint,
     },
     math as m,
 };
@@ -24,17 +24,20 @@ pub struct MeshRenderer {
     instance_unif_bind_group_layout: wgpu::BindGroupLayout,
     instance_unif_bind_group: wgpu::BindGroup,
     instance_capacity: usize,
-    meshes_sorted: Vec<(MeshId, Option<m::Pose>)>,
+    // mesh, model matrix, tint and whether the mesh is transparent
+    meshes_sorted: Vec<(MeshId, Option<m::uv::Mat4>, Tint, bool)>,
 }
 
 #[repr(C)]
 #[derive(Debug, Clone, Copy, AsBytes, FromBytes)]
 struct InstanceUniforms {
     // space could be saved here by packing the model matrix into three row vectors,
-    // but alignment with dynamic offsets requires a minimum of 64 bytes
-    // and we don't currently have anything but the model matrix here
-    // so it might as well be the full 4x4 matrix
+    // but dynamic offsets need to be aligned to 64 bytes
+    // so that wouldn't help until there's less than 16 bytes of other data
     model: GpuMat4,
+    tint: GpuVec4,
+    // padding to reach the dynamic offset alignment
+    _pad: [f32; 12],
 }
 
 impl MeshRenderer {
@@ -155,7 +158,7 @@ impl MeshRenderer {
                     ..Default::default()
                 },
             }),
-            multisample: DEFAULT_MULTISAMPLE_STATE,
+            multisample: crate::Renderer::default_multisample_state(),
             multiview: None,
         });
 
@@ -229,7 +232,7 @@ impl MeshRenderer {
                 stencil: wgpu::StencilState::default(),
                 bias: wgpu::DepthBiasState::default(),
             }),
-            multisample: DEFAULT_MULTISAMPLE_STATE,
+            multisample: crate::Renderer::default_multisample_state(),
             multiview: None,
         });
 
@@ -253,18 +256,34 @@ impl MeshRenderer {
         self.meshes_sorted.clear();
         self.meshes_sorted.extend(
             world
-                .query_mut::<(&MeshId, Option<&m::Pose>)>()
+                .query_mut::<(
+                    &MeshId,
+                    Option<&m::Pose>,
+                    Option<&m::Transform2D>,
+                    Option<&Tint>,
+                )>()
                 .into_iter()
-                .map(|(_, (id, pose))| (*id, pose.copied())),
+                .map(|(_, (id, pose, transform, tint))| {
+                    // a Transform2D takes precedence if an entity has both
+                    let model = match (transform, pose) {
+                        (Some(tr), _) => Some(tr.into_homogeneous_matrix()),
+                        (None, Some(pose)) => Some(pose.into_homogeneous_matrix()),
+                        (None, None) => None,
+                    };
+                    let tint = tint.copied().unwrap_or_default();
+                    let is_transparent =
+                        manager.get_mesh_material(id).is_transparent || tint.0[3] < 1.;
+                    (*id, model, tint, is_transparent)
+                }),
         );
         // sort in z order for transparency and efficient depth prepass.
         // the z order of meshes very rarely changes,
         // so there's some room for perf gains here by caching the order,
         // but it's a little finicky to do well.
         // prefer to profile before doing that
-        self.meshes_sorted.sort_by(|(_, pose_a), (_, pose_b)| {
-            let z_a = pose_a.map(|p| p.translation.z).unwrap_or(0.);
-            let z_b = pose_b.map(|p| p.translation.z).unwrap_or(0.);
+        self.meshes_sorted.sort_by(|a, b| {
+            let z_a = a.1.map(|m| m.cols[3].z).unwrap_or(0.);
+            let z_b = b.1.map(|m| m.cols[3].z).unwrap_or(0.);
             z_a.total_cmp(&z_b)
         });
 
@@ -275,19 +294,21 @@ impl MeshRenderer {
         // collect all instance uniforms into a big buffer;
         // we'll use dynamic offsets to bind them
         let mut instance_unifs = Vec::new();
-        for (mesh_id, pose) in &self.meshes_sorted {
+        for (mesh_id, entity_model, tint, _) in &self.meshes_sorted {
             let Some(mesh) = manager.get_mesh_mut(mesh_id) else {
                 continue;
             };
 
-            let model = match pose {
-                Some(entity_pose) => *entity_pose * mesh.offset,
-                None => mesh.offset,
-            }
-            .into_homogeneous_matrix();
+            let offset = mesh.offset.into_homogeneous_matrix();
+            let model = match entity_model {
+                Some(entity_model) => *entity_model * offset,
+                None => offset,
+            };
 
             instance_unifs.push(InstanceUniforms {
                 model: model.into(),
+                tint: GpuVec4(tint.0),
+                _pad: [0.; 12],
             });
         }
 
@@ -325,12 +346,15 @@ impl MeshRenderer {
         camera: &'pass Camera,
     ) {
         // depth prepass in forward z order (+z is away from camera),
-        // drawing closest things first to maximize depth test discards
+        // drawing closest things first to maximize depth test discards.
+        // transparent meshes are left out so that they don't hide things behind them
         pass.set_pipeline(&self.depth_pipeline);
         pass.set_bind_group(0, &camera.bind_group, &[]);
 
-        for (idx, (mesh_id, _)) in self.meshes_sorted.iter().enumerate() {
-            self.draw_mesh(pass, manager, idx, mesh_id, PassId::Depth);
+        for (idx, (mesh_id, _, _, is_transparent)) in self.meshes_sorted.iter().enumerate() {
+            if !is_transparent {
+                self.draw_mesh(pass, manager, idx, mesh_id, PassId::Depth);
+            }
         }
     }
 
@@ -344,7 +368,7 @@ impl MeshRenderer {
     ) {
         pass.set_pipeline(&self.emissive_pipeline);
         pass.set_bind_group(0, &camera.bind_group, &[]);
-        for (idx, (mesh_id, _)) in self.meshes_sorted.iter().enumerate() {
+        for (idx, (mesh_id, _, _, _)) in self.meshes_sorted.iter().enumerate() {
             self.draw_mesh(pass, manager, idx, mesh_id, PassId::Emissive);
         }
     }
@@ -356,14 +380,23 @@ impl MeshRenderer {
         camera: &'pass Camera,
         gi_pl: &'pass GlobalIlluminationPipeline,
     ) {
-        // full render in reverse z order for transparency
+        // full render with opaque meshes first,
+        // then transparent meshes in reverse z order so they blend correctly.
+        // depth writes are disabled in this pass, so transparent meshes
+        // are only hidden by opaque meshes in front of them
 
         pass.set_pipeline(&self.main_pipeline);
         pass.set_bind_group(0, &camera.bind_group, &[]);
         pass.set_bind_group(1, &gi_pl.bind_groups.render, &[]);
 
-        for (idx, (mesh_id, _)) in self.meshes_sorted.iter().enumerate().rev() {
-            self.draw_mesh(pass, manager, idx, mesh_id, PassId::Main);
+        for draw_transparent in [false, true] {
+            for (idx, (mesh_id, _, _, is_transparent)) in
+                self.meshes_sorted.iter().enumerate().rev()
+            {
+                if *is_transparent == draw_transparent {
+                    self.draw_mesh(pass, manager, idx, mesh_id, PassId::Main);
+                }
+            }
         }
     }
 
@@ -1,9 +1,13 @@
 use super::{
+    blob_renderer::BlobRenderer,
+    debug_text::{self, DebugTextRenderer},
     gi,
     line_renderer::LineRenderer,
     mesh::{skin::SkinPipeline, MeshRenderer},
 };
-use std::sync::OnceLock;
+use instant::Instant;
+use std::{sync::OnceLock, time::Duration};
+use zerocopy::AsBytes;
 
 use wgpu_profiler as wp;
 
@@ -15,23 +19,47 @@ use wgpu_profiler as wp;
 static DEVICE: OnceLock<wgpu::Device> = OnceLock::new();
 static QUEUE: OnceLock<wgpu::Queue> = OnceLock::new();
 static WINDOW: OnceLock<winit::window::Window> = OnceLock::new();
+// the sample count is needed when creating pipelines,
+// so it's also stored globally to avoid passing it around
+static MSAA_SAMPLE_COUNT: OnceLock<u32> = OnceLock::new();
 
 pub const SWAPCHAIN_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Bgra8UnormSrgb;
-pub const DEPTH_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Depth16Unorm;
+/// The sample count used before it became configurable.
+///
+/// This is only correct if [`GraphicsConfig::msaa_samples`][crate::GraphicsConfig::msaa_samples]
+/// is left at its default and the GPU supports it.
+/// Pipelines built with it fail validation otherwise.
+#[deprecated(note = "the sample count is configurable, use `Renderer::msaa_samples` instead")]
 pub const MSAA_SAMPLES: u32 = 4;
+/// The multisample state used before the sample count became configurable.
+///
+/// This has the same caveats as [`MSAA_SAMPLES`].
+#[deprecated(
+    note = "the sample count is configurable, use `Renderer::default_multisample_state` instead"
+)]
+#[allow(deprecated)]
 pub const DEFAULT_MULTISAMPLE_STATE: wgpu::MultisampleState = wgpu::MultisampleState {
     count: MSAA_SAMPLES,
     mask: !0,
     alpha_to_coverage_enabled: false,
 };
+/// Format of the window depth buffer.
+///
+/// Depth values are normalized to the range 0 to 1, going linearly
+/// from 0 at the camera's [`z_near`][crate::Camera::z_near]
+/// to 1 at its [`z_far`][crate::Camera::z_far].
+/// The buffer can be sampled in shaders, see [`Frame::depth_texture`].
+pub const DEPTH_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Depth16Unorm;
 
 /// A Renderer manages resources needed to draw graphics to the screen.
 pub struct Renderer {
     surface: wgpu::Surface<'static>,
     surface_config: wgpu::SurfaceConfiguration,
+    supported_present_modes: Vec<wgpu::PresentMode>,
     window_scale_factor: f64,
 
-    msaa_view: wgpu::TextureView,
+    // None if multisampling is disabled
+    msaa_view: Option<wgpu::TextureView>,
     // textures and bind group for depth and lights to use in GI
     depth_tex: wgpu::Texture,
     depth_view: wgpu::TextureView,
@@ -39,14 +67,79 @@ pub struct Renderer {
     emissive_view: wgpu::TextureView,
 
     gi_pipeline: gi::GlobalIlluminationPipeline,
+    background: Background,
     mesh_renderer: MeshRenderer,
     skin_pl: SkinPipeline,
     // rendering subsystems that aren't always used in lazily initialized Options
     // so we can have a unified API to call them through `Frame`
     // but don't pay for them if the user doesn't use them
     line_renderer: Option<LineRenderer>,
+    blob_renderer: Option<BlobRenderer>,
+    debug_text_renderer: Option<DebugTextRenderer>,
 
     pub(crate) profiler: wp::GpuProfiler,
+    frame_stats: FrameStats,
+    // cpu times of the frame currently being drawn,
+    // moved to frame_stats when the frame ends
+    frame_cpu_times: Vec<(String, Duration)>,
+}
+
+/// Time spent drawing the different parts of a frame,
+/// from [`Renderer::frame_stats`].
+///
+/// Times are labeled by the part of the frame they were spent on,
+/// e.g. "render depth" and "radiance cascades" for parts of [`Frame::draw_meshes`].
+/// Things drawn in custom render passes from [`Frame::pass`] are not included.
+#[derive(Clone, Debug, Default)]
+pub struct FrameStats {
+    /// Time the CPU spent recording commands for each part of the last frame,
+    /// in the order they were drawn.
+    pub cpu_times: Vec<(String, Duration)>,
+    /// Time the GPU spent executing each part of the most recent frame it has finished,
+    /// which lags a few frames behind the CPU.
+    /// Nested parts are labeled with their full path, e.g. "draw meshes/render depth".
+    ///
+    /// GPU timing uses timestamp queries, which are only enabled with the `tracy` feature
+    /// and only if the GPU supports them. Otherwise this is empty.
+    pub gpu_times: Vec<(String, Duration)>,
+    /// Number of spot lights that were in view and used in lighting during the last frame.
+    ///
+    /// Spot lights outside of the view are skipped and don't cost anything
+    /// in the lighting pass.
+    pub spot_lights_drawn: usize,
+}
+
+impl FrameStats {
+    /// Total time the CPU spent on the frame.
+    pub fn total_cpu_time(&self) -> Duration {
+        self.cpu_times.iter().map(|(_, t)| *t).sum()
+    }
+
+    /// Total time the GPU spent on the frame.
+    pub fn total_gpu_time(&self) -> Duration {
+        // only count top-level parts since nested ones are included in them
+        self.gpu_times
+            .iter()
+            .filter(|(label, _)| !label.contains('/'))
+            .map(|(_, t)| *t)
+            .sum()
+    }
+
+    fn push_gpu_results(&mut self, prefix: &str, results: &[wp::GpuTimerQueryResult]) {
+        for res in results {
+            let label = if prefix.is_empty() {
+                res.label.clone()
+            } else {
+                format!("{prefix}/{}", res.label)
+            };
+            if let Some(time) = &res.time {
+                let secs = (time.end - time.start).max(0.);
+                self.gpu_times
+                    .push((label.clone(), Duration::from_secs_f64(secs)));
+            }
+            self.push_gpu_results(&label, &res.nested_queries);
+        }
+    }
 }
 
 /// An error that occurred during renderer initialization.
@@ -95,10 +188,15 @@ impl Renderer {
         #[cfg(not(feature = "tracy"))]
         let profiling_features = wgpu::Features::empty();
 
+        let (msaa_samples, msaa_features) =
+            Self::choose_msaa_samples(&adapter, config.msaa_samples);
+
         let (device, queue) = adapter
             .request_device(
                 &wgpu::DeviceDescriptor {
-                    required_features: wgpu::Features::default() | profiling_features,
+                    required_features: wgpu::Features::default()
+                        | profiling_features
+                        | msaa_features,
                     required_limits: wgpu::Limits {
                         min_uniform_buffer_offset_alignment: 64,
                         ..Default::default()
@@ -156,9 +254,11 @@ impl Renderer {
         WINDOW
             .set(window)
             .map_err(|_| RendererInitError::AlreadyInitialized)?;
+        MSAA_SAMPLE_COUNT
+            .set(msaa_samples)
+            .map_err(|_| RendererInitError::AlreadyInitialized)?;
 
-        let msaa_tex = Self::create_msaa_texture(window_size);
-        let msaa_view = msaa_tex.create_view(&wgpu::TextureViewDescriptor::default());
+        let msaa_view = Self::create_msaa_view(window_size);
 
         let depth_tex = Self::create_depth_texture(window_size);
         let depth_view = depth_tex.create_view(&wgpu::TextureViewDescriptor::default());
@@ -172,6 +272,7 @@ impl Renderer {
         Ok(Renderer {
             surface,
             surface_config,
+            supported_present_modes: swapchain_capabilities.present_modes,
             window_scale_factor,
             msaa_view,
             depth_tex,
@@ -179,10 +280,15 @@ impl Renderer {
             emissive_tex,
             emissive_view,
             gi_pipeline,
+            background: Background::default(),
             mesh_renderer,
             skin_pl,
             line_renderer: None,
+            blob_renderer: None,
+            debug_text_renderer: None,
             profiler,
+            frame_stats: FrameStats::default(),
+            frame_cpu_times: Vec::new(),
         })
     }
 
@@ -214,16 +320,18 @@ impl Renderer {
 
     /// Change the size of the frame `draw_to_window` draws into.
     /// This is called automatically by the gameloop when the window size changes.
+    ///
+    /// Zero sizes (i.e. a minimized window) are ignored,
+    /// keeping the previous size until the window is restored.
     pub(crate) fn resize_swap_chain(&mut self, new_size: winit::dpi::PhysicalSize<u32>) {
-        if new_size == self.window_size() {
+        if new_size == self.window_size() || new_size.width == 0 || new_size.height == 0 {
             return;
         }
         let device = Self::device();
         self.surface_config.width = new_size.width;
         self.surface_config.height = new_size.height;
         self.surface.configure(device, &self.surface_config);
-        let msaa_tex = Self::create_msaa_texture(new_size);
-        self.msaa_view = msaa_tex.create_view(&wgpu::TextureViewDescriptor::default());
+        self.msaa_view = Self::create_msaa_view(new_size);
         self.depth_tex = Self::create_depth_texture(new_size);
         self.depth_view = self
             .depth_tex
@@ -235,9 +343,72 @@ impl Renderer {
         self.gi_pipeline.resize(new_size);
     }
 
-    fn create_msaa_texture(size: winit::dpi::PhysicalSize<u32>) -> wgpu::Texture {
+    /// Get the number of samples per pixel used for multisample antialiasing.
+    ///
+    /// This is chosen from [`GraphicsConfig::msaa_samples`][crate::GraphicsConfig::msaa_samples]
+    /// based on what the GPU supports when the Renderer is created.
+    /// Pipelines that draw to the screen must use this sample count,
+    /// e.g. in the [`default_multisample_state`][Self::default_multisample_state].
+    /// Use this in place of the deprecated [`MSAA_SAMPLES`] constant.
+    /// # Panics
+    /// This function panics if the renderer hasn't been initialized yet.
+    #[inline]
+    pub fn msaa_samples() -> u32 {
+        *MSAA_SAMPLE_COUNT
+            .get()
+            .expect("Renderer has not been initialized yet")
+    }
+
+    /// Multisample state that matches the window's render targets.
+    /// # Panics
+    /// This function panics if the renderer hasn't been initialized yet.
+    #[inline]
+    pub fn default_multisample_state() -> wgpu::MultisampleState {
+        wgpu::MultisampleState {
+            count: Self::msaa_samples(),
+            mask: !0,
+            alpha_to_coverage_enabled: false,
+        }
+    }
+
+    /// Pick the supported sample count closest to the requested one,
+    /// along with the device features needed to use it.
+    fn choose_msaa_samples(adapter: &wgpu::Adapter, requested: u32) -> (u32, wgpu::Features) {
+        // 1 and 4 are always supported,
+        // others need a native-only feature and support for both of our target formats
+        let extra_feature = wgpu::Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES;
+        let supported: Vec<u32> = if adapter.features().contains(extra_feature) {
+            let color_flags = adapter.get_texture_format_features(SWAPCHAIN_FORMAT).flags;
+            let depth_flags = adapter.get_texture_format_features(DEPTH_FORMAT).flags;
+            [1, 2, 4, 8, 16]
+                .into_iter()
+                .filter(|&count| {
+                    color_flags.sample_count_supported(count)
+                        && depth_flags.sample_count_supported(count)
+                })
+                .collect()
+        } else {
+            vec![1, 4]
+        };
+
+        let chosen = nearest_sample_count(&supported, requested);
+        if chosen != requested {
+            eprintln!("MSAA with {requested} samples is not supported, using {chosen} instead");
+        }
+        let features = if matches!(chosen, 1 | 4) {
+            wgpu::Features::empty()
+        } else {
+            extra_feature
+        };
+        (chosen, features)
+    }
+
+    fn create_msaa_view(size: winit::dpi::PhysicalSize<u32>) -> Option<wgpu::TextureView> {
+        if Self::msaa_samples() == 1 {
+            return None;
+        }
         let device = Self::device();
-        device.create_texture(&wgpu::TextureDescriptor {
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
             label: Some("msaa"),
             size: wgpu::Extent3d {
                 width: size.width,
@@ -245,12 +416,13 @@ impl Renderer {
                 depth_or_array_layers: 1,
             },
             mip_level_count: 1,
-            sample_count: MSAA_SAMPLES,
+            sample_count: Self::msaa_samples(),
             dimension: wgpu::TextureDimension::D2,
             format: SWAPCHAIN_FORMAT,
             usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::RENDER_ATTACHMENT,
             view_formats: &[],
-        })
+        });
+        Some(texture.create_view(&wgpu::TextureViewDescriptor::default()))
     }
 
     fn create_depth_texture(size: winit::dpi::PhysicalSize<u32>) -> wgpu::Texture {
@@ -263,7 +435,7 @@ impl Renderer {
                 depth_or_array_layers: 1,
             },
             mip_level_count: 1,
-            sample_count: MSAA_SAMPLES,
+            sample_count: Self::msaa_samples(),
             dimension: wgpu::TextureDimension::D2,
             format: DEPTH_FORMAT,
             usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::RENDER_ATTACHMENT,
@@ -311,6 +483,23 @@ impl Renderer {
         self.window_scale_factor
     }
 
+    /// Update the scale factor when the window moves to a screen with a different DPI.
+    /// This is called automatically by the gameloop.
+    #[inline]
+    pub(crate) fn set_window_scale_factor(&mut self, scale_factor: f64) {
+        self.window_scale_factor = scale_factor;
+    }
+
+    /// Check if the window is minimized or otherwise has a size of zero,
+    /// in which case there is nothing to draw to.
+    ///
+    /// The [`Game`][crate::Game] API skips drawing while this is the case.
+    #[inline]
+    pub fn is_minimized(&self) -> bool {
+        let size = Self::window().inner_size();
+        size.width == 0 || size.height == 0
+    }
+
     /// Depth-stencil state that uses the same depth format as the window depth buffer
     /// and writes depths to the buffer.
     #[inline]
@@ -324,25 +513,141 @@ impl Renderer {
         }
     }
 
+    /// Change how frames are presented to the window at runtime,
+    /// e.g. to toggle vsync from an options menu.
+    ///
+    /// If the requested mode isn't supported,
+    /// falls back to [`AutoVsync`][wgpu::PresentMode::AutoVsync] for `Fifo` and `FifoRelaxed`
+    /// and [`AutoNoVsync`][wgpu::PresentMode::AutoNoVsync] for `Immediate` and `Mailbox`,
+    /// which pick the closest supported mode.
+    /// Returns the mode actually set.
+    pub fn set_present_mode(&mut self, mode: wgpu::PresentMode) -> wgpu::PresentMode {
+        use wgpu::PresentMode as PM;
+        let mode = match mode {
+            PM::AutoVsync | PM::AutoNoVsync => mode,
+            _ if self.supported_present_modes.contains(&mode) => mode,
+            PM::Fifo | PM::FifoRelaxed => PM::AutoVsync,
+            PM::Immediate | PM::Mailbox => PM::AutoNoVsync,
+        };
+        if mode != self.surface_config.present_mode {
+            self.surface_config.present_mode = mode;
+            self.surface.configure(Self::device(), &self.surface_config);
+        }
+        mode
+    }
+
+    /// Get the current present mode,
+    /// set with [`GraphicsConfig::use_vsync`][crate::GraphicsConfig::use_vsync]
+    /// or [`set_present_mode`][Self::set_present_mode].
+    #[inline]
+    pub fn present_mode(&self) -> wgpu::PresentMode {
+        self.surface_config.present_mode
+    }
+
     /// Change lighting quality parameters at runtime.
     #[inline]
     pub fn set_lighting_quality(&mut self, conf: gi::LightingQualityConfig) {
         self.gi_pipeline.set_quality(conf);
     }
 
+    /// Set how lit colors are mapped to the range the screen can show.
+    /// See [`Tonemap`][crate::Tonemap] for the options.
+    /// The default leaves colors untouched.
+    #[inline]
+    pub fn set_tonemap(&mut self, tonemap: crate::Tonemap) {
+        self.gi_pipeline.set_tonemap(tonemap);
+    }
+
+    /// Get the tonemapping set with [`set_tonemap`][Self::set_tonemap].
+    #[inline]
+    pub fn tonemap(&self) -> crate::Tonemap {
+        self.gi_pipeline.tonemap()
+    }
+
+    /// Set what the screen is cleared with at the start of every frame.
+    /// See [`Background`] for the options. Default: solid black.
+    ///
+    /// This can be overridden for a single frame with [`Frame::set_clear_color`].
+    #[inline]
+    pub fn set_background(&mut self, background: Background) {
+        self.background = background;
+    }
+
+    /// Get the background set with [`set_background`][Self::set_background].
+    #[inline]
+    pub fn background(&self) -> Background {
+        self.background
+    }
+
     /// Set the environment map for lighting.
     #[inline]
     pub fn set_environment_map(&mut self, params: &crate::EnvironmentMap) {
         self.gi_pipeline.env_map.bake(params);
     }
 
+    /// Add a spot light to the scene.
+    ///
+    /// At most 16 spot lights are used in lighting at a time;
+    /// any more than that are ignored.
+    /// Lights outside of the camera's view don't count towards this limit,
+    /// see [`FrameStats::spot_lights_drawn`] for how many were used.
+    #[inline]
+    pub fn add_spot_light(&mut self, light: crate::SpotLight) -> crate::SpotLightKey {
+        self.gi_pipeline.add_spot_light(light)
+    }
+
+    /// Remove a spot light from the scene, returning it if it still existed.
+    #[inline]
+    pub fn remove_spot_light(&mut self, key: crate::SpotLightKey) -> Option<crate::SpotLight> {
+        self.gi_pipeline.remove_spot_light(key)
+    }
+
+    /// Mutably access a spot light in the scene to move it around or change its parameters.
+    #[inline]
+    pub fn get_spot_light_mut(
+        &mut self,
+        key: crate::SpotLightKey,
+    ) -> Option<&mut crate::SpotLight> {
+        self.gi_pipeline.get_spot_light_mut(key)
+    }
+
+    /// Get the time spent on the different parts of drawing the last frame.
+    ///
+    /// Useful for e.g. choosing a [`LightingQualityConfig`][crate::LightingQualityConfig]
+    /// or a performance overlay.
+    #[inline]
+    pub fn frame_stats(&self) -> &FrameStats {
+        &self.frame_stats
+    }
+
+    /// Finish the profiler's frame and collect GPU timings from frames that have finished.
+    /// The [`Game`][crate::Game] API calls this after every draw.
+    pub(crate) fn end_profiler_frame(&mut self) {
+        self.profiler.end_frame().unwrap();
+        if let Some(results) = self
+            .profiler
+            .process_finished_frame(Self::queue().get_timestamp_period())
+        {
+            self.frame_stats.gpu_times.clear();
+            self.frame_stats.push_gpu_results("", &results);
+        }
+    }
+
     /// Start drawing a frame.
     #[inline]
     pub fn begin_frame(&mut self) -> Frame<'_> {
-        let surface = self
-            .surface
-            .get_current_texture()
-            .expect("Failed to get next swap chain texture");
+        let surface = match self.surface.get_current_texture() {
+            Ok(surface) => surface,
+            // the surface can go out of date if the window changed
+            // in a way we haven't been notified about yet, try again once
+            Err(wgpu::SurfaceError::Outdated | wgpu::SurfaceError::Lost) => {
+                self.surface.configure(Self::device(), &self.surface_config);
+                self.surface
+                    .get_current_texture()
+                    .expect("Failed to get next swap chain texture")
+            }
+            Err(err) => panic!("Failed to get next swap chain texture: {err}"),
+        };
         let view = surface
             .texture
             .create_view(&wgpu::TextureViewDescriptor::default());
@@ -351,16 +656,81 @@ impl Renderer {
         let encoder =
             device.create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
 
+        // only set again if meshes are drawn this frame
+        self.frame_stats.spot_lights_drawn = 0;
+
+        let background = self.background;
         Frame {
             renderer: self,
             encoder: Some(encoder),
             surface: Some(surface),
             target_view: view,
-            clear_color: Some(wgpu::Color::BLACK),
+            clear: Some(background),
+            scissor_stack: Vec::new(),
+        }
+    }
+}
+
+/// A rectangular region of the screen in physical pixels,
+/// measured from the top left corner.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ScissorRect {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl ScissorRect {
+    /// Get the region covered by both this and `other`.
+    ///
+    /// If they don't overlap, the result has zero width or height
+    /// and its position is clamped so that it doesn't go past the end of either rect,
+    /// keeping it valid to use within the bounds of both.
+    pub fn intersection(&self, other: &Self) -> Self {
+        let x_end = self
+            .x
+            .saturating_add(self.width)
+            .min(other.x.saturating_add(other.width));
+        let y_end = self
+            .y
+            .saturating_add(self.height)
+            .min(other.y.saturating_add(other.height));
+        let x = self.x.max(other.x).min(x_end);
+        let y = self.y.max(other.y).min(y_end);
+        Self {
+            x,
+            y,
+            width: x_end.saturating_sub(x),
+            height: y_end.saturating_sub(y),
         }
     }
 }
 
+/// What the screen is filled with before anything is drawn,
+/// set with [`Renderer::set_background`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Background {
+    /// A single color in linear RGBA.
+    Solid([f32; 4]),
+    /// A vertical gradient of the sky colors of the current
+    /// [environment map][Renderer::set_environment_map],
+    /// so that the background matches the lighting.
+    ///
+    /// The gradient is fixed to the screen, not the world.
+    /// The top edge of the screen has the zenith color,
+    /// which blends into the horizon color at the middle of the screen,
+    /// and the bottom half has the ground color.
+    /// Colors go through the same [tonemapping][Renderer::set_tonemap] as lit meshes.
+    EnvironmentGradient,
+}
+
+impl Default for Background {
+    fn default() -> Self {
+        Self::Solid([0., 0., 0., 1.])
+    }
+}
+
 pub struct Frame<'a> {
     renderer: &'a mut Renderer,
     // encoder and surface in Options
@@ -368,19 +738,71 @@ pub struct Frame<'a> {
     encoder: Option<wgpu::CommandEncoder>,
     surface: Option<wgpu::SurfaceTexture>,
     target_view: wgpu::TextureView,
-    clear_color: Option<wgpu::Color>,
+    // taken by the first pass that draws to the screen
+    clear: Option<Background>,
+    // intersections of all pushed scissor rects, last one is in effect
+    scissor_stack: Vec<ScissorRect>,
 }
 
 impl Frame<'_> {
     /// Set the color the framebuffer will be cleared with
-    /// when the shading is executed. Black by default.
+    /// when the shading is executed,
+    /// replacing the [renderer's background][Renderer::set_background] for this frame.
     pub fn set_clear_color(&mut self, color: [f32; 4]) {
-        self.clear_color = Some(wgpu::Color {
-            r: color[0] as f64,
-            g: color[1] as f64,
-            b: color[2] as f64,
-            a: color[3] as f64,
+        self.clear = Some(Background::Solid(color));
+    }
+
+    /// See [`CustomPass::take_clear_color`].
+    fn take_clear_color(&mut self) -> Option<wgpu::Color> {
+        self.custom_pass().take_clear_color()
+    }
+
+    /// Get the command encoder and render targets of this frame
+    /// for recording a custom render pass, e.g. for a UI library or a post-processing effect.
+    ///
+    /// Commands recorded here run after everything drawn before this call
+    /// and before everything drawn after it.
+    /// To record commands in the middle of drawing meshes, use
+    /// [`draw_meshes_with_hook`][Self::draw_meshes_with_hook].
+    pub fn custom_pass(&mut self) -> CustomPass<'_> {
+        CustomPass {
+            encoder: self.encoder.as_mut().unwrap(),
+            target_view: &self.target_view,
+            msaa_view: self.renderer.msaa_view.as_ref(),
+            depth_view: &self.renderer.depth_view,
+            clear: &mut self.clear,
+            gi_pipeline: &self.renderer.gi_pipeline,
+        }
+    }
+
+    /// Clip all following draws to the given region of the screen
+    /// until [`pop_scissor`][Self::pop_scissor] is called.
+    ///
+    /// This can be used to e.g. draw a minimap with a second camera into a corner of the screen.
+    /// If a scissor rect is already active, the new one is intersected with it.
+    ///
+    /// Note that clearing the screen with the [clear color][Self::set_clear_color]
+    /// isn't affected by the scissor rect.
+    /// Global illumination is also computed for the entire screen
+    /// every time meshes are drawn, regardless of the scissor rect.
+    pub fn push_scissor(&mut self, rect: ScissorRect) {
+        let window_size = self.renderer.window_size();
+        let bounds = self.scissor_stack.last().copied().unwrap_or(ScissorRect {
+            x: 0,
+            y: 0,
+            width: window_size.width,
+            height: window_size.height,
         });
+        self.scissor_stack.push(rect.intersection(&bounds));
+    }
+
+    /// Remove the scissor rect added by the last call to [`push_scissor`][Self::push_scissor],
+    /// returning to the previous one or drawing to the entire screen if there is none.
+    ///
+    /// Returns the removed rect, which is the intersection of all the rects pushed so far,
+    /// or `None` if no scissor rect was active.
+    pub fn pop_scissor(&mut self) -> Option<ScissorRect> {
+        self.scissor_stack.pop()
     }
 
     /// Draw all meshes in the world.
@@ -390,9 +812,37 @@ impl Frame<'_> {
         world: &mut hecs::World,
         camera: &crate::Camera,
     ) {
+        self.draw_meshes_with_hook(manager, world, camera, |_, _| {});
+    }
+
+    /// Draw all meshes in the world,
+    /// calling `hook` at each [`MeshDrawStage`] to record custom passes
+    /// in between the steps of rendering.
+    pub fn draw_meshes_with_hook(
+        &mut self,
+        manager: &mut crate::GraphicsManager,
+        world: &mut hecs::World,
+        camera: &crate::Camera,
+        mut hook: impl FnMut(MeshDrawStage, CustomPass<'_>),
+    ) {
+        let mut timer = CpuTimer::start(&mut self.renderer.frame_cpu_times);
         let device = Renderer::device();
         let encoder = self.encoder.as_mut().unwrap();
         let mut scope = self.renderer.profiler.scope("draw meshes", encoder, device);
+        // the profiler scope and timer hold parts of the renderer,
+        // so passes are built from the other parts by hand instead of with `custom_pass`
+        macro_rules! custom_pass {
+            () => {
+                CustomPass {
+                    encoder: &mut scope,
+                    target_view: &self.target_view,
+                    msaa_view: self.renderer.msaa_view.as_ref(),
+                    depth_view: &self.renderer.depth_view,
+                    clear: &mut self.clear,
+                    gi_pipeline: &self.renderer.gi_pipeline,
+                }
+            };
+        }
 
         // compute skins
 
@@ -400,10 +850,12 @@ impl Frame<'_> {
             let mut cpass = scope.scoped_compute_pass("compute skins", device);
             self.renderer.skin_pl.compute_skins(&mut cpass, manager);
         }
+        timer.lap("compute skins");
 
         // upload mesh data
 
         self.renderer.mesh_renderer.prepare(manager, world);
+        timer.lap("prepare meshes");
 
         // render depth
 
@@ -427,9 +879,11 @@ impl Frame<'_> {
                 },
             );
 
+            Self::apply_scissor(self.scissor_stack.last(), &mut rpass);
             let mesh_rend = &mut self.renderer.mesh_renderer;
             mesh_rend.depth_pass(&mut rpass, manager, camera);
         }
+        timer.lap("render depth");
 
         // render light emitters and occluders
 
@@ -467,6 +921,10 @@ impl Frame<'_> {
             let mesh_rend = &mut self.renderer.mesh_renderer;
             mesh_rend.emissive_pass(&mut rpass, manager, camera);
         }
+        timer.lap("render lights");
+
+        hook(MeshDrawStage::BeforeLighting, custom_pass!());
+        timer.lap("hook before lighting");
 
         // compute global illumination
 
@@ -474,22 +932,34 @@ impl Frame<'_> {
             let mut cpass = scope.scoped_compute_pass("compute light mips", device);
             self.renderer.gi_pipeline.compute_light_mips(&mut cpass);
         }
+        timer.lap("compute light mips");
 
-        self.renderer.gi_pipeline.compute_gi(&mut scope, camera);
+        self.renderer.frame_stats.spot_lights_drawn =
+            self.renderer
+                .gi_pipeline
+                .compute_gi(&mut scope, camera, |light| {
+                    manager.spot_light_intensity(light)
+                });
+        timer.lap("radiance cascades");
+
+        hook(MeshDrawStage::AfterLighting, custom_pass!());
+        timer.lap("hook after lighting");
 
         // final render
 
+        let clear_color = custom_pass!().take_clear_color();
+
         {
             let mut rpass = scope.scoped_render_pass(
                 "render meshes",
                 device,
                 wgpu::RenderPassDescriptor {
                     label: None,
-                    color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                        view: &self.renderer.msaa_view,
-                        resolve_target: Some(&self.target_view),
-                        ops: Self::ops(self.clear_color.take()),
-                    })],
+                    color_attachments: &[Some(Self::color_attachment(
+                        self.renderer.msaa_view.as_ref(),
+                        &self.target_view,
+                        clear_color,
+                    ))],
                     depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
                         view: &self.renderer.depth_view,
                         depth_ops: Some(wgpu::Operations {
@@ -503,9 +973,11 @@ impl Frame<'_> {
                 },
             );
 
+            Self::apply_scissor(self.scissor_stack.last(), &mut rpass);
             let mesh_rend = &mut self.renderer.mesh_renderer;
             mesh_rend.draw_pass(&mut rpass, manager, camera, &self.renderer.gi_pipeline);
         }
+        timer.lap("render meshes");
     }
 
     /// Draw a collection of line strips with the line renderer.
@@ -515,6 +987,8 @@ impl Frame<'_> {
         camera: &crate::Camera,
         lines: impl IntoIterator<Item = &'s super::line_renderer::LineStrip>,
     ) {
+        let clear_color = self.take_clear_color();
+        let mut timer = CpuTimer::start(&mut self.renderer.frame_cpu_times);
         let device = Renderer::device();
         let encoder = self.encoder.as_mut().unwrap();
         let mut scope = self.renderer.profiler.scope("draw lines", encoder, device);
@@ -526,11 +1000,11 @@ impl Frame<'_> {
 
         let mut pass = scope.begin_render_pass(&wgpu::RenderPassDescriptor {
             label: Some("lines"),
-            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                view: &self.renderer.msaa_view,
-                resolve_target: Some(&self.target_view),
-                ops: Self::ops(self.clear_color.take()),
-            })],
+            color_attachments: &[Some(Self::color_attachment(
+                self.renderer.msaa_view.as_ref(),
+                &self.target_view,
+                clear_color,
+            ))],
             depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
                 view: &self.renderer.depth_view,
                 depth_ops: Some(wgpu::Operations {
@@ -542,21 +1016,38 @@ impl Frame<'_> {
             occlusion_query_set: None,
             timestamp_writes: None,
         });
+        Self::apply_scissor(self.scissor_stack.last(), &mut pass);
         for line in lines {
             line_rend.draw(&mut pass, manager, camera, line);
         }
+        timer.lap("draw lines");
     }
 
-    /// Begin a render pass with default parameters that draws to the screen.
-    pub fn pass(&mut self) -> wgpu::RenderPass<'_> {
+    /// Draw a collection of blob sets with the blob renderer.
+    pub fn draw_blobs<'s>(
+        &mut self,
+        manager: &crate::GraphicsManager,
+        camera: &crate::Camera,
+        blobs: impl IntoIterator<Item = &'s super::blob_renderer::BlobSet>,
+    ) {
+        let clear_color = self.take_clear_color();
+        let mut timer = CpuTimer::start(&mut self.renderer.frame_cpu_times);
+        let device = Renderer::device();
         let encoder = self.encoder.as_mut().unwrap();
-        encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
-            label: None,
-            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                view: &self.renderer.msaa_view,
-                resolve_target: Some(&self.target_view),
-                ops: Self::ops(self.clear_color.take()),
-            })],
+        let mut scope = self.renderer.profiler.scope("draw blobs", encoder, device);
+
+        let blob_rend = self
+            .renderer
+            .blob_renderer
+            .get_or_insert_with(BlobRenderer::new);
+
+        let mut pass = scope.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("blobs"),
+            color_attachments: &[Some(Self::color_attachment(
+                self.renderer.msaa_view.as_ref(),
+                &self.target_view,
+                clear_color,
+            ))],
             depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
                 view: &self.renderer.depth_view,
                 depth_ops: Some(wgpu::Operations {
@@ -567,26 +1058,278 @@ impl Frame<'_> {
             }),
             occlusion_query_set: None,
             timestamp_writes: None,
-        })
+        });
+        Self::apply_scissor(self.scissor_stack.last(), &mut pass);
+        for blob_set in blobs {
+            blob_rend.draw(&mut pass, manager, camera, blob_set);
+        }
+        timer.lap("draw blobs");
+    }
+
+    /// Draw a string of text on top of everything else, for quick debug information
+    /// without setting up a full UI library.
+    ///
+    /// `screen_pos` is the top left corner of the text in physical screen pixels.
+    /// Text is drawn in white with a tiny built-in font
+    /// that only has uppercase letters, numbers and common punctuation;
+    /// lowercase letters are drawn as uppercase and other characters as question marks.
+    /// Newlines start a new line.
+    pub fn debug_text(&mut self, screen_pos: crate::Vec2, text: &str) {
+        let window_size = self.renderer.window_size();
+        let pixels = debug_text::layout_text(
+            text,
+            screen_pos,
+            window_size.into(),
+            self.renderer.window_scale_factor,
+        );
+        if pixels.is_empty() {
+            return;
+        }
+        // clearing could draw the background, leave that out of the timing
+        let clear_color = self.take_clear_color();
+        let mut timer = CpuTimer::start(&mut self.renderer.frame_cpu_times);
+
+        use wgpu::util::DeviceExt;
+        let device = Renderer::device();
+        let pixel_buf = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("debug text"),
+            contents: pixels.as_bytes(),
+            usage: wgpu::BufferUsages::VERTEX,
+        });
+
+        let encoder = self.encoder.as_mut().unwrap();
+        let mut scope = self.renderer.profiler.scope("debug text", encoder, device);
+
+        let text_rend = self
+            .renderer
+            .debug_text_renderer
+            .get_or_insert_with(DebugTextRenderer::new);
+
+        let mut pass = scope.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("debug text"),
+            color_attachments: &[Some(Self::color_attachment(
+                self.renderer.msaa_view.as_ref(),
+                &self.target_view,
+                clear_color,
+            ))],
+            depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                view: &self.renderer.depth_view,
+                depth_ops: Some(wgpu::Operations {
+                    load: wgpu::LoadOp::Load,
+                    store: wgpu::StoreOp::Store,
+                }),
+                stencil_ops: None,
+            }),
+            occlusion_query_set: None,
+            timestamp_writes: None,
+        });
+        Self::apply_scissor(self.scissor_stack.last(), &mut pass);
+        text_rend.draw(&mut pass, &pixel_buf, pixels.len() as u32);
+        timer.lap("debug text");
+    }
+
+    /// Begin a render pass with default parameters that draws to the screen.
+    ///
+    /// The pass is clipped to the current [scissor rect][Self::push_scissor], if any.
+    pub fn pass(&mut self) -> wgpu::RenderPass<'_> {
+        let scissor = self.scissor_stack.last().copied();
+        let mut pass = self.custom_pass().begin_render_pass(None);
+        Self::apply_scissor(scissor.as_ref(), &mut pass);
+        pass
+    }
+
+    fn apply_scissor(scissor: Option<&ScissorRect>, pass: &mut wgpu::RenderPass<'_>) {
+        if let Some(rect) = scissor {
+            pass.set_scissor_rect(rect.x, rect.y, rect.width, rect.height);
+        }
     }
 
-    fn ops(clear_color: Option<wgpu::Color>) -> wgpu::Operations<wgpu::Color> {
-        wgpu::Operations {
+    /// Color attachment that draws to the screen,
+    /// resolving from the multisampled target if there is one.
+    fn color_attachment<'t>(
+        msaa_view: Option<&'t wgpu::TextureView>,
+        target_view: &'t wgpu::TextureView,
+        clear_color: Option<wgpu::Color>,
+    ) -> wgpu::RenderPassColorAttachment<'t> {
+        let ops = wgpu::Operations {
             load: match clear_color {
                 Some(color) => wgpu::LoadOp::Clear(color),
                 None => wgpu::LoadOp::Load,
             },
             store: wgpu::StoreOp::Store,
+        };
+        match msaa_view {
+            Some(msaa_view) => wgpu::RenderPassColorAttachment {
+                view: msaa_view,
+                resolve_target: Some(target_view),
+                ops,
+            },
+            None => wgpu::RenderPassColorAttachment {
+                view: target_view,
+                resolve_target: None,
+                ops,
+            },
         }
     }
 
+    /// Get the window depth buffer, e.g. for reading depth in a custom post-processing effect.
+    ///
+    /// The texture has format [`DEPTH_FORMAT`] and supports
+    /// [`TEXTURE_BINDING`][wgpu::TextureUsages::TEXTURE_BINDING].
+    /// When [multisampling][Renderer::msaa_samples] is enabled it's multisampled as well
+    /// and must be bound as `texture_depth_multisampled_2d`, otherwise as `texture_depth_2d`.
+    /// Depth textures can't be filtered, read them with `textureLoad`.
+    ///
+    /// The texture can't be sampled in a pass that also uses it as the depth attachment,
+    /// such as the one returned by [`pass`][Self::pass].
+    /// It's recreated when the window is resized,
+    /// so bind groups referring to it should be created every frame
+    /// or recreated when the window size changes.
+    #[inline]
+    pub fn depth_texture(&self) -> &wgpu::Texture {
+        &self.renderer.depth_tex
+    }
+
+    /// Get a view of the whole [depth texture][Self::depth_texture].
+    #[inline]
+    pub fn depth_view(&self) -> &wgpu::TextureView {
+        &self.renderer.depth_view
+    }
+
     /// Access the command encoder recording commands for this frame.
+    ///
+    /// Use [`custom_pass`][Self::custom_pass] instead to also get the render targets.
     #[inline]
     pub fn encoder_mut(&mut self) -> &mut wgpu::CommandEncoder {
         self.encoder.as_mut().unwrap()
     }
 }
 
+/// Points in [`Frame::draw_meshes_with_hook`] where custom passes can be recorded.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MeshDrawStage {
+    /// The depth buffer and the light emitters and occluders used for global illumination
+    /// have been rendered, but lighting hasn't been computed yet.
+    BeforeLighting,
+    /// Global illumination has been computed,
+    /// but meshes haven't been drawn to the screen yet.
+    AfterLighting,
+}
+
+/// The command encoder and render targets of a [`Frame`],
+/// for recording custom render or compute passes.
+///
+/// Get one with [`Frame::custom_pass`], or in the middle of drawing meshes
+/// with [`Frame::draw_meshes_with_hook`].
+///
+/// Pipelines for passes drawing to the screen need to match its render targets:
+/// - color format [`Renderer::swapchain_format`]
+/// - depth format [`Renderer::depth_format`], e.g. with [`Renderer::default_depth_stencil_state`]
+/// - sample count [`Renderer::msaa_samples`], e.g. with [`Renderer::default_multisample_state`]
+///
+/// Create resources with [`Renderer::device`] and upload data with [`Renderer::queue`].
+/// Passes recorded here aren't clipped to the frame's [scissor rect][Frame::push_scissor].
+pub struct CustomPass<'a> {
+    encoder: &'a mut wgpu::CommandEncoder,
+    target_view: &'a wgpu::TextureView,
+    msaa_view: Option<&'a wgpu::TextureView>,
+    depth_view: &'a wgpu::TextureView,
+    // shared with the frame so that whichever pass draws to the screen first clears it
+    clear: &'a mut Option<Background>,
+    gi_pipeline: &'a gi::GlobalIlluminationPipeline,
+}
+
+impl<'a> CustomPass<'a> {
+    /// Access the command encoder recording commands for this frame.
+    #[inline]
+    pub fn encoder(&mut self) -> &mut wgpu::CommandEncoder {
+        self.encoder
+    }
+
+    /// Color attachment that draws to the screen.
+    ///
+    /// This takes care of resolving multisampling
+    /// and clearing the screen if nothing has been drawn to it yet this frame,
+    /// so it should be used for every pass that draws to the screen
+    /// instead of the [target view][Self::target_view].
+    pub fn color_attachment(&mut self) -> wgpu::RenderPassColorAttachment<'a> {
+        let clear_color = self.take_clear_color();
+        Frame::color_attachment(self.msaa_view, self.target_view, clear_color)
+    }
+
+    /// Depth attachment that tests against and writes to the window depth buffer.
+    pub fn depth_attachment(&self) -> wgpu::RenderPassDepthStencilAttachment<'a> {
+        wgpu::RenderPassDepthStencilAttachment {
+            view: self.depth_view,
+            depth_ops: Some(wgpu::Operations {
+                load: wgpu::LoadOp::Load,
+                store: wgpu::StoreOp::Store,
+            }),
+            stencil_ops: None,
+        }
+    }
+
+    /// The texture the frame is finally presented from.
+    ///
+    /// When [multisampling][Renderer::msaa_samples] is enabled,
+    /// drawing happens to a separate multisampled texture that is resolved into this one,
+    /// see [`color_attachment`][Self::color_attachment].
+    #[inline]
+    pub fn target_view(&self) -> &'a wgpu::TextureView {
+        self.target_view
+    }
+
+    /// The window depth buffer, see [`Frame::depth_texture`] for details.
+    #[inline]
+    pub fn depth_view(&self) -> &'a wgpu::TextureView {
+        self.depth_view
+    }
+
+    /// Begin a render pass that draws to the screen
+    /// with the [color][Self::color_attachment] and [depth][Self::depth_attachment] attachments.
+    pub fn begin_render_pass(mut self, label: Option<&str>) -> wgpu::RenderPass<'a> {
+        let color = self.color_attachment();
+        let depth = self.depth_attachment();
+        self.encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label,
+            color_attachments: &[Some(color)],
+            depth_stencil_attachment: Some(depth),
+            occlusion_query_set: None,
+            timestamp_writes: None,
+        })
+    }
+
+    /// Clear the screen if nothing has been drawn to it yet this frame.
+    ///
+    /// Returns the color the next pass should clear with,
+    /// or None if the screen is already cleared and should be loaded instead.
+    fn take_clear_color(&mut self) -> Option<wgpu::Color> {
+        match self.clear.take()? {
+            Background::Solid(color) => Some(wgpu::Color {
+                r: color[0] as f64,
+                g: color[1] as f64,
+                b: color[2] as f64,
+                a: color[3] as f64,
+            }),
+            Background::EnvironmentGradient => {
+                let mut pass = self.encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                    label: Some("background"),
+                    color_attachments: &[Some(Frame::color_attachment(
+                        self.msaa_view,
+                        self.target_view,
+                        Some(wgpu::Color::BLACK),
+                    ))],
+                    depth_stencil_attachment: None,
+                    occlusion_query_set: None,
+                    timestamp_writes: None,
+                });
+                self.gi_pipeline.draw_background(&mut pass);
+                None
+            }
+        }
+    }
+}
+
 impl Drop for Frame<'_> {
     fn drop(&mut self) {
         let queue = Renderer::queue();
@@ -594,5 +1337,150 @@ impl Drop for Frame<'_> {
         self.renderer.profiler.resolve_queries(&mut encoder);
         queue.submit(Some(encoder.finish()));
         self.surface.take().unwrap().present();
+
+        let stats = &mut self.renderer.frame_stats;
+        std::mem::swap(&mut stats.cpu_times, &mut self.renderer.frame_cpu_times);
+        self.renderer.frame_cpu_times.clear();
+    }
+}
+
+/// Find the sample count in `supported` closest to `requested`,
+/// preferring the smaller one if two are equally close.
+fn nearest_sample_count(supported: &[u32], requested: u32) -> u32 {
+    supported
+        .iter()
+        .copied()
+        .min_by_key(|&count| (count.abs_diff(requested), count))
+        .unwrap_or(1)
+}
+
+/// Helper for recording the CPU time of consecutive parts of a frame.
+struct CpuTimer<'a> {
+    times: &'a mut Vec<(String, Duration)>,
+    start: Instant,
+}
+
+impl<'a> CpuTimer<'a> {
+    fn start(times: &'a mut Vec<(String, Duration)>) -> Self {
+        Self {
+            times,
+            start: Instant::now(),
+        }
+    }
+
+    /// Record the time since the last lap under `label`.
+    fn lap(&mut self, label: &str) {
+        let now = Instant::now();
+        self.times.push((label.to_string(), now - self.start));
+        self.start = now;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn msaa_fallback() {
+        assert_eq!(nearest_sample_count(&[1, 4], 4), 4);
+        assert_eq!(nearest_sample_count(&[1, 4], 8), 4);
+        assert_eq!(nearest_sample_count(&[1, 4], 2), 1);
+        assert_eq!(nearest_sample_count(&[1, 2, 4], 3), 2);
+        assert_eq!(nearest_sample_count(&[1, 2, 4, 8], 16), 8);
+    }
+
+    #[test]
+    fn gpu_times_flattened_with_paths() {
+        let query = |label: &str, time: std::ops::Range<f64>, nested| wp::GpuTimerQueryResult {
+            label: label.to_string(),
+            pid: 0,
+            tid: std::thread::current().id(),
+            time: Some(time),
+            nested_queries: nested,
+        };
+        let results = [
+            query(
+                "draw meshes",
+                0.0..0.003,
+                vec![
+                    query("render depth", 0.0..0.001, Vec::new()),
+                    query("render meshes", 0.001..0.003, Vec::new()),
+                ],
+            ),
+            query("draw lines", 0.003..0.004, Vec::new()),
+        ];
+        let mut stats = FrameStats::default();
+        stats.push_gpu_results("", &results);
+
+        let labels: Vec<&str> = stats.gpu_times.iter().map(|(l, _)| l.as_str()).collect();
+        assert_eq!(
+            labels,
+            [
+                "draw meshes",
+                "draw meshes/render depth",
+                "draw meshes/render meshes",
+                "draw lines"
+            ]
+        );
+        let total = stats.total_gpu_time().as_secs_f64();
+        assert!((total - 0.004).abs() < 1e-9, "{total}");
+    }
+
+    #[test]
+    fn scissor_intersection() {
+        let a = ScissorRect {
+            x: 0,
+            y: 10,
+            width: 100,
+            height: 50,
+        };
+        let b = ScissorRect {
+            x: 80,
+            y: 0,
+            width: 100,
+            height: 20,
+        };
+        let expected = ScissorRect {
+            x: 80,
+            y: 10,
+            width: 20,
+            height: 10,
+        };
+        assert_eq!(a.intersection(&b), expected);
+        assert_eq!(b.intersection(&a), expected);
+
+        let disjoint = ScissorRect {
+            x: 200,
+            y: 20,
+            width: 10,
+            height: 10,
+        };
+        let empty = a.intersection(&disjoint);
+        assert_eq!((empty.width, empty.height), (0, 10));
+        // mustn't go past the end of `a`, or it's invalid for a target of that size
+        assert_eq!(empty.x, 100);
+
+        let screen = ScissorRect {
+            x: 0,
+            y: 0,
+            width: 1920,
+            height: 1080,
+        };
+        let off_screen = ScissorRect {
+            x: 5000,
+            y: u32::MAX - 5,
+            width: 100,
+            height: 100,
+        };
+        let clipped = off_screen.intersection(&screen);
+        assert_eq!(
+            clipped,
+            ScissorRect {
+                x: 1920,
+                y: 1080,
+                width: 0,
+                height: 0,
+            }
+        );
     }
 }
@@ -0,0 +1,75 @@
+// shares the bind group of the final mesh render
+// to get the same environment colors and tonemapping
+
+struct RenderParams {
+    probe_spacing: f32,
+    probe_range: f32,
+    probe_count: vec2<u32>,
+    mip_bias: f32,
+    skip_raymarch: u32,
+    // 0 = none, 1 = reinhard, 2 = aces
+    tonemap_mode: u32,
+    exposure: f32,
+}
+@group(0) @binding(0)
+var<uniform> params: RenderParams;
+
+struct Environment {
+    ambient_light: vec3<f32>,
+    zenith: vec3<f32>,
+    horizon: vec3<f32>,
+    ground: vec3<f32>,
+}
+@group(0) @binding(4)
+var<uniform> environment: Environment;
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    // -1 at the bottom of the screen, 1 at the top
+    @location(0) height: f32,
+};
+
+// single full-screen triangle, same as in radiance_cascades.wgsl
+@vertex
+fn vs_main(
+    @builtin(vertex_index) vert_idx: u32,
+) -> VertexOutput {
+    var out: VertexOutput;
+
+    let uv = vec2<f32>(f32((vert_idx << 1u) & 2u), f32(vert_idx & 2u));
+    out.position = vec4<f32>(uv.x * 2.0 - 1.0, uv.y * -2.0 + 1.0, 0.0, 1.0);
+    out.height = out.position.y;
+
+    return out;
+}
+
+@fragment
+fn fs_main(
+    in: VertexOutput
+) -> @location(0) vec4<f32> {
+    // screen height treated as the sine of the view angle,
+    // blended the same way as when baking the environment map
+    var color = environment.ground;
+    if in.height >= 0. {
+        color = mix(environment.horizon, environment.zenith, in.height);
+    }
+    return vec4<f32>(tonemap(color), 1.);
+}
+
+// copied from mesh.wgsl
+fn tonemap(color: vec3<f32>) -> vec3<f32> {
+    let c = color * params.exposure;
+    switch params.tonemap_mode {
+        case 1u: {
+            return c / (1. + c);
+        }
+        case 2u: {
+            // Krzysztof Narkowicz's fit of the ACES filmic curve
+            let mapped = (c * (2.51 * c + 0.03)) / (c * (2.43 * c + 0.59) + 0.14);
+            return clamp(mapped, vec3<f32>(0.), vec3<f32>(1.));
+        }
+        default: {
+            return c;
+        }
+    }
+}
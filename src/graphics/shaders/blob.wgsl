@@ -0,0 +1,110 @@
+struct CameraUniforms {
+    view_proj: mat4x4<f32>,
+}
+
+@group(0) @binding(0)
+var<uniform> camera: CameraUniforms;
+
+struct MaterialUniforms {
+    base_color: vec4<f32>,
+}
+@group(1) @binding(0)
+var<uniform> material: MaterialUniforms;
+@group(1) @binding(1)
+var t_diffuse: texture_2d<f32>;
+@group(1) @binding(2)
+var s_diffuse: sampler;
+@group(1) @binding(3)
+var t_normal: texture_2d<f32>;
+@group(1) @binding(4)
+var s_normal: sampler;
+
+struct BlobParams {
+    bounds_min: vec2<f32>,
+    bounds_max: vec2<f32>,
+    depth: f32,
+    smoothness: f32,
+    shape_count: u32,
+}
+@group(2) @binding(0)
+var<uniform> params: BlobParams;
+
+// a capsule from start to end, or a circle if they're the same point
+struct BlobShape {
+    start: vec2<f32>,
+    end: vec2<f32>,
+    radius: f32,
+}
+@group(2) @binding(1)
+var<storage, read> shapes: array<BlobShape>;
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) pos_world: vec2<f32>,
+    @location(1) uv: vec2<f32>,
+};
+
+@vertex
+fn vs_main(@builtin(vertex_index) idx: u32) -> VertexOutput {
+    var out: VertexOutput;
+
+    // a quad covering the bounding box of all shapes, as two triangles
+    var corners = array<vec2<f32>, 6>(
+        vec2<f32>(0., 0.),
+        vec2<f32>(1., 0.),
+        vec2<f32>(1., 1.),
+        vec2<f32>(0., 0.),
+        vec2<f32>(1., 1.),
+        vec2<f32>(0., 1.),
+    );
+    let corner = corners[idx];
+    let pos_world = mix(params.bounds_min, params.bounds_max, corner);
+
+    out.clip_position = camera.view_proj * vec4<f32>(pos_world, params.depth, 1.);
+    out.pos_world = pos_world;
+    // texture y axis points down, world y up
+    out.uv = vec2<f32>(corner.x, 1. - corner.y);
+
+    return out;
+}
+
+fn capsule_distance(p: vec2<f32>, shape: BlobShape) -> f32 {
+    let to_p = p - shape.start;
+    let axis = shape.end - shape.start;
+    let axis_len_sq = dot(axis, axis);
+    var t = 0.;
+    if axis_len_sq > 0. {
+        t = clamp(dot(to_p, axis) / axis_len_sq, 0., 1.);
+    }
+    return length(to_p - t * axis) - shape.radius;
+}
+
+// polynomial smooth minimum, see https://iquilezles.org/articles/smin/
+fn smooth_min(a: f32, b: f32, k: f32) -> f32 {
+    if k <= 0. {
+        return min(a, b);
+    }
+    let h = max(k - abs(a - b), 0.) / k;
+    return min(a, b) - h * h * k * 0.25;
+}
+
+@fragment
+fn fs_main(
+    in: VertexOutput
+) -> @location(0) vec4<f32> {
+    var dist = 1e10;
+    for (var i = 0u; i < params.shape_count; i++) {
+        dist = smooth_min(dist, capsule_distance(in.pos_world, shapes[i]), params.smoothness);
+    }
+
+    // sampled before discarding because derivatives need uniform control flow
+    let tex_color = textureSample(t_diffuse, s_diffuse, in.uv);
+    let color = material.base_color * tex_color;
+
+    // antialias the edge over about one pixel
+    let coverage = clamp(0.5 - dist / fwidth(dist), 0., 1.);
+    if coverage <= 0. {
+        discard;
+    }
+    return vec4<f32>(color.rgb, color.a * coverage);
+}
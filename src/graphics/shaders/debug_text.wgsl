@@ -0,0 +1,42 @@
+// each instance is one lit pixel of a glyph,
+// given directly in clip space
+
+struct InstanceInput {
+    @location(0) min: vec2<f32>,
+    @location(1) size: vec2<f32>,
+    @location(2) color: vec4<f32>,
+}
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) color: vec4<f32>,
+};
+
+@vertex
+fn vs_main(
+    @builtin(vertex_index) idx: u32,
+    inst: InstanceInput,
+) -> VertexOutput {
+    var out: VertexOutput;
+
+    var corners = array<vec2<f32>, 6>(
+        vec2<f32>(0., 0.),
+        vec2<f32>(1., 0.),
+        vec2<f32>(1., 1.),
+        vec2<f32>(0., 0.),
+        vec2<f32>(1., 1.),
+        vec2<f32>(0., 1.),
+    );
+    let pos = inst.min + corners[idx] * inst.size;
+    out.clip_position = vec4<f32>(pos, 0., 1.);
+    out.color = inst.color;
+
+    return out;
+}
+
+@fragment
+fn fs_main(
+    in: VertexOutput
+) -> @location(0) vec4<f32> {
+    return in.color;
+}
@@ -33,6 +33,7 @@ var s_normal: sampler;
 
 struct InstanceUniforms {
     model: mat4x4<f32>,
+    tint: vec4<f32>,
 }
 
 @group(2) @binding(0)
@@ -78,7 +79,8 @@ struct LightOutput {
 fn fs_emissive(in: VertexOutput) -> LightOutput {
     var out: LightOutput;
 
-    out.emission = material.emissive_color;
+    // tint alpha only affects the main pass transparency
+    out.emission = vec4<f32>(instance.tint.rgb, 1.) * material.emissive_color;
     out.attenuation = material.attenuation;
 
     return out;
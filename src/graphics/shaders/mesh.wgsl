@@ -22,6 +22,9 @@ struct CascadeRenderParams {
     // improving performance at the cost of worse looking light edges.
     // actually a bool but using that type here breaks alignment
     skip_raymarch: u32,
+    // 0 = none, 1 = reinhard, 2 = aces
+    tonemap_mode: u32,
+    exposure: f32,
 }
 @group(1) @binding(0)
 var<uniform> light_params: CascadeRenderParams;
@@ -63,6 +66,7 @@ var s_normal: sampler;
 
 struct InstanceUniforms {
     model: mat4x4<f32>,
+    tint: vec4<f32>,
 }
 
 @group(3) @binding(0)
@@ -133,7 +137,7 @@ fn vs_main(
 // but somewhat increases the cost of pixel shading.
 // this is a simplified version of the `raymarch` function in radiance_cascades.wgsl,
 // only raymarching on the 0th mip level
-// and ignoring translucent materials for simplicity,
+// and ignoring translucent materials and spot lights for simplicity,
 // assuming the drop in quality is minimal due to short range
 
 struct Ray {
@@ -245,7 +249,8 @@ fn fs_main(
 ) -> @location(0) vec4<f32> {
     // get the necessary parameters
 
-    let diffuse_color = material.base_color * textureSample(t_diffuse, s_diffuse, in.tex_coords);
+    let tex_color = textureSample(t_diffuse, s_diffuse, in.tex_coords);
+    let diffuse_color = instance.tint * material.base_color * tex_color;
 
     let bitangent = cross(in.tangent, in.normal);
     let tbn = mat3x3(in.tangent, bitangent, in.normal);
@@ -343,6 +348,24 @@ fn fs_main(
     // regardless of the normal we should get exactly (1, 1, 1) irradiance
     irradiance /= total_weight;
 
-    return vec4<f32>(irradiance, 1.) * diffuse_color;
+    let color = vec4<f32>(irradiance, 1.) * diffuse_color;
+    return vec4<f32>(tonemap(color.rgb), color.a);
+}
+
+fn tonemap(color: vec3<f32>) -> vec3<f32> {
+    let c = color * light_params.exposure;
+    switch light_params.tonemap_mode {
+        case 1u: {
+            return c / (1. + c);
+        }
+        case 2u: {
+            // Krzysztof Narkowicz's fit of the ACES filmic curve
+            let mapped = (c * (2.51 * c + 0.03)) / (c * (2.43 * c + 0.59) + 0.14);
+            return clamp(mapped, vec3<f32>(0.), vec3<f32>(1.));
+        }
+        default: {
+            return c;
+        }
+    }
 }
 
@@ -12,6 +12,26 @@ var env_map: texture_1d<f32>;
 @group(0) @binding(2)
 var<uniform> frame: FrameParams;
 
+// positioned light shining in a cone,
+// in the pixel space of the light texture
+struct SpotLight {
+    position: vec2<f32>,
+    direction: vec2<f32>,
+    color: vec4<f32>,
+    radius: f32,
+    cos_outer: f32,
+    cos_inner: f32,
+    // per world unit
+    attenuation: f32,
+}
+const MAX_SPOT_LIGHTS: u32 = 16u;
+struct SpotLights {
+    count: u32,
+    lights: array<SpotLight, MAX_SPOT_LIGHTS>,
+}
+@group(0) @binding(3)
+var<uniform> spot_lights: SpotLights;
+
 struct CascadeParams {
     level: u32,
     level_count: u32,
@@ -80,6 +100,8 @@ struct Ray {
     start: vec2<f32>,
     dir: vec2<f32>,
     range: f32,
+    // distance from the probe to the start of the ray
+    offset: f32,
     // angle from the x axis normalized to the range [0, 1],
     // for sampling the environment map
     angle_normalized: f32,
@@ -100,6 +122,7 @@ fn get_ray(dir: DirectionInfo, subray_idx: u32) -> Ray {
     ray.dir = vec2<f32>(cos(ray_angle), sin(ray_angle));
     ray.start = dir.probe_pos + dir.range_start * ray.dir;
     ray.range = dir.range_length;
+    ray.offset = dir.range_start;
     ray.angle_normalized = ray_angle / TAU;
 
     return ray;
@@ -134,6 +157,37 @@ fn sample_attenuation(uv: vec2<f32>, mip_level: f32) -> vec4<f32> {
     );
 }
 
+// distance along the ray where it enters the emitting disk of a spot light,
+// or a negative value if it doesn't hit the light
+// or the light doesn't shine towards the start of the ray
+fn spot_light_hit(ray: Ray, light: SpotLight) -> f32 {
+    // light travels opposite to the ray
+    let cos_angle = dot(-ray.dir, light.direction);
+    if cos_angle <= light.cos_outer {
+        return -1.;
+    }
+
+    let to_light = light.position - ray.start;
+    let t_closest = dot(to_light, ray.dir);
+    let dist_sq = dot(to_light, to_light) - t_closest * t_closest;
+    let r_sq = light.radius * light.radius;
+    if dist_sq > r_sq {
+        return -1.;
+    }
+    let t_hit = max(t_closest - sqrt(r_sq - dist_sq), 0.);
+    if t_hit > ray.range {
+        return -1.;
+    }
+    return t_hit;
+}
+
+// radiance reaching the probe from a spot light hit by a ray
+fn spot_light_radiance(ray: Ray, light: SpotLight, t_hit: f32) -> vec3<f32> {
+    let cone = smoothstep(light.cos_outer, light.cos_inner, dot(-ray.dir, light.direction));
+    let dist_world = (ray.offset + t_hit) * frame.pixel_size_world;
+    return light.color.rgb * cone * exp(-light.attenuation * dist_world);
+}
+
 // raymarch on the light texture to gather radiance
 fn raymarch(ray: Ray) -> RayResult {
     var out: RayResult;
@@ -163,6 +217,17 @@ fn raymarch(ray: Ray) -> RayResult {
     let uv = ray_pos / screen_size;
     var prev_rad = sample_emission(uv, mip_level);
     var prev_attn = sample_attenuation(uv, mip_level);
+
+    // spot lights aren't in the light texture,
+    // instead we find where the ray hits them beforehand
+    // and add their radiance when the raymarch reaches that point
+    var spot_hits: array<f32, MAX_SPOT_LIGHTS>;
+    var any_spot_hit = false;
+    for (var light_idx = 0u; light_idx < spot_lights.count; light_idx++) {
+        spot_hits[light_idx] = spot_light_hit(ray, spot_lights.lights[light_idx]);
+        any_spot_hit = any_spot_hit || spot_hits[light_idx] >= 0.;
+    }
+
     // bounded loop as a failsafe to avoid hanging
     // in case there's a bug that causes the raymarch to stop in place
     for (var loop_idx = 0u; loop_idx < 10000u; loop_idx++) {
@@ -174,6 +239,16 @@ fn raymarch(ray: Ray) -> RayResult {
             range_overrun = true;
         }
 
+        if any_spot_hit {
+            for (var light_idx = 0u; light_idx < spot_lights.count; light_idx++) {
+                let t_hit = spot_hits[light_idx];
+                if t_hit >= t && t_hit < t + t_step {
+                    let light = spot_lights.lights[light_idx];
+                    out.radiance += out.transparency * spot_light_radiance(ray, light, t_hit);
+                }
+            }
+        }
+
         t += t_step;
         ray_pos += t_step * ray.dir;
 
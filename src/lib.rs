@@ -1,5 +1,5 @@
 pub mod game;
-pub use game::{Game, GameParams, GameState, GraphicsConfig};
+pub use game::{FrameTimings, Game, GameParams, GameState, GraphicsConfig};
 
 pub mod input;
 pub use input::{AxisQuery, Button, ButtonQuery, Input, Key, MouseButton};
@@ -7,34 +7,44 @@ pub use input::{AxisQuery, Button, ButtonQuery, Input, Key, MouseButton};
 pub mod math;
 #[cfg(feature = "serde-types")]
 pub use math::serde_pose;
-pub use math::{uv, Angle, DVec2, PhysicsPose, Pose, PoseBuilder, Rotor2, Rotor3, Vec2, Vec3};
+pub use math::{
+    uv, Angle, DVec2, PhysicsPose, Pose, PoseBuilder, Rotor2, Rotor3, Transform2D, Vec2, Vec3,
+};
 
 pub mod graphics;
 pub use graphics::{
+    atlas::{TextureAtlas, UvRect},
     camera::{Camera, MouseDragCameraController},
     gi::{
-        environment_map::{DirectionalLight, EnvironmentMap},
-        LightingQualityConfig,
+        environment_map::{
+            DirectionalLight, EnvironmentMap, EnvironmentMapAnimator, EnvironmentMapLoop,
+        },
+        spot_light::{LightAnimator, LightWaveform, SpotLight, SpotLightKey},
+        LightingQualityConfig, Tonemap, TonemapMode,
     },
     material::{AttenuationParams, Material, MaterialParams, Texture, TextureData},
-    mesh::{ConvexMeshShape, Mesh, MeshData, MeshParams, Skin},
-    AnimationId, Animator, GraphicsManager, LineStrip, LineVertex, MaterialId, MeshId, MeshVertex,
-    Renderer,
+    mesh::{ConvexMeshShape, Mesh, MeshBuilder, MeshData, MeshParams, Skin, Tint},
+    AnimationId, Animator, AnimatorId, Background, BlobSet, BlobShape, CustomPass, FrameStats,
+    GraphicsManager, LightAnimatorId, LineStrip, LineVertex, MaterialId, MeshDrawStage, MeshId,
+    MeshVertex, Renderer, ScissorRect,
 };
 
 pub mod physics;
 pub use physics::{
     body::{Body, ColliderInfo, Mass},
     collision::{
-        self, Collider, ColliderPolygon, ColliderShape, ColliderType, CollisionLayerMask,
-        CollisionMaskMatrix, CompoundColliderSetup, Contact, ContactResult, PhysicsMaterial, Ray,
+        self, Broadphase, Collider, ColliderPolygon, ColliderShape, ColliderType,
+        CollisionLayerMask, CollisionLayers, CollisionMaskMatrix, CollisionMaskMatrixBuilder,
+        CompoundColliderSetup, Contact, ContactResult, PhysicsMaterial, Ray, SpatialHashBroadphase,
         AABB,
     },
     constraint::{Constraint, ConstraintBuilder, ConstraintLimit, ConstraintType},
     forcefield,
     hecs_sync::{HecsSyncManager, HecsSyncOptions},
-    BodyKey, CastHit, ColliderKey, ConstraintKey, ContactInfo, PhysicsWorld, Rope, RopeKey,
-    RopeParameters, RopeSet, Velocity,
+    BodyKey, CastHit, CharacterController, CharacterMove, ColliderKey, ConstraintKey, ContactId,
+    ContactInfo, ContactOverride, GroundInfo, ImpulseFalloff, MouseSpring, PhysicsStats,
+    PhysicsWorld, PickHit, RegionEvent, RegionEventKind, Rope, RopeKey, RopeParameters, RopeSet,
+    SubstepView, SurfaceKind, Velocity,
 };
 
 // re-exported libraries used in public APIs to guarantee versions match
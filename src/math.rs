@@ -1,4 +1,13 @@
 //! Types, aliases and helper operations for doing math with `ultraviolet`.
+//!
+//! # Serialization
+//!
+//! With the `serde-types` feature, every public math type can be serialized.
+//! Vectors use `ultraviolet`'s representation, e.g. `(x: 1.0, y: 2.0)` in RON.
+//! Rotors are stored as their components `(s: .., bv: (xy: ..))`,
+//! where a counterclockwise rotation by angle `a` has `s = cos(a/2)` and `xy = -sin(a/2)`.
+//! To store a rotor as an [`Angle`] in degrees instead, use [`serde_rotor2`].
+//! [`Pose`] and [`Transform2D`] are stored with angles in degrees.
 
 use std::f32::consts::PI;
 
@@ -25,6 +34,11 @@ pub type PhysicsPose = uv::DIsometry2;
 /// The z component of position, rotations in the xz and yz planes,
 /// and scaling are ignored in physics and only relevant to graphics.
 #[derive(Clone, Copy, Debug, Default)]
+#[cfg_attr(
+    feature = "serde-types",
+    derive(serde::Deserialize, serde::Serialize),
+    serde(from = "PoseBuilder", into = "PoseBuilder")
+)]
 pub struct Pose(pub uv::Similarity3);
 
 impl AsRef<uv::Similarity3> for Pose {
@@ -155,9 +169,157 @@ impl From<PhysicsPose> for Pose {
     }
 }
 
+/// A 2D transform with separate scaling along each axis.
+///
+/// Use this in the ECS world in place of [`Pose`] for entities that need
+/// non-uniform scale or mirroring (negative scale), such as sprites.
+/// Meshes are rendered with this transform if the entity has one;
+/// if an entity has both a `Transform2D` and a `Pose`, the `Pose` is ignored.
+///
+/// # Physics compatibility
+///
+/// Like [`Pose`], this is synced with the physics engine
+/// through the translation and rotation in the xy plane.
+/// Colliders ignore the scale of this transform.
+/// If a collider needs to match a scaled mesh, scale its shape separately with
+/// [`PhysicsWorld::set_collider_scale_xy`][crate::PhysicsWorld::set_collider_scale_xy].
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde-types", derive(serde::Deserialize, serde::Serialize))]
+pub struct Transform2D {
+    /// Position in the xy plane, with depth in the z coordinate.
+    pub translation: Vec3,
+    /// Rotation in the xy plane.
+    #[cfg_attr(feature = "serde-types", serde(with = "serde_rotor2"))]
+    pub rotation: Rotor2,
+    /// Scaling along the local x and y axes, applied before rotation.
+    /// Negative values mirror along the corresponding axis.
+    pub scale: Vec2,
+}
+
+impl Default for Transform2D {
+    fn default() -> Self {
+        Self::identity()
+    }
+}
+
+impl std::ops::Mul<Vec2> for Transform2D {
+    type Output = Vec2;
+
+    fn mul(self, rhs: Vec2) -> Self::Output {
+        self.rotation * (self.scale * rhs) + self.translation.xy()
+    }
+}
+
+impl Transform2D {
+    /// Create a transform from a 2D position and angle, with a scale of one.
+    #[inline]
+    pub fn new(pos: Vec2, angle: Angle) -> Self {
+        Self {
+            translation: Vec3::new(pos.x, pos.y, 0.),
+            rotation: angle.into(),
+            scale: Vec2::one(),
+        }
+    }
+
+    /// Create an identity transform (position at the origin, no rotation, scale of one).
+    #[inline]
+    pub fn identity() -> Self {
+        Self::new(Vec2::zero(), Angle::default())
+    }
+
+    /// Set the z coordinate of this transform in a builder-style fashion.
+    #[inline]
+    pub fn with_depth(mut self, depth: f32) -> Self {
+        self.translation.z = depth;
+        self
+    }
+
+    /// Set the scale of this transform in a builder-style fashion.
+    #[inline]
+    pub fn with_scale(mut self, scale: impl Into<[f32; 2]>) -> Self {
+        self.scale = Vec2::from(scale.into());
+        self
+    }
+
+    /// Get the 2D part of this transform's position.
+    #[inline]
+    pub fn position_2d(&self) -> Vec2 {
+        self.translation.xy()
+    }
+
+    /// Get this transform's rotation as an angle.
+    #[inline]
+    pub fn angle_2d(&self) -> Angle {
+        Angle::from(self.rotation)
+    }
+
+    /// Set the position and rotation of this transform to the ones defined by a physics pose,
+    /// leaving depth and scale as they are.
+    #[inline]
+    pub fn sync_from_physics(&mut self, pose: PhysicsPose) {
+        self.translation.x = pose.translation.x as f32;
+        self.translation.y = pose.translation.y as f32;
+        self.rotation.s = pose.rotation.s as f32;
+        self.rotation.bv.xy = pose.rotation.bv.xy as f32;
+    }
+
+    /// Get the 4x4 matrix that applies scale, then rotation, then translation.
+    pub fn into_homogeneous_matrix(self) -> uv::Mat4 {
+        let rot = Rotor3::new(self.rotation.s, Bivec3::new(self.rotation.bv.xy, 0., 0.));
+        uv::Mat4::from_translation(self.translation)
+            * rot.into_matrix().into_homogeneous()
+            * uv::Mat4::from_nonuniform_scale(Vec3::new(self.scale.x, self.scale.y, 1.))
+    }
+}
+
+impl From<Transform2D> for PhysicsPose {
+    /// Scale and depth are dropped in the conversion.
+    fn from(tr: Transform2D) -> Self {
+        PhysicsPose::new(
+            uv::DVec2::new(tr.translation.x as f64, tr.translation.y as f64),
+            uv::DRotor2::new(
+                tr.rotation.s as f64,
+                uv::DBivec2::new(tr.rotation.bv.xy as f64),
+            ),
+        )
+    }
+}
+
+impl From<PhysicsPose> for Transform2D {
+    fn from(pose: PhysicsPose) -> Self {
+        let mut tr = Self::identity();
+        tr.sync_from_physics(pose);
+        tr
+    }
+}
+
+impl From<Pose> for Transform2D {
+    /// Rotations outside of the xy plane are dropped in the conversion.
+    fn from(pose: Pose) -> Self {
+        Self {
+            translation: pose.translation,
+            rotation: Rotor2::new(pose.rotation.s, Bivec2::new(pose.rotation.bv.xy)).normalized(),
+            scale: Vec2::broadcast(pose.scale),
+        }
+    }
+}
+
+impl From<Transform2D> for Pose {
+    /// A [`Pose`] can only scale uniformly,
+    /// so the scale is set to the average of the absolute values of the x and y scales.
+    fn from(tr: Transform2D) -> Self {
+        Pose(uv::Similarity3::new(
+            tr.translation,
+            Rotor3::new(tr.rotation.s, Bivec3::new(tr.rotation.bv.xy, 0., 0.)),
+            (tr.scale.x.abs() + tr.scale.y.abs()) / 2.,
+        ))
+    }
+}
+
 /// An angle in either degrees or radians.
 /// Default conversion from f64 is in degrees.
-#[derive(Clone, Copy, Debug, serde::Deserialize, serde::Serialize)]
+#[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "serde-types", derive(serde::Deserialize, serde::Serialize))]
 pub enum Angle {
     Rad(f32),
     Deg(f32),
@@ -214,7 +376,14 @@ impl From<Angle> for f64 {
 }
 
 /// A 2D unit vector. Used in physics.
+///
+/// Serialized as a plain vector, which is normalized when deserializing.
 #[derive(Clone, Copy, Debug)]
+#[cfg_attr(
+    feature = "serde-types",
+    derive(serde::Deserialize, serde::Serialize),
+    serde(from = "uv::DVec2", into = "uv::DVec2")
+)]
 pub struct UnitDVec2(uv::DVec2);
 
 impl UnitDVec2 {
@@ -235,6 +404,18 @@ impl UnitDVec2 {
     }
 }
 
+impl From<uv::DVec2> for UnitDVec2 {
+    fn from(v: uv::DVec2) -> Self {
+        Self::new_normalize(v)
+    }
+}
+
+impl From<UnitDVec2> for uv::DVec2 {
+    fn from(u: UnitDVec2) -> Self {
+        u.0
+    }
+}
+
 impl std::ops::Mul<UnitDVec2> for uv::DRotor2 {
     type Output = UnitDVec2;
 
@@ -264,12 +445,19 @@ impl std::ops::Neg for UnitDVec2 {
 //
 
 /// A builder to create [`Pose`][self::Pose]s.
-#[derive(Clone, Copy, Debug, serde::Serialize, serde::Deserialize)]
-#[serde(default)]
+///
+/// This is also the serialized form of a `Pose`.
+#[derive(Clone, Copy, Debug)]
+#[cfg_attr(
+    feature = "serde-types",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default)
+)]
 pub struct PoseBuilder {
     pub position: [f32; 2],
     pub depth: f32,
     pub rotation: Angle,
+    pub scale: f32,
 }
 impl PoseBuilder {
     pub fn new() -> Self {
@@ -277,6 +465,7 @@ impl PoseBuilder {
             position: [0., 0.],
             depth: 0.,
             rotation: Angle::default(),
+            scale: 1.,
         }
     }
 
@@ -298,10 +487,18 @@ impl PoseBuilder {
         self
     }
 
+    #[inline]
+    pub fn with_scale(mut self, scale: f32) -> Self {
+        self.scale = scale;
+        self
+    }
+
     #[inline]
     pub fn build(self) -> Pose {
-        Pose::new(Vec2::new(self.position[0], self.position[1]), self.rotation)
-            .with_depth(self.depth)
+        let mut pose = Pose::new(Vec2::new(self.position[0], self.position[1]), self.rotation)
+            .with_depth(self.depth);
+        pose.scale = self.scale;
+        pose
     }
 }
 impl Default for PoseBuilder {
@@ -334,8 +531,9 @@ impl From<Pose> for PoseBuilder {
         let pos = pose.position_2d();
         Self {
             position: [pos.x, pos.y],
-            depth: 0.,
-            rotation: pose.angle_2d(),
+            depth: pose.translation.z,
+            rotation: Angle::Deg(pose.angle_2d().deg()),
+            scale: pose.scale,
         }
     }
 }
@@ -386,6 +584,31 @@ pub mod serde_physics_pose {
     }
 }
 
+/// Module to serialize [`Rotor2`]s as an [`Angle`] in degrees
+/// instead of rotor components,
+/// using the serde attribute `#[serde(with = "serde_rotor2")]`.
+/// Either angle variant is accepted when deserializing.
+#[cfg(feature = "serde-types")]
+pub mod serde_rotor2 {
+    use super::*;
+
+    pub fn serialize<S>(rotor: &Rotor2, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        use serde::Serialize;
+        Angle::Deg(Angle::from(*rotor).deg()).serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Rotor2, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        use serde::Deserialize;
+        Angle::deserialize(deserializer).map(Rotor2::from)
+    }
+}
+
 //
 // Vec2 utils
 //
@@ -407,6 +630,45 @@ pub fn unit_right_normal(u: UnitDVec2) -> UnitDVec2 {
     UnitDVec2::new_unchecked(right_normal(*u))
 }
 
+/// Compute the convex hull of a set of points
+/// using Andrew's monotone chain algorithm.
+///
+/// Returns the vertices of the hull in counterclockwise order,
+/// starting from the point with the lowest x (and lowest y if tied).
+/// Duplicate points and points on the hull's edges are left out,
+/// so if all the points are on a line, only the two endpoints are returned.
+pub fn convex_hull(points: &[uv::DVec2]) -> Vec<uv::DVec2> {
+    let mut sorted = points.to_vec();
+    sorted.sort_by(|a, b| a.x.total_cmp(&b.x).then(a.y.total_cmp(&b.y)));
+    sorted.dedup();
+    if sorted.len() < 3 {
+        return sorted;
+    }
+
+    // positive if o -> a -> b is a counterclockwise turn
+    let turn = |o: uv::DVec2, a: uv::DVec2, b: uv::DVec2| (a - o).wedge(b - o).xy;
+
+    let mut hull: Vec<uv::DVec2> = Vec::with_capacity(sorted.len() + 1);
+    // lower hull from left to right
+    for &p in &sorted {
+        while hull.len() >= 2 && turn(hull[hull.len() - 2], hull[hull.len() - 1], p) <= 0.0 {
+            hull.pop();
+        }
+        hull.push(p);
+    }
+    // upper hull from right to left, leaving the lower hull alone
+    let lower_len = hull.len();
+    for &p in sorted.iter().rev().skip(1) {
+        while hull.len() > lower_len && turn(hull[hull.len() - 2], hull[hull.len() - 1], p) <= 0.0 {
+            hull.pop();
+        }
+        hull.push(p);
+    }
+    // the last point is the starting point again
+    hull.pop();
+    hull
+}
+
 /// Trait facilitating conversions between f64 types (for physics)
 /// and f32 types (for everything else).
 pub trait ConvertPrecision {
@@ -429,3 +691,114 @@ impl ConvertPrecision for uv::Vec2 {
         uv::DVec2::new(self.x as f64, self.y as f64)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn v(x: f64, y: f64) -> uv::DVec2 {
+        uv::DVec2::new(x, y)
+    }
+
+    #[cfg(feature = "serde-types")]
+    #[test]
+    fn serde_round_trip() {
+        fn round_trip<T>(value: &T) -> T
+        where
+            T: serde::Serialize + serde::de::DeserializeOwned,
+        {
+            ron::from_str(&ron::to_string(value).unwrap()).unwrap()
+        }
+
+        let v2 = Vec2::new(1.5, -2.);
+        assert_eq!(round_trip(&v2), v2);
+        let dv2 = uv::DVec2::new(1.5, -2.);
+        assert_eq!(round_trip(&dv2), dv2);
+        let rotor = Rotor2::from_angle(1.2);
+        assert_eq!(round_trip(&rotor), rotor);
+        let unit = UnitDVec2::new_normalize(uv::DVec2::new(3., 4.));
+        assert_eq!(*round_trip(&unit), *unit);
+
+        assert_eq!(ron::to_string(&Angle::Deg(90.)).unwrap(), "Deg(90.0)");
+        assert_eq!(round_trip(&Angle::Rad(0.5)).rad(), 0.5);
+
+        let mut pose = Pose::new(Vec2::new(1., 2.), Angle::Deg(45.)).with_depth(3.);
+        pose.scale = 2.;
+        let pose_rt = round_trip(&pose);
+        assert!((pose_rt.translation - pose.translation).mag() < 1e-5);
+        assert!((pose_rt.angle_2d().deg() - 45.).abs() < 1e-3);
+        assert_eq!(pose_rt.scale, 2.);
+
+        let tr = Transform2D::new(Vec2::new(1., 2.), Angle::Deg(30.))
+            .with_depth(-1.)
+            .with_scale([-1., 2.]);
+        let tr_rt = round_trip(&tr);
+        assert_eq!(tr_rt.translation, tr.translation);
+        assert_eq!(tr_rt.scale, tr.scale);
+        assert!((tr_rt.angle_2d().deg() - 30.).abs() < 1e-3);
+    }
+
+    #[test]
+    fn transform_2d_scale_and_mirror() {
+        let tr = Transform2D::new(Vec2::new(1., 2.), Angle::Deg(90.)).with_scale([-2., 0.5]);
+        let p = Vec2::new(1., 1.);
+        // scaled to (-2, 0.5), rotated to (-0.5, -2), translated
+        let expected = Vec2::new(0.5, 0.);
+        assert!((tr * p - expected).mag() < 1e-5);
+        let from_mat = tr.into_homogeneous_matrix() * uv::Vec4::new(p.x, p.y, 0., 1.);
+        assert!((from_mat.xy() - expected).mag() < 1e-5);
+
+        // physics only sees position and rotation
+        let phys = PhysicsPose::from(tr);
+        assert!((phys * uv::DVec2::new(1., 0.) - uv::DVec2::new(1., 3.)).mag() < 1e-5);
+    }
+
+    #[test]
+    fn convex_hull_square() {
+        let points = [
+            v(1.0, 1.0),
+            v(0.5, 0.5),
+            v(0.0, 0.0),
+            v(0.0, 1.0),
+            v(1.0, 0.0),
+            v(0.2, 0.7),
+        ];
+        assert_eq!(
+            convex_hull(&points),
+            vec![v(0.0, 0.0), v(1.0, 0.0), v(1.0, 1.0), v(0.0, 1.0)]
+        );
+    }
+
+    #[test]
+    fn convex_hull_collinear_and_duplicates() {
+        // points in the middle of edges and repeated corners are dropped
+        let points = [
+            v(0.0, 0.0),
+            v(2.0, 0.0),
+            v(1.0, 0.0),
+            v(2.0, 2.0),
+            v(2.0, 1.0),
+            v(0.0, 0.0),
+            v(0.0, 2.0),
+            v(2.0, 2.0),
+            v(1.0, 1.0),
+        ];
+        assert_eq!(
+            convex_hull(&points),
+            vec![v(0.0, 0.0), v(2.0, 0.0), v(2.0, 2.0), v(0.0, 2.0)]
+        );
+
+        // everything on a line gives just the endpoints
+        let line = [
+            v(1.0, 1.0),
+            v(0.0, 0.0),
+            v(3.0, 3.0),
+            v(2.0, 2.0),
+            v(3.0, 3.0),
+        ];
+        assert_eq!(convex_hull(&line), vec![v(0.0, 0.0), v(3.0, 3.0)]);
+
+        assert_eq!(convex_hull(&[v(1.0, 2.0); 3]), vec![v(1.0, 2.0)]);
+        assert!(convex_hull(&[]).is_empty());
+    }
+}
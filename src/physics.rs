@@ -1,17 +1,19 @@
 use itertools::izip;
+use std::{collections::HashMap, ops::ControlFlow};
 
 #[cfg(feature = "parallel")]
 use rayon::prelude::*;
 
-use crate::math::{left_normal, uv, PhysicsPose, UnitDVec2};
+use crate::math::{left_normal, uv, Angle, PhysicsPose, UnitDVec2};
 
 //
 
 pub mod collision;
 use collision::bvh::Bvh;
 pub use collision::{
-    Collider, ColliderPolygon, ColliderShape, ColliderType, CollisionLayerMask, Contact,
-    ContactResult, PhysicsMaterial, Ray,
+    Broadphase, Collider, ColliderPolygon, ColliderShape, ColliderType, CollisionLayerMask,
+    CollisionLayers, CollisionMaskMatrixBuilder, Contact, ContactResult, PhysicsMaterial, Ray,
+    SpatialHashBroadphase,
 };
 
 pub(super) mod constraint;
@@ -23,9 +25,19 @@ pub use forcefield::ForceField;
 pub(super) mod body;
 pub use body::{Body, ColliderInfo, Mass};
 
+mod region;
+use region::Region;
+pub use region::{RegionEvent, RegionEventKind};
+
 mod rope;
 pub use rope::{Rope, RopeKey, RopeParameters, RopeSet};
 
+mod mouse_spring;
+pub use mouse_spring::MouseSpring;
+
+mod character_controller;
+pub use character_controller::{CharacterController, CharacterMove};
+
 mod entity_set;
 pub use entity_set::{BodyKey, ColliderKey, EntitySet};
 
@@ -39,6 +51,9 @@ mod solver;
 
 pub mod hecs_sync;
 
+#[cfg(test)]
+mod scene_tests;
+
 //
 // public types
 //
@@ -108,11 +123,62 @@ impl std::ops::Mul<f64> for Velocity {
     }
 }
 
+/// Identifies a contact between two colliders
+/// and the parts of their surfaces that are touching.
+///
+/// The id stays the same from frame to frame as long as the same edges
+/// or corners of the colliders are in contact, e.g. while one slides along the other.
+/// This can be used to match up contacts between frames,
+/// for instance to keep playing a scraping sound.
+/// When the colliders separate or roll onto a different edge, the id changes.
+///
+/// The id doesn't depend on the order of the colliders,
+/// so a contact [flipped][PhysicsWorld::contacts_for_collider] to face the other way
+/// has the same id.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ContactId {
+    colliders: [ColliderKey; 2],
+    features: [u8; 2],
+}
+
+impl ContactId {
+    fn new(colliders: [ColliderKey; 2], features: [u8; 2]) -> Self {
+        if colliders[0] <= colliders[1] {
+            Self {
+                colliders,
+                features,
+            }
+        } else {
+            Self {
+                colliders: [colliders[1], colliders[0]],
+                features: [features[1], features[0]],
+            }
+        }
+    }
+
+    /// The colliders in contact, in ascending order.
+    #[inline]
+    pub fn colliders(&self) -> [ColliderKey; 2] {
+        self.colliders
+    }
+}
+
 /// Pertinent information about a contact between two colliders.
 #[derive(Clone, Copy, Debug)]
 pub struct ContactInfo {
     pub colliders: [ColliderKey; 2],
     pub normal: UnitDVec2,
+    /// Identity of the contact for matching it up with the same contact on other frames.
+    pub id: ContactId,
+    /// Magnitude of the total impulse applied along the normal to separate the colliders
+    /// during the last substep of the frame.
+    ///
+    /// This is always positive regardless of which way the contact is oriented.
+    /// Divide by the substep length to get the contact force.
+    pub impulse: f64,
+    // points in world space, only the first `point_count` are valid
+    points: [uv::DVec2; 2],
+    point_count: usize,
     // island id stored to allow retaining of sleeping contacts
     island_id: IslandId,
 }
@@ -121,11 +187,137 @@ impl ContactInfo {
         Self {
             colliders: [self.colliders[1], self.colliders[0]],
             normal: -self.normal,
+            id: self.id,
+            impulse: self.impulse,
+            points: self.points,
+            point_count: self.point_count,
             island_id: self.island_id,
         }
     }
+
+    /// The points in world space where the colliders touch, at the end of the frame.
+    ///
+    /// There are one or two of these, two meaning the colliders have a flat edge
+    /// in contact with each other. Each point is halfway between the surfaces
+    /// of the two colliders.
+    #[inline]
+    pub fn points(&self) -> &[uv::DVec2] {
+        &self.points[..self.point_count]
+    }
+
+    /// Classify the surface of the second collider as seen by the first
+    /// by comparing its normal to the `up` direction.
+    ///
+    /// `wall_angle` is the steepest slope that still counts as a floor.
+    /// Surfaces tilted further than this from `up` are walls,
+    /// and ones tilted further than this from down are ceilings.
+    /// This uses the same test as
+    /// [`is_collider_supported`][self::PhysicsWorld::is_collider_supported]
+    /// and [`ground_check`][self::PhysicsWorld::ground_check],
+    /// so a contact classified as [`Floor`][SurfaceKind::Floor] with the same angle
+    /// is one those would accept as ground.
+    pub fn classify(&self, up: UnitDVec2, wall_angle: Angle) -> SurfaceKind {
+        let min_cos = (wall_angle.rad() as f64).cos();
+        // the normal faces away from the first collider, towards the surface
+        let cos = (-self.normal).dot(*up);
+        if cos >= min_cos {
+            SurfaceKind::Floor
+        } else if cos <= -min_cos {
+            SurfaceKind::Ceiling
+        } else {
+            SurfaceKind::Wall
+        }
+    }
+}
+
+/// Which way a surface faces relative to up,
+/// returned from [`ContactInfo::classify`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SurfaceKind {
+    /// A surface facing up that things can stand on.
+    Floor,
+    /// A surface too steep to stand on.
+    Wall,
+    /// A surface facing down.
+    Ceiling,
+}
+
+/// Counts of things in the simulation during a frame,
+/// returned from [`PhysicsWorld::stats`].
+///
+/// If the physics is slow, a high number of collider pairs tested relative to contacts
+/// means bounding boxes are too large (see [`TuningConstants::broadphase_margin`]),
+/// whereas a high number of contacts or awake bodies means the scene is simply busy.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct PhysicsStats {
+    /// Bodies that were simulated.
+    pub awake_bodies: usize,
+    /// Bodies that were skipped because they were at rest.
+    /// Disabled bodies aren't counted.
+    pub sleeping_bodies: usize,
+    /// Groups of bodies connected by contacts or constraints that were simulated.
+    pub islands: usize,
+    /// Pairs of colliders in contact, including ones that are sleeping.
+    pub contacts: usize,
+    /// Pairs in [`contacts`][Self::contacts] that touch at a single point.
+    ///
+    /// Polygon edges lying flat against each other get two contact points
+    /// unless one end is farther apart than a small tolerance,
+    /// in which case the contact is reduced to the deepest point.
+    /// Corners touching edges and curved shapes always give a single point.
+    /// If resting stacks of boxes wobble, a high count here means
+    /// their faces aren't actually lying flat.
+    pub single_point_contacts: usize,
+    /// Pairs of colliders whose bounding boxes overlapped,
+    /// i.e. candidates for collision checked in the narrow phase.
+    pub collider_pairs_tested: usize,
+    /// Substeps that were run. This differs from [`TuningConstants::substeps`]
+    /// when ticking with a time scale, see [`TuningConstants::scale_substeps_with_time`].
+    pub substeps: usize,
+}
+
+/// How the strength of a [radial impulse][PhysicsWorld::apply_radial_impulse]
+/// decreases with distance from its center.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ImpulseFalloff {
+    /// Full strength everywhere within the radius.
+    Constant,
+    /// Full strength at the center, decreasing linearly to zero at the radius.
+    Linear,
+    /// Full strength at the center, decreasing quadratically to zero at the radius.
+    /// Feels more like an explosion than `Linear`, with only things close by thrown hard.
+    Quadratic,
+}
+
+impl ImpulseFalloff {
+    fn factor(&self, distance: f64, radius: f64) -> f64 {
+        let remaining = (1.0 - distance / radius).clamp(0.0, 1.0);
+        match self {
+            Self::Constant => 1.0,
+            Self::Linear => remaining,
+            Self::Quadratic => remaining * remaining,
+        }
+    }
+}
+
+/// What to do with a pair of colliders that may be touching,
+/// returned from a [contact filter][PhysicsWorld::set_contact_filter].
+#[derive(Clone, Copy, Debug)]
+pub enum ContactOverride {
+    /// Handle the contact normally.
+    Keep,
+    /// Handle the contact as if both colliders had this material,
+    /// e.g. to make an ice patch slippery for only some objects.
+    ///
+    /// Sensors still don't get a physics response.
+    Material(PhysicsMaterial),
+    /// Skip the contact entirely, as if the colliders were on layers that don't collide,
+    /// e.g. for one-way platforms or letting teammates pass through each other.
+    Ignore,
 }
 
+type ContactFilter = Box<dyn FnMut(ColliderKey, ColliderKey) -> ContactOverride + Send + Sync>;
+
 /// Result of a [`raycast`][self::PhysicsWorld::raycast]
 /// or [`spherecast`][self::PhysicsWorld::spherecast].
 #[derive(Clone, Copy, Debug)]
@@ -148,6 +340,74 @@ pub struct CastHit {
     pub t: f64,
 }
 
+/// Result of a [`ground_check`][self::PhysicsWorld::ground_check].
+#[derive(Clone, Copy, Debug)]
+pub struct GroundInfo {
+    /// The body's collider that is touching the ground.
+    pub collider: ColliderKey,
+    /// The collider the body is standing on.
+    pub ground: ColliderKey,
+    /// The normal of the ground surface, facing towards the body.
+    pub normal: UnitDVec2,
+    /// The point in world space where the body touches the ground.
+    ///
+    /// If the contact has two points (i.e. an edge is resting on the ground),
+    /// this is the point halfway between them.
+    pub point: uv::DVec2,
+}
+
+/// Result of a [`pick`][self::PhysicsWorld::pick].
+#[derive(Clone, Copy, Debug)]
+pub struct PickHit {
+    /// A key to the collider that was picked.
+    pub collider: ColliderKey,
+    /// A key to the body the collider is attached to, if there is one.
+    pub body: Option<BodyKey>,
+    /// The picked point in the local space of the body,
+    /// or the collider if it's not attached to a body.
+    ///
+    /// This stays attached to the same spot on the object as it moves,
+    /// e.g. for placing editor handles relative to the object.
+    pub local_point: uv::DVec2,
+}
+
+/// State of the simulation in the middle of a frame,
+/// given to the callback in [`tick_with_substep_hook`][PhysicsWorld::tick_with_substep_hook].
+pub struct SubstepView<'a, 'b> {
+    /// Index of the substep that was just finished.
+    pub substep: usize,
+    /// Total number of substeps being run this frame.
+    pub substep_count: usize,
+    island_views: &'a [solver::DataView<'b>],
+    entity_set: &'a EntitySet,
+}
+
+impl SubstepView<'_, '_> {
+    /// Get the current state of a body.
+    ///
+    /// Sleeping bodies aren't part of the simulation,
+    /// so the state stored in the [`EntitySet`] is returned for them.
+    pub fn get_body(&self, key: BodyKey) -> Option<&Body> {
+        let stored = self.entity_set.get_body(key)?;
+        let body_idx = match self.island_views.first() {
+            Some(view) => view.global_body_order[key.0.slot() as usize],
+            None => return Some(stored),
+        };
+        if body_idx == usize::MAX {
+            return Some(stored);
+        }
+        self.island_views
+            .iter()
+            .find(|view| body_idx < view.island_offset + view.bodies.len())
+            .map(|view| &view.bodies[body_idx - view.island_offset])
+    }
+
+    /// Iterate over all bodies that are currently awake, in no particular order.
+    pub fn awake_bodies(&self) -> impl '_ + Iterator<Item = &'_ Body> {
+        self.island_views.iter().flat_map(|view| view.bodies.iter())
+    }
+}
+
 //
 // internal types
 //
@@ -191,6 +451,13 @@ struct SleepingIsland {
     /// to avoid situations where constraints are working but velocity is briefly zero
     ticks_slept: usize,
 }
+/// Contact lambdas of a collider pair remembered between frames for warm starting.
+#[derive(Clone, Copy, Debug)]
+struct CachedContact {
+    lambdas: [f64; 2],
+    frames_unseen: usize,
+}
+
 impl From<IslandId> for SleepingIsland {
     fn from(id: IslandId) -> Self {
         Self {
@@ -220,6 +487,7 @@ struct WorkingBuffers {
     sorted_constraints: Vec<Constraint>,
     sorted_rope_views: Vec<solver::RopeView>,
     sorted_coll_pairs: Vec<[ColliderKey; 2]>,
+    sorted_material_overrides: Vec<Option<PhysicsMaterial>>,
 
     // bodies, sorted in island order
     bodies: Vec<Body>,
@@ -228,6 +496,8 @@ struct WorkingBuffers {
     rope_next_particles: Vec<Option<usize>>,
     rope_prev_particles: Vec<Option<usize>>,
     rope_lateral_corrections: Vec<Option<uv::DVec2>>,
+    // accumulated rope lambdas within a substep, [distance to next, bending], per particle
+    rope_lambdas: Vec<[f64; 2]>,
 
     old_poses: Vec<PhysicsPose>,
     pre_contact_poses: Vec<PhysicsPose>,
@@ -236,9 +506,22 @@ struct WorkingBuffers {
 
     constraint_body_pairs: Vec<(usize, Option<usize>)>,
     coll_pair_keys: Vec<[ColliderKey; 2]>,
+    // materials from the contact filter, empty if there is no filter
+    material_overrides: Vec<Option<PhysicsMaterial>>,
     contacts: Vec<ContactResult>,
     last_contacts: Vec<ContactResult>,
-    contact_lambdas: Vec<f64>,
+    contact_lambdas: Vec<[f64; 2]>,
+    // contact lambdas from earlier substeps or frames, for warm starting
+    warm_lambdas: Vec<[f64; 2]>,
+    // remaining errors after the last substep, for measuring convergence
+    contact_errors: Vec<f64>,
+    constraint_errors: Vec<f64>,
+    // accumulated constraint lambdas within a substep, [distance, angle]
+    constraint_lambdas: Vec<[f64; 2]>,
+    island_residuals: Vec<f64>,
+
+    // colliders found by the broadphase in user queries
+    query_candidates: Vec<ColliderKey>,
 }
 struct SortedIndices {
     bodies: Vec<usize>,
@@ -275,12 +558,14 @@ impl Default for WorkingBuffers {
             sorted_constraints: Vec::new(),
             sorted_rope_views: Vec::new(),
             sorted_coll_pairs: Vec::new(),
+            sorted_material_overrides: Vec::new(),
 
             bodies: Vec::new(),
             body_order: Vec::new(),
             rope_next_particles: Vec::new(),
             rope_prev_particles: Vec::new(),
             rope_lateral_corrections: Vec::new(),
+            rope_lambdas: Vec::new(),
 
             old_poses: Vec::new(),
             pre_contact_poses: Vec::new(),
@@ -289,9 +574,16 @@ impl Default for WorkingBuffers {
 
             constraint_body_pairs: Vec::new(),
             coll_pair_keys: Vec::new(),
+            material_overrides: Vec::new(),
             contacts: Vec::new(),
             last_contacts: Vec::new(),
             contact_lambdas: Vec::new(),
+            warm_lambdas: Vec::new(),
+            contact_errors: Vec::new(),
+            constraint_errors: Vec::new(),
+            constraint_lambdas: Vec::new(),
+            island_residuals: Vec::new(),
+            query_candidates: Vec::new(),
         }
     }
 }
@@ -330,6 +622,77 @@ pub struct TuningConstants {
     /// this can cause a missed collision, leading to a deep collision the next frame
     /// and bodies flying apart violently.
     pub max_expected_acceleration: f64,
+    /// Constant amount in world units to pad every collider's bounding box by
+    /// in the broadphase, on top of the padding from `max_expected_acceleration`.
+    ///
+    /// Larger margins make it less likely for fast-moving bodies to miss collisions,
+    /// but produce more candidate pairs for the narrow phase, which costs performance
+    /// especially in scenes with many bodies close to each other.
+    /// Individual bodies can override this with [`Body::broadphase_margin`].
+    pub broadphase_margin: f64,
+    /// Whether to stretch bounding boxes in the direction of each body's velocity
+    /// to cover the distance it will move during the frame.
+    ///
+    /// Turning this off reduces the number of candidate pairs for moving bodies,
+    /// but any collision that happens farther than the broadphase margin
+    /// from a body's starting position will be missed, causing tunneling.
+    /// Only turn this off if nothing moves faster than the margin in one frame.
+    pub extend_aabbs_by_velocity: bool,
+    /// The number of times contacts and constraints are solved during each substep.
+    ///
+    /// Increasing `substeps` is usually the more effective way to improve accuracy,
+    /// but extra iterations can help stiff systems like tall stacks of boxes
+    /// converge at a lower cost, because they don't repeat the whole step.
+    /// See [`PhysicsWorld::last_residual`] for measuring convergence.
+    pub iterations: usize,
+    /// Whether to carry contact forces over from previous substeps and frames.
+    ///
+    /// The position-based solver recomputes contact forces from scratch every substep,
+    /// but friction needs a normal force to work against.
+    /// A contact that is touching but not penetrating during a substep has no normal force,
+    /// so without warm starting, friction briefly disappears and resting bodies can slide
+    /// or jitter. Warm starting uses the last known force in such cases instead.
+    /// The cost is a hash map lookup per collider pair per frame.
+    ///
+    /// Only contacts that are still touching use the remembered force,
+    /// so bodies bouncing off or separating aren't slowed down by it.
+    ///
+    /// This is off by default, keeping the solver stateless between frames.
+    /// Turn it on if resting bodies slide or jitter.
+    pub warm_start: bool,
+    /// Number of frames a collider pair's contact forces are remembered for warm starting
+    /// after the pair stops touching.
+    ///
+    /// Higher values help contacts that flicker on and off, e.g. long shapes resting on
+    /// uneven ground, but forces that are too old may no longer be accurate.
+    /// Only used if [`warm_start`][Self::warm_start] is on.
+    pub contact_persistence_frames: usize,
+    /// Highest velocity at which overlapping bodies are pushed apart.
+    ///
+    /// Deep overlaps can happen when bodies are spawned or teleported inside each other,
+    /// or when something moves faster than expected. The solver would normally fix these
+    /// in a single substep, launching the bodies apart at very high velocities.
+    /// Limiting the velocity instead makes them separate gradually over several substeps.
+    /// Default: infinity, i.e. no limit.
+    pub max_depenetration_velocity: f64,
+    /// Whether to run more or fewer substeps when [ticking][PhysicsWorld::tick]
+    /// with a time scale other than 1, instead of making each substep longer or shorter.
+    ///
+    /// When this is on (the default), the number of substeps is scaled with time
+    /// and rounded up, keeping the substep length close to that of an unscaled frame.
+    /// This keeps the simulation equally stable at any time scale,
+    /// but since the solver behaves slightly differently with a different number of substeps,
+    /// things won't play out exactly the same in slow motion or fast forward.
+    /// Time scales that don't evenly divide into substeps also run slightly shorter substeps.
+    /// Fast forwarding gets more expensive as more substeps are run.
+    ///
+    /// When this is off, every frame runs exactly [`substeps`][Self::substeps] substeps
+    /// and only their length is scaled. This makes the cost of a frame constant,
+    /// but stiff things like stacks and constraints get softer and less stable
+    /// at high time scales as the substeps get longer.
+    ///
+    /// [`PhysicsWorld::stats`] reports how many substeps were actually run.
+    pub scale_substeps_with_time: bool,
     #[cfg(feature = "parallel")]
     /// Minimum limit for bodies per thread to make sure work is divided efficiently.
     pub min_bodies_per_thread: usize,
@@ -342,6 +705,13 @@ impl Default for TuningConstants {
             sleep_vel_threshold: 0.001,
             fall_asleep_frames: 10,
             max_expected_acceleration: 10.0,
+            broadphase_margin: 0.0,
+            extend_aabbs_by_velocity: true,
+            iterations: 1,
+            warm_start: false,
+            contact_persistence_frames: 2,
+            max_depenetration_velocity: f64::INFINITY,
+            scale_substeps_with_time: true,
             #[cfg(feature = "parallel")]
             min_bodies_per_thread: 64,
         }
@@ -355,11 +725,22 @@ pub struct PhysicsWorld {
     pub entity_set: EntitySet,
     pub rope_set: RopeSet,
     pub constraint_set: ConstraintSet,
-    pub(crate) bvh: Bvh,
+    broadphase: Box<dyn Broadphase>,
     constraint_graph: ConstraintGraph,
     sleeping_islands: Vec<SleepingIsland>,
+    // bodies whose islands should be woken up on the next tick
+    // even if nothing about them seems to have changed
+    wake_requests: Vec<BodyKey>,
+    // colliders switched from solid to sensor since the last tick,
+    // whose contacts are left out on the next tick to end them
+    new_sensors: Vec<ColliderKey>,
+    contact_cache: HashMap<[ColliderKey; 2], CachedContact>,
     working_bufs: WorkingBuffers,
     contacts: Vec<ContactInfo>,
+    contact_filter: Option<ContactFilter>,
+    regions: Vec<Region>,
+    region_events: Vec<RegionEvent>,
+    stats: PhysicsStats,
 }
 
 impl PhysicsWorld {
@@ -370,30 +751,93 @@ impl PhysicsWorld {
             entity_set: EntitySet::new(),
             rope_set: RopeSet::new(),
             constraint_set: ConstraintSet::new(),
-            bvh: Bvh::new(),
+            broadphase: Box::new(Bvh::new()),
             constraint_graph: ConstraintGraph {
                 first_nodes_per_body: Vec::new(),
                 last_nodes_per_body: Vec::new(),
                 nodes: Vec::new(),
             },
             sleeping_islands: Vec::new(),
+            wake_requests: Vec::new(),
+            new_sensors: Vec::new(),
+            contact_cache: HashMap::new(),
             working_bufs: WorkingBuffers::new(),
             contacts: Vec::new(),
+            contact_filter: None,
+            regions: Vec::new(),
+            region_events: Vec::new(),
+            stats: PhysicsStats::default(),
         }
     }
 
+    /// Use a different [`Broadphase`] to find potentially colliding pairs
+    /// and accelerate queries. The default is a [`Bvh`].
+    ///
+    /// ```
+    /// # use starframe::{PhysicsWorld, SpatialHashBroadphase};
+    /// // cells the size of the typical object
+    /// let physics = PhysicsWorld::new(Default::default(), Default::default())
+    ///     .with_broadphase(SpatialHashBroadphase::new(0.5));
+    /// assert!(physics.bvh().is_none());
+    /// ```
+    pub fn with_broadphase(mut self, broadphase: impl Broadphase) -> Self {
+        self.broadphase = Box::new(broadphase);
+        self
+    }
+
     /// Remove all constraints and reset internal state.
     pub fn clear(&mut self) {
         self.entity_set.clear();
         self.rope_set.clear();
         self.constraint_set.clear();
         self.sleeping_islands.clear();
+        self.wake_requests.clear();
+        self.new_sensors.clear();
+        self.contact_cache.clear();
         self.contacts.clear();
+        self.regions.clear();
+        self.region_events.clear();
         self.working_bufs = WorkingBuffers::default();
+        self.stats = PhysicsStats::default();
     }
 
     /// Advance the simulation forward by `frame_dt` seconds.
+    ///
+    /// If `time_scale` is given, the simulation advances by `time_scale * frame_dt` seconds
+    /// instead, e.g. for slow motion effects.
+    /// See [`TuningConstants::scale_substeps_with_time`] for how this affects substeps.
+    ///
+    /// The force field can be different on every tick,
+    /// but sleeping bodies don't notice when it changes.
+    /// Call [`wake_all`][Self::wake_all] after e.g. flipping gravity
+    /// so that resting bodies react to it.
     pub fn tick(&mut self, frame_dt: f64, time_scale: Option<f64>, forcefield: &impl ForceField) {
+        self.tick_inner(frame_dt, time_scale, forcefield, None);
+    }
+
+    /// Advance the simulation like [`tick`][Self::tick],
+    /// calling `on_substep` after every substep to allow inspecting intermediate state.
+    ///
+    /// This is a debugging tool for things like solver convergence issues.
+    /// Substeps are always run on a single thread here,
+    /// so don't use this in place of `tick` when performance matters.
+    pub fn tick_with_substep_hook(
+        &mut self,
+        frame_dt: f64,
+        time_scale: Option<f64>,
+        forcefield: &impl ForceField,
+        mut on_substep: impl FnMut(SubstepView<'_, '_>),
+    ) {
+        self.tick_inner(frame_dt, time_scale, forcefield, Some(&mut on_substep));
+    }
+
+    fn tick_inner(
+        &mut self,
+        frame_dt: f64,
+        time_scale: Option<f64>,
+        forcefield: &impl ForceField,
+        on_substep: Option<&mut dyn FnMut(SubstepView<'_, '_>)>,
+    ) {
         let _main_span = tracy_client::span!("physics tick");
 
         self.entity_set.remove_orphan_colliders();
@@ -408,6 +852,10 @@ impl PhysicsWorld {
                 substeps = self.consts.substeps;
                 dt = frame_dt / substeps as f64;
             }
+            Some(scale) if !self.consts.scale_substeps_with_time => {
+                substeps = self.consts.substeps;
+                dt = scale * frame_dt / substeps as f64;
+            }
             Some(scale) => {
                 substeps = (scale * self.consts.substeps as f64).ceil() as usize;
                 // dt here must be such that `dt * substeps == time_scale * frame_dt
@@ -426,9 +874,18 @@ impl PhysicsWorld {
                     .map(|t| self.entity_set.get_body(t).is_some())
                     .unwrap_or(true)
         });
+        // constraints with disabled bodies are kept but not simulated
+        let body_enabled = |key: BodyKey| self.entity_set.get_body(key).is_some_and(|b| b.enabled);
         bufs.user_constraints.clear();
-        bufs.user_constraints
-            .extend(self.constraint_set.constraints.iter().map(|(_, v)| v));
+        bufs.user_constraints.extend(
+            self.constraint_set
+                .constraints
+                .iter()
+                .map(|(_, v)| v)
+                .filter(|c| body_enabled(c.owner) && c.target.is_none_or(body_enabled)),
+        );
+        // same for ropes, which stop acting as ropes if any of their particles is disabled
+        let rope_enabled = |rope: &Rope| rope.particles.iter().all(|p| body_enabled(p.body));
 
         //
         // Prepare the spatial index
@@ -438,40 +895,65 @@ impl PhysicsWorld {
 
         // constant for padding bounding volumes to fit movement during substeps,
         // collisions may be missed if higher accelerations occur
-        let max_expected_accel_over_frame = self.consts.max_expected_acceleration * frame_dt;
+        let default_padding =
+            self.consts.broadphase_margin + self.consts.max_expected_acceleration * frame_dt;
 
-        self.bvh.clear();
+        self.broadphase.clear();
         bufs.coll_pair_keys.clear();
         // generate potentially colliding pairs,
         // these will be used to re-detect collisions every substep.
         for (coll_key, coll) in self.entity_set.colliders.iter() {
             let coll_key = ColliderKey(coll_key);
             let body = self.entity_set.get_collider_body(coll_key);
+            if !coll.enabled || body.is_some_and(|b| !b.enabled) {
+                continue;
+            }
             let aabb = match body {
                 Some(body) => {
                     let pose = body.pose * coll.pose;
-                    coll.shape
-                        .aabb(pose)
-                        .extended(body.velocity.linear * frame_dt)
-                        .padded(max_expected_accel_over_frame)
+                    let aabb = coll.shape.aabb(pose);
+                    let aabb = if self.consts.extend_aabbs_by_velocity {
+                        aabb.extended(body.velocity.linear * frame_dt)
+                    } else {
+                        aabb
+                    };
+                    aabb.padded(body.broadphase_margin.unwrap_or(default_padding))
                 }
                 None => coll.shape.aabb(coll.pose),
             };
 
-            bufs.coll_pair_keys.extend(
-                self.bvh
-                    .test_aabb(aabb)
-                    .filter(|other| {
-                        self.mask_matrix.get(
-                            coll.layer,
-                            // unwrap is safe here because we rebuild the BVH every frame,
-                            // hence nothing has had the opportunity to be deleted at this point
-                            self.entity_set.get_collider(*other).unwrap().layer,
-                        )
-                    })
-                    .map(move |other| [coll_key, other]),
-            );
-            self.bvh.insert(coll_key, aabb);
+            self.broadphase.test_aabb(aabb, &mut |other| {
+                let collides = self.mask_matrix.get(
+                    coll.layer,
+                    // unwrap is safe here because we rebuild the broadphase every frame,
+                    // hence nothing has had the opportunity to be deleted at this point
+                    self.entity_set.get_collider(other).unwrap().layer,
+                );
+                if collides {
+                    bufs.coll_pair_keys.push([coll_key, other]);
+                }
+            });
+            self.broadphase.insert(coll_key, aabb);
+        }
+
+        bufs.material_overrides.clear();
+        if let Some(filter) = &mut self.contact_filter {
+            let _span = tracy_client::span!("contact filter");
+            bufs.coll_pair_keys.retain(|pair| {
+                let bodies = pair.map(|c| self.entity_set.get_collider_body_key(c));
+                // pairs that never collide are thrown out later anyway,
+                // don't bother the user with them
+                if bodies[0] == bodies[1] {
+                    bufs.material_overrides.push(None);
+                    return true;
+                }
+                match filter(pair[0], pair[1]) {
+                    ContactOverride::Keep => bufs.material_overrides.push(None),
+                    ContactOverride::Material(mat) => bufs.material_overrides.push(Some(mat)),
+                    ContactOverride::Ignore => return false,
+                }
+                true
+            });
         }
 
         tracy_client::plot!("colliders", self.entity_set.colliders.len() as f64);
@@ -491,6 +973,9 @@ impl PhysicsWorld {
 
         // rope constraints
         for (rope_key, rope) in self.rope_set.ropes.iter() {
+            if !rope_enabled(rope) {
+                continue;
+            }
             let rope_slot = rope_key.slot() as usize;
             let mut iter = rope.particles.iter().peekable();
             while let Some(particle) = iter.next() {
@@ -638,18 +1123,26 @@ impl PhysicsWorld {
                         body_idx,
                         constr_idx,
                     } => {
-                        bufs.sorted_first_pass.constraints.push(*constr_idx);
-                        island.constr_count += 1;
+                        // the edge is stored for both bodies,
+                        // only add the constraint from its owner's end
+                        let constr = &bufs.user_constraints[*constr_idx];
+                        if constr.owner.0.slot() as usize == root_body_idx {
+                            bufs.sorted_first_pass.constraints.push(*constr_idx);
+                            island.constr_count += 1;
+                        }
 
-                        if !bufs.user_constraints[*constr_idx].can_sleep {
+                        if !constr.can_sleep {
                             island.can_sleep = false;
                         }
                         island.id.edge_sum += (root_body_idx + 1) * (body_idx + 1);
                         search(*body_idx, island, constraint_graph, bufs);
                     }
                     Edge::Contact { body_idx, pair_idx } => {
-                        bufs.sorted_first_pass.coll_pairs.push(*pair_idx);
-                        island.pair_count += 1;
+                        // the edge is stored for both bodies, only add the pair once
+                        if root_body_idx < *body_idx {
+                            bufs.sorted_first_pass.coll_pairs.push(*pair_idx);
+                            island.pair_count += 1;
+                        }
 
                         island.id.edge_sum += (root_body_idx + 1) * (body_idx + 1);
                         search(*body_idx, island, constraint_graph, bufs);
@@ -676,8 +1169,10 @@ impl PhysicsWorld {
             }
         }
 
-        for (body_key, _) in self.entity_set.bodies.iter() {
-            if bufs.island_assigned[body_key.slot() as usize] {
+        for (body_key, body) in self.entity_set.bodies.iter() {
+            // disabled bodies are never reached through edges
+            // because everything connecting to them was skipped above
+            if bufs.island_assigned[body_key.slot() as usize] || !body.enabled {
                 continue;
             }
             let mut island = Island {
@@ -719,12 +1214,14 @@ impl PhysicsWorld {
                 .find(|slep| slep.id == isl.id)
             {
                 // we need to check if anything started moving between frames due to user code
+                // or was otherwise changed in a way that requires waking up
                 if bufs.sorted_first_pass.bodies
                     [isl.body_range_start..isl.body_range_start + isl.body_count]
                     .iter()
                     .any(|bi| {
-                        let (_, body) = self.entity_set.bodies.get_by_slot(*bi as u32).unwrap();
+                        let (key, body) = self.entity_set.bodies.get_by_slot(*bi as u32).unwrap();
                         body.velocity.mag_sq() >= self.consts.sleep_vel_threshold
+                            || self.wake_requests.contains(&BodyKey(key))
                     })
                 {
                     return true;
@@ -739,6 +1236,7 @@ impl PhysicsWorld {
         });
         // remove sleeping island ids that weren't found
         self.sleeping_islands.retain(|slep| slep.continues_sleeping);
+        self.wake_requests.clear();
         // sort remaining islands by size for better work distribution over threads
         bufs.islands
             .sort_unstable_by_key(|isl| usize::MAX - isl.body_count);
@@ -826,7 +1324,7 @@ impl PhysicsWorld {
         bufs.rope_next_particles.resize(bufs.bodies.len(), None);
         bufs.rope_prev_particles.clear();
         bufs.rope_prev_particles.resize(bufs.bodies.len(), None);
-        for (_, rope) in self.rope_set.ropes.iter() {
+        for (_, rope) in self.rope_set.ropes.iter().filter(|(_, r)| rope_enabled(r)) {
             let mut iter = rope.particles.iter().peekable();
             while let Some(particle) = iter.next() {
                 if let Some(next_particle) = iter.peek() {
@@ -841,6 +1339,9 @@ impl PhysicsWorld {
         bufs.rope_lateral_corrections.clear();
         bufs.rope_lateral_corrections
             .resize(bufs.sorted_second_pass.bodies.len(), None);
+        bufs.rope_lambdas.clear();
+        bufs.rope_lambdas
+            .resize(bufs.sorted_second_pass.bodies.len(), [0.0; 2]);
 
         bufs.old_poses.clear();
         bufs.old_poses.extend(bufs.bodies.iter().map(|b| b.pose));
@@ -873,6 +1374,13 @@ impl PhysicsWorld {
                 .iter()
                 .map(|pi| bufs.coll_pair_keys[*pi]),
         );
+        bufs.sorted_material_overrides.clear();
+        bufs.sorted_material_overrides.extend(
+            bufs.sorted_second_pass
+                .coll_pairs
+                .iter()
+                .map(|pi| bufs.material_overrides.get(*pi).copied().flatten()),
+        );
         // store latest contacts for use in the velocity step
         bufs.contacts.clear();
         bufs.contacts
@@ -884,7 +1392,30 @@ impl PhysicsWorld {
         // store contact forces for friction purposes
         bufs.contact_lambdas.clear();
         bufs.contact_lambdas
+            .resize(bufs.sorted_coll_pairs.len(), [0.0; 2]);
+        // start from the contact forces of the previous frame if we have them
+        bufs.warm_lambdas.clear();
+        if self.consts.warm_start {
+            bufs.warm_lambdas
+                .extend(bufs.sorted_coll_pairs.iter().map(|pair| {
+                    self.contact_cache
+                        .get(pair)
+                        .map_or([0.0; 2], |cached| cached.lambdas)
+                }));
+        } else {
+            bufs.warm_lambdas
+                .resize(bufs.sorted_coll_pairs.len(), [0.0; 2]);
+        }
+        // store errors for measuring how well the solver converged
+        bufs.contact_errors.clear();
+        bufs.contact_errors
             .resize(bufs.sorted_coll_pairs.len(), 0.0);
+        bufs.constraint_errors.clear();
+        bufs.constraint_errors
+            .resize(bufs.sorted_constraints.len(), 0.0);
+        bufs.constraint_lambdas.clear();
+        bufs.constraint_lambdas
+            .resize(bufs.sorted_constraints.len(), [0.0; 2]);
 
         drop(buf_span);
 
@@ -961,12 +1492,18 @@ impl PhysicsWorld {
         let mut rope_next_p_s = bufs.rope_next_particles.as_mut_slice();
         let mut rope_prev_p_s = bufs.rope_prev_particles.as_mut_slice();
         let mut rope_lat_s = bufs.rope_lateral_corrections.as_mut_slice();
+        let mut rope_lambda_s = bufs.rope_lambdas.as_mut_slice();
         let mut constr_s = bufs.sorted_constraints.as_slice();
         let mut constr_bodies_s = bufs.constraint_body_pairs.as_mut_slice();
         let mut coll_pairs_s = bufs.sorted_coll_pairs.as_mut_slice();
+        let mut mat_overrides_s = bufs.sorted_material_overrides.as_slice();
         let mut contacts_s = bufs.contacts.as_mut_slice();
         let mut last_contacts_s = bufs.last_contacts.as_mut_slice();
         let mut cont_lambda_s = bufs.contact_lambdas.as_mut_slice();
+        let mut warm_lambda_s = bufs.warm_lambdas.as_mut_slice();
+        let mut cont_err_s = bufs.contact_errors.as_mut_slice();
+        let mut constr_err_s = bufs.constraint_errors.as_mut_slice();
+        let mut constr_lambda_s = bufs.constraint_lambdas.as_mut_slice();
 
         let mut island_start_idx = 0;
 
@@ -1016,6 +1553,8 @@ impl PhysicsWorld {
 
             let (rope_lateral_corrections, rope_lat_rest) = rope_lat_s.split_at_mut(body_count);
             rope_lat_s = rope_lat_rest;
+            let (rope_lambdas, rope_l_rest) = rope_lambda_s.split_at_mut(body_count);
+            rope_lambda_s = rope_l_rest;
             let (constraints, constr_rest) = constr_s.split_at(constr_count);
             constr_s = constr_rest;
             let (constraint_body_pairs, constr_bod_rest) =
@@ -1030,17 +1569,31 @@ impl PhysicsWorld {
 
             let (coll_pairs, coll_p_rest) = coll_pairs_s.split_at_mut(pair_count);
             coll_pairs_s = coll_p_rest;
+            let (material_overrides, mat_over_rest) = mat_overrides_s.split_at(pair_count);
+            mat_overrides_s = mat_over_rest;
             let (contacts, contacts_rest) = contacts_s.split_at_mut(pair_count);
             contacts_s = contacts_rest;
             let (last_contacts, last_conts_rest) = last_contacts_s.split_at_mut(pair_count);
             last_contacts_s = last_conts_rest;
             let (contact_lambdas, cont_l_rest) = cont_lambda_s.split_at_mut(pair_count);
             cont_lambda_s = cont_l_rest;
+            let (warm_lambdas, warm_l_rest) = warm_lambda_s.split_at_mut(pair_count);
+            warm_lambda_s = warm_l_rest;
+            let (contact_errors, cont_err_rest) = cont_err_s.split_at_mut(pair_count);
+            cont_err_s = cont_err_rest;
+            let (constraint_errors, constr_err_rest) = constr_err_s.split_at_mut(constr_count);
+            constr_err_s = constr_err_rest;
+            let (constraint_lambdas, constr_l_rest) = constr_lambda_s.split_at_mut(constr_count);
+            constr_lambda_s = constr_l_rest;
 
             island_group_views.push(solver::DataView {
                 dt,
                 inv_dt,
                 inv_dt_sq,
+                iterations: self.consts.iterations,
+                warm_start: self.consts.warm_start,
+                max_depenetration: self.consts.max_depenetration_velocity * dt
+                    / self.consts.iterations as f64,
                 island_offset: island_start_idx,
                 global_body_order: &bufs.body_order,
                 bodies,
@@ -1052,12 +1605,18 @@ impl PhysicsWorld {
                 rope_next_particles,
                 rope_prev_particles,
                 rope_lateral_corrections,
+                rope_lambdas,
                 constraints,
                 constraint_body_pairs,
                 coll_pairs,
+                material_overrides,
                 contacts,
                 last_contacts,
                 contact_lambdas,
+                warm_lambdas,
+                contact_errors,
+                constraint_errors,
+                constraint_lambdas,
             });
 
             island_start_idx += body_count;
@@ -1067,19 +1626,38 @@ impl PhysicsWorld {
         // Actual physics step
         //
 
-        #[cfg(feature = "parallel")]
-        let island_iter = island_group_views.par_iter_mut();
+        match on_substep {
+            None => {
+                #[cfg(feature = "parallel")]
+                let island_iter = island_group_views.par_iter_mut();
 
-        #[cfg(not(feature = "parallel"))]
-        let island_iter = island_group_views.iter_mut();
+                #[cfg(not(feature = "parallel"))]
+                let island_iter = island_group_views.iter_mut();
 
-        island_iter.for_each(|island_view| {
-            for _substep in 0..substeps {
-                let _substep_span = tracy_client::span!("substep");
+                island_iter.for_each(|island_view| {
+                    for _substep in 0..substeps {
+                        let _substep_span = tracy_client::span!("substep");
 
-                solver::solve(forcefield, island_view, &self.entity_set);
+                        solver::solve(forcefield, island_view, &self.entity_set);
+                    }
+                });
             }
-        });
+            Some(on_substep) => {
+                // every island needs to be at the same substep when we stop to inspect,
+                // so the loops are the other way around compared to the normal case
+                for substep in 0..substeps {
+                    for island_view in &mut island_group_views {
+                        solver::solve(forcefield, island_view, &self.entity_set);
+                    }
+                    on_substep(SubstepView {
+                        substep,
+                        substep_count: substeps,
+                        island_views: &island_group_views,
+                        entity_set: &self.entity_set,
+                    });
+                }
+            }
+        }
 
         tracy_client::plot!(
             "contacts",
@@ -1090,6 +1668,60 @@ impl PhysicsWorld {
                 .count() as f64
         );
 
+        //
+        // measure how close to converged the solver got
+        //
+
+        bufs.island_residuals.clear();
+        bufs.island_residuals.extend(bufs.islands.iter().map(|isl| {
+            let contact_errors =
+                &bufs.contact_errors[isl.pair_range_start..isl.pair_range_start + isl.pair_count];
+            let constraint_errors = &bufs.constraint_errors
+                [isl.constr_range_start..isl.constr_range_start + isl.constr_count];
+            contact_errors
+                .iter()
+                .chain(constraint_errors)
+                .fold(0.0, |acc, err| f64::max(acc, *err))
+        }));
+
+        tracy_client::plot!(
+            "max residual",
+            bufs.island_residuals
+                .iter()
+                .fold(0.0, |acc, res| f64::max(acc, *res))
+        );
+
+        //
+        // remember contact forces for warm starting the next frame
+        //
+
+        if self.consts.warm_start {
+            for cached in self.contact_cache.values_mut() {
+                cached.frames_unseen += 1;
+            }
+            for (pair, contact, lambdas) in izip!(
+                &bufs.sorted_coll_pairs,
+                &bufs.last_contacts,
+                &bufs.warm_lambdas
+            ) {
+                let is_new_sensor = pair.iter().any(|c| self.new_sensors.contains(c));
+                if !matches!(contact, ContactResult::Zero) && !is_new_sensor {
+                    self.contact_cache.insert(
+                        *pair,
+                        CachedContact {
+                            lambdas: *lambdas,
+                            frames_unseen: 0,
+                        },
+                    );
+                }
+            }
+            let max_frames = self.consts.contact_persistence_frames;
+            self.contact_cache
+                .retain(|_, cached| cached.frames_unseen <= max_frames);
+        } else {
+            self.contact_cache.clear();
+        }
+
         //
         // store contacts for user queries and other systems
         //
@@ -1103,23 +1735,77 @@ impl PhysicsWorld {
                 isl.id == cont.island_id && isl.ticks_slept >= self.consts.fall_asleep_frames
             })
         });
+        // contact offsets are in the space of the body if the collider is attached
+        // and the collider otherwise, see `solver::solve_contacts`
+        let offset_to_world = |coll_key: ColliderKey, offset: uv::DVec2| -> uv::DVec2 {
+            match self.entity_set.coll_bodies.get(coll_key.0) {
+                Some(body_key) => {
+                    bufs.bodies[bufs.body_order[body_key.0.slot() as usize]].pose * offset
+                }
+                None => self.entity_set.colliders[coll_key.0].pose * offset,
+            }
+        };
+        // which part of the collider's surface faces along the given world space direction
+        let surface_feature = |coll_key: ColliderKey, dir: UnitDVec2| -> u8 {
+            let coll = &self.entity_set.colliders[coll_key.0];
+            let rotation = match self.entity_set.coll_bodies.get(coll_key.0) {
+                Some(body_key) => {
+                    bufs.bodies[bufs.body_order[body_key.0.slot() as usize]]
+                        .pose
+                        .rotation
+                        * coll.pose.rotation
+                }
+                None => coll.pose.rotation,
+            };
+            collision::shape_shape::surface_feature(coll.shape.polygon, rotation.reversed() * dir)
+        };
         for isl in &bufs.islands {
             self.contacts.extend(
                 izip!(
                     &bufs.sorted_coll_pairs
                         [isl.pair_range_start..isl.pair_range_start + isl.pair_count],
                     &bufs.last_contacts
+                        [isl.pair_range_start..isl.pair_range_start + isl.pair_count],
+                    &bufs.contact_lambdas
                         [isl.pair_range_start..isl.pair_range_start + isl.pair_count]
                 )
-                .filter_map(|(pair, contact)| {
+                // contacts with a collider that just became a sensor end here,
+                // the pair starts over as a sensor contact on the next tick
+                .filter(|(pair, _, _)| !pair.iter().any(|c| self.new_sensors.contains(c)))
+                .filter_map(|(pair, contact, lambda)| {
+                    let mut points = [uv::DVec2::zero(); 2];
+                    let mut point_count = 0;
+                    for (cont, point) in izip!(contact.iter(), &mut points) {
+                        let surface_points = [
+                            offset_to_world(pair[0], cont.offsets[0]),
+                            offset_to_world(pair[1], cont.offsets[1]),
+                        ];
+                        *point = 0.5 * (surface_points[0] + surface_points[1]);
+                        point_count += 1;
+                    }
                     contact.iter().next().map(|cont| ContactInfo {
                         colliders: *pair,
                         normal: cont.normal,
+                        id: ContactId::new(
+                            *pair,
+                            [
+                                surface_feature(pair[0], cont.normal),
+                                surface_feature(pair[1], -cont.normal),
+                            ],
+                        ),
+                        // positional XPBD lambda over one substep is an impulse times dt
+                        impulse: (lambda[0].abs() + lambda[1].abs()) * inv_dt,
+                        points,
+                        point_count,
                         island_id: isl.id,
                     })
                 }),
             );
         }
+        // the order of islands depends on which bodies were awake and how they're connected,
+        // sort to give users a stable order to iterate in
+        self.contacts.sort_unstable_by_key(|cont| cont.colliders);
+        self.new_sensors.clear();
 
         //
         // set islands where movement was below a threshold to sleep
@@ -1149,19 +1835,209 @@ impl PhysicsWorld {
 
         for (body_key, body) in self.entity_set.bodies.iter_mut() {
             let working_body = bufs.body_order[body_key.slot() as usize];
-            if working_body == usize::MAX {
-                // this body is sleeping
+            // usize::MAX means this body is sleeping
+            if working_body != usize::MAX {
+                *body = bufs.bodies[working_body];
+            }
+            // applied forces only last for one tick
+            body.force = uv::DVec2::zero();
+            body.torque = 0.0;
+        }
+
+        // disabled bodies are left out of the solver too but aren't sleeping
+        let enabled_bodies = self.entity_set.bodies.iter().filter(|(_, b)| b.enabled);
+        self.stats = PhysicsStats {
+            awake_bodies: bufs.bodies.len(),
+            sleeping_bodies: enabled_bodies.count() - bufs.bodies.len(),
+            islands: bufs.islands.len(),
+            contacts: self.contacts.len(),
+            single_point_contacts: self.contacts.iter().filter(|c| c.point_count == 1).count(),
+            collider_pairs_tested: bufs.coll_pair_keys.len(),
+            substeps,
+        };
+
+        self.update_regions();
+    }
+
+    fn update_regions(&mut self) {
+        let _span = tracy_client::span!("update regions");
+
+        self.region_events.clear();
+        // taken out temporarily so we can query while mutating the regions
+        let mut regions = std::mem::take(&mut self.regions);
+        for region in &mut regions {
+            let mut bodies: Vec<BodyKey> = self
+                .query_shape(region.pose, region.shape, region.mask)
+                .filter_map(|(_, body)| body)
+                .collect();
+            // a body with multiple colliders can be found multiple times
+            bodies.sort_unstable_by_key(|body| body.0.to_bits());
+            bodies.dedup();
+            region.update(bodies, &mut self.region_events);
+        }
+        self.regions = regions;
+        self.region_events
+            .sort_by_key(|event| (event.region_id, event.body));
+    }
+
+    /// Add a region that reports bodies entering and leaving it
+    /// through [`region_events`][Self::region_events].
+    ///
+    /// Only colliders on layers enabled in `mask` are considered,
+    /// and only colliders attached to bodies.
+    /// If a region with the same `id` already exists, its shape, pose and mask are replaced
+    /// but the bodies known to be inside it are kept, so this can be used to move a region
+    /// without generating events for bodies that stay inside it.
+    pub fn add_region(
+        &mut self,
+        id: u64,
+        shape: ColliderShape,
+        pose: PhysicsPose,
+        mask: CollisionLayerMask,
+    ) {
+        if let Some(region) = self.regions.iter_mut().find(|r| r.id == id) {
+            region.shape = shape;
+            region.pose = pose;
+            region.mask = mask;
+        } else {
+            self.regions.push(Region {
+                id,
+                shape,
+                pose,
+                mask,
+                overlapping: Vec::new(),
+            });
+        }
+    }
+
+    /// Remove a region added with [`add_region`][Self::add_region].
+    /// No exit events are generated for bodies that were inside it.
+    pub fn remove_region(&mut self, id: u64) {
+        self.regions.retain(|r| r.id != id);
+    }
+
+    /// Get the bodies that entered or left a region during the last tick.
+    ///
+    /// Events are sorted by region id and then by body key,
+    /// so they come in the same order every time the same things happen.
+    #[inline]
+    pub fn region_events(&self) -> &[RegionEvent] {
+        &self.region_events
+    }
+
+    /// Push every body near `center` directly away from it, e.g. for an explosion.
+    ///
+    /// Bodies with a collider on a layer enabled in `mask` within `radius` of `center`
+    /// get an impulse of magnitude `strength` scaled by `falloff`
+    /// according to the distance from `center` to the body's center of mass.
+    /// The impulse is applied at the center of mass, so it doesn't make bodies spin.
+    /// Bodies that were sleeping are woken up.
+    ///
+    /// If `occlusion` is true, a ray is cast from `center` to each body
+    /// and bodies behind another solid collider are left alone.
+    /// This includes colliders of other bodies, so a body can shield things behind it.
+    /// This costs one raycast per body in range.
+    pub fn apply_radial_impulse(
+        &mut self,
+        center: uv::DVec2,
+        radius: f64,
+        strength: f64,
+        falloff: ImpulseFalloff,
+        mask: CollisionLayerMask,
+        occlusion: bool,
+    ) {
+        let query_pose = PhysicsPose::new(center, uv::DRotor2::identity());
+        let mut bodies: Vec<BodyKey> = self
+            .query_shape(query_pose, Collider::new_circle(radius).shape, mask)
+            .filter_map(|(_, body)| body)
+            .collect();
+        // a body with multiple colliders can be found multiple times
+        bodies.sort_unstable_by_key(|body| body.0.to_bits());
+        bodies.dedup();
+
+        for body_key in bodies {
+            let Some(body) = self.entity_set.get_body(body_key) else {
+                continue;
+            };
+            let body_pos = body.pose.translation;
+            let offset = body_pos - center;
+            let distance = offset.mag();
+            // no meaningful direction to push in
+            if distance < 1e-9 {
                 continue;
             }
-            *body = bufs.bodies[working_body];
+            let dir = offset / distance;
+
+            if occlusion {
+                let ray = Ray {
+                    start: center,
+                    dir: UnitDVec2::new_unchecked(dir),
+                };
+                let blocked = self.raycast(ray, distance).is_some_and(|hit| {
+                    self.entity_set.get_collider_body_key(hit.collider) != Some(body_key)
+                });
+                if blocked {
+                    continue;
+                }
+            }
+
+            let magnitude = strength * falloff.factor(distance, radius);
+            let body = self.entity_set.get_body_mut(body_key).unwrap();
+            body.apply_impulse(magnitude * dir, body_pos);
+            self.wake_body(body_key);
         }
     }
 
+    /// Get counts of things in the simulation during the last frame,
+    /// useful for e.g. a performance overlay.
+    #[inline]
+    pub fn stats(&self) -> PhysicsStats {
+        self.stats
+    }
+
+    /// Get the bounding volume hierarchy used to accelerate collision detection and queries,
+    /// e.g. to visualize it with [`Bvh::iter_levels`].
+    /// Returns `None` if a different [`Broadphase`] was chosen
+    /// with [`with_broadphase`][Self::with_broadphase].
+    ///
+    /// The hierarchy is rebuilt during [`tick`][Self::tick],
+    /// so colliders added or moved since the last tick are not reflected in it.
+    #[inline]
+    pub fn bvh(&self) -> Option<&Bvh> {
+        self.broadphase.as_any().downcast_ref()
+    }
+
+    /// Get the broadphase used to accelerate collision detection and queries.
+    ///
+    /// Like the [`bvh`][Self::bvh], this is rebuilt during [`tick`][Self::tick].
+    #[inline]
+    pub fn broadphase(&self) -> &dyn Broadphase {
+        self.broadphase.as_ref()
+    }
+
+    /// Get all contacts between colliders during the last frame,
+    /// including contacts of sensors and of bodies that are sleeping.
+    ///
+    /// Each touching pair of colliders appears once, in whichever orientation
+    /// collision detection found it; see [`are_in_contact`][Self::are_in_contact]
+    /// to get a contact oriented a specific way.
+    /// Use this rather than calling [`contacts_for_collider`][Self::contacts_for_collider]
+    /// for every collider when processing all contacts at once, e.g. to count impacts.
+    ///
+    /// Contacts are sorted by their `colliders`,
+    /// so they come in the same order every time the same things happen
+    /// regardless of how bodies were grouped together for solving.
+    #[inline]
+    pub fn contacts(&self) -> &[ContactInfo] {
+        &self.contacts
+    }
+
     /// Get all contacts that the given collider participated in during the last frame.
     ///
     /// All returned [`ContactInfo`][self::ContactInfo] objects are oriented such that the
     /// collider being searched for is the first item in `colliders` and `normal`
     /// faces away from it.
+    /// They come in the same order as in [`contacts`][Self::contacts].
     pub fn contacts_for_collider(
         &self,
         coll: ColliderKey,
@@ -1177,71 +2053,689 @@ impl PhysicsWorld {
         })
     }
 
-    /// Find every collider that intersects with the given point.
-    /// Returns a key to the collider, and if it's attached to a body,
-    /// also a key to the body.
+    /// Get the contact between two specific colliders during the last frame, if there was one.
     ///
-    /// Takes a mutable reference because bounding volume hierarchy
-    /// traversal uses a mutable shared stack.
-    /// This is subject to change if I figure out a good way to
-    /// do this with interior mutability. (TODO)
-    pub fn query_point(
-        &mut self,
-        point: uv::DVec2,
-    ) -> impl '_ + Iterator<Item = (ColliderKey, Option<BodyKey>)> {
-        // TODO: using this requires dropping the iterator.
-        // restructure this such that references to the collider and body
-        // can be acquired during iteration
-        let entity_set = &self.entity_set;
-        self.bvh.test_point(point).filter_map(move |coll_key| {
-            let coll = entity_set.get_collider(coll_key)?;
-            let body_key = entity_set.coll_bodies.get(coll_key.0).copied();
-            let body = body_key.and_then(|k| entity_set.get_body(k));
-            let pose = match body {
-                Some(body) => body.pose * coll.pose,
-                None => coll.pose,
-            };
-            if collision::query::point_collider_bool(point, pose, *coll) {
-                Some((coll_key, body_key))
+    /// The returned [`ContactInfo`][self::ContactInfo] is oriented such that `a` is the first
+    /// item in `colliders` and `normal` faces away from it.
+    pub fn are_in_contact(&self, a: ColliderKey, b: ColliderKey) -> Option<ContactInfo> {
+        self.contacts.iter().find_map(|&cont| {
+            if cont.colliders == [a, b] {
+                Some(cont)
+            } else if cont.colliders == [b, a] {
+                Some(cont.flip())
             } else {
                 None
             }
         })
     }
 
-    /// Get all colliders that intersect with the given shape.
-    /// Returns a key to the collider, and if it's attached to a body,
-    /// also a key to the body.
-    pub fn query_shape<'p, 'g: 'p>(
-        &'p mut self,
-        pose: PhysicsPose,
-        shape: ColliderShape,
-        mask: CollisionLayerMask,
-    ) -> impl 'p + Iterator<Item = (ColliderKey, Option<BodyKey>)> {
-        let entity_set = &self.entity_set;
-        self.bvh
-            .test_aabb(shape.aabb(pose))
-            .filter_map(move |coll_key| {
-                let coll = entity_set.get_collider(coll_key)?;
-                if !mask.get(coll.layer) {
-                    return None;
-                }
-                let body_key = entity_set.coll_bodies.get(coll_key.0).copied();
+    /// Check whether the collider is resting on something,
+    /// i.e. it had a contact during the last frame
+    /// where the surface it was touching faced in approximately the `up` direction.
+    ///
+    /// `max_angle` is the steepest slope that still counts as support.
+    /// This is useful for checking if a character is on the ground.
+    pub fn is_collider_supported(
+        &self,
+        coll: ColliderKey,
+        up: UnitDVec2,
+        max_angle: Angle,
+    ) -> bool {
+        let min_cos = (max_angle.rad() as f64).cos();
+        // contact normals face away from the collider, towards the supporting surface
+        self.contacts_for_collider(coll)
+            .any(|cont| (-cont.normal).dot(*up) >= min_cos)
+    }
+
+    /// Find the ground a body is standing on, if any,
+    /// i.e. a contact during the last frame where the surface the body was touching
+    /// faced in approximately the `up` direction.
+    ///
+    /// `max_slope_angle` is the steepest slope that still counts as ground.
+    /// All colliders attached to the body are checked,
+    /// and if there are multiple candidates the flattest one is returned.
+    /// This is like [`is_collider_supported`][Self::is_collider_supported]
+    /// with additional information for slope-aware movement.
+    pub fn ground_check(
+        &self,
+        body: BodyKey,
+        up: UnitDVec2,
+        max_slope_angle: Angle,
+    ) -> Option<GroundInfo> {
+        let min_cos = (max_slope_angle.rad() as f64).cos();
+        self.entity_set
+            .coll_bodies
+            .iter()
+            .filter(|(_, b)| **b == body)
+            .flat_map(|(coll_idx, _)| self.contacts_for_collider(ColliderKey(coll_idx)))
+            .map(|cont| (cont, (-cont.normal).dot(*up)))
+            .filter(|(_, cos)| *cos >= min_cos)
+            .max_by(|(_, cos_a), (_, cos_b)| cos_a.total_cmp(cos_b))
+            .map(|(cont, _)| {
+                let points = cont.points();
+                GroundInfo {
+                    collider: cont.colliders[0],
+                    ground: cont.colliders[1],
+                    normal: -cont.normal,
+                    point: points.iter().fold(uv::DVec2::zero(), |acc, p| acc + *p)
+                        / points.len() as f64,
+                }
+            })
+    }
+
+    /// Get the [`user_data`][Collider::user_data] of a collider,
+    /// e.g. one returned from a raycast or query.
+    ///
+    /// Returns `None` if the collider doesn't exist.
+    #[inline]
+    pub fn collider_user_data(&self, coll: ColliderKey) -> Option<u64> {
+        self.entity_set.get_collider(coll).map(|c| c.user_data)
+    }
+
+    /// Spawn many identical dynamic bodies at once,
+    /// each with a single collider of the given shape and material.
+    ///
+    /// Mass and moment of inertia are computed once from the shape and `density`
+    /// and shared by every body.
+    /// Returns keys to the bodies in the same order as `poses`.
+    pub fn spawn_batch(
+        &mut self,
+        shape: ColliderShape,
+        material: PhysicsMaterial,
+        density: f64,
+        poses: &[PhysicsPose],
+    ) -> Vec<BodyKey> {
+        let collider = Collider {
+            shape,
+            ..Default::default()
+        }
+        .with_material(material);
+        let body = Body::new_dynamic(collider.info(), density);
+
+        let mut keys = Vec::with_capacity(poses.len());
+        for &pose in poses {
+            let key = self.entity_set.insert_body(body.with_pose(pose));
+            self.entity_set.attach_collider(key, collider);
+            keys.push(key);
+        }
+        keys
+    }
+
+    /// Uniformly scale the shape of a collider by the given factor.
+    ///
+    /// See [`set_collider_scale_xy`][Self::set_collider_scale_xy] for details.
+    #[inline]
+    pub fn set_collider_scale(&mut self, coll: ColliderKey, scale: f64) {
+        self.set_collider_scale_xy(coll, uv::DVec2::new(scale, scale));
+    }
+
+    /// Scale the shape of a collider by separate factors along its local x and y axes
+    /// (see [`ColliderShape::scaled`] for which shapes can be stretched).
+    /// Scaling is relative to the collider's current shape,
+    /// so repeatedly scaling by 1.1 keeps growing it.
+    ///
+    /// If the collider is attached to a body with finite mass,
+    /// the body's mass and moment of inertia are recomputed from the new shape,
+    /// keeping the body's density the same.
+    /// If the body's colliders have no area, its density is unknown
+    /// and the mass is left unchanged.
+    /// The bounding volume hierarchy is rebuilt from current shapes every tick,
+    /// so queries see the new shape after the next [`tick`][Self::tick].
+    ///
+    /// Does nothing if the collider doesn't exist.
+    pub fn set_collider_scale_xy(&mut self, coll: ColliderKey, scale: uv::DVec2) {
+        let Some(collider) = self.entity_set.get_collider_mut(coll) else {
+            return;
+        };
+        let old_collider = *collider;
+        collider.shape = collider.shape.scaled(scale);
+        let new_collider = *collider;
+
+        let Some(body_key) = self.entity_set.get_collider_body_key(coll) else {
+            return;
+        };
+        // info of all colliders attached to the body, measured around the body's origin
+        // because that's where the poses of the colliders are relative to
+        let mut siblings: Vec<Collider> = self
+            .entity_set
+            .coll_bodies
+            .iter()
+            .filter(|(coll_idx, b)| **b == body_key && *coll_idx != coll.0)
+            .filter_map(|(coll_idx, _)| self.entity_set.colliders.get(coll_idx).copied())
+            .collect();
+        siblings.push(old_collider);
+        let old_info =
+            collision::CompoundColliderSetup::new(&siblings).info_around_point(uv::DVec2::zero());
+        *siblings.last_mut().unwrap() = new_collider;
+        let new_info =
+            collision::CompoundColliderSetup::new(&siblings).info_around_point(uv::DVec2::zero());
+
+        // no area means no density to keep, leave the mass as it was
+        if old_info.area <= 0.0 {
+            return;
+        }
+
+        let Some(body) = self.entity_set.get_body_mut(body_key) else {
+            return;
+        };
+        if let Mass::Finite { mass, .. } = body.mass {
+            let density = mass / old_info.area;
+            body.mass = Mass::from(new_info.area * density);
+            if let Mass::Finite { .. } = body.moment_of_inertia {
+                body.moment_of_inertia = Mass::from(new_info.second_moment_of_area * density);
+            }
+        }
+    }
+
+    /// Change the pose of a collider relative to the body it's attached to,
+    /// or relative to the world if it's not attached to a body.
+    ///
+    /// This is meant for animating parts of compound colliders,
+    /// e.g. moving a hitbox during an attack.
+    /// The body's island is woken up so the change takes effect even if it was sleeping.
+    /// The body's mass and center of mass are not updated.
+    ///
+    /// Does nothing if the collider doesn't exist.
+    pub fn set_collider_local_pose(&mut self, coll: ColliderKey, pose: PhysicsPose) {
+        let Some(collider) = self.entity_set.get_collider_mut(coll) else {
+            return;
+        };
+        collider.pose = pose;
+        if let Some(body_key) = self.entity_set.get_collider_body_key(coll) {
+            self.wake_body(body_key);
+        }
+    }
+
+    /// Make sure the island containing the given body is simulated on the next tick,
+    /// even if it was sleeping.
+    ///
+    /// Sleeping islands are woken up automatically if something in them is given a velocity,
+    /// but not if they're changed in other ways, e.g. teleporting a body
+    /// by changing its pose. Call this after such changes.
+    /// The island may fall asleep again later if it comes to rest.
+    #[inline]
+    pub fn wake_body(&mut self, body: BodyKey) {
+        self.wake_requests.push(body);
+    }
+
+    /// Enable or disable a body without removing it from the world,
+    /// e.g. to turn something off temporarily during a cutscene.
+    /// See [`Body::enabled`].
+    ///
+    /// A disabled body keeps its key and state,
+    /// but doesn't move, collide with anything, show up in queries
+    /// or take part in constraints and ropes until it's enabled again.
+    /// Bodies touching it are woken up so they don't stay resting on nothing.
+    /// Like other changes made between ticks,
+    /// this isn't seen by queries until the next [`tick`][Self::tick].
+    ///
+    /// Does nothing if the body doesn't exist.
+    pub fn set_body_enabled(&mut self, body: BodyKey, enabled: bool) {
+        let Some(b) = self.entity_set.get_body_mut(body) else {
+            return;
+        };
+        b.enabled = enabled;
+        self.wake_body(body);
+        let colliders: Vec<ColliderKey> = self
+            .entity_set
+            .coll_bodies
+            .iter()
+            .filter(|(_, b)| **b == body)
+            .map(|(coll_idx, _)| ColliderKey(coll_idx))
+            .collect();
+        for coll in colliders {
+            self.wake_touching(coll);
+        }
+    }
+
+    /// Enable or disable a collider without removing it from the world,
+    /// e.g. to switch off a hazard temporarily.
+    /// See [`Collider::enabled`].
+    ///
+    /// A disabled collider keeps its key and state,
+    /// but doesn't collide with anything or show up in queries until it's enabled again.
+    /// The body it's attached to and bodies touching it are woken up.
+    /// Like other changes made between ticks,
+    /// this isn't seen by queries until the next [`tick`][Self::tick].
+    ///
+    /// Does nothing if the collider doesn't exist.
+    pub fn set_collider_enabled(&mut self, coll: ColliderKey, enabled: bool) {
+        let Some(c) = self.entity_set.get_collider_mut(coll) else {
+            return;
+        };
+        c.enabled = enabled;
+        if let Some(body) = self.entity_set.get_collider_body_key(coll) {
+            self.wake_body(body);
+        }
+        self.wake_touching(coll);
+    }
+
+    /// Switch a collider between solid and sensor or change its material,
+    /// e.g. for a door that becomes passable or an enemy that phases through walls.
+    ///
+    /// The body it's attached to and bodies touching it are woken up,
+    /// so a collider that becomes solid starts pushing things out on the next tick
+    /// and things resting on a collider that becomes a sensor start falling.
+    ///
+    /// When a solid collider becomes a sensor, its contacts end:
+    /// they're left out of [`contacts`][Self::contacts] on the next tick
+    /// and their contact forces are forgotten for warm starting.
+    /// Sensors still report contacts, so a pair that keeps overlapping
+    /// comes back as a sensor contact on the tick after that;
+    /// check [`Collider::is_solid`] to tell the two apart.
+    ///
+    /// Does nothing if the collider doesn't exist.
+    pub fn set_collider_type(&mut self, coll: ColliderKey, ty: ColliderType) {
+        let Some(c) = self.entity_set.get_collider_mut(coll) else {
+            return;
+        };
+        if c.is_solid() && matches!(ty, ColliderType::Sensor) {
+            self.new_sensors.push(coll);
+            self.contact_cache.retain(|pair, _| !pair.contains(&coll));
+        }
+        c.ty = ty;
+        if let Some(body) = self.entity_set.get_collider_body_key(coll) {
+            self.wake_body(body);
+        }
+        self.wake_touching(coll);
+    }
+
+    /// Set a function to decide what to do with each pair of colliders that may be touching,
+    /// to either override the material of the contact or ignore it entirely.
+    /// See [`ContactOverride`] for the options.
+    /// This replaces any previously set filter.
+    ///
+    /// The filter is called with the keys of the two colliders in no particular order
+    /// once per tick for every pair whose bounding boxes overlap
+    /// and whose layers collide according to the [mask matrix][Self::mask_matrix],
+    /// except pairs of colliders attached to the same body.
+    /// This happens before islands are formed, so ignored pairs don't wake each other up
+    /// and don't show up in [`contacts`][Self::contacts].
+    ///
+    /// In a busy scene there can be thousands of these pairs every tick,
+    /// and the filter is always called on a single thread,
+    /// so it should be cheap, e.g. a lookup based on [`user_data`][Collider::user_data]
+    /// captured from outside rather than anything that searches through the world.
+    /// Prefer [collision layers][CollisionLayers] for pairs that never collide.
+    pub fn set_contact_filter(
+        &mut self,
+        filter: impl FnMut(ColliderKey, ColliderKey) -> ContactOverride + Send + Sync + 'static,
+    ) {
+        self.contact_filter = Some(Box::new(filter));
+    }
+
+    /// Remove the function set with [`set_contact_filter`][Self::set_contact_filter].
+    pub fn remove_contact_filter(&mut self) {
+        self.contact_filter = None;
+    }
+
+    /// Wake up the bodies that were in contact with the given collider during the last frame.
+    fn wake_touching(&mut self, coll: ColliderKey) {
+        let touching: Vec<BodyKey> = self
+            .contacts_for_collider(coll)
+            .filter_map(|cont| self.entity_set.get_collider_body_key(cont.colliders[1]))
+            .collect();
+        self.wake_requests.extend(touching);
+    }
+
+    /// Wake up every sleeping island, e.g. after changing the direction of gravity.
+    ///
+    /// Islands that are still at rest under the new forces fall asleep again
+    /// after [`fall_asleep_frames`][TuningConstants::fall_asleep_frames].
+    #[inline]
+    pub fn wake_all(&mut self) {
+        self.sleeping_islands.clear();
+    }
+
+    /// Find every collider that intersects with the given point.
+    /// Returns a key to the collider, and if it's attached to a body,
+    /// also a key to the body.
+    ///
+    /// Takes a mutable reference because bounding volume hierarchy
+    /// traversal uses a mutable shared stack.
+    /// This is subject to change if I figure out a good way to
+    /// do this with interior mutability. (TODO)
+    pub fn query_point(
+        &mut self,
+        point: uv::DVec2,
+    ) -> impl '_ + Iterator<Item = (ColliderKey, Option<BodyKey>)> {
+        // TODO: using this requires dropping the iterator.
+        // restructure this such that references to the collider and body
+        // can be acquired during iteration
+        let candidates = &mut self.working_bufs.query_candidates;
+        candidates.clear();
+        self.broadphase
+            .test_point(point, &mut |coll_key| candidates.push(coll_key));
+        let entity_set = &self.entity_set;
+        candidates.iter().filter_map(move |&coll_key| {
+            let coll = entity_set.get_collider(coll_key)?;
+            let body_key = entity_set.coll_bodies.get(coll_key.0).copied();
+            let body = body_key.and_then(|k| entity_set.get_body(k));
+            let pose = match body {
+                Some(body) => body.pose * coll.pose,
+                None => coll.pose,
+            };
+            if collision::query::point_collider_bool(point, pose, *coll) {
+                Some((coll_key, body_key))
+            } else {
+                None
+            }
+        })
+    }
+
+    /// Find the single collider that should be selected when clicking on the given point,
+    /// e.g. for selecting objects in an editor.
+    ///
+    /// Unlike [`query_point`][Self::query_point], which returns every collider
+    /// under the point in no particular order, this chooses one collider:
+    /// the one with the smallest area,
+    /// so that small objects in front of large ones can always be picked.
+    /// Ties are broken by choosing the collider whose origin is closest to the point.
+    /// Only colliders on layers enabled in `mask` are considered.
+    pub fn pick(&mut self, point: uv::DVec2, mask: CollisionLayerMask) -> Option<PickHit> {
+        let candidates = &mut self.working_bufs.query_candidates;
+        candidates.clear();
+        self.broadphase
+            .test_point(point, &mut |coll_key| candidates.push(coll_key));
+        let entity_set = &self.entity_set;
+        candidates
+            .iter()
+            .filter_map(|&coll_key| {
+                let coll = entity_set.get_collider(coll_key)?;
+                if !mask.get(coll.layer) {
+                    return None;
+                }
+                let body_key = entity_set.coll_bodies.get(coll_key.0).copied();
                 let body = body_key.and_then(|k| entity_set.get_body(k));
-                let their_pose = match body {
+                let pose = match body {
                     Some(body) => body.pose * coll.pose,
                     None => coll.pose,
                 };
-                let result = collision::shape_shape::intersection_check(
-                    [pose, their_pose],
-                    [shape, coll.shape],
-                );
-                if result.is_zero() {
-                    None
-                } else {
-                    Some((coll_key, body_key))
+                if !collision::query::point_collider_bool(point, pose, *coll) {
+                    return None;
                 }
+                let local_point = match body {
+                    Some(body) => body.pose.inversed() * point,
+                    None => coll.pose.inversed() * point,
+                };
+                let dist_sq = (pose.translation - point).mag_sq();
+                let hit = PickHit {
+                    collider: coll_key,
+                    body: body_key,
+                    local_point,
+                };
+                Some((coll.shape.area(), dist_sq, hit))
             })
+            .min_by(|(area_a, dist_a, _), (area_b, dist_b, _)| {
+                area_a.total_cmp(area_b).then(dist_a.total_cmp(dist_b))
+            })
+            .map(|(_, _, hit)| hit)
+    }
+
+    /// Find the point on the surface of a collider closest to a point in world space,
+    /// e.g. for pulling an object's nearest surface toward a target or snapping attachments.
+    ///
+    /// Returns the closest point in world space along with the signed distance to it,
+    /// which is negative if `from` is inside the collider.
+    /// Rounded corners from the shape's [`circle_r`][ColliderShape::circle_r] are taken into account.
+    /// Returns `None` if the collider doesn't exist.
+    pub fn closest_point_on_collider(
+        &self,
+        coll: ColliderKey,
+        from: uv::DVec2,
+    ) -> Option<(uv::DVec2, f64)> {
+        let c = self.entity_set.get_collider(coll)?;
+        let pose = match self.entity_set.get_collider_body(coll) {
+            Some(body) => body.pose * c.pose,
+            None => c.pose,
+        };
+        let (distance, point) = c.signed_distance(pose, from);
+        Some((point, distance))
+    }
+
+    /// Get every pair of colliders whose bounding boxes overlap,
+    /// i.e. the pairs that the physics broadphase considers for collision.
+    ///
+    /// Useful for custom interactions between nearby objects
+    /// without building a separate spatial index.
+    /// Like the broadphase, this skips pairs whose layers don't collide
+    /// according to the mask matrix, and pairs of colliders attached to the same body.
+    /// Each pair is returned once, in no particular order.
+    ///
+    /// This reflects the state of the broadphase,
+    /// which is rebuilt during [`tick`][Self::tick].
+    /// Colliders added or moved since the last tick are not taken into account.
+    pub fn potential_pairs(&mut self) -> impl Iterator<Item = [ColliderKey; 2]> {
+        let mut leaves: Vec<(ColliderKey, collision::AABB)> = Vec::new();
+        self.broadphase
+            .leaves(&mut |coll_key, aabb| leaves.push((coll_key, aabb)));
+        let mut pairs = Vec::new();
+        for (coll_key, aabb) in leaves {
+            // colliders may have been removed since the broadphase was built
+            let Some(coll) = self.entity_set.get_collider(coll_key) else {
+                continue;
+            };
+            let body_key = self.entity_set.get_collider_body_key(coll_key);
+            self.broadphase.test_aabb(aabb, &mut |other| {
+                // the broadphase finds each pair from both ends, only keep one
+                if other.0.slot() <= coll_key.0.slot() {
+                    return;
+                }
+                let Some(other_coll) = self.entity_set.get_collider(other) else {
+                    return;
+                };
+                if self.mask_matrix.get(coll.layer, other_coll.layer)
+                    && (body_key.is_none()
+                        || self.entity_set.get_collider_body_key(other) != body_key)
+                {
+                    pairs.push([coll_key, other]);
+                }
+            });
+        }
+        pairs.into_iter()
+    }
+
+    /// Get all colliders that intersect with the given shape.
+    /// Returns a key to the collider, and if it's attached to a body,
+    /// also a key to the body.
+    pub fn query_shape<'p, 'g: 'p>(
+        &'p mut self,
+        pose: PhysicsPose,
+        shape: ColliderShape,
+        mask: CollisionLayerMask,
+    ) -> impl 'p + Iterator<Item = (ColliderKey, Option<BodyKey>)> {
+        let candidates = &mut self.working_bufs.query_candidates;
+        candidates.clear();
+        self.broadphase
+            .test_aabb(shape.aabb(pose), &mut |coll_key| candidates.push(coll_key));
+        let entity_set = &self.entity_set;
+        candidates.iter().filter_map(move |&coll_key| {
+            let coll = entity_set.get_collider(coll_key)?;
+            if !mask.get(coll.layer) {
+                return None;
+            }
+            let body_key = entity_set.coll_bodies.get(coll_key.0).copied();
+            let body = body_key.and_then(|k| entity_set.get_body(k));
+            let their_pose = match body {
+                Some(body) => body.pose * coll.pose,
+                None => coll.pose,
+            };
+            let result =
+                collision::shape_shape::intersection_check([pose, their_pose], [shape, coll.shape]);
+            if result.is_zero() {
+                None
+            } else {
+                Some((coll_key, body_key))
+            }
+        })
+    }
+
+    /// Find every collider within a cone, e.g. for checking what an AI character can see.
+    /// Returns a key to the collider, and if it's attached to a body,
+    /// also a key to the body.
+    ///
+    /// The cone starts at `apex` and opens towards `direction`
+    /// by `half_angle` on either side, up to a distance of `range`.
+    /// A collider is considered inside if its origin is in the cone,
+    /// so large colliders that only poke into it with an edge aren't found.
+    /// Only colliders on layers enabled in `mask` are considered.
+    ///
+    /// If `occlusion` is true, a ray is cast from `apex` to each collider
+    /// and colliders behind another solid collider are left out.
+    /// Colliders attached to the same body don't block each other.
+    /// This costs one raycast per collider in the cone.
+    pub fn query_cone(
+        &mut self,
+        apex: uv::DVec2,
+        direction: UnitDVec2,
+        half_angle: Angle,
+        range: f64,
+        mask: CollisionLayerMask,
+        occlusion: bool,
+    ) -> impl Iterator<Item = (ColliderKey, Option<BodyKey>)> {
+        let half_angle = half_angle.rad() as f64;
+        let min_cos = half_angle.cos();
+
+        // bounding box of the cone's corners and the extremes of its arc
+        let mut aabb = collision::AABB {
+            min: apex,
+            max: apex,
+        };
+        let edges = [-half_angle, half_angle].map(|a| uv::DRotor2::from_angle(a) * *direction);
+        let axes = [
+            uv::DVec2::unit_x(),
+            uv::DVec2::unit_y(),
+            -uv::DVec2::unit_x(),
+            -uv::DVec2::unit_y(),
+        ];
+        let axes_in_cone = axes
+            .into_iter()
+            .filter(|axis| axis.dot(*direction) >= min_cos);
+        for dir in edges.into_iter().chain(axes_in_cone) {
+            let point = apex + range * dir;
+            aabb.min = aabb.min.min_by_component(point);
+            aabb.max = aabb.max.max_by_component(point);
+        }
+
+        let candidates = &mut self.working_bufs.query_candidates;
+        candidates.clear();
+        self.broadphase
+            .test_aabb(aabb, &mut |coll_key| candidates.push(coll_key));
+        let entity_set = &self.entity_set;
+        let in_cone: Vec<(ColliderKey, Option<BodyKey>, uv::DVec2)> = candidates
+            .iter()
+            .filter_map(|&coll_key| {
+                let coll = entity_set.get_collider(coll_key)?;
+                if !mask.get(coll.layer) {
+                    return None;
+                }
+                let body_key = entity_set.coll_bodies.get(coll_key.0).copied();
+                let body = body_key.and_then(|k| entity_set.get_body(k));
+                let position = match body {
+                    Some(body) => body.pose * coll.pose.translation,
+                    None => coll.pose.translation,
+                };
+                let offset = position - apex;
+                let distance = offset.mag();
+                if distance > range || offset.dot(*direction) < distance * min_cos {
+                    return None;
+                }
+                Some((coll_key, body_key, offset))
+            })
+            .collect();
+
+        let mut found = Vec::with_capacity(in_cone.len());
+        for (coll_key, body_key, offset) in in_cone {
+            if occlusion {
+                let distance = offset.mag();
+                if distance > 1e-9 {
+                    let ray = Ray {
+                        start: apex,
+                        dir: UnitDVec2::new_unchecked(offset / distance),
+                    };
+                    let blocked = self.raycast(ray, distance).is_some_and(|hit| {
+                        let hit_body = self.entity_set.get_collider_body_key(hit.collider);
+                        hit.collider != coll_key && (body_key.is_none() || hit_body != body_key)
+                    });
+                    if blocked {
+                        continue;
+                    }
+                }
+            }
+            found.push((coll_key, body_key));
+        }
+        found.into_iter()
+    }
+
+    /// Find the time in seconds until two bodies first touch,
+    /// assuming they keep moving at their current linear and angular velocities,
+    /// e.g. for an AI to predict whether it's about to be hit by something.
+    ///
+    /// Only solid colliders attached to the bodies are considered,
+    /// and forces like gravity and other objects in the way are not taken into account.
+    /// Returns `Some(0.0)` if the bodies are already touching,
+    /// and `None` if they don't touch within `horizon` seconds
+    /// or either body doesn't exist or has no solid colliders.
+    pub fn time_of_impact(&self, a: BodyKey, b: BodyKey, horizon: f64) -> Option<f64> {
+        // how close counts as touching
+        const TOLERANCE: f64 = 1e-4;
+
+        let bodies = [self.entity_set.get_body(a)?, self.entity_set.get_body(b)?];
+        let colliders = [a, b].map(|body| -> Vec<Collider> {
+            self.entity_set
+                .coll_bodies
+                .iter()
+                .filter(|(_, b)| **b == body)
+                .filter_map(|(coll_idx, _)| self.entity_set.colliders.get(coll_idx))
+                .filter(|coll| coll.is_solid())
+                .copied()
+                .collect()
+        });
+        if colliders.iter().any(|colls| colls.is_empty()) {
+            return None;
+        }
+
+        // conservative advancement: step forward by the distance between the bodies
+        // divided by the fastest any of their points can move towards each other,
+        // which can never step past the moment they touch.
+        // every step covers at least TOLERANCE / max_approach_speed seconds,
+        // so this ends once the horizon is passed even if progress is slow,
+        // e.g. when a spinning body grazes the other
+        let reaches = colliders.each_ref().map(|colls| {
+            colls
+                .iter()
+                .map(|coll| coll.pose.translation.mag() + coll.shape.bounding_sphere_r())
+                .fold(0.0, f64::max)
+        });
+        let max_approach_speed = (bodies[0].velocity.linear - bodies[1].velocity.linear).mag()
+            + bodies[0].velocity.angular.abs() * reaches[0]
+            + bodies[1].velocity.angular.abs() * reaches[1];
+
+        let mut t = 0.0;
+        loop {
+            let poses = bodies.map(|body| body.velocity.apply_to_pose(t, body.pose));
+            let distance = colliders[0]
+                .iter()
+                .flat_map(|coll_a| {
+                    colliders[1].iter().map(move |coll_b| {
+                        collision::shape_shape::distance(
+                            [poses[0] * coll_a.pose, poses[1] * coll_b.pose],
+                            [coll_a.shape, coll_b.shape],
+                        )
+                    })
+                })
+                .fold(f64::INFINITY, f64::min);
+            if distance < TOLERANCE {
+                return Some(t);
+            }
+            if max_approach_speed == 0.0 {
+                return None;
+            }
+            t += distance / max_approach_speed;
+            if t > horizon {
+                return None;
+            }
+        }
     }
 
     /// Find the first solid collider intersected by the given ray.
@@ -1251,6 +2745,8 @@ impl PhysicsWorld {
     /// without having to worry about offsetting it just right.
     /// If you need to also know if the ray starts inside something, use
     /// [`query_point_body`][Self::query_point_body] in addition to this.
+    ///
+    /// Sleeping bodies are hit just like awake ones.
     #[inline]
     pub fn raycast(&mut self, ray: Ray, max_distance: f64) -> Option<CastHit> {
         self.spherecast(0.0, ray, max_distance)
@@ -1264,50 +2760,94 @@ impl PhysicsWorld {
     /// is (TODO think about this), but for now you can use [`query_shape`][Self::query_shape]
     /// with a circle, similarly to how you would check a point when raycasting.
     pub fn spherecast(&mut self, radius: f64, ray: Ray, max_distance: f64) -> Option<CastHit> {
-        // BVH traversal returns colliders in spatial order by their AABBs,
-        // but this may not return the actual closest thing first if there are
-        // small things near something large and diagonal.
-        // we need to keep traversing the BVH until we get something farther than currently found t
-        let mut closest_hit: Option<CastHit> = None;
-        for leaf in self.bvh.sweep_aabb(radius, ray, max_distance) {
-            if leaf.t >= max_distance || matches!(closest_hit, Some(closest) if leaf.t >= closest.t)
-            {
-                return closest_hit;
-            }
-
-            let coll = match self.entity_set.get_collider(leaf.coll_key) {
-                Some(c) => c,
-                None => continue,
-            };
-            if !coll.is_solid() {
-                continue;
-            }
-            let body = self
-                .entity_set
-                .coll_bodies
-                .get(leaf.coll_key.0)
-                .and_then(|bk| self.entity_set.get_body(*bk));
-            let pose = match body {
-                Some(body) => body.pose * coll.pose,
-                None => coll.pose,
-            };
+        let broadphase = &mut self.broadphase;
+        closest_cast_hit(
+            &self.entity_set,
+            |visit| broadphase.sweep_aabb(radius, ray, max_distance, visit),
+            radius,
+            ray,
+            max_distance,
+            CollisionLayerMask::default(),
+        )
+    }
 
-            let hit = match collision::query::spherecast_collider(ray, radius, pose, *coll) {
-                Some(hit) if hit.t <= max_distance => hit,
-                _ => continue,
-            };
-            let already_found_closer = matches!(closest_hit, Some(closest) if closest.t <= hit.t);
-            if already_found_closer {
-                continue;
-            }
-            closest_hit = Some(CastHit {
-                collider: leaf.coll_key,
-                point: hit.point,
-                normal: hit.normal,
-                t: hit.t,
-            });
+    /// Cast many rays at once, e.g. for a fan of vision rays or a lidar-like sensor.
+    /// Returns the first hit of each ray like [`raycast`][Self::raycast] does,
+    /// in the same order as `rays`.
+    /// Only colliders on layers enabled in `mask` are hit.
+    ///
+    /// With the `parallel` feature and the default [`Bvh`] broadphase,
+    /// the rays are divided between threads.
+    /// Otherwise they're cast one after another.
+    pub fn raycast_batch(
+        &mut self,
+        rays: &[Ray],
+        max_distance: f64,
+        mask: CollisionLayerMask,
+    ) -> Vec<Option<CastHit>> {
+        #[cfg(feature = "parallel")]
+        if let Some(bvh) = self.bvh() {
+            let entity_set = &self.entity_set;
+            return rays
+                .par_iter()
+                .map_init(collision::bvh::RayStack::default, |stack, &ray| {
+                    let sweep = |visit: &mut dyn FnMut(_) -> ControlFlow<()>| {
+                        let _ = bvh
+                            .sweep_aabb_with_stack(stack, 0.0, ray, max_distance)
+                            .try_for_each(visit);
+                    };
+                    closest_cast_hit(entity_set, sweep, 0.0, ray, max_distance, mask)
+                })
+                .collect();
         }
-        closest_hit
+
+        rays.iter()
+            .map(|&ray| {
+                let broadphase = &mut self.broadphase;
+                let sweep = |visit: &mut dyn FnMut(_) -> ControlFlow<()>| {
+                    broadphase.sweep_aabb(0.0, ray, max_distance, visit)
+                };
+                closest_cast_hit(&self.entity_set, sweep, 0.0, ray, max_distance, mask)
+            })
+            .collect()
+    }
+
+    /// The largest contact penetration or constraint violation
+    /// measured during the final iteration of the last substep of the previous frame.
+    ///
+    /// This is a measure of how well the solver converged.
+    /// If it's consistently high, increase [`TuningConstants::substeps`]
+    /// or [`TuningConstants::iterations`].
+    pub fn last_residual(&self) -> f64 {
+        self.working_bufs
+            .island_residuals
+            .iter()
+            .fold(0.0, |acc, res| f64::max(acc, *res))
+    }
+
+    /// The same value as [`last_residual`][Self::last_residual], but for every island
+    /// (group of bodies connected by contacts or constraints) separately,
+    /// along with the bodies in the island.
+    ///
+    /// Useful for finding which part of a scene is causing solver trouble.
+    /// Sleeping islands are not included.
+    pub fn island_residuals(
+        &self,
+    ) -> impl '_ + Iterator<Item = (f64, impl '_ + Iterator<Item = BodyKey>)> {
+        izip!(
+            &self.working_bufs.islands,
+            &self.working_bufs.island_residuals
+        )
+        .map(move |(island, residual)| {
+            let bodies = (island.body_range_start..island.body_range_start + island.body_count)
+                .filter_map(move |bi| {
+                    self.entity_set
+                        .bodies
+                        .get_by_slot(self.working_bufs.sorted_second_pass.bodies[bi] as u32)
+                        .map(|(idx, _)| BodyKey(idx))
+                });
+            (*residual, bodies)
+        })
     }
 
     /// For debug visualization
@@ -1327,3 +2867,1560 @@ impl PhysicsWorld {
         })
     }
 }
+
+/// Find the closest solid collider hit by a sphere swept along a ray
+/// among the candidates found by a broadphase sweep.
+///
+/// `sweep` runs the broadphase sweep, passing each candidate to the given visitor
+/// until it returns [`ControlFlow::Break`].
+fn closest_cast_hit(
+    entity_set: &EntitySet,
+    sweep: impl FnOnce(&mut dyn FnMut(collision::bvh::SweepItem) -> ControlFlow<()>),
+    radius: f64,
+    ray: Ray,
+    max_distance: f64,
+    mask: CollisionLayerMask,
+) -> Option<CastHit> {
+    // broadphase sweeps return colliders in spatial order by their AABBs,
+    // but this may not return the actual closest thing first if there are
+    // small things near something large and diagonal.
+    // we need to keep sweeping until we get something farther than currently found t
+    let mut closest_hit: Option<CastHit> = None;
+    sweep(&mut |leaf| {
+        if leaf.t >= max_distance || matches!(closest_hit, Some(closest) if leaf.t >= closest.t) {
+            return ControlFlow::Break(());
+        }
+
+        let Some(coll) = entity_set.get_collider(leaf.coll_key) else {
+            return ControlFlow::Continue(());
+        };
+        if !coll.is_solid() || !mask.get(coll.layer) {
+            return ControlFlow::Continue(());
+        }
+        let body = entity_set
+            .coll_bodies
+            .get(leaf.coll_key.0)
+            .and_then(|bk| entity_set.get_body(*bk));
+        let pose = match body {
+            Some(body) => body.pose * coll.pose,
+            None => coll.pose,
+        };
+
+        let hit = match collision::query::spherecast_collider(ray, radius, pose, *coll) {
+            Some(hit) if hit.t <= max_distance => hit,
+            _ => return ControlFlow::Continue(()),
+        };
+        let already_found_closer = matches!(closest_hit, Some(closest) if closest.t <= hit.t);
+        if !already_found_closer {
+            closest_hit = Some(CastHit {
+                collider: leaf.coll_key,
+                point: hit.point,
+                normal: hit.normal,
+                t: hit.t,
+            });
+        }
+        ControlFlow::Continue(())
+    });
+    closest_hit
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use forcefield::Gravity;
+
+    /// A box resting on static ground, ticked until its island has been set to sleep.
+    /// Returns the world, the ground collider and the box collider.
+    fn sleeping_box_on_ground() -> (PhysicsWorld, ColliderKey, ColliderKey) {
+        let mut physics = PhysicsWorld::new(Default::default(), Default::default());
+        let ground_pose = PhysicsPose::new(uv::DVec2::new(0.0, -0.5), uv::DRotor2::identity());
+        let ground_key = physics
+            .entity_set
+            .insert_collider(Collider::new_rect(10.0, 1.0).with_pose(ground_pose));
+        let box_coll = Collider::new_square(1.0).with_user_data(42);
+        let box_pose = PhysicsPose::new(uv::DVec2::new(0.0, 0.5), uv::DRotor2::identity());
+        let box_body = physics
+            .entity_set
+            .insert_body(Body::new_dynamic(box_coll.info(), 1.0).with_pose(box_pose));
+        let box_key = physics.entity_set.attach_collider(box_body, box_coll);
+
+        let gravity = Gravity(uv::DVec2::new(0.0, -9.81));
+        for _ in 0..300 {
+            physics.tick(1.0 / 60.0, None, &gravity);
+        }
+        assert!(
+            physics
+                .sleeping_islands
+                .iter()
+                .any(|isl| isl.ticks_slept >= physics.consts.fall_asleep_frames),
+            "box never fell asleep"
+        );
+
+        (physics, ground_key, box_key)
+    }
+
+    #[test]
+    fn resting_contact_impulse_matches_weight() {
+        let (physics, _, box_key) = sleeping_box_on_ground();
+        let contact = physics
+            .contacts_for_collider(box_key)
+            .next()
+            .expect("resting box has no contacts");
+        // a unit box with density 1 has mass 1,
+        // so the contact has to cancel out gravity over one substep
+        let substep_dt = 1.0 / 60.0 / physics.consts.substeps as f64;
+        let expected = 9.81 * substep_dt;
+        assert!(
+            (contact.impulse - expected).abs() < 0.25 * expected,
+            "impulse {} (expected {expected})",
+            contact.impulse,
+        );
+    }
+
+    #[test]
+    fn resting_contact_points_at_box_corners() {
+        let (physics, _, box_key) = sleeping_box_on_ground();
+        let contact = physics.contacts_for_collider(box_key).next().unwrap();
+        let points = contact.points();
+        assert_eq!(points.len(), 2);
+        for p in points {
+            assert!((p.x.abs() - 0.5).abs() < 0.01, "contact point at {p:?}");
+            assert!(p.y.abs() < 0.01, "contact point at {p:?}");
+        }
+    }
+
+    #[test]
+    fn resting_box_is_supported() {
+        let (physics, ground, box_key) = sleeping_box_on_ground();
+
+        let contact = physics.are_in_contact(ground, box_key).unwrap();
+        assert_eq!(contact.colliders, [ground, box_key]);
+        assert!(contact.normal.y > 0.99);
+
+        let up = UnitDVec2::unit_y();
+        assert!(physics.is_collider_supported(box_key, up, Angle::Deg(45.0)));
+        assert!(!physics.is_collider_supported(box_key, -up, Angle::Deg(45.0)));
+        assert!(!physics.is_collider_supported(ground, up, Angle::Deg(45.0)));
+    }
+
+    #[test]
+    fn classify_contact_surfaces() {
+        let (physics, ground, box_key) = sleeping_box_on_ground();
+        let up = UnitDVec2::unit_y();
+        let wall_angle = Angle::Deg(45.0);
+
+        let from_box = physics.are_in_contact(box_key, ground).unwrap();
+        assert_eq!(from_box.classify(up, wall_angle), SurfaceKind::Floor);
+        let from_ground = physics.are_in_contact(ground, box_key).unwrap();
+        assert_eq!(from_ground.classify(up, wall_angle), SurfaceKind::Ceiling);
+        let sideways = UnitDVec2::unit_x();
+        assert_eq!(from_box.classify(sideways, wall_angle), SurfaceKind::Wall);
+    }
+
+    #[test]
+    fn sleeping_bodies_block_rays() {
+        let (mut physics, _, box_key) = sleeping_box_on_ground();
+        let hit = physics
+            .raycast(
+                Ray {
+                    start: uv::DVec2::new(0.0, 5.0),
+                    dir: -UnitDVec2::unit_y(),
+                },
+                10.0,
+            )
+            .expect("ray passed through a sleeping box");
+        assert_eq!(hit.collider, box_key);
+        assert_eq!(physics.collider_user_data(hit.collider), Some(42));
+        assert!((hit.point.y - 1.0).abs() < 0.01, "hit at {:?}", hit.point);
+    }
+
+    #[test]
+    fn sleeping_bodies_found_by_queries() {
+        let (mut physics, _, box_key) = sleeping_box_on_ground();
+        assert!(physics
+            .query_point(uv::DVec2::new(0.0, 0.5))
+            .any(|(coll, _)| coll == box_key));
+        assert!(physics
+            .query_shape(
+                PhysicsPose::new(uv::DVec2::new(0.6, 0.5), uv::DRotor2::identity()),
+                Collider::new_circle(0.2).shape,
+                CollisionLayerMask::default(),
+            )
+            .any(|(coll, _)| coll == box_key));
+    }
+
+    #[test]
+    fn pick_prefers_smaller_collider() {
+        let (mut physics, ground_key, box_key) = sleeping_box_on_ground();
+        // large sensor covering both the box and the ground
+        let backdrop_key = physics.entity_set.insert_collider(Collider {
+            ty: ColliderType::Sensor,
+            layer: 1,
+            ..Collider::new_rect(4.0, 4.0)
+        });
+        physics.tick(1.0 / 60.0, None, &Gravity(uv::DVec2::new(0.0, -9.81)));
+
+        let hit = physics
+            .pick(uv::DVec2::new(0.2, 0.6), CollisionLayerMask::default())
+            .expect("nothing picked");
+        assert_eq!(hit.collider, box_key);
+        assert!(hit.body.is_some());
+        assert!((hit.local_point - uv::DVec2::new(0.2, 0.1)).mag() < 0.01);
+
+        let hit = physics
+            .pick(uv::DVec2::new(0.2, -0.6), CollisionLayerMask::default())
+            .expect("nothing picked");
+        assert_eq!(hit.collider, ground_key);
+
+        let hit = physics
+            .pick(
+                uv::DVec2::new(0.2, 0.6),
+                CollisionLayerMask::from_layers(&[1]),
+            )
+            .expect("nothing picked");
+        assert_eq!(hit.collider, backdrop_key);
+        assert!(hit.body.is_none());
+
+        assert!(physics
+            .pick(uv::DVec2::new(3.0, 3.0), CollisionLayerMask::default())
+            .is_none());
+    }
+
+    #[test]
+    fn warm_start_ignores_separating_contacts() {
+        // a box sliding along the ground while tipping over,
+        // lifting one corner off the ground during the frame
+        let velocity_after_tipping = |warm_start: bool| {
+            let mut physics = PhysicsWorld::new(
+                TuningConstants {
+                    warm_start,
+                    ..Default::default()
+                },
+                Default::default(),
+            );
+            let ground_pose = PhysicsPose::new(uv::DVec2::new(0.0, -0.5), uv::DRotor2::identity());
+            physics
+                .entity_set
+                .insert_collider(Collider::new_rect(10.0, 1.0).with_pose(ground_pose));
+            let coll = Collider::new_square(1.0);
+            let pose = PhysicsPose::new(uv::DVec2::new(0.0, 0.5), uv::DRotor2::identity());
+            let body_key = physics
+                .entity_set
+                .insert_body(Body::new_dynamic(coll.info(), 1.0).with_pose(pose));
+            physics.entity_set.attach_collider(body_key, coll);
+
+            let gravity = Gravity(uv::DVec2::new(0.0, -9.81));
+            for _ in 0..5 {
+                physics.tick(1.0 / 60.0, None, &gravity);
+            }
+            physics.wake_body(body_key);
+            physics.entity_set.get_body_mut(body_key).unwrap().velocity = Velocity {
+                linear: uv::DVec2::new(1.0, 0.0),
+                angular: -2.0,
+            };
+            physics.tick(1.0 / 60.0, None, &gravity);
+            physics.entity_set.get_body(body_key).unwrap().velocity
+        };
+
+        // forces remembered from resting must not add friction to the lifting corner
+        let warm = velocity_after_tipping(true);
+        let cold = velocity_after_tipping(false);
+        assert!(
+            (warm.linear - cold.linear).mag() < 1e-6 && (warm.angular - cold.angular).abs() < 1e-6,
+            "warm started {warm:?}, cold {cold:?}"
+        );
+    }
+
+    #[test]
+    fn applied_force_lasts_one_tick() {
+        let mut physics = PhysicsWorld::new(Default::default(), Default::default());
+        let coll = Collider::new_square(1.0);
+        let body_key = physics
+            .entity_set
+            .insert_body(Body::new_dynamic_const_mass(coll.info(), 2.0));
+        physics.entity_set.attach_collider(body_key, coll);
+
+        let dt = 1.0 / 60.0;
+        let body = physics.entity_set.get_body_mut(body_key).unwrap();
+        // off-center to also make it spin counterclockwise
+        body.apply_force(uv::DVec2::new(4.0, 0.0), uv::DVec2::new(0.0, -0.5));
+        physics.tick(dt, None, &forcefield::NoneField);
+
+        let body = physics.entity_set.get_body(body_key).unwrap();
+        assert!((body.velocity.linear.x - 2.0 * dt).abs() < 1e-9);
+        assert!(body.velocity.angular > 0.0);
+        assert_eq!(body.force, uv::DVec2::zero());
+        assert_eq!(body.torque, 0.0);
+
+        let velocity = body.velocity;
+        physics.tick(dt, None, &forcefield::NoneField);
+        let body = physics.entity_set.get_body(body_key).unwrap();
+        assert!((body.velocity.linear - velocity.linear).mag() < 1e-9);
+    }
+
+    #[test]
+    fn closest_point_on_collider_in_world_space() {
+        let (mut physics, ground_key, box_key) = sleeping_box_on_ground();
+
+        let (point, dist) = physics
+            .closest_point_on_collider(box_key, uv::DVec2::new(0.0, 3.0))
+            .unwrap();
+        assert!((point - uv::DVec2::new(0.0, 1.0)).mag() < 0.01);
+        assert!((dist - 2.0).abs() < 0.01);
+        // inside the box, closest to its right side
+        let (point, dist) = physics
+            .closest_point_on_collider(box_key, uv::DVec2::new(0.3, 0.5))
+            .unwrap();
+        assert!((point - uv::DVec2::new(0.5, 0.5)).mag() < 0.01);
+        assert!((dist + 0.2).abs() < 0.01);
+
+        let (point, dist) = physics
+            .closest_point_on_collider(ground_key, uv::DVec2::new(3.0, 2.0))
+            .unwrap();
+        assert!((point - uv::DVec2::new(3.0, 0.0)).mag() < 1e-9);
+        assert!((dist - 2.0).abs() < 1e-9);
+
+        physics.entity_set.remove_collider(ground_key);
+        assert!(physics
+            .closest_point_on_collider(ground_key, uv::DVec2::zero())
+            .is_none());
+    }
+
+    #[test]
+    fn spawn_batch_keys_in_order() {
+        let mut physics = PhysicsWorld::new(Default::default(), Default::default());
+        let poses: Vec<PhysicsPose> = (0..5)
+            .map(|i| PhysicsPose::new(uv::DVec2::new(i as f64, 0.0), uv::DRotor2::identity()))
+            .collect();
+        let shape = Collider::new_circle(0.5).shape;
+        let keys = physics.spawn_batch(shape, PhysicsMaterial::default(), 2.0, &poses);
+
+        assert_eq!(keys.len(), poses.len());
+        let expected_inv_mass = 1.0 / (Collider::new_circle(0.5).info().area * 2.0);
+        for (key, pose) in keys.iter().zip(&poses) {
+            let body = physics.entity_set.get_body(*key).unwrap();
+            assert_eq!(body.pose.translation, pose.translation);
+            assert!((body.mass.inv() - expected_inv_mass).abs() < 1e-9);
+        }
+        assert_eq!(physics.entity_set.coll_bodies.len(), poses.len());
+    }
+
+    #[test]
+    fn iteration_follows_slot_order() {
+        let mut entity_set = EntitySet::default();
+        let mut bodies: Vec<BodyKey> = (0..4)
+            .map(|_| entity_set.insert_body(Body::new_particle(1.0)))
+            .collect();
+        let colls: Vec<ColliderKey> = (0..4)
+            .map(|_| entity_set.insert_collider(Collider::new_circle(0.5)))
+            .collect();
+        // the freed slot is reused, so the new body goes in the middle
+        entity_set.remove_body(bodies[1]);
+        bodies[1] = entity_set.insert_body(Body::new_particle(1.0));
+        entity_set.remove_collider(colls[2]);
+
+        let keys: Vec<BodyKey> = entity_set.iter_bodies().map(|(k, _)| k).collect();
+        assert_eq!(keys, bodies);
+        let keys_mut: Vec<BodyKey> = entity_set.iter_bodies_mut().map(|(k, _)| k).collect();
+        assert_eq!(keys_mut, bodies);
+
+        let expected_colls = [colls[0], colls[1], colls[3]];
+        let coll_keys: Vec<ColliderKey> = entity_set.iter_colliders().map(|(k, _)| k).collect();
+        assert_eq!(coll_keys, expected_colls);
+        let coll_keys_mut: Vec<ColliderKey> =
+            entity_set.iter_colliders_mut().map(|(k, _)| k).collect();
+        assert_eq!(coll_keys_mut, expected_colls);
+    }
+
+    #[test]
+    fn potential_pairs_from_bvh() {
+        let (mut physics, ground_key, box_key) = sleeping_box_on_ground();
+        let far_pose = PhysicsPose::new(uv::DVec2::new(20.0, 0.0), uv::DRotor2::identity());
+        physics
+            .entity_set
+            .insert_collider(Collider::new_square(1.0).with_pose(far_pose));
+        physics.tick(1.0 / 60.0, None, &Gravity(uv::DVec2::new(0.0, -9.81)));
+
+        let pairs: Vec<[ColliderKey; 2]> = physics.potential_pairs().collect();
+        assert_eq!(pairs.len(), 1, "pairs: {pairs:?}");
+        let pair = pairs[0];
+        assert!(pair == [ground_key, box_key] || pair == [box_key, ground_key]);
+    }
+
+    #[test]
+    fn spatial_hash_matches_bvh() {
+        let scene = |physics: PhysicsWorld| {
+            let mut physics = physics;
+            let ground_pose = PhysicsPose::new(uv::DVec2::new(0.0, -0.5), uv::DRotor2::identity());
+            physics
+                .entity_set
+                .insert_collider(Collider::new_rect(40.0, 1.0).with_pose(ground_pose));
+            for i in 0..30 {
+                let pos = uv::DVec2::new((i % 10) as f64 * 1.5 - 7.0, (i / 10) as f64 * 1.1 + 0.4);
+                let coll = if i % 2 == 0 {
+                    Collider::new_square(1.0)
+                } else {
+                    Collider::new_circle(0.5)
+                };
+                let body = Body::new_dynamic(coll.info(), 1.0)
+                    .with_pose(PhysicsPose::new(pos, uv::DRotor2::identity()));
+                let body = physics.entity_set.insert_body(body);
+                physics.entity_set.attach_collider(body, coll);
+            }
+            physics.tick(1.0 / 60.0, None, &Gravity(uv::DVec2::new(0.0, -9.81)));
+            physics
+        };
+        let mut bvh_world = scene(PhysicsWorld::new(Default::default(), Default::default()));
+        let mut hash_world = scene(
+            PhysicsWorld::new(Default::default(), Default::default())
+                .with_broadphase(SpatialHashBroadphase::new(1.0)),
+        );
+        assert!(hash_world.bvh().is_none());
+
+        let contact_pairs = |physics: &PhysicsWorld| -> Vec<[ColliderKey; 2]> {
+            physics.contacts().iter().map(|c| c.colliders).collect()
+        };
+        assert!(!bvh_world.contacts().is_empty());
+        assert_eq!(contact_pairs(&bvh_world), contact_pairs(&hash_world));
+
+        let mut bvh_pairs: Vec<[ColliderKey; 2]> = bvh_world.potential_pairs().collect();
+        let mut hash_pairs: Vec<[ColliderKey; 2]> = hash_world.potential_pairs().collect();
+        bvh_pairs.sort_unstable();
+        hash_pairs.sort_unstable();
+        assert_eq!(bvh_pairs, hash_pairs);
+
+        let ray = Ray {
+            start: uv::DVec2::new(-10.0, 0.3),
+            dir: UnitDVec2::unit_x(),
+        };
+        let bvh_hit = bvh_world.raycast(ray, 50.0).unwrap();
+        let hash_hit = hash_world.raycast(ray, 50.0).unwrap();
+        assert_eq!(bvh_hit.t, hash_hit.t);
+        let mut bvh_under: Vec<_> = bvh_world.query_point(uv::DVec2::new(-7.0, 0.4)).collect();
+        let mut hash_under: Vec<_> = hash_world.query_point(uv::DVec2::new(-7.0, 0.4)).collect();
+        bvh_under.sort_unstable();
+        hash_under.sort_unstable();
+        assert_eq!(bvh_under.len(), 1);
+        assert_eq!(bvh_under, hash_under);
+    }
+
+    #[test]
+    fn bvh_levels() {
+        let mut physics = PhysicsWorld::new(Default::default(), Default::default());
+        for i in 0..5 {
+            let pose =
+                PhysicsPose::new(uv::DVec2::new(i as f64 * 3.0, 0.0), uv::DRotor2::identity());
+            physics
+                .entity_set
+                .insert_collider(Collider::new_square(1.0).with_pose(pose));
+        }
+        physics.tick(1.0 / 60.0, None, &Gravity(uv::DVec2::zero()));
+        let bvh = physics.bvh().expect("BVH is the default broadphase");
+
+        assert_eq!(bvh.iter_levels(0).count(), 0);
+        let root: Vec<(usize, collision::AABB)> = bvh.iter_levels(1).collect();
+        assert_eq!(root.len(), 1);
+        assert_eq!(root[0].0, 0);
+        assert!(root[0].1.min.x <= -0.5 && root[0].1.max.x >= 12.5);
+
+        // a binary tree with 5 leaves has 4 branches
+        let all: Vec<(usize, collision::AABB)> = bvh.iter_levels(usize::MAX).collect();
+        assert_eq!(all.len(), 9);
+        let max_depth = all.iter().map(|(depth, _)| *depth).max().unwrap();
+        assert!(bvh.iter_levels(max_depth).count() < all.len());
+        // children are always contained in the node before them at the previous level
+        for (i, (depth, aabb)) in all.iter().enumerate().skip(1) {
+            let (_, parent) = all[..i]
+                .iter()
+                .rev()
+                .find(|(d, _)| d + 1 == *depth)
+                .unwrap();
+            assert!(parent.min.x <= aabb.min.x && parent.max.x >= aabb.max.x);
+        }
+    }
+
+    #[test]
+    fn vortex_swirls_and_spins() {
+        let mut physics = PhysicsWorld::new(Default::default(), Default::default());
+        let coll = Collider::new_circle(0.5);
+        let pose = PhysicsPose::new(uv::DVec2::new(3.0, 0.0), uv::DRotor2::identity());
+        let body_key = physics
+            .entity_set
+            .insert_body(Body::new_dynamic(coll.info(), 1.0).with_pose(pose));
+        physics.entity_set.attach_collider(body_key, coll);
+
+        let vortex = forcefield::Vortex {
+            position: uv::DVec2::zero(),
+            strength: 10.0,
+            spin: 10.0,
+            falloff: 1.0,
+        };
+        for _ in 0..10 {
+            physics.tick(1.0 / 60.0, None, &vortex);
+        }
+        let body = physics.entity_set.get_body(body_key).unwrap();
+        // counterclockwise around the origin from the positive x-axis is upward
+        assert!(body.velocity.linear.y > 0.0, "{:?}", body.velocity);
+        assert!(body.velocity.linear.x.abs() < body.velocity.linear.y);
+        assert!(body.velocity.angular > 0.0, "{:?}", body.velocity);
+    }
+
+    #[test]
+    fn vortex_falloff_keeps_center_strength() {
+        let gentle = forcefield::Vortex {
+            position: uv::DVec2::zero(),
+            strength: 10.0,
+            spin: 10.0,
+            falloff: 0.5,
+        };
+        let steep = forcefield::Vortex {
+            falloff: 4.0,
+            ..gentle
+        };
+        assert_eq!(gentle.torque_at(uv::DVec2::zero()), 10.0);
+        assert_eq!(steep.torque_at(uv::DVec2::zero()), 10.0);
+
+        let far = uv::DVec2::new(2.0, 0.0);
+        assert!(steep.torque_at(far) < gentle.torque_at(far));
+        assert!(steep.value_at(far).mag() < gentle.value_at(far).mag());
+    }
+
+    #[test]
+    fn ground_check_finds_supporting_collider() {
+        let (physics, ground_key, box_key) = sleeping_box_on_ground();
+        let body_key = physics.entity_set.get_collider_body_key(box_key).unwrap();
+        let up = UnitDVec2::unit_y();
+
+        let ground = physics
+            .ground_check(body_key, up, Angle::Deg(45.0))
+            .expect("box not grounded");
+        assert_eq!(ground.collider, box_key);
+        assert_eq!(ground.ground, ground_key);
+        assert!(ground.normal.dot(*up) > 0.99);
+        assert!(ground.point.x.abs() < 0.01 && ground.point.y.abs() < 0.01);
+
+        assert!(physics
+            .ground_check(body_key, -up, Angle::Deg(45.0))
+            .is_none());
+    }
+
+    /// Flat ground with its top at y = 0, a step up to y = `step` starting at x = 2
+    /// (no step if `step` is zero),
+    /// and a kinematic circle character with radius 0.5 resting on the ground at x = 0.
+    fn character_on_ground(step: f64) -> (PhysicsWorld, BodyKey) {
+        let mut physics = PhysicsWorld::new(Default::default(), Default::default());
+        let ground_pose = PhysicsPose::new(uv::DVec2::new(0.0, -0.5), uv::DRotor2::identity());
+        physics
+            .entity_set
+            .insert_collider(Collider::new_rect(20.0, 1.0).with_pose(ground_pose));
+        if step > 0.0 {
+            let step_pose =
+                PhysicsPose::new(uv::DVec2::new(5.0, step / 2.0), uv::DRotor2::identity());
+            physics
+                .entity_set
+                .insert_collider(Collider::new_rect(6.0, step).with_pose(step_pose));
+        }
+        let char_pose = PhysicsPose::new(uv::DVec2::new(0.0, 0.52), uv::DRotor2::identity());
+        let body = physics
+            .entity_set
+            .insert_body(Body::new_kinematic().with_pose(char_pose));
+        physics
+            .entity_set
+            .attach_collider(body, Collider::new_circle(0.5));
+        physics.tick(1.0 / 60.0, None, &forcefield::NoneField);
+        (physics, body)
+    }
+
+    #[test]
+    fn character_controller_lands_and_walks() {
+        let (mut physics, body) = character_on_ground(0.1);
+        let controller = CharacterController::new(0.5);
+
+        // falling is stopped by the ground
+        let result = controller.move_body(&mut physics, body, uv::DVec2::new(0.0, -3.0));
+        assert!(result.is_grounded());
+        let pos = physics.entity_set.get_body(body).unwrap().pose.translation;
+        assert!(pos.y > 0.5 && pos.y < 0.52, "sank to {pos:?}");
+
+        // small step is walked over
+        let result = controller.move_body(&mut physics, body, uv::DVec2::new(3.0, 0.0));
+        let pos = physics.entity_set.get_body(body).unwrap().pose.translation;
+        assert!((result.displacement.x - 3.0).abs() < 0.05, "{result:?}");
+        assert!(pos.y > 0.6, "didn't step up: {pos:?}");
+        assert!(result.is_grounded());
+    }
+
+    #[test]
+    fn character_controller_blocked_by_wall() {
+        let (mut physics, body) = character_on_ground(1.0);
+        let controller = CharacterController::new(0.5);
+
+        let result = controller.move_body(&mut physics, body, uv::DVec2::new(3.0, 0.0));
+        let pos = physics.entity_set.get_body(body).unwrap().pose.translation;
+        // wall at x = 2, stopped one radius away
+        assert!(
+            pos.x < 1.5 && pos.x > 1.45,
+            "went through the wall: {pos:?}"
+        );
+        assert!(pos.y < 0.6, "climbed the wall: {pos:?}");
+        assert!(result.is_grounded());
+    }
+
+    #[test]
+    fn character_controller_slope_limit() {
+        for (slope_deg, should_climb) in [(30.0, true), (60.0, false)] {
+            let (mut physics, body) = character_on_ground(0.0);
+            // ramp whose top surface starts from the ground at x = 2
+            let rot = uv::DRotor2::from_angle(f64::to_radians(slope_deg));
+            let ramp_pose = PhysicsPose::new(
+                uv::DVec2::new(2.0, 0.0) + rot * uv::DVec2::new(10.0, -0.5),
+                rot,
+            );
+            physics
+                .entity_set
+                .insert_collider(Collider::new_rect(20.0, 1.0).with_pose(ramp_pose));
+            physics.tick(1.0 / 60.0, None, &forcefield::NoneField);
+
+            let controller = CharacterController::new(0.5);
+            for _ in 0..30 {
+                controller.move_body(&mut physics, body, uv::DVec2::new(0.1, -0.1));
+            }
+            let pos = physics.entity_set.get_body(body).unwrap().pose.translation;
+            assert_eq!(
+                pos.y > 1.0,
+                should_climb,
+                "{slope_deg} degree slope: {pos:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn moving_collider_wakes_body() {
+        let (mut physics, _, box_key) = sleeping_box_on_ground();
+        physics.tick(1.0 / 60.0, None, &Gravity(uv::DVec2::new(0.0, -9.81)));
+        assert_eq!(physics.stats().awake_bodies, 0);
+
+        let raised = PhysicsPose::new(uv::DVec2::new(0.0, 0.2), uv::DRotor2::identity());
+        physics.set_collider_local_pose(box_key, raised);
+        physics.tick(1.0 / 60.0, None, &Gravity(uv::DVec2::new(0.0, -9.81)));
+        assert_eq!(physics.stats().awake_bodies, 1);
+    }
+
+    #[test]
+    fn teleported_body_wakes_up() {
+        let gravity = Gravity(uv::DVec2::new(0.0, -9.81));
+        let (mut physics, _, box_key) = sleeping_box_on_ground();
+        let body = physics.entity_set.get_collider_body_key(box_key).unwrap();
+        // sliding along the ground keeps the same contacts,
+        // so nothing else would wake the island
+        let box_body = physics.entity_set.get_body_mut(body).unwrap();
+        box_body.pose.translation.x = 2.0;
+        physics.tick(1.0 / 60.0, None, &gravity);
+        assert_eq!(
+            physics.stats().awake_bodies,
+            0,
+            "teleporting without waking shouldn't wake the body"
+        );
+
+        physics.wake_body(body);
+        physics.tick(1.0 / 60.0, None, &gravity);
+        assert_eq!(physics.stats().awake_bodies, 1);
+
+        let (mut physics, _, _) = sleeping_box_on_ground();
+        physics.wake_all();
+        physics.tick(1.0 / 60.0, None, &gravity);
+        assert_eq!(physics.stats().awake_bodies, 1);
+    }
+
+    #[test]
+    fn flipped_gravity_lifts_woken_box() {
+        let (mut physics, _, box_key) = sleeping_box_on_ground();
+        let body = physics.entity_set.get_collider_body_key(box_key).unwrap();
+        let resting_pose = physics.entity_set.get_body(body).unwrap().pose;
+        let flipped = Gravity(uv::DVec2::new(0.0, 9.81));
+        physics.tick(1.0 / 60.0, None, &flipped);
+        let pose = physics.entity_set.get_body(body).unwrap().pose;
+        assert_eq!(pose, resting_pose, "sleeping box reacted to gravity");
+
+        physics.wake_all();
+        for _ in 0..30 {
+            physics.tick(1.0 / 60.0, None, &flipped);
+        }
+        let pose = physics.entity_set.get_body(body).unwrap().pose;
+        assert!(pose.translation.y > 1.5, "box didn't fall up ({pose:?})");
+    }
+
+    #[test]
+    fn rope_particle_poses_span_the_rope() {
+        let mut physics = PhysicsWorld::new(Default::default(), Default::default());
+        let start = uv::DVec2::new(-1.0, 2.0);
+        let end = uv::DVec2::new(1.0, 2.0);
+        let rope = Rope::spawn_line(Default::default(), start, end, &mut physics.entity_set);
+        let particle_count = rope.particles.len();
+        let rope_key = physics.rope_set.insert(rope);
+
+        let poses: Vec<PhysicsPose> = physics
+            .rope_set
+            .particle_poses(rope_key, &physics.entity_set)
+            .collect();
+        assert_eq!(poses.len(), particle_count);
+        assert!((poses[0].translation - start).mag() < 1e-9);
+        assert!((poses[particle_count - 1].translation - end).mag() < 1e-9);
+        assert!(poses
+            .windows(2)
+            .all(|pair| pair[1].translation.x > pair[0].translation.x));
+
+        physics.rope_set.remove(rope_key, &mut physics.entity_set);
+        assert_eq!(
+            physics
+                .rope_set
+                .particle_poses(rope_key, &physics.entity_set)
+                .count(),
+            0
+        );
+    }
+
+    #[test]
+    fn split_ropes_keep_user_data() {
+        let mut physics = PhysicsWorld::new(Default::default(), Default::default());
+        let rope = Rope::spawn_line(
+            Default::default(),
+            uv::DVec2::zero(),
+            uv::DVec2::new(1.0, 0.0),
+            &mut physics.entity_set,
+        )
+        .with_user_data(7);
+        let middle = rope.particles[rope.particles.len() / 2].body;
+        physics.rope_set.insert(rope);
+
+        physics.entity_set.remove_body(middle);
+        physics.tick(1.0 / 60.0, None, &Gravity(uv::DVec2::zero()));
+        assert_eq!(physics.rope_set.ropes.len(), 2);
+        assert!(physics
+            .rope_set
+            .ropes
+            .iter()
+            .all(|(_, rope)| rope.user_data == 7));
+    }
+
+    #[test]
+    fn raycast_batch_matches_single_raycasts() {
+        let mut physics = PhysicsWorld::new(Default::default(), Default::default());
+        for i in 0..10 {
+            let pose = PhysicsPose::new(
+                uv::DVec2::new(i as f64 * 1.5 - 7.0, (i % 3) as f64 * 2.0),
+                uv::DRotor2::from_angle(i as f64 * 0.3),
+            );
+            physics.entity_set.insert_collider(
+                Collider::new_rect(1.0, 0.5)
+                    .with_pose(pose)
+                    .with_layer(i % 2),
+            );
+        }
+        physics.tick(1.0 / 60.0, None, &Gravity(uv::DVec2::zero()));
+
+        // a fan of rays from above pointing down at the row of boxes
+        let rays: Vec<Ray> = (0..32)
+            .map(|i| Ray {
+                start: uv::DVec2::new(0.0, 10.0),
+                dir: UnitDVec2::new_normalize(uv::DVec2::new(i as f64 / 31.0 - 0.5, -1.0)),
+            })
+            .collect();
+        let batch = physics.raycast_batch(&rays, 20.0, CollisionLayerMask::default());
+        let single: Vec<Option<CastHit>> =
+            rays.iter().map(|&ray| physics.raycast(ray, 20.0)).collect();
+        assert_eq!(batch.len(), rays.len());
+        assert!(batch.iter().any(|hit| hit.is_some()));
+        for (b, s) in batch.iter().zip(&single) {
+            assert_eq!(
+                b.map(|hit| (hit.collider, hit.t)),
+                s.map(|hit| (hit.collider, hit.t))
+            );
+        }
+
+        let layer_0 = CollisionLayerMask::from_layers(&[0]);
+        let masked = physics.raycast_batch(&rays, 20.0, layer_0);
+        for hit in masked.iter().flatten() {
+            let coll = physics.entity_set.get_collider(hit.collider).unwrap();
+            assert_eq!(coll.layer, 0);
+        }
+    }
+
+    #[test]
+    fn radial_impulse_pushes_bodies_away() {
+        let mut physics = PhysicsWorld::new(Default::default(), Default::default());
+        let mut add_box = |x: f64, y: f64| {
+            let coll = Collider::new_square(0.5);
+            let pose = PhysicsPose::new(uv::DVec2::new(x, y), uv::DRotor2::identity());
+            let body = physics
+                .entity_set
+                .insert_body(Body::new_dynamic(coll.info(), 1.0).with_pose(pose));
+            physics.entity_set.attach_collider(body, coll);
+            body
+        };
+        let near = add_box(1.0, 0.0);
+        let far = add_box(0.0, 3.0);
+        let hidden = add_box(-3.0, 0.0);
+        let wall_pose = PhysicsPose::new(uv::DVec2::new(-1.5, 0.0), uv::DRotor2::identity());
+        physics
+            .entity_set
+            .insert_collider(Collider::new_rect(0.2, 4.0).with_pose(wall_pose));
+        // builds the BVH so the impulse can find the bodies
+        physics.tick(1.0 / 60.0, None, &forcefield::NoneField);
+
+        physics.apply_radial_impulse(
+            uv::DVec2::zero(),
+            5.0,
+            1.0,
+            ImpulseFalloff::Linear,
+            CollisionLayerMask::default(),
+            true,
+        );
+        let vel = |physics: &PhysicsWorld, key| physics.entity_set.get_body(key).unwrap().velocity;
+        let near_vel = vel(&physics, near);
+        let far_vel = vel(&physics, far);
+        // linear falloff and mass 0.25 gives (1 - d/5) * 4 away from the center
+        assert!((near_vel.linear.x - 3.2).abs() < 1e-9, "{near_vel:?}");
+        assert_eq!(near_vel.linear.y, 0.0);
+        assert_eq!(near_vel.angular, 0.0);
+        assert!((far_vel.linear.y - 1.6).abs() < 1e-9, "{far_vel:?}");
+        assert_eq!(
+            vel(&physics, hidden).linear.mag(),
+            0.0,
+            "wall should block the impulse"
+        );
+
+        physics.apply_radial_impulse(
+            uv::DVec2::zero(),
+            5.0,
+            1.0,
+            ImpulseFalloff::Constant,
+            CollisionLayerMask::default(),
+            false,
+        );
+        assert!((vel(&physics, hidden).linear.x + 4.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn cone_query() {
+        let mut physics = PhysicsWorld::new(Default::default(), Default::default());
+        let add_box = |physics: &mut PhysicsWorld, x: f64, y: f64| {
+            let pose = PhysicsPose::new(uv::DVec2::new(x, y), uv::DRotor2::identity());
+            physics
+                .entity_set
+                .insert_collider(Collider::new_square(0.5).with_pose(pose))
+        };
+        let ahead = add_box(&mut physics, 3.0, 0.5);
+        let behind_wall = add_box(&mut physics, 4.0, -1.0);
+        let wall = add_box(&mut physics, 2.0, -0.5);
+        // out of range and outside the angle respectively
+        add_box(&mut physics, 6.0, 0.0);
+        add_box(&mut physics, 1.0, 2.0);
+        // builds the BVH so the query can find the colliders
+        physics.tick(1.0 / 60.0, None, &forcefield::NoneField);
+
+        let mut query = |occlusion: bool| {
+            let mut found: Vec<ColliderKey> = physics
+                .query_cone(
+                    uv::DVec2::zero(),
+                    UnitDVec2::unit_x(),
+                    Angle::Deg(30.0),
+                    5.0,
+                    CollisionLayerMask::default(),
+                    occlusion,
+                )
+                .map(|(coll, _)| coll)
+                .collect();
+            found.sort_unstable_by_key(|key| key.0.to_bits());
+            found
+        };
+        let mut expected = vec![ahead, behind_wall, wall];
+        expected.sort_unstable_by_key(|key| key.0.to_bits());
+        assert_eq!(query(false), expected);
+        let mut expected = vec![ahead, wall];
+        expected.sort_unstable_by_key(|key| key.0.to_bits());
+        assert_eq!(query(true), expected);
+    }
+
+    #[test]
+    fn time_of_impact_between_bodies() {
+        let mut physics = PhysicsWorld::new(Default::default(), Default::default());
+        let mut add_body = |x: f64, velocity: Velocity| {
+            let coll = Collider::new_square(1.0);
+            let pose = PhysicsPose::new(uv::DVec2::new(x, 0.0), uv::DRotor2::identity());
+            let body = physics.entity_set.insert_body(
+                Body::new_dynamic(coll.info(), 1.0)
+                    .with_pose(pose)
+                    .with_velocity(velocity),
+            );
+            physics.entity_set.attach_collider(body, coll);
+            body
+        };
+        let moving = add_body(
+            0.0,
+            Velocity {
+                linear: uv::DVec2::new(2.0, 0.0),
+                angular: 0.0,
+            },
+        );
+        let target = add_body(5.0, Velocity::default());
+        let behind = add_body(-3.0, Velocity::default());
+        let spinning = add_body(
+            6.1,
+            Velocity {
+                linear: uv::DVec2::zero(),
+                angular: 1.0,
+            },
+        );
+
+        // a gap of 4 closing at 2 m/s
+        let toi = physics.time_of_impact(moving, target, 5.0).unwrap();
+        assert!((toi - 2.0).abs() < 1e-3, "{toi}");
+        assert_eq!(physics.time_of_impact(moving, target, 1.0), None);
+        assert_eq!(physics.time_of_impact(moving, behind, 5.0), None);
+
+        // the corner of the spinning box reaches across the gap of 0.1
+        // when the half-width of the box along x, 0.5 * (cos a + sin a), is 0.6
+        let toi = physics.time_of_impact(spinning, target, 5.0).unwrap();
+        let expected = (0.6 * 2.0_f64.sqrt()).asin() - std::f64::consts::FRAC_PI_4;
+        assert!((toi - expected).abs() < 1e-3, "{toi}, expected {expected}");
+        physics
+            .entity_set
+            .get_body_mut(spinning)
+            .unwrap()
+            .pose
+            .translation
+            .x = 6.5;
+        assert_eq!(physics.time_of_impact(spinning, target, 5.0), None);
+    }
+
+    #[test]
+    fn time_of_impact_glancing_spin() {
+        let mut physics = PhysicsWorld::new(Default::default(), Default::default());
+        // a fast spinning rod flying past a ball, just close enough for its tip to clip it.
+        // the spin makes the approach speed bound much larger than the actual approach,
+        // so it takes many small steps to get there
+        let rod_coll = Collider::new_rect(4.0, 0.1);
+        let rod = physics.entity_set.insert_body(
+            Body::new_dynamic(rod_coll.info(), 1.0)
+                .with_pose(PhysicsPose::new(
+                    uv::DVec2::new(-10.0, 0.0),
+                    uv::DRotor2::identity(),
+                ))
+                .with_velocity(Velocity {
+                    linear: uv::DVec2::new(5.0, 0.0),
+                    angular: 40.0,
+                }),
+        );
+        physics.entity_set.attach_collider(rod, rod_coll);
+        let ball_coll = Collider::new_circle(0.5);
+        let ball_pose = PhysicsPose::new(uv::DVec2::new(0.0, 2.49), uv::DRotor2::identity());
+        let ball = physics
+            .entity_set
+            .insert_body(Body::new_dynamic(ball_coll.info(), 1.0).with_pose(ball_pose));
+        physics.entity_set.attach_collider(ball, ball_coll);
+
+        let toi = physics
+            .time_of_impact(rod, ball, 5.0)
+            .expect("missed the impact");
+        // compare against the first touch found by sampling the motion finely
+        let distance_at = |t: f64| {
+            let [rod_body, ball_body] =
+                [rod, ball].map(|b| *physics.entity_set.get_body(b).unwrap());
+            collision::shape_shape::distance(
+                [
+                    rod_body.velocity.apply_to_pose(t, rod_body.pose),
+                    ball_body.pose,
+                ],
+                [rod_coll.shape, ball_coll.shape],
+            )
+        };
+        let first_touch = (0..5000)
+            .map(|i| i as f64 * 0.001)
+            .find(|&t| distance_at(t) < 1e-4)
+            .unwrap();
+        assert!(toi <= first_touch && toi > first_touch - 0.001, "{toi}");
+    }
+
+    #[test]
+    fn mouse_spring_drags_body() {
+        let mut physics = PhysicsWorld::new(Default::default(), Default::default());
+        let coll = Collider::new_square(0.5);
+        let body = physics
+            .entity_set
+            .insert_body(Body::new_dynamic(coll.info(), 1.0));
+        physics.entity_set.attach_collider(body, coll);
+        // builds the BVH so the spring can find the body
+        physics.tick(1.0 / 60.0, None, &forcefield::NoneField);
+
+        let mut spring = MouseSpring::new();
+        assert_eq!(spring.grab(&mut physics, uv::DVec2::new(5.0, 0.0)), None);
+        spring.update(&mut physics, uv::DVec2::new(0.1, 0.1), true);
+        assert_eq!(spring.grabbed_body(), Some(body));
+
+        let target = uv::DVec2::new(2.0, 1.0);
+        for _ in 0..120 {
+            spring.update(&mut physics, target, true);
+            physics.tick(1.0 / 60.0, None, &forcefield::NoneField);
+        }
+        let pose = physics.entity_set.get_body(body).unwrap().pose;
+        // the grabbed point, not the body center, ends up at the target
+        let grabbed_point = pose * uv::DVec2::new(0.1, 0.1);
+        assert!((grabbed_point - target).mag() < 0.05, "{grabbed_point:?}");
+
+        spring.update(&mut physics, target, false);
+        assert_eq!(spring.grabbed_body(), None);
+        assert!(physics.constraint_set.constraints.is_empty());
+    }
+
+    /// Hang a particle from a compliant distance constraint until it comes to rest
+    /// and return how far the constraint is stretched.
+    fn hanging_particle_stretch(iterations: usize) -> f64 {
+        let consts = TuningConstants {
+            iterations,
+            ..Default::default()
+        };
+        let mut physics = PhysicsWorld::new(consts, Default::default());
+        let particle = physics.entity_set.insert_body(
+            Body::new_particle(2.0)
+                .with_pose(PhysicsPose::new(
+                    uv::DVec2::new(0.0, -1.0),
+                    uv::DRotor2::identity(),
+                ))
+                .with_linear_damping(5.0),
+        );
+        physics.constraint_set.insert(
+            ConstraintBuilder::new(particle)
+                .with_compliance(0.01)
+                .disable_sleeping()
+                .build_distance(1.0),
+        );
+        let gravity = Gravity(uv::DVec2::new(0.0, -10.0));
+        for _ in 0..600 {
+            physics.tick(1.0 / 60.0, None, &gravity);
+        }
+        let pose = physics.entity_set.get_body(particle).unwrap().pose;
+        -pose.translation.y - 1.0
+    }
+
+    /// Hang a compliant rope from a fixed point until it comes to rest
+    /// and return how far it's stretched in total.
+    fn hanging_rope_stretch(iterations: usize) -> f64 {
+        let consts = TuningConstants {
+            iterations,
+            ..Default::default()
+        };
+        let mut physics = PhysicsWorld::new(consts, Default::default());
+        let params = rope::RopeParameters {
+            spacing: 0.25,
+            compliance: 0.001,
+            ..Default::default()
+        };
+        let top = uv::DVec2::zero();
+        let rope = Rope::spawn_line(
+            params,
+            top,
+            uv::DVec2::new(0.0, -1.0),
+            &mut physics.entity_set,
+        );
+        let (first, last) = (rope.particles[0].body, rope.particles[4].body);
+        physics.rope_set.insert(rope);
+        physics.constraint_set.insert(
+            ConstraintBuilder::new(first)
+                .with_target_origin(top)
+                .disable_sleeping()
+                .build_attachment(),
+        );
+        let gravity = Gravity(uv::DVec2::new(0.0, -10.0));
+        for _ in 0..600 {
+            physics.tick(1.0 / 60.0, None, &gravity);
+        }
+        let pose = physics.entity_set.get_body(last).unwrap().pose;
+        -pose.translation.y - 1.0
+    }
+
+    #[test]
+    fn compliance_independent_of_iterations() {
+        let stretch_1 = hanging_particle_stretch(1);
+        let stretch_4 = hanging_particle_stretch(4);
+        // at rest the constraint holds the particle's weight: compliance * m * g,
+        // slightly less because damping eats some of each substep's gravity
+        assert!((stretch_1 - 0.2).abs() < 5e-3, "{stretch_1}");
+        assert!((stretch_4 - stretch_1).abs() < 1e-6, "{stretch_4}");
+
+        // with enough iterations to converge along the chain, each segment holds
+        // the weight of the particles below it: compliance * m * g * (1 + 2 + 3 + 4)
+        let expected_rope_stretch = 0.001 * 0.02 * 10.0 * 10.0;
+        for iterations in [4, 16] {
+            let rope_stretch = hanging_rope_stretch(iterations);
+            assert!(
+                (rope_stretch - expected_rope_stretch).abs() < 1e-3 * expected_rope_stretch,
+                "{rope_stretch} with {iterations} iterations"
+            );
+        }
+    }
+
+    #[test]
+    fn two_body_constraint_solved_once() {
+        let mut physics = PhysicsWorld::new(Default::default(), Default::default());
+        let anchor = physics.entity_set.insert_body(Body::new_kinematic());
+        let particle = physics.entity_set.insert_body(
+            Body::new_particle(2.0)
+                .with_pose(PhysicsPose::new(
+                    uv::DVec2::new(0.0, -1.0),
+                    uv::DRotor2::identity(),
+                ))
+                .with_linear_damping(5.0),
+        );
+        physics.constraint_set.insert(
+            ConstraintBuilder::new(particle)
+                .with_target(anchor)
+                .with_compliance(0.01)
+                .disable_sleeping()
+                .build_distance(1.0),
+        );
+        let gravity = Gravity(uv::DVec2::new(0.0, -10.0));
+        for _ in 0..600 {
+            physics.tick(1.0 / 60.0, None, &gravity);
+        }
+        // compliance * m * g, minus what damping removes from each substep's gravity.
+        // a constraint solved twice per iteration would stretch less
+        let substep_dt = 1.0 / 60.0 / physics.consts.substeps as f64;
+        let expected = 0.01 * 2.0 * 10.0 / (1.0 + 5.0 * substep_dt);
+        let pose = physics.entity_set.get_body(particle).unwrap().pose;
+        let stretch = -pose.translation.y - 1.0;
+        assert!((stretch - expected).abs() < 1e-6, "{stretch}");
+    }
+
+    #[test]
+    fn scaling_collider_updates_mass() {
+        let mut physics = PhysicsWorld::new(Default::default(), Default::default());
+        let coll = Collider::new_rect(1.0, 2.0);
+        let body = physics
+            .entity_set
+            .insert_body(Body::new_dynamic(coll.info(), 3.0));
+        let coll_key = physics.entity_set.attach_collider(body, coll);
+
+        physics.set_collider_scale_xy(coll_key, uv::DVec2::new(2.0, 0.5));
+        let shape = physics.entity_set.get_collider(coll_key).unwrap().shape;
+        let ColliderPolygon::Rect { hw, hh } = shape.polygon else {
+            panic!("shape changed type");
+        };
+        assert!((hw - 1.0).abs() < 1e-9 && (hh - 0.5).abs() < 1e-9);
+
+        let expected = Body::new_dynamic(Collider::from(shape).info(), 3.0);
+        let body = physics.entity_set.get_body(body).unwrap();
+        assert!((body.mass.inv() - expected.mass.inv()).abs() < 1e-9);
+        assert!((body.moment_of_inertia.inv() - expected.moment_of_inertia.inv()).abs() < 1e-9);
+
+        // no area to get a density from
+        let point = Collider::from(ColliderShape {
+            polygon: ColliderPolygon::Point,
+            circle_r: 0.0,
+        });
+        let particle = physics.entity_set.insert_body(Body::new_particle(2.0));
+        let point_key = physics.entity_set.attach_collider(particle, point);
+        physics.set_collider_scale(point_key, 2.0);
+        let particle = physics.entity_set.get_body(particle).unwrap();
+        assert_eq!(particle.mass.inv(), 0.5);
+    }
+
+    #[test]
+    fn contacts_sorted_by_colliders() {
+        let mut physics = PhysicsWorld::new(Default::default(), Default::default());
+        let ground_pose = PhysicsPose::new(uv::DVec2::new(0.0, -0.5), uv::DRotor2::identity());
+        physics
+            .entity_set
+            .insert_collider(Collider::new_rect(20.0, 1.0).with_pose(ground_pose));
+        // separate stacks become separate islands
+        for x in [4.0, -4.0, 0.0, 2.0, -2.0] {
+            for y in [0.5, 1.5] {
+                let coll = Collider::new_square(1.0);
+                let pose = PhysicsPose::new(uv::DVec2::new(x, y), uv::DRotor2::identity());
+                let body = physics
+                    .entity_set
+                    .insert_body(Body::new_dynamic(coll.info(), 1.0).with_pose(pose));
+                physics.entity_set.attach_collider(body, coll);
+            }
+        }
+
+        let gravity = Gravity(uv::DVec2::new(0.0, -9.81));
+        for _ in 0..10 {
+            physics.tick(1.0 / 60.0, None, &gravity);
+            let contacts = physics.contacts();
+            // every pair once, including ones between two bodies
+            assert_eq!(contacts.len(), 10);
+            assert!(contacts
+                .windows(2)
+                .all(|pair| pair[0].colliders < pair[1].colliders));
+        }
+    }
+
+    #[test]
+    fn disabled_collider_drops_resting_box() {
+        let (mut physics, ground_key, box_key) = sleeping_box_on_ground();
+        let box_body = physics.entity_set.get_collider_body_key(box_key).unwrap();
+        physics.set_collider_enabled(ground_key, false);
+
+        let gravity = Gravity(uv::DVec2::new(0.0, -9.81));
+        for _ in 0..30 {
+            physics.tick(1.0 / 60.0, None, &gravity);
+        }
+        let box_pose = physics.entity_set.get_body(box_body).unwrap().pose;
+        assert!(
+            box_pose.translation.y < -0.5,
+            "box didn't fall through ({box_pose:?})"
+        );
+        assert!(physics.contacts().is_empty());
+        assert!(physics
+            .query_point(uv::DVec2::new(2.0, -0.5))
+            .all(|(coll, _)| coll != ground_key));
+    }
+
+    #[test]
+    fn collider_type_switches_at_runtime() {
+        let (mut physics, ground_key, box_key) = sleeping_box_on_ground();
+        let box_body = physics.entity_set.get_collider_body_key(box_key).unwrap();
+        physics.set_collider_type(ground_key, ColliderType::Sensor);
+
+        let gravity = Gravity(uv::DVec2::new(0.0, -9.81));
+        // the solid contact ends on the tick after the switch
+        physics.tick(1.0 / 60.0, None, &gravity);
+        assert!(physics.are_in_contact(ground_key, box_key).is_none());
+        for _ in 0..10 {
+            physics.tick(1.0 / 60.0, None, &gravity);
+        }
+        let box_pose = physics.entity_set.get_body(box_body).unwrap().pose;
+        assert!(box_pose.translation.y < 0.45, "box didn't start falling");
+        // still overlapping, reported again as a sensor contact
+        assert!(physics.are_in_contact(ground_key, box_key).is_some());
+
+        // solid again while overlapping, should get pushed back out and stop.
+        // limit the push so the box doesn't get launched
+        physics.consts.max_depenetration_velocity = 10.0;
+        physics.set_collider_type(ground_key, ColliderType::Solid(Default::default()));
+        for _ in 0..120 {
+            physics.tick(1.0 / 60.0, None, &gravity);
+        }
+        let body = physics.entity_set.get_body(box_body).unwrap();
+        assert!(
+            (body.pose.translation.y - 0.5).abs() < 0.01,
+            "box didn't come back to rest on the ground ({:?})",
+            body.pose
+        );
+        assert!(body.velocity.linear.mag() < 0.01);
+    }
+
+    #[test]
+    fn disabled_body_is_left_out() {
+        let mut physics = PhysicsWorld::new(Default::default(), Default::default());
+        let coll = Collider::new_square(0.5);
+        let mut add_body = |y: f64| {
+            let pose = PhysicsPose::new(uv::DVec2::new(0.0, y), uv::DRotor2::identity());
+            let body = physics
+                .entity_set
+                .insert_body(Body::new_dynamic(coll.info(), 1.0).with_pose(pose));
+            physics.entity_set.attach_collider(body, coll);
+            body
+        };
+        let disabled = add_body(0.0);
+        let hanging = add_body(-1.0);
+        // would hold the bodies together if both were enabled
+        physics.constraint_set.insert(
+            ConstraintBuilder::new(hanging)
+                .with_target(disabled)
+                .build_distance(1.0),
+        );
+        physics.set_body_enabled(disabled, false);
+
+        let gravity = Gravity(uv::DVec2::new(0.0, -9.81));
+        for _ in 0..30 {
+            physics.tick(1.0 / 60.0, None, &gravity);
+        }
+        let pose = |physics: &PhysicsWorld, key| physics.entity_set.get_body(key).unwrap().pose;
+        assert_eq!(pose(&physics, disabled).translation, uv::DVec2::zero());
+        let hanging_y = pose(&physics, hanging).translation.y;
+        assert!(
+            hanging_y < -2.0,
+            "constraint held the body up (y = {hanging_y})"
+        );
+        let ray = Ray {
+            start: uv::DVec2::new(0.0, 2.0),
+            dir: -UnitDVec2::unit_y(),
+        };
+        assert!(physics.raycast(ray, 3.0).is_none());
+
+        physics.set_body_enabled(disabled, true);
+        physics.tick(1.0 / 60.0, None, &gravity);
+        assert!(pose(&physics, disabled).translation.y < 0.0);
+    }
+
+    #[test]
+    fn disabled_body_not_counted_as_sleeping() {
+        let mut physics = PhysicsWorld::new(Default::default(), Default::default());
+        physics.entity_set.insert_body(Body::new_particle(1.0));
+        let disabled = physics.entity_set.insert_body(Body::new_particle(1.0));
+        physics.set_body_enabled(disabled, false);
+
+        physics.tick(1.0 / 60.0, None, &Gravity(uv::DVec2::new(0.0, -9.81)));
+        let stats = physics.stats();
+        assert_eq!(stats.awake_bodies, 1);
+        assert_eq!(stats.sleeping_bodies, 0);
+    }
+
+    #[test]
+    fn time_scale_substeps() {
+        let mut physics = PhysicsWorld::new(Default::default(), Default::default());
+        let body = physics.entity_set.insert_body(Body::new_particle(1.0));
+        let gravity = Gravity(uv::DVec2::new(0.0, -10.0));
+        let frame_dt = 1.0 / 60.0;
+
+        physics.tick(frame_dt, Some(0.25), &gravity);
+        assert_eq!(physics.stats().substeps, 3);
+        let vel_scaled = physics.entity_set.get_body(body).unwrap().velocity.linear.y;
+
+        physics.entity_set.get_body_mut(body).unwrap().velocity = Velocity::default();
+        physics.consts.scale_substeps_with_time = false;
+        physics.tick(frame_dt, Some(0.25), &gravity);
+        assert_eq!(physics.stats().substeps, physics.consts.substeps);
+        let vel_fixed = physics.entity_set.get_body(body).unwrap().velocity.linear.y;
+
+        // either way the same amount of time passes
+        let expected = -10.0 * 0.25 * frame_dt;
+        assert!((vel_scaled - expected).abs() < 1e-9, "{vel_scaled}");
+        assert!((vel_fixed - expected).abs() < 1e-9, "{vel_fixed}");
+    }
+
+    #[test]
+    fn contact_id_stable_while_sliding() {
+        let mut physics = PhysicsWorld::new(Default::default(), Default::default());
+        let ground_pose = PhysicsPose::new(uv::DVec2::new(0.0, -0.5), uv::DRotor2::identity());
+        let ground = physics
+            .entity_set
+            .insert_collider(Collider::new_rect(20.0, 1.0).with_pose(ground_pose));
+        let coll = Collider::new_rect(2.0, 0.5);
+        let pose = PhysicsPose::new(uv::DVec2::new(0.0, 0.25), uv::DRotor2::identity());
+        let body = Body::new_dynamic(coll.info(), 1.0)
+            .with_pose(pose)
+            .with_velocity(Velocity {
+                linear: uv::DVec2::new(3.0, 0.0),
+                angular: 0.0,
+            });
+        let body = physics.entity_set.insert_body(body);
+        let slider = physics.entity_set.attach_collider(body, coll);
+
+        let gravity = Gravity(uv::DVec2::new(0.0, -9.81));
+        let mut ids = Vec::new();
+        for _ in 0..30 {
+            physics.tick(1.0 / 60.0, None, &gravity);
+            ids.push(physics.are_in_contact(slider, ground).unwrap().id);
+        }
+        assert!(ids.iter().all(|id| *id == ids[0]), "{ids:?}");
+        // same id regardless of orientation
+        assert_eq!(physics.are_in_contact(ground, slider).unwrap().id, ids[0]);
+
+        // standing the box on its end touches different edges
+        let standing = PhysicsPose::new(
+            uv::DVec2::new(0.0, 1.0),
+            uv::DRotor2::from_angle(std::f64::consts::FRAC_PI_2),
+        );
+        let b = physics.entity_set.get_body_mut(body).unwrap();
+        b.pose = standing;
+        b.velocity = Velocity::default();
+        // it may have stopped and fallen asleep
+        physics.wake_body(body);
+        physics.tick(1.0 / 60.0, None, &gravity);
+        let standing_id = physics.are_in_contact(slider, ground).unwrap().id;
+        assert_eq!(standing_id.colliders(), ids[0].colliders());
+        assert_ne!(standing_id, ids[0]);
+    }
+
+    #[test]
+    fn contact_filter_overrides_and_ignores() {
+        let final_pos = |filter: Option<ContactOverride>| {
+            let mut physics = PhysicsWorld::new(Default::default(), Default::default());
+            let ground_pose = PhysicsPose::new(uv::DVec2::new(0.0, -0.5), uv::DRotor2::identity());
+            physics
+                .entity_set
+                .insert_collider(Collider::new_rect(20.0, 1.0).with_pose(ground_pose));
+            // flat so it doesn't tip over from friction
+            let coll = Collider::new_rect(2.0, 0.5);
+            let pose = PhysicsPose::new(uv::DVec2::new(0.0, 0.25), uv::DRotor2::identity());
+            let body = physics.entity_set.insert_body(
+                Body::new_dynamic(coll.info(), 1.0)
+                    .with_pose(pose)
+                    .with_velocity(Velocity {
+                        linear: uv::DVec2::new(5.0, 0.0),
+                        angular: 0.0,
+                    }),
+            );
+            physics.entity_set.attach_collider(body, coll);
+            if let Some(result) = filter {
+                physics.set_contact_filter(move |_, _| result);
+            }
+
+            let gravity = Gravity(uv::DVec2::new(0.0, -9.81));
+            for _ in 0..60 {
+                physics.tick(1.0 / 60.0, None, &gravity);
+            }
+            physics.entity_set.get_body(body).unwrap().pose.translation
+        };
+
+        let normal = final_pos(Some(ContactOverride::Keep));
+        assert!(normal.x < 1.5, "{normal:?}");
+        assert!((normal.y - 0.25).abs() < 0.01, "{normal:?}");
+        let ice = PhysicsMaterial {
+            static_friction_coef: None,
+            dynamic_friction_coef: None,
+            restitution_coef: 0.0,
+        };
+        let slippery = final_pos(Some(ContactOverride::Material(ice)));
+        assert!((slippery.x - 5.0).abs() < 0.1, "{slippery:?}");
+        assert!((slippery.y - 0.25).abs() < 0.01, "{slippery:?}");
+        let ignored = final_pos(Some(ContactOverride::Ignore));
+        assert!(ignored.y < -2.0, "{ignored:?}");
+    }
+
+    #[test]
+    fn falling_box_enters_and_exits_region() {
+        let mut physics = PhysicsWorld::new(Default::default(), Default::default());
+        let box_coll = Collider::new_square(1.0);
+        let box_pose = PhysicsPose::new(uv::DVec2::new(0.0, 5.0), uv::DRotor2::identity());
+        let box_body = physics
+            .entity_set
+            .insert_body(Body::new_dynamic(box_coll.info(), 1.0).with_pose(box_pose));
+        physics.entity_set.attach_collider(box_body, box_coll);
+        physics.add_region(
+            7,
+            Collider::new_rect(4.0, 1.0).shape,
+            PhysicsPose::default(),
+            CollisionLayerMask::default(),
+        );
+
+        let gravity = Gravity(uv::DVec2::new(0.0, -9.81));
+        let mut events = Vec::new();
+        for _ in 0..120 {
+            physics.tick(1.0 / 60.0, None, &gravity);
+            events.extend_from_slice(physics.region_events());
+        }
+        let expected =
+            [RegionEventKind::Entered, RegionEventKind::Exited].map(|kind| RegionEvent {
+                region_id: 7,
+                body: box_body,
+                kind,
+            });
+        assert_eq!(events, expected);
+    }
+
+    /// A long capsule dropped on two pillars, starting from the given pose.
+    /// Returns the world, the capsule's body and collider,
+    /// and the capsule's highest linear or angular speed on every frame.
+    fn capsule_on_pillars(
+        start: PhysicsPose,
+        consts: TuningConstants,
+    ) -> (PhysicsWorld, BodyKey, ColliderKey, Vec<f64>) {
+        let mut physics = PhysicsWorld::new(consts, Default::default());
+        for x in [-2.0, 2.0] {
+            let pillar_pose = PhysicsPose::new(uv::DVec2::new(x, 1.0), uv::DRotor2::identity());
+            physics
+                .entity_set
+                .insert_collider(Collider::new_rect(0.5, 2.0).with_pose(pillar_pose));
+        }
+        let capsule = Collider::new_capsule(6.0, 0.1);
+        let body = physics
+            .entity_set
+            .insert_body(Body::new_dynamic(capsule.info(), 1.0).with_pose(start));
+        let coll = physics.entity_set.attach_collider(body, capsule);
+
+        let gravity = Gravity(uv::DVec2::new(0.0, -9.81));
+        let speeds = (0..240)
+            .map(|_| {
+                physics.tick(1.0 / 60.0, None, &gravity);
+                let vel = physics.entity_set.get_body(body).unwrap().velocity;
+                vel.linear.mag().max(vel.angular.abs())
+            })
+            .collect();
+        (physics, body, coll, speeds)
+    }
+
+    fn assert_capsule_at_rest(physics: &PhysicsWorld, body: BodyKey, speeds: &[f64]) {
+        // no oscillation during the last second
+        let max_speed = speeds[180..].iter().fold(0.0, |acc, s| f64::max(acc, *s));
+        assert!(max_speed < 0.001, "capsule still moving at {max_speed}");
+        let pose = physics.entity_set.get_body(body).unwrap().pose;
+        assert!(
+            (pose.translation.y - 2.1).abs() < 0.01,
+            "capsule at {pose:?}"
+        );
+    }
+
+    #[test]
+    fn long_capsule_settles_on_pillars() {
+        let start = PhysicsPose::new(uv::DVec2::new(0.3, 2.5), uv::DRotor2::from_angle(0.05));
+        let (physics, body, coll, speeds) = capsule_on_pillars(start, Default::default());
+        assert_capsule_at_rest(&physics, body, &speeds);
+        // a full manifold on each pillar
+        let points: usize = physics
+            .contacts_for_collider(coll)
+            .map(|cont| cont.points().len())
+            .sum();
+        assert_eq!(points, 4);
+    }
+
+    #[test]
+    fn capsule_recovers_from_deep_penetration() {
+        // start the capsule far inside both pillars
+        let start = PhysicsPose::new(uv::DVec2::new(0.0, 1.6), uv::DRotor2::identity());
+        let consts = TuningConstants {
+            max_depenetration_velocity: 10.0,
+            ..Default::default()
+        };
+        let (physics, body, _, speeds) = capsule_on_pillars(start, consts);
+        let peak_speed = speeds.iter().fold(0.0, |acc, s| f64::max(acc, *s));
+        assert!(
+            peak_speed <= physics.consts.max_depenetration_velocity,
+            "capsule launched at {peak_speed}"
+        );
+        assert_capsule_at_rest(&physics, body, &speeds);
+    }
+
+    #[test]
+    fn box_stack_stays_upright() {
+        let mut physics = PhysicsWorld::new(Default::default(), Default::default());
+        let ground_pose = PhysicsPose::new(uv::DVec2::new(0.0, -0.5), uv::DRotor2::identity());
+        physics
+            .entity_set
+            .insert_collider(Collider::new_rect(10.0, 1.0).with_pose(ground_pose));
+        let boxes: Vec<(BodyKey, ColliderKey)> = (0..10)
+            .map(|i| {
+                let coll = Collider::new_square(1.0);
+                let pose =
+                    PhysicsPose::new(uv::DVec2::new(0.0, 0.5 + i as f64), uv::DRotor2::identity());
+                let body = physics
+                    .entity_set
+                    .insert_body(Body::new_dynamic(coll.info(), 1.0).with_pose(pose));
+                (body, physics.entity_set.attach_collider(body, coll))
+            })
+            .collect();
+
+        let gravity = Gravity(uv::DVec2::new(0.0, -9.81));
+        for _ in 0..600 {
+            physics.tick(1.0 / 60.0, None, &gravity);
+            for (i, &(body, coll)) in boxes.iter().enumerate() {
+                let pose = physics.entity_set.get_body(body).unwrap().pose;
+                assert!(
+                    pose.translation.x.abs() < 0.01,
+                    "box {i} drifted to {pose:?}"
+                );
+                for contact in physics.contacts_for_collider(coll) {
+                    assert_eq!(contact.points().len(), 2, "box {i} has {contact:?}");
+                }
+            }
+        }
+        let top = physics.entity_set.get_body(boxes[9].0).unwrap().pose;
+        assert!((top.translation.y - 9.5).abs() < 0.05, "top box at {top:?}");
+        let stats = physics.stats();
+        assert_eq!(stats.contacts, 10);
+        assert_eq!(stats.single_point_contacts, 0);
+    }
+}
@@ -1,20 +1,79 @@
 use super::Velocity;
-use crate::math::PhysicsPose;
+use crate::math::{uv, PhysicsPose};
 
 /// A body is something that moves, typically a physics-enabled rigid body or particle.
 /// Connect a Body with a Collider to make it collide with other things.
+///
+/// # Mass
+///
+/// The solver only looks at [`mass`][Self::mass] and
+/// [`moment_of_inertia`][Self::moment_of_inertia]; colliders don't affect them after creation.
+/// There are two ways to have these computed from a collider's shape:
+///
+/// - [`new_dynamic`][Self::new_dynamic] takes a density, so bigger objects are heavier.
+///   Mass is the collider's area times density.
+/// - [`new_dynamic_const_mass`][Self::new_dynamic_const_mass] takes the mass directly,
+///   useful when an object should weigh the same regardless of its size.
+///
+/// Either way the moment of inertia is that of a uniformly dense object with the given shape.
+/// Given the same collider, a body with density `d` is identical to one with mass `area * d`:
+///
+/// ```
+/// # use starframe::{Body, Collider};
+/// let coll = Collider::new_rect(2.0, 1.0);
+/// let from_density = Body::new_dynamic(coll.info(), 3.0);
+/// let from_mass = Body::new_dynamic_const_mass(coll.info(), 6.0);
+/// assert_eq!(from_density.mass.inv(), from_mass.mass.inv());
+/// assert_eq!(
+///     from_density.moment_of_inertia.inv(),
+///     from_mass.moment_of_inertia.inv()
+/// );
+/// ```
 #[derive(Clone, Copy, Debug)]
 pub struct Body {
     pub pose: PhysicsPose,
     pub velocity: Velocity,
+    /// Resistance to changes in linear velocity. See [the type-level docs][Self#mass].
     pub mass: Mass,
+    /// Resistance to changes in angular velocity. See [the type-level docs][Self#mass].
     pub moment_of_inertia: Mass,
     pub ignores_gravity: bool,
+    /// Bounding box padding to use for this body's colliders in the broadphase
+    /// instead of the global one derived from [`TuningConstants`][super::TuningConstants].
+    ///
+    /// Set this higher for bodies known to accelerate quickly
+    /// to avoid missed collisions without fattening every other body's bounding box.
+    pub broadphase_margin: Option<f64>,
+    /// Drag coefficient slowing down the body's linear velocity over time,
+    /// like air resistance. Defaults to zero.
+    pub linear_damping: f64,
+    /// Drag coefficient slowing down the body's angular velocity over time.
+    /// Defaults to zero.
+    pub angular_damping: f64,
+    /// Whether the body is part of the simulation. Defaults to true.
+    ///
+    /// Disabled bodies keep their state but don't move, collide with anything
+    /// or take part in constraints and ropes.
+    /// Prefer [`PhysicsWorld::set_body_enabled`][super::PhysicsWorld::set_body_enabled]
+    /// over setting this directly, since it also wakes up things that were touching the body.
+    pub enabled: bool,
+    /// Force accumulated with [`apply_force`][Self::apply_force] for the next tick.
+    ///
+    /// The solver applies this continuously over every substep of the next
+    /// [`tick`][super::PhysicsWorld::tick] alongside the force field, then resets it to zero.
+    pub force: uv::DVec2,
+    /// Torque accumulated with [`apply_force`][Self::apply_force]
+    /// and [`apply_torque`][Self::apply_torque] for the next tick,
+    /// reset to zero after it like [`force`][Self::force].
+    pub torque: f64,
 }
 
+/// Properties of a collider's shape used to compute a body's mass,
+/// obtained with [`Collider::info`][super::Collider::info].
 #[derive(Clone, Copy, Debug)]
 pub struct ColliderInfo {
     pub area: f64,
+    /// Moment of inertia of the shape per unit of density.
     pub second_moment_of_area: f64,
 }
 
@@ -27,12 +86,19 @@ impl Body {
             mass: Mass::from(mass),
             moment_of_inertia: Mass::Infinite,
             ignores_gravity: false,
+            broadphase_margin: None,
+            linear_damping: 0.0,
+            angular_damping: 0.0,
+            enabled: true,
+            force: uv::DVec2::zero(),
+            torque: 0.0,
         }
     }
 
     /// Dynamic bodies respond to external forces and are allowed to rotate.
     /// This constructor calculates mass and moment of inertia from the given density and
-    /// collider shape.
+    /// collider shape, so that larger colliders make heavier bodies.
+    /// See [Mass][Self#mass].
     pub fn new_dynamic(coll_info: ColliderInfo, density: f64) -> Self {
         let mass = coll_info.area * density;
         Self {
@@ -41,11 +107,18 @@ impl Body {
             mass: Mass::from(mass),
             moment_of_inertia: Mass::from(coll_info.second_moment_of_area * density),
             ignores_gravity: false,
+            broadphase_margin: None,
+            linear_damping: 0.0,
+            angular_damping: 0.0,
+            enabled: true,
+            force: uv::DVec2::zero(),
+            torque: 0.0,
         }
     }
 
     /// Create a dynamic body with the given mass instead of using density.
-    /// The collider is still required in order to compute moment of inertia.
+    /// The collider is still required in order to compute moment of inertia,
+    /// which is scaled to match the mass. See [Mass][Self#mass].
     pub fn new_dynamic_const_mass(coll_info: ColliderInfo, mass: f64) -> Self {
         let density = mass / coll_info.area;
         Self {
@@ -54,6 +127,12 @@ impl Body {
             mass: Mass::from(mass),
             moment_of_inertia: Mass::from(coll_info.second_moment_of_area * density),
             ignores_gravity: false,
+            broadphase_margin: None,
+            linear_damping: 0.0,
+            angular_damping: 0.0,
+            enabled: true,
+            force: uv::DVec2::zero(),
+            torque: 0.0,
         }
     }
 
@@ -65,6 +144,12 @@ impl Body {
             mass: Mass::Infinite,
             moment_of_inertia: Mass::Infinite,
             ignores_gravity: false,
+            broadphase_margin: None,
+            linear_damping: 0.0,
+            angular_damping: 0.0,
+            enabled: true,
+            force: uv::DVec2::zero(),
+            torque: 0.0,
         }
     }
 
@@ -80,12 +165,66 @@ impl Body {
         self
     }
 
+    /// Override the broadphase bounding box padding for this body's colliders.
+    /// See [`broadphase_margin`][Self::broadphase_margin].
+    pub fn with_broadphase_margin(mut self, margin: f64) -> Self {
+        self.broadphase_margin = Some(margin);
+        self
+    }
+
+    /// Set the [`linear_damping`][Self::linear_damping] coefficient of the body.
+    pub fn with_linear_damping(mut self, damping: f64) -> Self {
+        self.linear_damping = damping;
+        self
+    }
+
+    /// Set the [`angular_damping`][Self::angular_damping] coefficient of the body.
+    pub fn with_angular_damping(mut self, damping: f64) -> Self {
+        self.angular_damping = damping;
+        self
+    }
+
     /// Stop this body from being accelerated by gravity.
     pub fn ignore_gravity(mut self) -> Self {
         self.ignores_gravity = true;
         self
     }
 
+    /// Instantly change the body's velocity as if hit by `impulse` at `point`,
+    /// both given in world space.
+    ///
+    /// An impulse applied off the body's center also makes it spin.
+    /// Note that this doesn't wake up a sleeping body;
+    /// use [`PhysicsWorld::wake_body`][super::PhysicsWorld::wake_body] for that.
+    #[inline]
+    pub fn apply_impulse(&mut self, impulse: uv::DVec2, point: uv::DVec2) {
+        self.velocity.linear += self.mass.inv() * impulse;
+        let offset = point - self.pose.translation;
+        self.velocity.angular += self.moment_of_inertia.inv() * offset.wedge(impulse).xy;
+    }
+
+    /// Push the body with `force` at `point`, both given in world space,
+    /// over the duration of the next physics tick.
+    ///
+    /// Unlike [`apply_impulse`][Self::apply_impulse], which changes velocity instantly,
+    /// this accelerates the body gradually over every substep, e.g. for thrusters or wind.
+    /// Forces applied before a tick add up and are cleared after it,
+    /// so continuous forces need to be applied again before every tick.
+    /// Like with impulses, this doesn't wake up a sleeping body.
+    #[inline]
+    pub fn apply_force(&mut self, force: uv::DVec2, point: uv::DVec2) {
+        self.force += force;
+        let offset = point - self.pose.translation;
+        self.torque += offset.wedge(force).xy;
+    }
+
+    /// Spin the body with `torque` over the duration of the next physics tick.
+    /// See [`apply_force`][Self::apply_force].
+    #[inline]
+    pub fn apply_torque(&mut self, torque: f64) {
+        self.torque += torque;
+    }
+
     /// Check whether the body has finite mass or moment of inertia, allowing forces to have an
     /// effect on it.
     #[inline]
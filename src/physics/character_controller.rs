@@ -0,0 +1,265 @@
+use super::{BodyKey, PhysicsWorld, Ray};
+use crate::math::{uv, Angle, UnitDVec2};
+
+/// Helper for moving a character through the world with collide-and-slide logic,
+/// e.g. the player in a platformer or top-down game.
+///
+/// The character is moved directly instead of through forces and collisions,
+/// so it should be a kinematic body (see [`Body::new_kinematic`][super::Body::new_kinematic]).
+/// Each movement is swept through the world with spherecasts,
+/// so it never tunnels through walls however fast it moves.
+/// When it hits something, it slides along the surface,
+/// walks up slopes no steeper than [`max_slope`][Self::max_slope],
+/// and steps over ledges no higher than [`step_height`][Self::step_height].
+/// A character that starts a move on the ground stays on the ground
+/// when walking down slopes and steps, instead of flying off them.
+///
+/// For casting purposes the character's shape is treated as a circle
+/// of [`radius`][Self::radius] around the body's position,
+/// regardless of the colliders attached to the body.
+/// Casts only see what was in the world during the last [`tick`][PhysicsWorld::tick].
+#[derive(Clone, Copy, Debug)]
+pub struct CharacterController {
+    /// Radius of the circle used for casting.
+    pub radius: f64,
+    /// The direction the character stands upright in. Default: positive y-axis.
+    pub up: UnitDVec2,
+    /// Steepest slope the character can walk up. Default: 45 degrees.
+    pub max_slope: Angle,
+    /// Height of the tallest ledge the character can step over. Default: 0.2
+    pub step_height: f64,
+    /// Distance the character is kept away from surfaces it touches. Default: 0.01
+    ///
+    /// Casts starting inside a collider don't hit it,
+    /// so this must be positive to keep the character from sinking into things.
+    pub skin_width: f64,
+    /// Maximum number of surfaces slid along in a single move. Default: 4
+    pub max_slides: usize,
+}
+
+/// Result of a [`CharacterController::move_body`].
+#[derive(Clone, Copy, Debug)]
+pub struct CharacterMove {
+    /// How far the character actually moved.
+    pub displacement: uv::DVec2,
+    /// Normal of the walkable surface the character is standing on after the move,
+    /// or `None` if it's in the air or on a slope that's too steep.
+    pub ground_normal: Option<UnitDVec2>,
+}
+
+impl CharacterMove {
+    /// Check if the character is standing on walkable ground after the move.
+    #[inline]
+    pub fn is_grounded(&self) -> bool {
+        self.ground_normal.is_some()
+    }
+}
+
+/// Result of sliding the character along a path.
+struct Slide {
+    end: uv::DVec2,
+    hit_wall: bool,
+}
+
+impl CharacterController {
+    /// Create a controller for a character of the given radius with default settings.
+    pub fn new(radius: f64) -> Self {
+        Self {
+            radius,
+            up: UnitDVec2::unit_y(),
+            max_slope: Angle::Deg(45.0),
+            step_height: 0.2,
+            skin_width: 0.01,
+            max_slides: 4,
+        }
+    }
+
+    /// Set the up direction. See [`up`][Self::up].
+    #[inline]
+    pub fn with_up(mut self, up: UnitDVec2) -> Self {
+        self.up = up;
+        self
+    }
+
+    /// Set the maximum slope. See [`max_slope`][Self::max_slope].
+    #[inline]
+    pub fn with_max_slope(mut self, max_slope: Angle) -> Self {
+        self.max_slope = max_slope;
+        self
+    }
+
+    /// Set the step height. See [`step_height`][Self::step_height].
+    #[inline]
+    pub fn with_step_height(mut self, step_height: f64) -> Self {
+        self.step_height = step_height;
+        self
+    }
+
+    /// Set the skin width. See [`skin_width`][Self::skin_width].
+    #[inline]
+    pub fn with_skin_width(mut self, skin_width: f64) -> Self {
+        self.skin_width = skin_width;
+        self
+    }
+
+    /// Move the body by `movement` as far as it can go, sliding along anything in the way.
+    ///
+    /// The component of `movement` perpendicular to [`up`][Self::up] is the walking motion
+    /// and is allowed to climb slopes and steps,
+    /// while the component along `up` (e.g. from jumping or gravity) is not.
+    /// The body is teleported to its new position and woken up.
+    ///
+    /// Does nothing if the body doesn't exist.
+    pub fn move_body(
+        &self,
+        physics: &mut PhysicsWorld,
+        body: BodyKey,
+        movement: uv::DVec2,
+    ) -> CharacterMove {
+        let Some(start) = physics
+            .entity_set
+            .get_body(body)
+            .map(|b| b.pose.translation)
+        else {
+            return CharacterMove {
+                displacement: uv::DVec2::zero(),
+                ground_normal: None,
+            };
+        };
+        let up = *self.up;
+        let vertical = up * movement.dot(up);
+        let horizontal = movement - vertical;
+        let was_grounded = self.probe_ground(physics, start).is_some();
+
+        let walk = self.slide(physics, start, horizontal, true);
+        let mut pos = walk.end;
+        if walk.hit_wall && self.step_height > 0.0 {
+            // try going over the obstacle: up, forward, then back down
+            let raised = self.slide(physics, start, up * self.step_height, false).end;
+            let forward = self.slide(physics, raised, horizontal, true).end;
+            let (lowered, landing) = self.cast(physics, forward, -up * self.step_height);
+            let landed_on_ground = landing.is_some_and(|normal| self.is_walkable(normal));
+            let walk_dir = horizontal.normalized();
+            if landed_on_ground
+                && (lowered - start).dot(walk_dir) > (pos - start).dot(walk_dir) + self.skin_width
+            {
+                pos = lowered;
+            }
+        }
+        if was_grounded && movement.dot(up) <= 0.0 {
+            // stick to the ground if we walked off a step or over the top of a slope.
+            // the farthest we can drop while still walking on a slope
+            // depends on how far we walked
+            let max_slope = self.max_slope.rad() as f64;
+            let snap_dist = self.step_height + horizontal.mag() * max_slope.tan();
+            let (snapped, ground) = self.cast(physics, pos, -up * snap_dist);
+            if ground.is_some_and(|normal| self.is_walkable(normal)) {
+                pos = snapped;
+            }
+        }
+        pos = self.slide(physics, pos, vertical, false).end;
+
+        let ground_normal = self.probe_ground(physics, pos);
+
+        if let Some(b) = physics.entity_set.get_body_mut(body) {
+            b.pose.translation = pos;
+        }
+        physics.wake_body(body);
+
+        CharacterMove {
+            displacement: pos - start,
+            ground_normal,
+        }
+    }
+
+    /// Check for walkable ground just below the given position.
+    fn probe_ground(&self, physics: &mut PhysicsWorld, pos: uv::DVec2) -> Option<UnitDVec2> {
+        let (_, ground) = self.cast(physics, pos, -*self.up * (2.0 * self.skin_width));
+        ground.filter(|normal| self.is_walkable(*normal))
+    }
+
+    #[inline]
+    fn is_walkable(&self, normal: UnitDVec2) -> bool {
+        normal.dot(*self.up) >= (self.max_slope.rad() as f64).cos()
+    }
+
+    /// Move along `motion` until something is hit, stopping `skin_width` away from it.
+    /// Returns the end position and the normal of the surface hit, if any.
+    fn cast(
+        &self,
+        physics: &mut PhysicsWorld,
+        start: uv::DVec2,
+        motion: uv::DVec2,
+    ) -> (uv::DVec2, Option<UnitDVec2>) {
+        let dist = motion.mag();
+        if dist < f64::EPSILON {
+            return (start, None);
+        }
+        let dir = motion / dist;
+        let ray = Ray {
+            start,
+            dir: UnitDVec2::new_unchecked(dir),
+        };
+        match physics.spherecast(self.radius, ray, dist + self.skin_width) {
+            Some(hit) => {
+                // keep skin_width between us and the surface along its normal,
+                // which is farther along the ray when hitting at an angle
+                let cos = (-dir.dot(*hit.normal)).max(0.01);
+                let travel = (hit.t - self.skin_width / cos).clamp(0.0, dist);
+                (start + dir * travel, Some(hit.normal))
+            }
+            None => (start + motion, None),
+        }
+    }
+
+    /// Move along `motion`, sliding along surfaces that are hit.
+    ///
+    /// If `is_walking`, walkable slopes are climbed
+    /// and steeper surfaces are reported as walls.
+    /// Otherwise the motion is never redirected upward,
+    /// and moving down onto walkable ground stops it.
+    fn slide(
+        &self,
+        physics: &mut PhysicsWorld,
+        start: uv::DVec2,
+        motion: uv::DVec2,
+        is_walking: bool,
+    ) -> Slide {
+        let up = *self.up;
+        let mut pos = start;
+        let mut remaining = motion;
+        let mut hit_wall = false;
+        for _ in 0..self.max_slides {
+            let (end, hit) = self.cast(physics, pos, remaining);
+            remaining -= end - pos;
+            pos = end;
+            let Some(normal) = hit else {
+                break;
+            };
+            if !is_walking && self.is_walkable(normal) && remaining.dot(up) <= 0.0 {
+                // landed on the ground, don't slide down slopes
+                break;
+            }
+            // project the rest of the motion onto the surface
+            let mut next = remaining - *normal * remaining.dot(*normal);
+            if is_walking && self.is_walkable(normal) {
+                // keep the same horizontal progress on slopes as on flat ground
+                let horizontal_mag = |v: uv::DVec2| (v - up * v.dot(up)).mag();
+                let next_h = horizontal_mag(next);
+                if next_h > f64::EPSILON {
+                    next *= horizontal_mag(remaining) / next_h;
+                }
+            } else {
+                if is_walking {
+                    hit_wall = true;
+                }
+                if next.dot(up) > 0.0 {
+                    // don't climb walls or slopes that are too steep
+                    next -= up * next.dot(up);
+                }
+            }
+            remaining = next;
+        }
+        Slide { end: pos, hit_wall }
+    }
+}
@@ -1,4 +1,10 @@
-pub(crate) mod bvh;
+pub mod bvh;
+
+mod broadphase;
+pub use broadphase::Broadphase;
+
+mod spatial_hash;
+pub use spatial_hash::SpatialHashBroadphase;
 
 mod collider;
 pub use collider::{Collider, ColliderPolygon, ColliderShape, ColliderType, PhysicsMaterial};
@@ -121,6 +127,17 @@ impl Default for CollisionLayerMask {
     }
 }
 impl CollisionLayerMask {
+    /// Create a mask with only the given layers enabled.
+    ///
+    /// # Panics
+    /// Panics if any of the layers is 64 or greater.
+    pub fn from_layers(layers: &[usize]) -> Self {
+        Self(layers.iter().fold(0, |mask, &layer| {
+            assert!(layer < 64, "Collision layer {layer} out of range (max 63)");
+            mask | (1 << layer)
+        }))
+    }
+
     /// Check whether the given layer is enabled in this mask.
     pub fn get(&self, other_layer: usize) -> bool {
         self.0 & (1 << other_layer) != 0
@@ -140,6 +157,19 @@ impl Default for CollisionMaskMatrix {
 }
 
 impl CollisionMaskMatrix {
+    /// Start building a mask matrix with named layers.
+    ///
+    /// Names can be any comparable type, typically string literals or a fieldless enum.
+    /// See [`CollisionMaskMatrixBuilder`] for details.
+    #[inline]
+    pub fn builder<L: Copy + Eq + std::fmt::Debug>() -> CollisionMaskMatrixBuilder<L> {
+        CollisionMaskMatrixBuilder {
+            matrix: Self::default(),
+            layers: CollisionLayers { names: Vec::new() },
+            current: None,
+        }
+    }
+
     /// Stop collision detection between a pair of collision layers.
     #[inline]
     pub fn ignore(&mut self, layer1: usize, layer2: usize) {
@@ -180,3 +210,169 @@ impl CollisionMaskMatrix {
         CollisionLayerMask(self.0[layer])
     }
 }
+
+/// A builder that assigns names to collision layers
+/// and sets up a [`CollisionMaskMatrix`] in terms of those names.
+///
+/// Layers are assigned indices in the order they are first mentioned, starting from zero.
+/// Every layer collides with every other layer by default,
+/// so only restrictions need to be stated.
+///
+/// ```
+/// # use starframe::CollisionMaskMatrix;
+/// let (matrix, layers) = CollisionMaskMatrix::builder()
+///     .layer("player")
+///     .collides_with(&["enemy", "wall"])
+///     .layer("enemy")
+///     .ignores(&["enemy"])
+///     .build();
+/// assert!(matrix.get(layers.get("player"), layers.get("wall")));
+/// assert!(!matrix.get(layers.get("enemy"), layers.get("enemy")));
+/// ```
+#[derive(Clone, Debug)]
+pub struct CollisionMaskMatrixBuilder<L> {
+    matrix: CollisionMaskMatrix,
+    layers: CollisionLayers<L>,
+    current: Option<usize>,
+}
+
+impl<L: Copy + Eq + std::fmt::Debug> CollisionMaskMatrixBuilder<L> {
+    /// Select a layer for the following `collides_with` and `ignores` calls,
+    /// registering it if it hasn't been mentioned before.
+    pub fn layer(mut self, name: L) -> Self {
+        self.current = Some(self.layers.get_or_insert(name));
+        self
+    }
+
+    /// Make the selected layer collide with the given layers and nothing else.
+    ///
+    /// Since collision is symmetric, this also affects the other layers' masks.
+    /// Later calls on other layers can re-enable collisions with the selected layer.
+    ///
+    /// # Panics
+    /// Panics if no layer has been selected with [`layer`][Self::layer].
+    pub fn collides_with(mut self, others: &[L]) -> Self {
+        let layer = self.expect_current();
+        self.matrix.ignore_all(layer);
+        for &other in others {
+            let other = self.layers.get_or_insert(other);
+            self.matrix.unignore(layer, other);
+        }
+        self
+    }
+
+    /// Stop collision detection between the selected layer and the given layers.
+    ///
+    /// # Panics
+    /// Panics if no layer has been selected with [`layer`][Self::layer].
+    pub fn ignores(mut self, others: &[L]) -> Self {
+        let layer = self.expect_current();
+        for &other in others {
+            let other = self.layers.get_or_insert(other);
+            self.matrix.ignore(layer, other);
+        }
+        self
+    }
+
+    /// Finish building, returning the matrix
+    /// and the mapping from names to layer indices.
+    pub fn build(self) -> (CollisionMaskMatrix, CollisionLayers<L>) {
+        (self.matrix, self.layers)
+    }
+
+    fn expect_current(&self) -> usize {
+        self.current
+            .expect("No layer selected, call `layer` before setting its collisions")
+    }
+}
+
+/// A mapping from layer names to collision layer indices,
+/// created with [`CollisionMaskMatrixBuilder`].
+#[derive(Clone, Debug)]
+pub struct CollisionLayers<L> {
+    // the index of a name is its layer
+    names: Vec<L>,
+}
+
+impl<L: Copy + Eq + std::fmt::Debug> CollisionLayers<L> {
+    /// Get the layer index of a name, if it has been registered.
+    #[inline]
+    pub fn try_get(&self, name: L) -> Option<usize> {
+        self.names.iter().position(|n| *n == name)
+    }
+
+    /// Get the layer index of a name.
+    ///
+    /// # Panics
+    /// Panics if the name hasn't been registered in the builder.
+    #[inline]
+    pub fn get(&self, name: L) -> usize {
+        self.try_get(name)
+            .unwrap_or_else(|| panic!("Unknown collision layer {name:?}"))
+    }
+
+    /// Create a query mask with only the given layers enabled.
+    pub fn mask(&self, names: &[L]) -> CollisionLayerMask {
+        let layers: Vec<usize> = names.iter().map(|name| self.get(*name)).collect();
+        CollisionLayerMask::from_layers(&layers)
+    }
+
+    fn get_or_insert(&mut self, name: L) -> usize {
+        if let Some(idx) = self.try_get(name) {
+            return idx;
+        }
+        // the last layer is reserved for ropes
+        assert!(
+            self.names.len() < ROPE_LAYER,
+            "Too many named collision layers (max {ROPE_LAYER})"
+        );
+        self.names.push(name);
+        self.names.len() - 1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn collides_with_overrides_default() {
+        let (matrix, layers) = CollisionMaskMatrix::builder()
+            .layer("player")
+            .collides_with(&["wall"])
+            .layer("enemy")
+            .build();
+        let [player, wall, enemy] = ["player", "wall", "enemy"].map(|l| layers.get(l));
+        assert!(matrix.get(player, wall));
+        assert!(!matrix.get(player, player));
+        assert!(!matrix.get(player, enemy));
+        assert!(!matrix.get(enemy, player));
+        // layers not restricted keep colliding with everything else
+        assert!(matrix.get(enemy, wall));
+        assert!(matrix.get(enemy, enemy));
+    }
+
+    #[test]
+    fn ignores_only_given_layers() {
+        let (matrix, layers) = CollisionMaskMatrix::builder()
+            .layer("enemy")
+            .ignores(&["enemy", "pickup"])
+            .layer("wall")
+            .build();
+        let [enemy, pickup, wall] = ["enemy", "pickup", "wall"].map(|l| layers.get(l));
+        assert!(!matrix.get(enemy, enemy));
+        assert!(!matrix.get(enemy, pickup));
+        assert!(!matrix.get(pickup, enemy));
+        assert!(matrix.get(enemy, wall));
+        assert!(matrix.get(pickup, pickup));
+
+        let mask = layers.mask(&["enemy", "wall"]);
+        assert!(mask.get(enemy) && mask.get(wall) && !mask.get(pickup));
+    }
+
+    #[test]
+    #[should_panic(expected = "out of range")]
+    fn mask_layer_out_of_range() {
+        CollisionLayerMask::from_layers(&[3, 64]);
+    }
+}
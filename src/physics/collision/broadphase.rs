@@ -0,0 +1,100 @@
+//! The interface between the physics world and the spatial data structure
+//! used to find potentially colliding pairs and to accelerate queries.
+
+use std::{any::Any, ops::ControlFlow};
+
+use crate::{
+    math::uv,
+    physics::{
+        collision::{
+            bvh::{Bvh, SweepItem},
+            Ray, AABB,
+        },
+        ColliderKey,
+    },
+};
+
+/// A spatial data structure for finding colliders whose bounding boxes overlap.
+///
+/// The broadphase is cleared and rebuilt from scratch every [`tick`][crate::PhysicsWorld::tick].
+/// Each collider's bounding box is first tested against the ones already inserted
+/// and then inserted itself, so the structure must support queries between insertions.
+///
+/// Query results are passed to a callback rather than returned
+/// so that implementations don't need to allocate for every query.
+///
+/// [`Bvh`] is the default and works well for most scenes.
+/// [`SpatialHashBroadphase`][super::SpatialHashBroadphase] can be faster
+/// when objects are of similar size and evenly spread out.
+/// Choose one with [`PhysicsWorld::with_broadphase`][crate::PhysicsWorld::with_broadphase].
+pub trait Broadphase: Send + Sync + 'static {
+    /// Remove everything from the structure.
+    fn clear(&mut self);
+
+    /// Add a collider with the given bounding box.
+    fn insert(&mut self, coll_key: ColliderKey, aabb: AABB);
+
+    /// Call `f` with every collider in the structure along with its bounding box.
+    fn leaves(&self, f: &mut dyn FnMut(ColliderKey, AABB));
+
+    /// Call `f` with every collider whose bounding box may intersect the given one.
+    fn test_aabb(&mut self, aabb: AABB, f: &mut dyn FnMut(ColliderKey));
+
+    /// Call `f` with every collider whose bounding box may contain the given point.
+    fn test_point(&mut self, point: uv::DVec2, f: &mut dyn FnMut(ColliderKey));
+
+    /// Sweep a square box with the given half size along a ray
+    /// and call `f` with every collider whose bounding box it hits before `max_t`.
+    ///
+    /// Items must be given in order of increasing `t`,
+    /// and the sweep must stop when `f` returns [`ControlFlow::Break`].
+    /// Casts rely on this to stop early.
+    fn sweep_aabb(
+        &mut self,
+        box_half_size: f64,
+        ray: Ray,
+        max_t: f64,
+        f: &mut dyn FnMut(SweepItem) -> ControlFlow<()>,
+    );
+
+    /// Get the broadphase as [`Any`] to downcast it to a concrete type.
+    fn as_any(&self) -> &dyn Any;
+}
+
+impl Broadphase for Bvh {
+    fn clear(&mut self) {
+        Bvh::clear(self);
+    }
+
+    fn insert(&mut self, coll_key: ColliderKey, aabb: AABB) {
+        Bvh::insert(self, coll_key, aabb);
+    }
+
+    fn leaves(&self, f: &mut dyn FnMut(ColliderKey, AABB)) {
+        for (coll_key, aabb) in Bvh::leaves(self) {
+            f(coll_key, aabb);
+        }
+    }
+
+    fn test_aabb(&mut self, aabb: AABB, f: &mut dyn FnMut(ColliderKey)) {
+        Bvh::test_aabb(self, aabb).for_each(f);
+    }
+
+    fn test_point(&mut self, point: uv::DVec2, f: &mut dyn FnMut(ColliderKey)) {
+        Bvh::test_point(self, point).for_each(f);
+    }
+
+    fn sweep_aabb(
+        &mut self,
+        box_half_size: f64,
+        ray: Ray,
+        max_t: f64,
+        f: &mut dyn FnMut(SweepItem) -> ControlFlow<()>,
+    ) {
+        let _ = Bvh::sweep_aabb(self, box_half_size, ray, max_t).try_for_each(f);
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
@@ -33,7 +33,7 @@ pub struct Stack(Vec<usize>);
 
 /// Like a Stack, but ordered by reverse distance
 /// for traversing the BVH in spatial order along a ray.
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Default)]
 pub struct RayStack(BinaryHeap<RayStackEntry>);
 
 impl RayStack {
@@ -90,7 +90,7 @@ pub struct Bvh {
 }
 
 impl Bvh {
-    pub fn new() -> Self {
+    pub(crate) fn new() -> Self {
         Self {
             nodes: Vec::new(),
             shared_stack: Stack(Vec::new()),
@@ -168,6 +168,14 @@ impl Bvh {
         }
     }
 
+    /// Iterate over every collider in the tree along with its bounding box.
+    pub fn leaves(&self) -> impl '_ + Iterator<Item = (ColliderKey, AABB)> {
+        self.nodes.iter().filter_map(|node| match node.kind {
+            NodeKind::Leaf { coll_key } => Some((coll_key, node.aabb)),
+            NodeKind::Branch { .. } => None,
+        })
+    }
+
     pub fn test_aabb(&mut self, aabb: AABB) -> AABBIter<'_> {
         AABBIter {
             aabb,
@@ -209,16 +217,47 @@ impl Bvh {
     }
 
     pub fn sweep_aabb(&mut self, box_half_size: f64, ray: Ray, max_t: f64) -> AABBSweep<'_> {
+        Self::sweep_aabb_in(
+            &self.nodes,
+            &mut self.shared_ray_stack,
+            box_half_size,
+            ray,
+            max_t,
+        )
+    }
+
+    /// Like [`sweep_aabb`][Self::sweep_aabb], but using a separately owned stack
+    /// instead of the shared one, so that many sweeps can run at the same time
+    /// e.g. on different threads.
+    pub fn sweep_aabb_with_stack<'a>(
+        &'a self,
+        stack: &'a mut RayStack,
+        box_half_size: f64,
+        ray: Ray,
+        max_t: f64,
+    ) -> AABBSweep<'a> {
+        Self::sweep_aabb_in(&self.nodes, stack, box_half_size, ray, max_t)
+    }
+
+    fn sweep_aabb_in<'a>(
+        nodes: &'a [Node],
+        stack: &'a mut RayStack,
+        box_half_size: f64,
+        ray: Ray,
+        max_t: f64,
+    ) -> AABBSweep<'a> {
+        // a previous sweep may have been stopped before the end
+        stack.0.clear();
         AABBSweep {
             ray,
             box_half_size,
             max_t,
-            stack: &mut self.shared_ray_stack,
-            nodes: &self.nodes,
-            next_node: if self.nodes.is_empty() {
+            stack,
+            nodes,
+            next_node: if nodes.is_empty() {
                 None
-            } else if self.nodes.len() == 1 {
-                ray_aabb(ray, self.nodes[0].aabb.padded(box_half_size)).and_then(|t| {
+            } else if nodes.len() == 1 {
+                ray_aabb(ray, nodes[0].aabb.padded(box_half_size)).and_then(|t| {
                     if t <= max_t {
                         Some(RayStackEntry { node_idx: 0, t })
                     } else {
@@ -234,52 +273,33 @@ impl Bvh {
         }
     }
 
-    // Generate a list of AABBS for debug drawing.
-    /// (currently unused as the old visualizing pipelines don't work anymore
-    /// and I haven't needed them since)
-    #[allow(dead_code)]
-    pub(crate) fn all_nodes(&self) -> Vec<NodeInfo> {
-        if self.nodes.is_empty() {
-            return Vec::new();
-        }
-
-        // list of right children of branch nodes we've only been to the left child of (DFS)
+    /// Iterate over the nodes of the tree down to the given depth,
+    /// yielding each node's depth (0 for the root) and bounding box.
+    ///
+    /// Useful for visualizing the tree to diagnose broadphase performance.
+    /// Nodes are visited in depth-first order.
+    /// Nodes deeper than `max_levels - 1` are skipped,
+    /// so e.g. `max_levels = 1` only yields the root.
+    pub fn iter_levels(&self, max_levels: usize) -> impl '_ + Iterator<Item = (usize, AABB)> {
+        // indices of nodes yet to visit along with their depths
         let mut stack: Vec<(usize, usize)> = Vec::new();
-        // collected list of nodes to return
-        let mut nodes = Vec::new();
-        let mut curr_node_idx = 0;
-        let mut curr_depth = 0;
-        'dfs: loop {
-            let curr_node = self.nodes[curr_node_idx];
-            nodes.push(NodeInfo {
-                aabb: curr_node.aabb,
-                depth: curr_depth,
-            });
-            match curr_node.kind {
-                NodeKind::Branch { left, right } => {
-                    stack.push((right, curr_depth + 1));
-                    curr_node_idx = left;
-                    curr_depth += 1;
+        if !self.nodes.is_empty() && max_levels > 0 {
+            stack.push((0, 0));
+        }
+        std::iter::from_fn(move || {
+            let (node_idx, depth) = stack.pop()?;
+            let node = self.nodes[node_idx];
+            if let NodeKind::Branch { left, right } = node.kind {
+                if depth + 1 < max_levels {
+                    stack.push((right, depth + 1));
+                    stack.push((left, depth + 1));
                 }
-                NodeKind::Leaf { .. } => match stack.pop() {
-                    Some((next, depth)) => {
-                        curr_node_idx = next;
-                        curr_depth = depth;
-                    }
-                    None => break 'dfs,
-                },
             }
-        }
-        nodes
+            Some((depth, node.aabb))
+        })
     }
 }
 
-#[allow(dead_code)]
-pub(crate) struct NodeInfo {
-    pub aabb: AABB,
-    pub depth: usize,
-}
-
 //
 // Iterators
 //
@@ -21,11 +21,23 @@ pub struct Collider {
     pub ty: ColliderType,
     /// Pose of the collider relative to the body it's attached to,
     /// or the world if it's not attached to a body.
-    #[serde(with = "m::serde_physics_pose")]
+    #[cfg_attr(feature = "serde-types", serde(with = "m::serde_physics_pose"))]
     pub pose: m::PhysicsPose,
     /// Collision layer, see [`MaskMatrix`][super::MaskMatrix] for info.
     /// Defaults to 0.
     pub layer: usize,
+    /// Arbitrary data for identifying what a collider belongs to,
+    /// e.g. an enum discriminant or an index into a list of game objects.
+    /// Not used by the physics engine.
+    /// Defaults to 0.
+    pub user_data: u64,
+    /// Whether the collider takes part in collision detection and queries.
+    /// Defaults to true.
+    ///
+    /// Prefer [`PhysicsWorld::set_collider_enabled`][crate::PhysicsWorld::set_collider_enabled]
+    /// over setting this directly,
+    /// since it also wakes up things that were touching the collider.
+    pub enabled: bool,
 }
 impl Default for Collider {
     fn default() -> Self {
@@ -37,6 +49,8 @@ impl Default for Collider {
             ty: ColliderType::default(),
             pose: m::PhysicsPose::default(),
             layer: 0,
+            user_data: 0,
+            enabled: true,
         }
     }
 }
@@ -124,6 +138,39 @@ impl Collider {
         ColliderPolygon::Triangle { outer_r }.into()
     }
 
+    /// Create a solid regular polygon collider with the given number of vertices
+    /// that fits inside a circle of the given radius.
+    ///
+    /// Only the regular polygons supported by [`ColliderPolygon`] can be created,
+    /// i.e. triangles, squares and hexagons.
+    /// Returns `None` for any other number of vertices.
+    pub fn new_regular_polygon(vertex_count: usize, outer_r: f64) -> Option<Self> {
+        match vertex_count {
+            3 => Some(Self::new_triangle(outer_r)),
+            4 => Some(Self::new_square(outer_r * std::f64::consts::SQRT_2)),
+            6 => Some(Self::new_hexagon(outer_r)),
+            _ => None,
+        }
+    }
+
+    /// Create a solid regular polygon collider with rounded corners
+    /// that fits inside a circle of the given radius.
+    ///
+    /// Like in [`new_rounded_rect`][Self::new_rounded_rect],
+    /// the edges stay where they would be without rounding and the corners are cut out.
+    /// If the radius is greater than there's room for, it's reduced until it fits.
+    /// Returns `None` for unsupported vertex counts,
+    /// see [`new_regular_polygon`][Self::new_regular_polygon].
+    pub fn new_rounded_regular_polygon(
+        vertex_count: usize,
+        outer_r: f64,
+        corner_radius: f64,
+    ) -> Option<Self> {
+        let mut coll = Self::new_regular_polygon(vertex_count, outer_r)?;
+        coll.shape = coll.shape.rounded_inward(corner_radius);
+        Some(coll)
+    }
+
     /// Set the pose of the collider relative to the body it's attached to,
     /// or relative to the world if it's not attached to a body.
     #[inline]
@@ -154,6 +201,13 @@ impl Collider {
         self
     }
 
+    /// Set the [`user_data`][Self::user_data] of the collider.
+    #[inline]
+    pub fn with_user_data(mut self, user_data: u64) -> Self {
+        self.user_data = user_data;
+        self
+    }
+
     #[inline]
     pub fn is_solid(&self) -> bool {
         matches!(self.ty, ColliderType::Solid(_))
@@ -164,6 +218,47 @@ impl Collider {
         matches!(self.ty, ColliderType::Sensor)
     }
 
+    /// Check whether a point in world space is inside this collider.
+    ///
+    /// `pose` is the pose of the collider in world space,
+    /// e.g. `body.pose * collider.pose` for a collider attached to a body.
+    /// The collider's own [`pose`][Self::pose] field is not used.
+    #[inline]
+    pub fn contains_point(&self, pose: m::PhysicsPose, point: uv::DVec2) -> bool {
+        super::query::point_collider_bool(point, pose, *self)
+    }
+
+    /// Get the signed distance from a point in world space to the surface of this collider,
+    /// along with the closest point on the surface.
+    ///
+    /// The distance is negative if the point is inside the collider.
+    /// `pose` is the pose of the collider in world space,
+    /// as in [`contains_point`][Self::contains_point].
+    pub fn signed_distance(&self, pose: m::PhysicsPose, point: uv::DVec2) -> (f64, uv::DVec2) {
+        let p_wrt_c = pose.inversed() * point;
+        let closest = self.shape.polygon.closest_boundary_point(p_wrt_c);
+        // direction pointing out of the polygon at the closest point
+        let outward = if closest.is_interior {
+            closest.pt - p_wrt_c
+        } else {
+            p_wrt_c - closest.pt
+        };
+        let dist_to_polygon = outward.mag();
+        let signed_dist = if closest.is_interior {
+            -dist_to_polygon
+        } else {
+            dist_to_polygon
+        };
+        // exactly on the polygon's boundary there's no direction to push the point out in,
+        // but this only matters with rounding and is vanishingly rare in practice
+        let surface_pt = if dist_to_polygon > 0.0 {
+            closest.pt + outward * (self.shape.circle_r / dist_to_polygon)
+        } else {
+            closest.pt
+        };
+        (signed_dist - self.shape.circle_r, pose * surface_pt)
+    }
+
     /// Get the info required to construct a body with this collider.
     #[inline]
     pub fn info(&self) -> body::ColliderInfo {
@@ -196,6 +291,13 @@ impl Default for ColliderType {
 #[cfg_attr(feature = "serde-types", serde(default))]
 pub struct ColliderShape {
     pub polygon: ColliderPolygon,
+    /// Radius of the circle the polygon is expanded by.
+    ///
+    /// The shape covers every point within this distance of the polygon,
+    /// so all corners are rounded with this same radius
+    /// and all edges are pushed outward by it.
+    /// Use [`rounded_inward`][Self::rounded_inward] to round corners
+    /// without making the shape larger.
     pub circle_r: f64,
 }
 
@@ -348,6 +450,18 @@ impl ColliderShape {
             .translated(pose.translation)
     }
 
+    /// Set the radius that rounds every corner of the shape,
+    /// replacing the current [`circle_r`][Self::circle_r].
+    ///
+    /// This grows the shape outward by the radius,
+    /// e.g. a rectangle polygon 1 unit wide with a corner radius of 0.1 becomes 1.2 units wide.
+    /// To keep the outer dimensions, use [`rounded_inward`][Self::rounded_inward] instead.
+    #[inline]
+    pub fn with_corner_radius(mut self, radius: f64) -> Self {
+        self.circle_r = radius;
+        self
+    }
+
     /// Enlarge the circle component of the shape.
     pub fn expanded(&self, amount: f64) -> Self {
         Self {
@@ -356,6 +470,34 @@ impl ColliderShape {
         }
     }
 
+    /// Scale the shape by separate factors along the local x and y axes.
+    ///
+    /// Only rectangles and line segments can be stretched along one axis.
+    /// Shapes that can't be stretched (the circle part, triangles and hexagons)
+    /// are scaled uniformly by the smaller of the two factors.
+    pub fn scaled(&self, scale: uv::DVec2) -> Self {
+        let uniform = scale.x.min(scale.y);
+        use ColliderPolygon as P;
+        let polygon = match self.polygon {
+            P::Point => P::Point,
+            P::LineSegment { hl } => P::LineSegment { hl: hl * scale.x },
+            P::Rect { hw, hh } => P::Rect {
+                hw: hw * scale.x,
+                hh: hh * scale.y,
+            },
+            P::Triangle { outer_r } => P::Triangle {
+                outer_r: outer_r * uniform,
+            },
+            P::Hexagon { outer_r } => P::Hexagon {
+                outer_r: outer_r * uniform,
+            },
+        };
+        Self {
+            polygon,
+            circle_r: self.circle_r * uniform,
+        }
+    }
+
     /// Increase rounding such that edges remain the same distance from the origin.
     /// Works like the CSS "corner-radius" property.
     ///
@@ -959,6 +1101,120 @@ mod tests {
         ColliderPolygon::Hexagon { outer_r: 1.0 },
     ];
 
+    /// Points are inside colliders of every shape, with and without rounding,
+    /// up to the rounded boundary and no further
+    #[test]
+    fn contains_point() {
+        let pose = m::PhysicsPose::new(
+            uv::DVec2::new(3.0, -2.0),
+            uv::DRotor2::from_angle(30f64.to_radians()),
+        );
+        let polygons = [
+            ColliderPolygon::Point,
+            ColliderPolygon::LineSegment { hl: 0.5 },
+        ]
+        .into_iter()
+        .chain(TEST_POLYGONS);
+        for polygon in polygons {
+            for circle_r in [0.0, 0.2] {
+                let coll = Collider {
+                    shape: ColliderShape { polygon, circle_r },
+                    ..Default::default()
+                };
+                let has_area = circle_r > 0.0
+                    || !matches!(
+                        polygon,
+                        ColliderPolygon::Point | ColliderPolygon::LineSegment { .. }
+                    );
+                assert_eq!(
+                    coll.contains_point(pose, pose.translation),
+                    has_area,
+                    "center of {polygon:?} with r {circle_r}"
+                );
+
+                for i in 0..16 {
+                    let dir = uv::DRotor2::from_angle(i as f64 * PI / 8.0) * uv::DVec2::unit_x();
+                    let far = 5.0 * dir;
+                    let boundary = polygon.closest_boundary_point(far).pt;
+                    let normal = (far - boundary).normalized();
+                    assert_eq!(
+                        coll.contains_point(pose, pose * (boundary + 0.1 * normal)),
+                        circle_r > 0.1,
+                        "just outside {polygon:?} with r {circle_r} towards {dir:?}"
+                    );
+                    assert!(
+                        !coll.contains_point(pose, pose * (boundary + 0.3 * normal)),
+                        "outside rounding of {polygon:?} with r {circle_r} towards {dir:?}"
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn regular_polygons() {
+        for vertex_count in [3, 4, 6] {
+            let coll = Collider::new_regular_polygon(vertex_count, 1.0).unwrap();
+            // every vertex is on the outer circle
+            let bounds = coll.shape.aabb(m::PhysicsPose::identity());
+            assert!(bounds.max.x <= 1.0 + 1e-9 && bounds.max.y <= 1.0 + 1e-9);
+            let pose = m::PhysicsPose::identity();
+            assert!(coll.contains_point(pose, uv::DVec2::new(0.0, 0.49)));
+            assert!(!coll.contains_point(pose, uv::DVec2::new(0.0, 1.01)));
+        }
+        assert!(Collider::new_regular_polygon(5, 1.0).is_none());
+    }
+
+    #[test]
+    fn rounding_corners() {
+        let pose = m::PhysicsPose::identity();
+        for vertex_count in [3, 4, 6] {
+            let sharp = Collider::new_regular_polygon(vertex_count, 1.0).unwrap();
+            let rounded = Collider::new_rounded_regular_polygon(vertex_count, 1.0, 0.2).unwrap();
+            assert!((rounded.shape.circle_r - 0.2).abs() < 1e-9);
+            // edges stay at the same distance from the center
+            let (sharp_dist, _) = sharp.signed_distance(pose, uv::DVec2::zero());
+            let (rounded_dist, _) = rounded.signed_distance(pose, uv::DVec2::zero());
+            assert!((sharp_dist - rounded_dist).abs() < 1e-9);
+        }
+        assert!(Collider::new_rounded_regular_polygon(5, 1.0, 0.2).is_none());
+
+        let rect = ColliderShape::from(ColliderPolygon::Rect { hw: 0.5, hh: 0.5 });
+        let shape = rect.with_corner_radius(0.1);
+        let bounds = shape.aabb(pose);
+        assert!((bounds.max.x - 0.6).abs() < 1e-9);
+    }
+
+    #[test]
+    fn signed_distance() {
+        let pose = m::PhysicsPose::new(
+            uv::DVec2::new(-1.0, 2.0),
+            uv::DRotor2::from_angle(90f64.to_radians()),
+        );
+        let v = uv::DVec2::new;
+        let rounded_rect = Collider::new_rounded_rect(1.4, 2.0, 0.2);
+        let circle = Collider::new_circle(1.0);
+        let cases = [
+            // outside, inside and at the surface of a rounded rectangle
+            (rounded_rect, v(1.0, 0.0), 0.3, v(0.7, 0.0)),
+            (rounded_rect, v(0.3, 0.0), -0.4, v(0.7, 0.0)),
+            (rounded_rect, v(0.0, -1.0), 0.0, v(0.0, -1.0)),
+            (circle, v(2.0, 0.0), 1.0, v(1.0, 0.0)),
+            (circle, v(0.0, 0.25), -0.75, v(0.0, 1.0)),
+        ];
+        for (coll, local_pt, expected_dist, expected_closest) in cases {
+            let (dist, closest) = coll.signed_distance(pose, pose * local_pt);
+            assert!(
+                (dist - expected_dist).abs() < 1e-9,
+                "distance {dist} at {local_pt:?}, expected {expected_dist}"
+            );
+            assert!(
+                (closest - pose * expected_closest).mag() < 1e-9,
+                "closest point {closest:?} at {local_pt:?}"
+            );
+        }
+    }
+
     /// Closest boundary points are found correctly
     /// from every Voronoi region of every polygon shape
     #[test]
@@ -15,9 +15,9 @@ pub fn point_collider_bool(point: uv::DVec2, pose: PhysicsPose, coll: Collider)
             x_dist * x_dist + y_dist * y_dist < r * r
         }
         ColliderPolygon::Rect { hw, hh } => {
-            let x_dist = p_wrt_c.x.abs() - hw;
-            let y_dist = p_wrt_c.y.abs() - hh;
-            (x_dist <= 0.0 && y_dist <= 0.0) || x_dist * x_dist + y_dist * y_dist < r * r
+            let x_dist = (p_wrt_c.x.abs() - hw).max(0.0);
+            let y_dist = (p_wrt_c.y.abs() - hh).max(0.0);
+            (x_dist == 0.0 && y_dist == 0.0) || x_dist * x_dist + y_dist * y_dist < r * r
         }
         // this will probably be what I do for all other polygons,
         // but keeping the match explicit so I have to look here every time and think about it
@@ -142,7 +142,8 @@ pub fn ray_collider(ray: Ray, pose: PhysicsPose, coll: Collider) -> Option<CastH
                         ray,
                         uv::DVec2::new(hl.copysign(ray.start.x), 0.0),
                         coll.shape.circle_r,
-                    );
+                    )
+                    .map(|hit| hit_to_worldspace(hit, pose));
                 }
             }
 
@@ -169,6 +170,7 @@ pub fn ray_collider(ray: Ray, pose: PhysicsPose, coll: Collider) -> Option<CastH
                     uv::DVec2::new(hl.copysign(x_at_edge_hit), 0.0),
                     coll.shape.circle_r,
                 )
+                .map(|hit| hit_to_worldspace(hit, pose))
             }
         }
         // this works for all actual polygons
@@ -269,10 +271,11 @@ pub fn ray_collider(ray: Ray, pose: PhysicsPose, coll: Collider) -> Option<CastH
                 None
             } else {
                 match vertex_for_circle_check {
-                    Some(vert) => ray_circle(ray, vert, coll.shape.circle_r),
+                    Some(vert) => ray_circle(ray, vert, coll.shape.circle_r)
+                        .map(|hit| hit_to_worldspace(hit, pose)),
                     None => Some(CastHit {
                         t: closest_hit_t,
-                        normal: closest_edge_normal,
+                        normal: pose.rotation * closest_edge_normal,
                         point: ray_worldspace.point_at_t(closest_hit_t),
                     }),
                 }
@@ -281,6 +284,16 @@ pub fn ray_collider(ray: Ray, pose: PhysicsPose, coll: Collider) -> Option<CastH
     }
 }
 
+/// Transform a hit computed in object-local space into world space.
+#[inline]
+fn hit_to_worldspace(hit: CastHit, pose: PhysicsPose) -> CastHit {
+    CastHit {
+        t: hit.t,
+        normal: pose.rotation * hit.normal,
+        point: pose * hit.point,
+    }
+}
+
 fn ray_circle(ray: Ray, circ_pos: uv::DVec2, r: f64) -> Option<CastHit> {
     // source: Real-Time Collision Detection chapter 5
 
@@ -461,6 +474,38 @@ mod tests {
         should_hit_circle(ray, uv::DVec2::new(-2.0, -1.0));
     }
 
+    /// Hit points and normals are in world space for every kind of surface
+    #[test]
+    fn hits_in_world_space() {
+        let pose = PhysicsPose::new(uv::DVec2::new(-3.0, 2.0), Angle::Deg(40.0).into());
+        let colliders = [
+            Collider::new_rect(4.0, 2.0),
+            Collider::new_rounded_rect(4.0, 2.0, 0.5),
+            Collider::new_capsule(3.0, 0.5),
+            Collider::new_hexagon(1.5),
+        ];
+        for coll in colliders {
+            for i in 0..12 {
+                // rays from all around aimed near the corners and caps
+                let dir = UnitDVec2::new_normalize(
+                    uv::DRotor2::from_angle(i as f64 * std::f64::consts::PI / 6.0)
+                        * uv::DVec2::unit_x(),
+                );
+                let ray = Ray {
+                    start: -10.0 * *dir + 1.2 * *crate::math::unit_left_normal(dir),
+                    dir,
+                };
+                let Some(hit) = ray_collider(pose * ray, pose, coll) else {
+                    continue;
+                };
+                let (dist, _) = coll.signed_distance(pose, hit.point);
+                assert!(dist.abs() < 1e-6, "hit point off the surface by {dist}");
+                assert!(coll.contains_point(pose, hit.point - 0.01 * *hit.normal));
+                assert!(!coll.contains_point(pose, hit.point + 0.01 * *hit.normal));
+            }
+        }
+    }
+
     /// Convention: ray always misses if it starts inside the collider
     #[test]
     fn inside_always_misses() {
@@ -72,6 +72,74 @@ pub fn intersection_check(poses: [PhysicsPose; 2], shapes: [ColliderShape; 2]) -
     }
 }
 
+/// Get the distance between the surfaces of two colliders,
+/// or zero if they intersect.
+pub fn distance(poses: [PhysicsPose; 2], shapes: [ColliderShape; 2]) -> f64 {
+    if !intersection_check(poses, shapes).is_zero() {
+        return 0.0;
+    }
+    // the polygons don't intersect,
+    // so the closest point on one of them is always one of its vertices
+    let vertex_dist = |from: usize, to: usize| {
+        let to_local = poses[to].inversed() * poses[from];
+        polygon_vertices(shapes[from].polygon)
+            .map(|v| {
+                let v = to_local * v;
+                (v - shapes[to].polygon.closest_boundary_point(v).pt).mag()
+            })
+            .fold(f64::INFINITY, f64::min)
+    };
+    let polygon_dist = vertex_dist(0, 1).min(vertex_dist(1, 0));
+    (polygon_dist - shapes[0].circle_r - shapes[1].circle_r).max(0.0)
+}
+
+/// Cosine of the largest angle (about 5 degrees) between a direction and an edge normal
+/// for the direction to be considered facing that edge in [`surface_feature`].
+const EDGE_FEATURE_COS: f64 = 0.996;
+
+/// Identify the part of a polygon's surface facing the given direction in local space:
+/// an edge if the direction is close to its normal, otherwise the vertex farthest along it.
+///
+/// The returned index is only meaningful for comparing with other features of the same polygon.
+pub(crate) fn surface_feature(polygon: ColliderPolygon, dir: UnitDVec2) -> u8 {
+    if matches!(polygon, ColliderPolygon::Point) {
+        return 0;
+    }
+    let mirror_count = if polygon.is_rotationally_symmetrical() {
+        polygon.edge_count()
+    } else {
+        0
+    };
+    let normals = (0..polygon.edge_count())
+        .map(|i| polygon.get_edge(i).normal)
+        .chain((0..mirror_count).map(|i| -polygon.get_edge(i).normal));
+    if let Some(edge_idx) = normals
+        .clone()
+        .position(|n| n.dot(*dir) >= EDGE_FEATURE_COS)
+    {
+        return edge_idx as u8;
+    }
+    // vertices are numbered after the edges
+    let vertex_idx = polygon_vertices(polygon)
+        .enumerate()
+        .max_by(|(_, v0), (_, v1)| v0.dot(*dir).total_cmp(&v1.dot(*dir)))
+        .map_or(0, |(i, _)| i);
+    (normals.count() + vertex_idx) as u8
+}
+
+/// Iterate over the corners of a polygon, or its single point if it's a point.
+fn polygon_vertices(polygon: ColliderPolygon) -> impl Iterator<Item = uv::DVec2> {
+    let point = matches!(polygon, ColliderPolygon::Point).then(uv::DVec2::zero);
+    let mirror_count = if polygon.is_rotationally_symmetrical() {
+        polygon.edge_count()
+    } else {
+        0
+    };
+    let starts = (0..polygon.edge_count()).map(move |i| polygon.get_edge(i).edge.start);
+    let mirrored_starts = (0..mirror_count).map(move |i| -polygon.get_edge(i).edge.start);
+    point.into_iter().chain(starts).chain(mirrored_starts)
+}
+
 #[inline]
 fn flip_contacts(contacts: ContactResult) -> ContactResult {
     contacts.map(|c| Contact {
@@ -153,6 +221,15 @@ fn circle_any(
     }
 }
 
+/// How far apart the second point of a two-point contact can be
+/// and still be reported as part of the contact.
+///
+/// Without this, a box resting on another at a tiny angle (e.g. from floating point error)
+/// flickers between one and two contact points, which makes stacks wobble.
+/// The solver recomputes depth every iteration and skips points that are apart,
+/// so including a separated point doesn't push things together.
+const MANIFOLD_SEPARATION_TOLERANCE: f64 = 0.005;
+
 //
 // generic test for all other shape pairs
 //
@@ -253,7 +330,9 @@ fn any_any(poses: [PhysicsPose; 2], shapes: [ColliderShape; 2]) -> ContactResult
         incident_edge_inner.edge.offset(offset)
     };
 
-    match clip_edge(owning_edge, incident_edge_outer) {
+    // clip to the slab even if the edges cross,
+    // since a nearly flat edge crossing the other at a tiny angle is still a resting contact
+    match clip_edge_to_slab(owning_edge, incident_edge_outer) {
         // check if the edge passes on the "inside" of the other,
         // if so this is a two-point contact
         EdgeClipResult::Passes { enters, exits } => {
@@ -261,33 +340,39 @@ fn any_any(poses: [PhysicsPose; 2], shapes: [ColliderShape; 2]) -> ContactResult
                 - incident_edge_outer.start.dot(*pen_edge.normal);
             let dir_dot_axis = incident_edge_outer.dir.dot(*pen_edge.normal);
 
+            let normal_worldspace = poses[shape_order[0]].rotation * pen_edge.normal;
+            let contact_at = |t: f64| {
+                let depth = start_depth - t * dir_dot_axis;
+                let point = incident_edge_outer.start + (t * *incident_edge_outer.dir);
+                Contact {
+                    normal: normal_worldspace,
+                    offsets: [
+                        point + depth * *pen_edge.normal,
+                        relative_poses[shape_order[0]] * point,
+                    ],
+                }
+            };
+
             let enter_depth = start_depth - enters * dir_dot_axis;
             let exit_depth = start_depth - exits * dir_dot_axis;
-
-            if enter_depth > 0.0 && exit_depth > 0.0 {
-                let enter_point = incident_edge_outer.start + (enters * *incident_edge_outer.dir);
-                let exit_point = incident_edge_outer.start + (exits * *incident_edge_outer.dir);
-
-                let normal_worldspace = poses[shape_order[0]].rotation * pen_edge.normal;
-
-                return orient_result(ContactResult::Two(
-                    Contact {
-                        normal: normal_worldspace,
-                        offsets: [
-                            enter_point + enter_depth * *pen_edge.normal,
-                            relative_poses[shape_order[0]] * enter_point,
-                        ],
-                    },
-                    Contact {
-                        normal: normal_worldspace,
-                        offsets: [
-                            exit_point + exit_depth * *pen_edge.normal,
-                            relative_poses[shape_order[0]] * exit_point,
-                        ],
-                    },
-                ));
+            let (deep_t, deep_depth, shallow_depth) = if enter_depth >= exit_depth {
+                (enters, enter_depth, exit_depth)
+            } else {
+                (exits, exit_depth, enter_depth)
+            };
+
+            if deep_depth > 0.0 && shallow_depth > -MANIFOLD_SEPARATION_TOLERANCE {
+                // the order of points is kept stable (enter first)
+                // so that they match up with the previous frame's for warm starting
+                return orient_result(ContactResult::Two(contact_at(enters), contact_at(exits)));
             } else if both_simple_polygons {
-                return ContactResult::Zero;
+                // only one end of the edge is touching.
+                // shapes with a circle part get this from the more general check below
+                return if deep_depth > 0.0 {
+                    orient_result(ContactResult::One(contact_at(deep_t)))
+                } else {
+                    ContactResult::Zero
+                };
             }
         }
         EdgeClipResult::Misses if both_simple_polygons => {
@@ -442,49 +527,37 @@ impl Edge {
 
 #[derive(Clone, Copy, Debug)]
 enum EdgeClipResult {
-    /// If edges intersect, we don't care about anything else
-    /// as this means a single contact point at an already known location
-    Intersects,
-    /// If they don't intersect, we want the distances at which edge 1 intersects
+    /// The distances at which edge 1 intersects
     /// with the lines perpendicular to edge 2 going through edge 2's endpoints.
     ///
-    /// The values are the `t`s at which the *second* edge passed to `clip_edge`
+    /// The values are the `t`s at which the *second* edge passed to `clip_edge_to_slab`
     /// crosses the boundaries defined by the *first* edge.
     Passes { enters: f64, exits: f64 },
     /// If edge 1 is completely outside the slab defined by edge 1, this is returned.
     Misses,
 }
 
-fn clip_edge(target: Edge, edge: Edge) -> EdgeClipResult {
+/// Find where `edge` enters and exits the slab defined by `target`,
+/// i.e. the region between the lines perpendicular to `target` through its endpoints.
+///
+/// The edges may cross, in which case the result is the same as if they didn't.
+fn clip_edge_to_slab(target: Edge, edge: Edge) -> EdgeClipResult {
     let start_dist = target.start - edge.start;
-    // cramer's rule solution for t in At = b
-    // where A = [dir1, -dir2] and b = start_dist.
-    // denom is 0 and t is NaN if dir1 and dir2 are parallel, but this is ok because the following
-    // comparison correctly evaluates to false and t isn't used after that.
-    let denom = edge.dir.x * (-target.dir.y) - (-target.dir.x) * edge.dir.y;
-    let t = [
-        (start_dist.x * (-target.dir.y) - (-target.dir.x) * start_dist.y) / denom,
-        (edge.dir.x * start_dist.y - start_dist.x * edge.dir.y) / denom,
-    ];
-    if t[0] >= 0.0 && t[0] <= edge.length && t[1] >= 0.0 && t[1] <= target.length {
-        EdgeClipResult::Intersects
-    } else {
-        let dist_dot_dir2 = start_dist.dot(*target.dir);
-        let dirs_dot = edge.dir.dot(*target.dir);
-        let start_clip_t = dist_dot_dir2 / dirs_dot;
-        let end_clip_t = (target.length + dist_dot_dir2) / dirs_dot;
-        if (start_clip_t <= 0.0 && end_clip_t <= 0.0)
-            || (start_clip_t >= edge.length && end_clip_t >= edge.length)
-        {
-            return EdgeClipResult::Misses;
-        }
-        let (enters, exits) = if start_clip_t < end_clip_t {
-            (start_clip_t.max(0.0), end_clip_t.min(edge.length))
-        } else {
-            (end_clip_t.max(0.0), start_clip_t.min(edge.length))
-        };
-        EdgeClipResult::Passes { enters, exits }
+    let dist_dot_dir2 = start_dist.dot(*target.dir);
+    let dirs_dot = edge.dir.dot(*target.dir);
+    let start_clip_t = dist_dot_dir2 / dirs_dot;
+    let end_clip_t = (target.length + dist_dot_dir2) / dirs_dot;
+    if (start_clip_t <= 0.0 && end_clip_t <= 0.0)
+        || (start_clip_t >= edge.length && end_clip_t >= edge.length)
+    {
+        return EdgeClipResult::Misses;
     }
+    let (enters, exits) = if start_clip_t < end_clip_t {
+        (start_clip_t.max(0.0), end_clip_t.min(edge.length))
+    } else {
+        (end_clip_t.max(0.0), start_clip_t.min(edge.length))
+    };
+    EdgeClipResult::Passes { enters, exits }
 }
 
 #[cfg(test)]
@@ -496,8 +569,8 @@ mod tests {
     #[allow(clippy::float_cmp)]
     #[test]
     fn clip_various_edges() {
-        // intersection
-        match clip_edge(
+        // edges that cross are clipped like any other
+        match clip_edge_to_slab(
             Edge {
                 start: uv::DVec2::new(1.0, 1.0),
                 dir: UnitDVec2::unit_x(),
@@ -509,11 +582,14 @@ mod tests {
                 length: 2.0,
             },
         ) {
-            EdgeClipResult::Intersects => (),
-            _ => panic!("Didn't intersect"),
+            EdgeClipResult::Passes { enters, exits } => {
+                assert_eq!(enters, 0.0);
+                assert_eq!(exits, 2.0);
+            }
+            _ => panic!("Missed but shouldn't have"),
         }
         // miss that starts at 0
-        match clip_edge(
+        match clip_edge_to_slab(
             Edge {
                 start: uv::DVec2::new(1.0, 1.0),
                 dir: UnitDVec2::unit_x(),
@@ -529,11 +605,11 @@ mod tests {
                 assert_eq!(enters, 0.0);
                 assert!((exits - 1.0 / (PI / 6.0).cos()).abs() < 0.001);
             }
-            _ => panic!("Intersected but shouldn't have"),
+            _ => panic!("Missed but shouldn't have"),
         }
         // miss that starts before 0 but ends at length
         // and also starts at the end of the other one
-        match clip_edge(
+        match clip_edge_to_slab(
             Edge {
                 start: uv::DVec2::new(1.0, 1.0),
                 dir: UnitDVec2::unit_x(),
@@ -549,7 +625,121 @@ mod tests {
                 assert!((enters - 1.0 / (PI / 8.0).cos()).abs() < 0.001);
                 assert_eq!(exits, 2.0);
             }
-            _ => panic!("Intersected but shouldn't have"),
+            _ => panic!("Missed but shouldn't have"),
+        }
+    }
+
+    #[test]
+    fn surface_features() {
+        let rect = ColliderPolygon::Rect { hw: 1.0, hh: 0.5 };
+        let down = surface_feature(rect, -UnitDVec2::unit_y());
+        // slightly tilted still counts as the same edge
+        let tilted = uv::DRotor2::from_angle(0.02) * -UnitDVec2::unit_y();
+        assert_eq!(surface_feature(rect, tilted), down);
+        assert_ne!(surface_feature(rect, UnitDVec2::unit_y()), down);
+        assert_ne!(surface_feature(rect, UnitDVec2::unit_x()), down);
+
+        // diagonal directions give the corners, each different
+        let corners: Vec<u8> = [(1.0, 1.0), (-1.0, 1.0), (-1.0, -1.0), (1.0, -1.0)]
+            .into_iter()
+            .map(|(x, y)| surface_feature(rect, UnitDVec2::new_normalize(uv::DVec2::new(x, y))))
+            .collect();
+        for (i, corner) in corners.iter().enumerate() {
+            assert!(corner >= &4, "corner {i} was an edge");
+            assert!(!corners[..i].contains(corner), "corner {i} was a duplicate");
+        }
+        // any direction between the edges picks the same corner
+        let near_corner = UnitDVec2::new_normalize(uv::DVec2::new(1.0, 0.2));
+        assert_eq!(surface_feature(rect, near_corner), corners[0]);
+
+        let circle = ColliderPolygon::Point;
+        assert_eq!(
+            surface_feature(circle, UnitDVec2::unit_x()),
+            surface_feature(circle, UnitDVec2::unit_y())
+        );
+    }
+
+    #[test]
+    fn distance_between_shapes() {
+        let square = ColliderShape::from(ColliderPolygon::Rect { hw: 0.5, hh: 0.5 });
+        let circle = ColliderShape {
+            polygon: ColliderPolygon::Point,
+            circle_r: 0.5,
+        };
+        let at = |x: f64, y: f64, angle: f64| {
+            PhysicsPose::new(uv::DVec2::new(x, y), uv::DRotor2::from_angle(angle))
+        };
+
+        let dist = distance([at(0.0, 0.0, 0.0), at(3.0, 0.2, 0.0)], [square, circle]);
+        assert!((dist - 2.0).abs() < 1e-9, "{dist}");
+        // corner of a rotated square pointing at the circle
+        let dist = distance(
+            [at(0.0, 0.0, PI / 4.0), at(3.0, 0.0, 0.0)],
+            [square, circle],
+        );
+        assert!((dist - (2.5 - 0.5_f64.sqrt())).abs() < 1e-9, "{dist}");
+        let dist = distance([at(0.0, 0.0, 0.0), at(0.8, 0.0, 0.3)], [square, square]);
+        assert_eq!(dist, 0.0);
+    }
+
+    /// A capsule lying across a narrower box gets a contact at both corners of the box,
+    /// no matter how deep it is or which shape is given first.
+    #[test]
+    fn capsule_on_box_has_two_contacts() {
+        let capsule = ColliderShape {
+            polygon: ColliderPolygon::LineSegment { hl: 3.0 },
+            circle_r: 0.1,
+        };
+        let pillar = ColliderShape::from(ColliderPolygon::Rect { hw: 0.25, hh: 1.0 });
+        let pillar_pose = PhysicsPose::new(uv::DVec2::new(2.0, 1.0), uv::DRotor2::identity());
+        for (y, angle) in [(2.05, 0.0), (2.0, 0.02), (1.8, -0.05), (1.5, 0.0)] {
+            let capsule_pose =
+                PhysicsPose::new(uv::DVec2::new(0.0, y), uv::DRotor2::from_angle(angle));
+            for result in [
+                intersection_check([capsule_pose, pillar_pose], [capsule, pillar]),
+                flip_contacts(intersection_check(
+                    [pillar_pose, capsule_pose],
+                    [pillar, capsule],
+                )),
+            ] {
+                let ContactResult::Two(c1, c2) = result else {
+                    panic!("expected two contacts at y {y} angle {angle}, got {result:?}");
+                };
+                for cont in [c1, c2] {
+                    // normal points away from the capsule, towards the pillar
+                    assert!(cont.normal.y < -0.99, "{cont:?}");
+                }
+            }
+        }
+    }
+
+    /// A box resting on another at a tiny angle keeps both of its contact points,
+    /// while a clearly tilted one only touches with one corner.
+    #[test]
+    fn tilted_box_on_box_contacts() {
+        let square = ColliderShape::from(ColliderPolygon::Rect { hw: 0.5, hh: 0.5 });
+        let lower_pose = PhysicsPose::default();
+        for (angle, expected_points) in [(0.0, 2), (0.004, 2), (-0.004, 2), (0.1, 1), (-0.1, 1)] {
+            // overlapping by a millimeter, so at 0.004 radians one corner is slightly apart
+            let upper_pose =
+                PhysicsPose::new(uv::DVec2::new(0.0, 0.999), uv::DRotor2::from_angle(angle));
+            for result in [
+                intersection_check([lower_pose, upper_pose], [square, square]),
+                flip_contacts(intersection_check(
+                    [upper_pose, lower_pose],
+                    [square, square],
+                )),
+            ] {
+                assert_eq!(
+                    result.iter().count(),
+                    expected_points,
+                    "angle {angle}, got {result:?}"
+                );
+                for cont in result.iter() {
+                    // normal points away from the lower box
+                    assert!(cont.normal.y > 0.99, "{cont:?}");
+                }
+            }
         }
     }
 }
@@ -0,0 +1,327 @@
+//! A uniform grid broadphase stored in a hash map,
+//! an alternative to the [`Bvh`][super::bvh::Bvh].
+
+use crate::{
+    math::uv,
+    physics::{
+        collision::{bvh::SweepItem, query::ray_aabb, Broadphase, Ray, AABB},
+        ColliderKey,
+    },
+};
+
+use std::{any::Any, collections::HashMap, ops::ControlFlow};
+
+/// Colliders covering more cells than this are kept in a separate list
+/// that every query checks, instead of being added to each cell.
+const MAX_CELLS_PER_ENTRY: usize = 16;
+
+/// A broadphase that sorts colliders into a grid of equally sized square cells.
+///
+/// Inserting and querying take constant time per cell touched,
+/// which makes this faster than a [`Bvh`][super::bvh::Bvh]
+/// for scenes full of similarly sized objects spread evenly over the world,
+/// such as bullets or particles.
+/// Cells should be about as large as a typical object;
+/// much larger cells hold many objects each, and much smaller cells
+/// make each object span many cells.
+/// A few large objects such as level geometry are fine,
+/// they're stored outside of the grid and tested against every query.
+#[derive(Clone, Debug)]
+pub struct SpatialHashBroadphase {
+    cell_size: f64,
+    entries: Vec<(ColliderKey, AABB)>,
+    cells: HashMap<[i64; 2], Vec<usize>>,
+    oversized: Vec<usize>,
+    bounds: Option<AABB>,
+    // objects spanning multiple cells are found once per cell,
+    // the query id they were last seen in is used to skip duplicates
+    last_seen: Vec<u32>,
+    query_id: u32,
+    // buffers kept around to avoid allocating for every query
+    candidates: Vec<usize>,
+    sweep_results: Vec<SweepItem>,
+}
+
+/// Range of grid cells covered by an AABB, inclusive on both ends.
+#[derive(Clone, Copy, Debug)]
+struct CellRange {
+    min: [i64; 2],
+    max: [i64; 2],
+}
+
+impl CellRange {
+    fn cells(self) -> impl Iterator<Item = [i64; 2]> {
+        (self.min[0]..=self.max[0])
+            .flat_map(move |x| (self.min[1]..=self.max[1]).map(move |y| [x, y]))
+    }
+}
+
+impl SpatialHashBroadphase {
+    /// Create an empty grid with cells of the given width and height.
+    ///
+    /// Panics if `cell_size` is not positive.
+    pub fn new(cell_size: f64) -> Self {
+        assert!(cell_size > 0.0, "Cell size must be positive");
+        Self {
+            cell_size,
+            entries: Vec::new(),
+            cells: HashMap::new(),
+            oversized: Vec::new(),
+            bounds: None,
+            last_seen: Vec::new(),
+            query_id: 0,
+            candidates: Vec::new(),
+            sweep_results: Vec::new(),
+        }
+    }
+
+    /// Width and height of a grid cell.
+    #[inline]
+    pub fn cell_size(&self) -> f64 {
+        self.cell_size
+    }
+
+    /// Get the cells covered by an AABB,
+    /// or None if there are more than `max_cells` of them.
+    fn cell_range(&self, aabb: AABB, max_cells: usize) -> Option<CellRange> {
+        let min = aabb.min / self.cell_size;
+        let max = aabb.max / self.cell_size;
+        let count = (max.x.floor() - min.x.floor() + 1.0) * (max.y.floor() - min.y.floor() + 1.0);
+        // infinite or NaN if the AABB is
+        if count.is_nan() || count > max_cells as f64 {
+            return None;
+        }
+        Some(CellRange {
+            min: [min.x.floor() as i64, min.y.floor() as i64],
+            max: [max.x.floor() as i64, max.y.floor() as i64],
+        })
+    }
+
+    /// Collect the indices of every entry that may overlap the given region
+    /// into `self.candidates`, each exactly once.
+    fn gather_candidates(&mut self, region: AABB) {
+        self.candidates.clear();
+        let Some(bounds) = self.bounds else {
+            return;
+        };
+        // clamp to where things are to avoid visiting a lot of empty cells
+        // with huge regions such as long raycasts
+        let region = AABB {
+            min: region.min.max_by_component(bounds.min),
+            max: region.max.min_by_component(bounds.max),
+        };
+        if region.min.x > region.max.x || region.min.y > region.max.y {
+            return;
+        }
+
+        let Some(range) = self.cell_range(region, self.entries.len()) else {
+            // checking everything is cheaper than going through the cells
+            self.candidates.extend(0..self.entries.len());
+            return;
+        };
+
+        self.query_id = self.query_id.wrapping_add(1);
+        if self.query_id == 0 {
+            self.last_seen.fill(0);
+            self.query_id = 1;
+        }
+        for cell in range.cells() {
+            let Some(cell) = self.cells.get(&cell) else {
+                continue;
+            };
+            for &idx in cell {
+                if self.last_seen[idx] != self.query_id {
+                    self.last_seen[idx] = self.query_id;
+                    self.candidates.push(idx);
+                }
+            }
+        }
+        self.candidates.extend_from_slice(&self.oversized);
+    }
+}
+
+impl Broadphase for SpatialHashBroadphase {
+    fn clear(&mut self) {
+        self.entries.clear();
+        self.oversized.clear();
+        self.last_seen.clear();
+        self.bounds = None;
+        // keep the allocations of cells that were used last time,
+        // things tend to stay in roughly the same place from frame to frame
+        self.cells.retain(|_, cell| {
+            let was_used = !cell.is_empty();
+            cell.clear();
+            was_used
+        });
+    }
+
+    fn insert(&mut self, coll_key: ColliderKey, aabb: AABB) {
+        let idx = self.entries.len();
+        self.entries.push((coll_key, aabb));
+        self.last_seen.push(0);
+        self.bounds = Some(match self.bounds {
+            Some(bounds) => bounds.union(&aabb),
+            None => aabb,
+        });
+
+        match self.cell_range(aabb, MAX_CELLS_PER_ENTRY) {
+            Some(range) => {
+                for cell in range.cells() {
+                    self.cells.entry(cell).or_default().push(idx);
+                }
+            }
+            None => self.oversized.push(idx),
+        }
+    }
+
+    fn leaves(&self, f: &mut dyn FnMut(ColliderKey, AABB)) {
+        for &(coll_key, aabb) in &self.entries {
+            f(coll_key, aabb);
+        }
+    }
+
+    fn test_aabb(&mut self, aabb: AABB, f: &mut dyn FnMut(ColliderKey)) {
+        self.gather_candidates(aabb);
+        for &idx in &self.candidates {
+            let (coll_key, entry_aabb) = self.entries[idx];
+            if entry_aabb.intersection(&aabb).is_some() {
+                f(coll_key);
+            }
+        }
+    }
+
+    fn test_point(&mut self, point: uv::DVec2, f: &mut dyn FnMut(ColliderKey)) {
+        self.gather_candidates(AABB {
+            min: point,
+            max: point,
+        });
+        for &idx in &self.candidates {
+            let (coll_key, entry_aabb) = self.entries[idx];
+            if entry_aabb.contains_point(point) {
+                f(coll_key);
+            }
+        }
+    }
+
+    fn sweep_aabb(
+        &mut self,
+        box_half_size: f64,
+        ray: Ray,
+        max_t: f64,
+        f: &mut dyn FnMut(SweepItem) -> ControlFlow<()>,
+    ) {
+        // f64::MAX instead of infinity so that zero components of the direction stay zero
+        let reach = *ray.dir * max_t.min(f64::MAX);
+        let region = AABB {
+            min: ray.start,
+            max: ray.start,
+        }
+        .extended(reach)
+        .padded(box_half_size);
+        self.gather_candidates(region);
+
+        self.sweep_results.clear();
+        self.sweep_results
+            .extend(self.candidates.iter().filter_map(|&idx| {
+                let (coll_key, entry_aabb) = self.entries[idx];
+                // same Minkowski sum trick as in the BVH
+                let t = ray_aabb(ray, entry_aabb.padded(box_half_size))?;
+                (t < max_t).then_some(SweepItem { t, coll_key })
+            }));
+        self.sweep_results
+            .sort_unstable_by(|a, b| a.t.total_cmp(&b.t));
+        let _ = self.sweep_results.iter().copied().try_for_each(f);
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::UnitDVec2;
+
+    fn found_in_aabb(grid: &mut SpatialHashBroadphase, aabb: AABB) -> Vec<ColliderKey> {
+        let mut found = Vec::new();
+        grid.test_aabb(aabb, &mut |coll_key| found.push(coll_key));
+        found
+    }
+
+    fn found_at_point(grid: &mut SpatialHashBroadphase, point: uv::DVec2) -> Vec<ColliderKey> {
+        let mut found = Vec::new();
+        grid.test_point(point, &mut |coll_key| found.push(coll_key));
+        found
+    }
+
+    fn swept(
+        grid: &mut SpatialHashBroadphase,
+        half_size: f64,
+        ray: Ray,
+        max_t: f64,
+    ) -> Vec<SweepItem> {
+        let mut hits = Vec::new();
+        grid.sweep_aabb(half_size, ray, max_t, &mut |hit| {
+            hits.push(hit);
+            ControlFlow::Continue(())
+        });
+        hits
+    }
+
+    fn square(center: uv::DVec2, half_size: f64) -> AABB {
+        AABB {
+            min: center - uv::DVec2::broadcast(half_size),
+            max: center + uv::DVec2::broadcast(half_size),
+        }
+    }
+
+    #[test]
+    fn queries_find_each_collider_once() {
+        let mut colliders = thunderdome::Arena::new();
+        let mut grid = SpatialHashBroadphase::new(1.0);
+        let small = ColliderKey(colliders.insert(()));
+        let spanning = ColliderKey(colliders.insert(()));
+        let huge = ColliderKey(colliders.insert(()));
+        grid.insert(small, square(uv::DVec2::new(0.5, 0.5), 0.25));
+        // covers four cells
+        grid.insert(spanning, square(uv::DVec2::new(2.0, 2.0), 0.5));
+        // too big for the grid
+        grid.insert(huge, square(uv::DVec2::new(30.0, 0.0), 10.0));
+
+        let found = found_in_aabb(&mut grid, square(uv::DVec2::new(2.0, 2.0), 1.0));
+        assert_eq!(found, vec![spanning]);
+        let found = found_at_point(&mut grid, uv::DVec2::new(0.5, 0.5));
+        assert_eq!(found, vec![small]);
+        let found = found_at_point(&mut grid, uv::DVec2::new(30.0, 0.0));
+        assert_eq!(found, vec![huge]);
+        assert!(found_at_point(&mut grid, uv::DVec2::new(-5.0, 5.0)).is_empty());
+
+        let ray = Ray {
+            start: uv::DVec2::new(0.5, 5.0),
+            dir: UnitDVec2::new_unchecked(uv::DVec2::new(0.0, -1.0)),
+        };
+        let hits = swept(&mut grid, 0.1, ray, f64::INFINITY);
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].coll_key, small);
+        let ray = Ray {
+            start: uv::DVec2::new(-5.0, 2.0),
+            dir: UnitDVec2::new_unchecked(uv::DVec2::new(1.0, 0.0)),
+        };
+        let hits = swept(&mut grid, 0.0, ray, 100.0);
+        let hit_keys: Vec<ColliderKey> = hits.iter().map(|hit| hit.coll_key).collect();
+        assert_eq!(hit_keys, vec![spanning, huge]);
+        let mut first = Vec::new();
+        grid.sweep_aabb(0.0, ray, 100.0, &mut |hit| {
+            first.push(hit.coll_key);
+            ControlFlow::Break(())
+        });
+        assert_eq!(first, vec![spanning]);
+        assert!((hits[0].t - 6.5).abs() < 1e-9);
+        assert!((hits[1].t - 25.0).abs() < 1e-9);
+        assert!(swept(&mut grid, 0.0, ray, 5.0).is_empty());
+
+        grid.clear();
+        assert!(found_at_point(&mut grid, uv::DVec2::new(0.5, 0.5)).is_empty());
+    }
+}
@@ -1,6 +1,9 @@
 //! Types of physical constraints.
 
-use crate::{math::uv, physics::BodyKey};
+use crate::{
+    math::{uv, UnitDVec2},
+    physics::{BodyKey, EntitySet},
+};
 
 /// A constraint restricts the relative motion of two bodies,
 /// or the motion of a single body in the world.
@@ -25,6 +28,12 @@ pub struct Constraint {
     pub offsets: [uv::DVec2; 2],
     /// Which directions to enforce the constraint in.
     pub limit: ConstraintLimit,
+    /// How much of the velocity towards a one-sided limit is reflected back
+    /// when the limit is hit, between 0 and 1.
+    ///
+    /// Only applies to hard (zero-compliance) distance constraints with a
+    /// [`Lt`][ConstraintLimit::Lt] or [`Gt`][ConstraintLimit::Gt] limit.
+    pub limit_restitution: f64,
     /// Type of the constraint.
     pub ty: ConstraintType,
     /// Whether or not this constraint can be set to sleep when at rest.
@@ -43,10 +52,49 @@ pub enum ConstraintType {
         /// The desired distance.
         distance: f64,
     },
+    /// A weld constraint attaches the origin points together like a zero-distance constraint
+    /// and additionally locks the relative rotation of the bodies,
+    /// making them move as one rigid object.
+    ///
+    /// The limit of a weld is ignored for the rotational part.
+    Weld {
+        /// The desired angle of the owner relative to the target (or the world) in radians.
+        relative_angle: f64,
+    },
+    /// An angular motor drives the angular velocity of the owner
+    /// (relative to the target, if there is one) towards a target value,
+    /// applying at most a limited amount of torque.
+    ///
+    /// Origin points and the limit have no effect on motors.
+    AngularMotor {
+        /// The desired angular velocity in radians per second.
+        target_velocity: f64,
+        /// The highest torque the motor can apply, in N·m.
+        max_torque: f64,
+    },
+    /// A linear motor drives the velocity of the owner along an axis
+    /// (relative to the target, if there is one) towards a target speed,
+    /// applying at most a limited amount of force.
+    ///
+    /// The force is applied at the center of mass,
+    /// so origin points and the limit have no effect.
+    LinearMotor {
+        /// The direction to drive the owner in, in world space.
+        axis: UnitDVec2,
+        /// The desired speed along `axis` in metres per second.
+        target_speed: f64,
+        /// The highest force the motor can apply, in newtons.
+        max_force: f64,
+    },
 }
 
 /// Some constraints can be set to only work in one direction,
 /// to e.g. set a maximum distance while allowing shorter distances.
+///
+/// By default limits are hard and stop the bodies dead.
+/// Adding [compliance][ConstraintBuilder::with_compliance] turns the limit into a spring
+/// that eases the bodies to a stop past the limit and pushes them back out,
+/// and [restitution][ConstraintBuilder::with_limit_restitution] makes a hard limit bounce.
 #[derive(Clone, Copy, Debug)]
 pub enum ConstraintLimit {
     /// Always apply a correction to the constraint.
@@ -64,6 +112,7 @@ pub struct ConstraintBuilder {
     target: Option<BodyKey>,
     offsets: [uv::DVec2; 2],
     limit: ConstraintLimit,
+    limit_restitution: f64,
     compliance: f64,
     linear_damping: f64,
     angular_damping: f64,
@@ -82,6 +131,7 @@ impl ConstraintBuilder {
             target: None,
             offsets: [uv::DVec2::zero(); 2],
             limit: ConstraintLimit::Eq,
+            limit_restitution: 0.0,
             compliance: 0.0,
             linear_damping: 0.1,
             angular_damping: 0.0,
@@ -98,6 +148,9 @@ impl ConstraintBuilder {
     /// Set the origin point of the constraint on the owning body
     /// relative to the center of mass.
     ///
+    /// The point is given in the body's local space and rotates with it,
+    /// so e.g. a corner of a box can be used as a pivot.
+    ///
     /// This has no effect on angular-only constraints.
     pub fn with_origin(mut self, point: uv::DVec2) -> Self {
         self.offsets[0] = point;
@@ -108,6 +161,9 @@ impl ConstraintBuilder {
     /// relative to the center of mass,
     /// or in the world if the target is None.
     ///
+    /// Like [`with_origin`][Self::with_origin],
+    /// this is in the target body's local space.
+    ///
     /// This has no effect on angular-only constraints.
     pub fn with_target_origin(mut self, point: uv::DVec2) -> Self {
         self.offsets[1] = point;
@@ -143,6 +199,18 @@ impl ConstraintBuilder {
         self
     }
 
+    /// Make a one-sided limit bounce back when it's hit.
+    /// See [`Constraint::limit_restitution`].
+    ///
+    /// A restitution of 0 (the default) stops the bodies at the limit
+    /// and 1 bounces them back at the speed they arrived with.
+    /// This has no effect on compliant constraints,
+    /// which bounce back on their own like springs.
+    pub fn with_limit_restitution(mut self, restitution: f64) -> Self {
+        self.limit_restitution = restitution;
+        self
+    }
+
     /// Don't allow this constraint to be set to sleep when at rest.
     ///
     /// Generally you shouldn't do this unless you intend to modify the constraint at runtime.
@@ -162,6 +230,72 @@ impl ConstraintBuilder {
         self.build(ConstraintType::Distance { distance: 0.0 })
     }
 
+    /// Build an angular motor constraint
+    /// that drives the owner to rotate at `target_velocity` radians per second,
+    /// e.g. to spin a wheel or turn a turret.
+    ///
+    /// Set `max_torque` to `f64::INFINITY` to make the motor reach its target immediately.
+    pub fn build_angular_motor(self, target_velocity: f64, max_torque: f64) -> Constraint {
+        self.build(ConstraintType::AngularMotor {
+            target_velocity,
+            max_torque,
+        })
+    }
+
+    /// Build a linear motor constraint
+    /// that drives the owner to move along `axis` at `target_speed` metres per second,
+    /// e.g. for a piston or a launcher.
+    ///
+    /// Only velocity along the axis is affected.
+    /// Other forces like gravity still act on the body as usual,
+    /// and the motor has to work against their component along the axis,
+    /// so a vertical motor needs more than `mass * gravity` of `max_force` to lift its owner.
+    pub fn build_linear_motor(
+        self,
+        axis: UnitDVec2,
+        target_speed: f64,
+        max_force: f64,
+    ) -> Constraint {
+        self.build(ConstraintType::LinearMotor {
+            axis,
+            target_speed,
+            max_force,
+        })
+    }
+
+    /// Build a weld constraint that locks the owner and target
+    /// in their current positions and orientations relative to each other.
+    ///
+    /// The origin of the constraint on the owner is used as the attachment point,
+    /// and the target origin is overwritten to match its current position.
+    /// If the bodies are colliding with each other, you probably want to put them on
+    /// collision layers that ignore each other.
+    ///
+    /// Returns `None` if the owner or target doesn't exist in the entity set.
+    pub fn build_weld(mut self, entity_set: &EntitySet) -> Option<Constraint> {
+        let owner_pose = entity_set.get_body(self.owner)?.pose;
+        let target_pose = match self.target {
+            Some(target) => Some(entity_set.get_body(target)?.pose),
+            None => None,
+        };
+
+        let attach_point = owner_pose * self.offsets[0];
+        let rel_rotation = match target_pose {
+            Some(target_pose) => {
+                self.offsets[1] = target_pose.inversed() * attach_point;
+                owner_pose.rotation * target_pose.rotation.reversed()
+            }
+            None => {
+                self.offsets[1] = attach_point;
+                owner_pose.rotation
+            }
+        };
+        let relative_angle = -rel_rotation.bv.xy.atan2(rel_rotation.s) * 2.0;
+
+        self.limit = ConstraintLimit::Eq;
+        Some(self.build(ConstraintType::Weld { relative_angle }))
+    }
+
     fn build(self, ty: ConstraintType) -> Constraint {
         Constraint {
             owner: self.owner,
@@ -171,8 +305,227 @@ impl ConstraintBuilder {
             angular_damping: self.angular_damping,
             offsets: self.offsets,
             limit: self.limit,
+            limit_restitution: self.limit_restitution,
             can_sleep: self.can_sleep,
             ty,
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::PhysicsPose;
+    use crate::physics::{forcefield::Gravity, Body, Collider, PhysicsWorld, Velocity};
+
+    /// A box pinned to the world at one corner swings around that corner
+    #[test]
+    fn corner_pinned_pendulum() {
+        let mut physics = PhysicsWorld::new(Default::default(), Default::default());
+        let body = physics
+            .entity_set
+            .insert_body(Body::new_dynamic(Collider::new_square(1.0).info(), 1.0));
+        let corner = uv::DVec2::new(0.5, 0.5);
+        physics.constraint_set.insert(
+            ConstraintBuilder::new(body)
+                .with_origin(corner)
+                .with_target_origin(corner)
+                .build_attachment(),
+        );
+
+        let gravity = Gravity(uv::DVec2::new(0.0, -9.81));
+        let mut max_center_x = f64::MIN;
+        for _ in 0..120 {
+            physics.tick(1.0 / 60.0, None, &gravity);
+            let pose = physics.entity_set.get_body(body).unwrap().pose;
+
+            let corner_error = (pose * corner - corner).mag();
+            assert!(corner_error < 0.01, "pivot drifted by {corner_error}");
+            let arm_length = (pose.translation - corner).mag();
+            assert!(
+                (arm_length - 0.5_f64.sqrt()).abs() < 0.01,
+                "center of mass not rotating around the pivot (distance {arm_length})"
+            );
+            max_center_x = max_center_x.max(pose.translation.x);
+        }
+        // starting from the left of the pivot, a swing takes the center past it to the right
+        assert!(
+            max_center_x > corner.x + 0.1,
+            "body didn't swing (max x {max_center_x})"
+        );
+    }
+
+    /// Two welded boxes keep their relative pose while tumbling through the air
+    #[test]
+    fn welded_boxes_move_as_one() {
+        let mut physics = PhysicsWorld::new(Default::default(), Default::default());
+        let coll_info = Collider::new_square(1.0).info();
+        let spinning = Velocity {
+            linear: uv::DVec2::new(1.0, 3.0),
+            angular: 2.0,
+        };
+        let left = physics.entity_set.insert_body(
+            Body::new_dynamic(coll_info, 1.0)
+                .with_pose(PhysicsPose::new(
+                    uv::DVec2::new(-0.5, 0.0),
+                    uv::DRotor2::identity(),
+                ))
+                .with_velocity(spinning),
+        );
+        let right = physics
+            .entity_set
+            .insert_body(
+                Body::new_dynamic(coll_info, 1.0).with_pose(PhysicsPose::new(
+                    uv::DVec2::new(0.5, 0.0),
+                    uv::DRotor2::from_angle(0.3),
+                )),
+            );
+        let weld = ConstraintBuilder::new(left)
+            .with_target(right)
+            .with_origin(uv::DVec2::new(0.5, 0.0))
+            .build_weld(&physics.entity_set)
+            .unwrap();
+        physics.constraint_set.insert(weld);
+
+        let relative_pose = |physics: &PhysicsWorld| {
+            let left = physics.entity_set.get_body(left).unwrap().pose;
+            let right = physics.entity_set.get_body(right).unwrap().pose;
+            left.inversed() * right
+        };
+        let initial = relative_pose(&physics);
+
+        let gravity = Gravity(uv::DVec2::new(0.0, -9.81));
+        for _ in 0..60 {
+            physics.tick(1.0 / 60.0, None, &gravity);
+            let curr = relative_pose(&physics);
+            let offset_error = (curr.translation - initial.translation).mag();
+            assert!(offset_error < 0.01, "weld stretched by {offset_error}");
+            let angle_error = (curr.rotation * initial.rotation.reversed()).bv.xy.abs();
+            assert!(angle_error < 0.01, "weld twisted by {angle_error}");
+        }
+        // the momentum of the spinning box should have carried over to the pair
+        let left_vel = physics.entity_set.get_body(left).unwrap().velocity;
+        assert!(left_vel.angular.abs() > 0.1, "weld stopped rotation");
+    }
+
+    /// A weld to a body that has been removed isn't built
+    #[test]
+    fn weld_to_removed_body() {
+        let mut physics = PhysicsWorld::new(Default::default(), Default::default());
+        let owner = physics.entity_set.insert_body(Body::new_particle(1.0));
+        let target = physics.entity_set.insert_body(Body::new_particle(1.0));
+        physics.entity_set.remove_body(target);
+
+        let weld = ConstraintBuilder::new(owner)
+            .with_target(target)
+            .build_weld(&physics.entity_set);
+        assert!(weld.is_none());
+        assert!(ConstraintBuilder::new(target)
+            .build_weld(&physics.entity_set)
+            .is_none());
+    }
+
+    /// A motor with limited torque spins a body up gradually instead of instantly
+    #[test]
+    fn angular_motor_torque_limit() {
+        let mut physics = PhysicsWorld::new(Default::default(), Default::default());
+        let body = Body::new_dynamic(Collider::new_circle(1.0).info(), 1.0);
+        let moment_of_inertia = match body.moment_of_inertia {
+            crate::physics::Mass::Finite { mass, .. } => mass,
+            crate::physics::Mass::Infinite => unreachable!(),
+        };
+        let body = physics.entity_set.insert_body(body);
+        let max_torque = 1.0;
+        physics
+            .constraint_set
+            .insert(ConstraintBuilder::new(body).build_angular_motor(5.0, max_torque));
+
+        let no_gravity = Gravity(uv::DVec2::zero());
+        physics.tick(1.0 / 60.0, None, &no_gravity);
+        let angular_vel = physics.entity_set.get_body(body).unwrap().velocity.angular;
+        let expected = max_torque / moment_of_inertia / 60.0;
+        assert!(
+            (angular_vel - expected).abs() < 1e-6,
+            "angular velocity {angular_vel} after one frame (expected {expected})"
+        );
+
+        for _ in 0..600 {
+            physics.tick(1.0 / 60.0, None, &no_gravity);
+        }
+        let angular_vel = physics.entity_set.get_body(body).unwrap().velocity.angular;
+        assert!(
+            (angular_vel - 5.0).abs() < 1e-6,
+            "motor didn't reach target"
+        );
+    }
+
+    /// A particle on a rope stops at full length with a hard limit
+    /// and bounces back when the limit has restitution
+    #[test]
+    fn limit_restitution_bounce() {
+        let final_vel = |restitution: f64| {
+            let mut physics = PhysicsWorld::new(Default::default(), Default::default());
+            let body = physics.entity_set.insert_body(
+                Body::new_particle(1.0)
+                    .with_pose(PhysicsPose::new(
+                        uv::DVec2::new(0.0, -0.5),
+                        uv::DRotor2::identity(),
+                    ))
+                    .with_velocity(Velocity {
+                        linear: uv::DVec2::new(0.0, -4.0),
+                        angular: 0.0,
+                    }),
+            );
+            physics.constraint_set.insert(
+                ConstraintBuilder::new(body)
+                    .with_limit(ConstraintLimit::Lt)
+                    .with_limit_restitution(restitution)
+                    .with_linear_damping(0.0)
+                    .build_distance(1.0),
+            );
+            let no_gravity = Gravity(uv::DVec2::zero());
+            for _ in 0..20 {
+                physics.tick(1.0 / 60.0, None, &no_gravity);
+            }
+            let body = physics.entity_set.get_body(body).unwrap();
+            assert!(body.pose.translation.mag() < 1.0 + 1e-3);
+            body.velocity.linear
+        };
+
+        let hard = final_vel(0.0);
+        assert!(
+            hard.mag() < 0.01,
+            "hard limit didn't stop the body ({hard:?})"
+        );
+        let bouncy = final_vel(0.5);
+        assert!((bouncy.y - 2.0).abs() < 0.01, "velocity {bouncy:?}");
+    }
+
+    /// A vertical linear motor only lifts its owner if it can overcome gravity
+    #[test]
+    fn linear_motor_against_gravity() {
+        let gravity = Gravity(uv::DVec2::new(0.0, -9.81));
+        let final_vel = |max_force: f64| {
+            let mut physics = PhysicsWorld::new(Default::default(), Default::default());
+            // unit mass
+            let body = physics.entity_set.insert_body(Body::new_particle(1.0));
+            physics
+                .constraint_set
+                .insert(ConstraintBuilder::new(body).build_linear_motor(
+                    UnitDVec2::unit_y(),
+                    2.0,
+                    max_force,
+                ));
+            for _ in 0..120 {
+                physics.tick(1.0 / 60.0, None, &gravity);
+            }
+            physics.entity_set.get_body(body).unwrap().velocity.linear
+        };
+
+        let strong = final_vel(20.0);
+        assert!((strong.y - 2.0).abs() < 0.01, "velocity {strong:?}");
+        assert!(strong.x.abs() < 1e-9);
+        let weak = final_vel(5.0);
+        assert!(weak.y < 0.0, "weak motor lifted its owner ({weak:?})");
+    }
+}
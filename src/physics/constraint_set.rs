@@ -3,7 +3,7 @@ use super::Constraint;
 use thunderdome as td;
 
 /// Key type to look up a constraint stored in the physics world.
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub struct ConstraintKey(pub(super) td::Index);
 
 /// Manager struct holding constraints inside of a physics world.
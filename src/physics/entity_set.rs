@@ -1,15 +1,28 @@
 use super::{Body, Collider};
 
+use itertools::Either;
 use thunderdome as td;
 
 /// Key type to look up a collider stored in the physics world.
 ///
 /// When using a [`hecs`][crate::hecs] World, this type should be stored
 /// in the world instead of [`Collider`][super::Collider].
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+/// See [`EntitySet`] for details on when keys are reused.
+///
+/// Keys are ordered by the slot they occupy in the physics world,
+/// which is used to give events and contacts a stable order.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct ColliderKey(pub(super) td::Index);
 
 impl ColliderKey {
+    /// Create a key from a [`thunderdome::Index`][thunderdome::Index]
+    /// previously obtained with [`index`][Self::index],
+    /// e.g. one received over the network.
+    #[inline]
+    pub fn from_index(index: td::Index) -> Self {
+        Self(index)
+    }
+
     /// Get the underlying [`thunderdome::Index`][thunderdome::Index] of this key.
     /// Useful for creating your own mappings from colliders to other things
     /// such as [`hecs`][hecs] entities.
@@ -23,10 +36,22 @@ impl ColliderKey {
 ///
 /// When using a [`hecs`][crate::hecs] World, this type should be stored
 /// in the world instead of [`Body`][super::Body].
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+/// See [`EntitySet`] for details on when keys are reused.
+///
+/// Keys are ordered by the slot they occupy in the physics world,
+/// which is used to give events a stable order.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct BodyKey(pub(super) td::Index);
 
 impl BodyKey {
+    /// Create a key from a [`thunderdome::Index`][thunderdome::Index]
+    /// previously obtained with [`index`][Self::index],
+    /// e.g. one received over the network.
+    #[inline]
+    pub fn from_index(index: td::Index) -> Self {
+        Self(index)
+    }
+
     /// Get the underlying [`thunderdome::Index`][thunderdome::Index] of this key.
     /// Useful for creating your own mappings from bodies to other things
     /// such as [`hecs`][hecs] entities.
@@ -41,6 +66,28 @@ impl BodyKey {
 ///
 /// Represented as a graph where dynamic bodies can have multiple colliders
 /// and colliders can be attached to dynamic bodies or be static.
+///
+/// # Key reuse
+///
+/// [`BodyKey`]s and [`ColliderKey`]s are [`thunderdome`] indices
+/// made of a slot and a generation.
+/// When an object is removed, its slot is given to a later insertion
+/// but with a new generation, so a key to a removed object never refers to a new one.
+/// Looking up an object with a stale key returns `None`.
+/// This also holds after clearing the world with
+/// [`PhysicsWorld::clear`][super::PhysicsWorld::clear].
+///
+/// Slots are always reused instead of handing out ever-increasing ones,
+/// because the solver addresses its working memory by slot
+/// and sizes it by the highest slot in use.
+///
+/// Key allocation is deterministic: two entity sets given the same sequence
+/// of insertions and removals hand out identical keys.
+/// This can be used to refer to objects across the network,
+/// e.g. between a server and a client running the same simulation,
+/// as long as both sides insert and remove objects in the same order.
+/// To send a key elsewhere, convert it with `key.index().to_bits()`
+/// and back with [`td::Index::from_bits`] and `from_index`.
 #[derive(Default)]
 pub struct EntitySet {
     // pub fields instead of immutable accessors because I'm lazy,
@@ -73,6 +120,51 @@ impl EntitySet {
         self.bodies.get_mut(body.0)
     }
 
+    /// Check if a body still exists,
+    /// e.g. to clean up entries for removed bodies from your own maps keyed by [`BodyKey`].
+    ///
+    /// A key whose slot was reused by a later insertion is not considered alive.
+    ///
+    /// ```
+    /// # use starframe::{Body, PhysicsWorld};
+    /// let mut physics = PhysicsWorld::new(Default::default(), Default::default());
+    /// let old = physics.entity_set.insert_body(Body::new_particle(1.0));
+    /// physics.entity_set.remove_body(old);
+    /// let new = physics.entity_set.insert_body(Body::new_particle(1.0));
+    /// assert_eq!(old.index().slot(), new.index().slot());
+    /// assert!(!physics.entity_set.contains_body(old));
+    /// assert!(physics.entity_set.contains_body(new));
+    /// ```
+    #[inline]
+    pub fn contains_body(&self, body: BodyKey) -> bool {
+        self.bodies.contains(body.0)
+    }
+
+    /// Mutably access two different [`Body`][super::Body]s at the same time,
+    /// e.g. to apply equal and opposite impulses to them.
+    ///
+    /// Returns `None` if the keys are the same or either body no longer exists.
+    ///
+    /// ```
+    /// # use starframe::{Body, PhysicsWorld};
+    /// let mut physics = PhysicsWorld::new(Default::default(), Default::default());
+    /// let a = physics.entity_set.insert_body(Body::new_particle(1.0));
+    /// let b = physics.entity_set.insert_body(Body::new_particle(1.0));
+    /// let (body_a, body_b) = physics.entity_set.get_two_bodies_mut(a, b).unwrap();
+    /// std::mem::swap(&mut body_a.velocity, &mut body_b.velocity);
+    /// assert!(physics.entity_set.get_two_bodies_mut(a, a).is_none());
+    /// ```
+    #[inline]
+    pub fn get_two_bodies_mut(&mut self, a: BodyKey, b: BodyKey) -> Option<(&mut Body, &mut Body)> {
+        if a == b {
+            return None;
+        }
+        match self.bodies.get2_mut(a.0, b.0) {
+            (Some(body_a), Some(body_b)) => Some((body_a, body_b)),
+            _ => None,
+        }
+    }
+
     /// Access a [`Collider`][super::Collider] in the physics world, if it still exists.
     #[inline]
     pub fn get_collider(&self, coll: ColliderKey) -> Option<&Collider> {
@@ -85,6 +177,13 @@ impl EntitySet {
         self.colliders.get_mut(coll.0)
     }
 
+    /// Check if a collider still exists.
+    /// See [`contains_body`][Self::contains_body].
+    #[inline]
+    pub fn contains_collider(&self, coll: ColliderKey) -> bool {
+        self.colliders.contains(coll.0)
+    }
+
     /// Get the key to the Body connected to the given Collider, if there is one.
     #[inline]
     pub fn get_collider_body_key(&self, coll: ColliderKey) -> Option<BodyKey> {
@@ -105,6 +204,45 @@ impl EntitySet {
             .and_then(|b| self.bodies.get_mut(b.0))
     }
 
+    /// Iterate over all bodies in the physics world along with their keys.
+    ///
+    /// Bodies are visited in the order of their storage slots,
+    /// which is also the order of their keys.
+    /// This order stays the same between calls as long as nothing is inserted or removed.
+    /// Slots freed by removing a body are reused by later insertions,
+    /// so new bodies don't necessarily come last.
+    #[inline]
+    pub fn iter_bodies(&self) -> impl '_ + Iterator<Item = (BodyKey, &'_ Body)> {
+        // thunderdome doesn't define an iteration order, so go through the slots ourselves
+        (0..self.body_slot_count as u32)
+            .filter_map(|slot| self.bodies.get_by_slot(slot))
+            .map(|(key, body)| (BodyKey(key), body))
+    }
+
+    /// Mutably iterate over all bodies in the physics world along with their keys,
+    /// in the same order as [`iter_bodies`][Self::iter_bodies].
+    pub fn iter_bodies_mut(&mut self) -> impl '_ + Iterator<Item = (BodyKey, &'_ mut Body)> {
+        iter_slots_mut(&mut self.bodies).map(|(key, body)| (BodyKey(key), body))
+    }
+
+    /// Iterate over all colliders in the physics world along with their keys.
+    ///
+    /// The order follows the same rules as [`iter_bodies`][Self::iter_bodies].
+    #[inline]
+    pub fn iter_colliders(&self) -> impl '_ + Iterator<Item = (ColliderKey, &'_ Collider)> {
+        (0..self.coll_slot_count as u32)
+            .filter_map(|slot| self.colliders.get_by_slot(slot))
+            .map(|(key, coll)| (ColliderKey(key), coll))
+    }
+
+    /// Mutably iterate over all colliders in the physics world along with their keys,
+    /// in the same order as [`iter_colliders`][Self::iter_colliders].
+    pub fn iter_colliders_mut(
+        &mut self,
+    ) -> impl '_ + Iterator<Item = (ColliderKey, &'_ mut Collider)> {
+        iter_slots_mut(&mut self.colliders).map(|(key, coll)| (ColliderKey(key), coll))
+    }
+
     /// Insert a dynamic body into the world.
     pub fn insert_body(&mut self, body: Body) -> BodyKey {
         let key = self.bodies.insert(body);
@@ -184,3 +322,22 @@ impl EntitySet {
         self.coll_bodies.clear();
     }
 }
+
+/// Mutably iterate over the items of an arena in the order of their slots.
+///
+/// `get_by_slot_mut` can't be called in a loop while handing out the references
+/// it returns, so this uses `iter_mut` instead.
+/// Thunderdome doesn't define the order of `iter` or `iter_mut`, but in practice both go
+/// through the slots in order, so check that first without allocating
+/// and only fall back to collecting and sorting if it doesn't hold.
+fn iter_slots_mut<T>(
+    arena: &mut td::Arena<T>,
+) -> impl '_ + Iterator<Item = (td::Index, &'_ mut T)> {
+    if arena.iter().is_sorted_by_key(|(key, _)| key.slot()) {
+        Either::Left(arena.iter_mut())
+    } else {
+        let mut items: Vec<_> = arena.iter_mut().collect();
+        items.sort_unstable_by_key(|(key, _)| key.slot());
+        Either::Right(items.into_iter())
+    }
+}
@@ -1,9 +1,18 @@
-use crate::math::uv;
+use crate::math::{left_normal, uv};
 
 /// A (possibly) position-dependent force that is typically
 /// fed to a physics solver and applied to all rigid bodies each frame.
 pub trait ForceField: Send + Sync {
     fn value_at(&self, position: uv::DVec2) -> uv::DVec2;
+
+    /// Angular acceleration applied to bodies at the given position,
+    /// counterclockwise if positive.
+    ///
+    /// Defaults to zero, so fields that only push bodies around
+    /// don't need to implement this.
+    fn torque_at(&self, _position: uv::DVec2) -> f64 {
+        0.0
+    }
 }
 
 #[derive(Clone, Copy, Debug)]
@@ -21,6 +30,10 @@ impl<F1: ForceField, F2: ForceField> ForceField for Sum<F1, F2> {
     fn value_at(&self, pos: uv::DVec2) -> uv::DVec2 {
         self.0.value_at(pos) + self.1.value_at(pos)
     }
+
+    fn torque_at(&self, pos: uv::DVec2) -> f64 {
+        self.0.torque_at(pos) + self.1.torque_at(pos)
+    }
 }
 
 /// Constant gravity field over all of space.
@@ -52,3 +65,40 @@ impl ForceField for PointGravity {
         strength * dist.normalized()
     }
 }
+
+/// Swirling field that pushes bodies around a point in space
+/// and also spins them.
+///
+/// With a positive `strength` bodies circle counterclockwise,
+/// and with a positive `spin` they rotate counterclockwise.
+#[derive(Clone, Copy, Debug)]
+pub struct Vortex {
+    /// The position of the center of the vortex.
+    pub position: uv::DVec2,
+    /// The strength of the swirling force at the center.
+    pub strength: f64,
+    /// The angular acceleration given to bodies at the center.
+    pub spin: f64,
+    /// How quickly both forces fall off with distance.
+    /// Zero gives the same force everywhere.
+    pub falloff: f64,
+}
+impl Vortex {
+    fn falloff_at(&self, pos: uv::DVec2) -> f64 {
+        // 1.0 + so that the divisor is 1 at the center regardless of falloff
+        1.0 / (1.0 + self.falloff * (pos - self.position).mag_sq())
+    }
+}
+impl ForceField for Vortex {
+    fn value_at(&self, pos: uv::DVec2) -> uv::DVec2 {
+        let dist = pos - self.position;
+        if dist.mag_sq() == 0.0 {
+            return uv::DVec2::zero();
+        }
+        self.strength * self.falloff_at(pos) * left_normal(dist).normalized()
+    }
+
+    fn torque_at(&self, pos: uv::DVec2) -> f64 {
+        self.spin * self.falloff_at(pos)
+    }
+}
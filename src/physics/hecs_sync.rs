@@ -146,13 +146,13 @@ impl HecsSyncManager {
             }
             if opts.hecs_to_physics {
                 // sync poses for bodies that do still exist
-                let Ok(pose) = hecs_world.query_one_mut::<&m::Pose>(*entity) else {
+                let Some(pose) = entity_physics_pose(hecs_world, *entity) else {
                     return true;
                 };
                 let Some(body) = physics.entity_set.get_body_mut(BodyKey(body_key)) else {
                     return true;
                 };
-                body.pose = m::PhysicsPose::from(*pose);
+                body.pose = pose;
             }
             true
         });
@@ -165,14 +165,14 @@ impl HecsSyncManager {
                 return false;
             }
             if opts.hecs_to_physics {
-                let Ok(pose) = hecs_world.query_one_mut::<&m::Pose>(*entity) else {
+                let Some(pose) = entity_physics_pose(hecs_world, *entity) else {
                     return true;
                 };
 
                 if let Some(body) = physics.entity_set.get_collider_body_mut(coll_key) {
-                    body.pose = m::PhysicsPose::from(*pose);
+                    body.pose = pose;
                 } else if let Some(coll) = physics.entity_set.get_collider_mut(coll_key) {
-                    coll.pose = m::PhysicsPose::from(*pose);
+                    coll.pose = pose;
                 };
             }
             true
@@ -194,10 +194,7 @@ impl HecsSyncManager {
             if opts.physics_to_hecs {
                 // existence checked in the autodelete step
                 let body = physics.entity_set.get_body(BodyKey(body_key)).unwrap();
-                let Ok(pose) = hecs_world.query_one_mut::<&mut m::Pose>(*entity) else {
-                    return true;
-                };
-                pose.sync_from_physics(body.pose);
+                sync_entity_pose(hecs_world, *entity, body.pose);
             }
             true
         });
@@ -210,19 +207,40 @@ impl HecsSyncManager {
             }
             if opts.physics_to_hecs {
                 let coll_key = ColliderKey(coll_key);
-                let Ok(pose) = hecs_world.query_one_mut::<&mut m::Pose>(*entity) else {
-                    return true;
-                };
                 // sync the global pose of the collider if it's attached to a body
                 // (again, existence checked previously)
                 let coll = physics.entity_set.get_collider(coll_key).unwrap();
-                if let Some(body) = physics.entity_set.get_collider_body(coll_key) {
-                    pose.sync_from_physics(body.pose * coll.pose);
-                } else {
-                    pose.sync_from_physics(coll.pose);
-                }
+                let pose = match physics.entity_set.get_collider_body(coll_key) {
+                    Some(body) => body.pose * coll.pose,
+                    None => coll.pose,
+                };
+                sync_entity_pose(hecs_world, *entity, pose);
             }
             true
         });
     }
 }
+
+/// Get the pose of an entity as seen by physics,
+/// from either its [`Transform2D`][m::Transform2D] or [`Pose`][m::Pose].
+fn entity_physics_pose(
+    hecs_world: &mut hecs::World,
+    entity: hecs::Entity,
+) -> Option<m::PhysicsPose> {
+    if let Ok(tr) = hecs_world.query_one_mut::<&m::Transform2D>(entity) {
+        return Some(m::PhysicsPose::from(*tr));
+    }
+    let pose = hecs_world.query_one_mut::<&m::Pose>(entity).ok()?;
+    Some(m::PhysicsPose::from(*pose))
+}
+
+/// Set the 2D parts of an entity's [`Transform2D`][m::Transform2D] and [`Pose`][m::Pose]
+/// to match a physics pose, if it has them.
+fn sync_entity_pose(hecs_world: &mut hecs::World, entity: hecs::Entity, pose: m::PhysicsPose) {
+    if let Ok(tr) = hecs_world.query_one_mut::<&mut m::Transform2D>(entity) {
+        tr.sync_from_physics(pose);
+    }
+    if let Ok(entity_pose) = hecs_world.query_one_mut::<&mut m::Pose>(entity) {
+        entity_pose.sync_from_physics(pose);
+    }
+}
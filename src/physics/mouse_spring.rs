@@ -0,0 +1,119 @@
+use super::{BodyKey, ConstraintBuilder, ConstraintKey, PhysicsWorld};
+use crate::math::uv;
+
+/// Tool for grabbing bodies and dragging them around with a soft spring,
+/// typically attached to the mouse cursor for debugging or sandbox games.
+///
+/// The spring is an attachment constraint between the grabbed point on the body
+/// and a target point in the world.
+/// Pick a body with [`grab`][Self::grab], move the target with [`drag_to`][Self::drag_to]
+/// and let go with [`release`][Self::release],
+/// or do all of these based on the state of a button with [`update`][Self::update].
+#[derive(Clone, Copy, Debug)]
+pub struct MouseSpring {
+    /// Compliance (inverse of stiffness) of the spring. Default: 0.01
+    pub compliance: f64,
+    /// Damping of relative linear velocity. Default: 10.0
+    pub linear_damping: f64,
+    /// Damping of relative angular velocity. Default: 0.5
+    pub angular_damping: f64,
+    grabbed: Option<(BodyKey, ConstraintKey)>,
+}
+
+impl Default for MouseSpring {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MouseSpring {
+    pub fn new() -> Self {
+        Self {
+            compliance: 0.01,
+            linear_damping: 10.0,
+            angular_damping: 0.5,
+            grabbed: None,
+        }
+    }
+
+    /// Set the compliance of the spring. See [`compliance`][Self::compliance].
+    #[inline]
+    pub fn with_compliance(mut self, compliance: f64) -> Self {
+        self.compliance = compliance;
+        self
+    }
+
+    /// Set the damping of the spring. See [`linear_damping`][Self::linear_damping]
+    /// and [`angular_damping`][Self::angular_damping].
+    #[inline]
+    pub fn with_damping(mut self, linear: f64, angular: f64) -> Self {
+        self.linear_damping = linear;
+        self.angular_damping = angular;
+        self
+    }
+
+    /// Grab or drag while `held` is true and release when it's false.
+    ///
+    /// Call once per frame with the cursor position in world space
+    /// and the state of the button used for grabbing.
+    pub fn update(&mut self, physics: &mut PhysicsWorld, target: uv::DVec2, held: bool) {
+        if !held {
+            self.release(physics);
+        } else if self.grabbed.is_some() {
+            self.drag_to(physics, target);
+        } else {
+            self.grab(physics, target);
+        }
+    }
+
+    /// Grab the body under the given point, if there is one,
+    /// releasing any previously grabbed body.
+    ///
+    /// Returns the key of the grabbed body.
+    pub fn grab(&mut self, physics: &mut PhysicsWorld, point: uv::DVec2) -> Option<BodyKey> {
+        self.release(physics);
+
+        let body_key = physics.query_point(point).find_map(|(_, b)| b)?;
+        let body = physics.entity_set.get_body(body_key)?;
+        let constraint = ConstraintBuilder::new(body_key)
+            .with_origin(body.pose.inversed() * point)
+            .with_target_origin(point)
+            .with_compliance(self.compliance)
+            .with_linear_damping(self.linear_damping)
+            .with_angular_damping(self.angular_damping)
+            .disable_sleeping()
+            .build_attachment();
+        let constraint_key = physics.constraint_set.insert(constraint);
+        physics.wake_body(body_key);
+
+        self.grabbed = Some((body_key, constraint_key));
+        Some(body_key)
+    }
+
+    /// Move the point the grabbed body is pulled towards.
+    ///
+    /// Does nothing if no body is grabbed.
+    pub fn drag_to(&mut self, physics: &mut PhysicsWorld, target: uv::DVec2) {
+        let Some((_, constraint_key)) = self.grabbed else {
+            return;
+        };
+        match physics.constraint_set.get_mut(constraint_key) {
+            Some(constraint) => constraint.offsets[1] = target,
+            // removed by someone else, e.g. when the body was removed
+            None => self.grabbed = None,
+        }
+    }
+
+    /// Let go of the grabbed body, if there is one.
+    pub fn release(&mut self, physics: &mut PhysicsWorld) {
+        if let Some((_, constraint_key)) = self.grabbed.take() {
+            physics.constraint_set.remove(constraint_key);
+        }
+    }
+
+    /// Get the key of the currently grabbed body, if there is one.
+    #[inline]
+    pub fn grabbed_body(&self) -> Option<BodyKey> {
+        self.grabbed.map(|(body, _)| body)
+    }
+}
@@ -0,0 +1,66 @@
+use super::{collision::CollisionLayerMask, BodyKey, ColliderShape};
+use crate::math::PhysicsPose;
+
+/// An area of the world that reports bodies entering and leaving it,
+/// e.g. a checkpoint, a kill zone or a level transition.
+/// Created with [`PhysicsWorld::add_region`][super::PhysicsWorld::add_region].
+///
+/// Unlike sensor colliders, regions aren't part of the collision detection pipeline
+/// and don't show up in queries or raycasts.
+/// Instead, overlaps are checked once at the end of every tick.
+#[derive(Clone, Debug)]
+pub(super) struct Region {
+    pub id: u64,
+    pub shape: ColliderShape,
+    pub pose: PhysicsPose,
+    pub mask: CollisionLayerMask,
+    // bodies that were inside the region at the end of the previous tick
+    pub overlapping: Vec<BodyKey>,
+}
+
+impl Region {
+    /// Replace the set of overlapping bodies,
+    /// emitting events for the ones that changed.
+    pub fn update(&mut self, mut new_overlapping: Vec<BodyKey>, events: &mut Vec<RegionEvent>) {
+        for &body in &new_overlapping {
+            if !self.overlapping.contains(&body) {
+                events.push(RegionEvent {
+                    region_id: self.id,
+                    body,
+                    kind: RegionEventKind::Entered,
+                });
+            }
+        }
+        for &body in &self.overlapping {
+            if !new_overlapping.contains(&body) {
+                events.push(RegionEvent {
+                    region_id: self.id,
+                    body,
+                    kind: RegionEventKind::Exited,
+                });
+            }
+        }
+        std::mem::swap(&mut self.overlapping, &mut new_overlapping);
+    }
+}
+
+/// A body entering or leaving a region,
+/// returned from [`PhysicsWorld::region_events`][super::PhysicsWorld::region_events].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RegionEvent {
+    /// The id the region was created with.
+    pub region_id: u64,
+    pub body: BodyKey,
+    pub kind: RegionEventKind,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RegionEventKind {
+    /// The body started overlapping the region during the last tick.
+    Entered,
+    /// The body stopped overlapping the region during the last tick.
+    ///
+    /// This is also sent if the body was removed from the world while inside the region,
+    /// in which case the body key is no longer valid.
+    Exited,
+}
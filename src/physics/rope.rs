@@ -12,13 +12,28 @@ use thunderdome as td;
 //
 
 /// Parameters for constructing a [`Rope`][self::Rope].
+///
+/// How much the rope resists bending is controlled by
+/// `bending_max_angle` and `bending_compliance` together.
+/// The defaults make a floppy rope that hangs straight down under gravity.
+/// A `bending_max_angle` of zero with a `bending_compliance` close to zero
+/// makes a stiff cable that keeps its shape,
+/// and values in between make something like a hose that bends under its own weight.
 #[derive(Clone, Copy, Debug)]
 pub struct RopeParameters {
+    /// Distance between neighboring particles.
     pub spacing: f64,
+    /// Diameter of the particles' colliders.
     pub thickness: f64,
+    /// How much the distance between particles can stretch, zero being unstretchable.
     pub compliance: f64,
+    /// Angle in radians that the rope can bend at each particle before it starts resisting.
+    /// Setting this to π turns off bending resistance entirely.
     pub bending_max_angle: f64,
+    /// How softly the rope resists bending past `bending_max_angle`,
+    /// zero being completely rigid.
     pub bending_compliance: f64,
+    /// How quickly differences in velocity between neighboring particles are evened out.
     pub damping: f64,
     pub material: PhysicsMaterial,
     pub particle_mass: f64,
@@ -47,6 +62,13 @@ impl Default for RopeParameters {
 pub struct Rope {
     pub params: RopeParameters,
     pub particles: Vec<RopeParticle>,
+    /// Arbitrary data for identifying what a rope belongs to,
+    /// like [`Collider::user_data`].
+    /// Not used by the physics engine.
+    /// Ropes split off of this one, either by [`cut_after`][Self::cut_after]
+    /// or by removing particles in the middle, get the same value.
+    /// Defaults to 0.
+    pub user_data: u64,
 }
 
 #[derive(Clone, Copy, Debug)]
@@ -82,7 +104,18 @@ impl Rope {
             entity_set,
         );
 
-        Rope { params, particles }
+        Rope {
+            params,
+            particles,
+            user_data: 0,
+        }
+    }
+
+    /// Set the [`user_data`][Self::user_data] of the rope.
+    #[inline]
+    pub fn with_user_data(mut self, user_data: u64) -> Self {
+        self.user_data = user_data;
+        self
     }
 
     /// Add `count` particles to the end of an existing rope in a line.
@@ -159,6 +192,7 @@ impl Rope {
             let cut_rope = Rope {
                 params: self.params,
                 particles: cut_particles,
+                user_data: self.user_data,
             };
             Some(cut_rope)
         }
@@ -166,7 +200,7 @@ impl Rope {
 }
 
 /// Key type to look up a rope stored in the physics world.
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub struct RopeKey(pub(super) td::Index);
 
 /// Manager struct holding ropes inside of a physics world.
@@ -198,6 +232,24 @@ impl RopeSet {
         self.ropes.get_mut(key.0)
     }
 
+    /// Get the current pose of every particle in a rope in order from start to end,
+    /// e.g. to build a line strip or a mesh for rendering it.
+    ///
+    /// The first and last poses are exactly at the ends of the rope,
+    /// so things attached to the end particles line up with them.
+    /// Yields nothing if the rope doesn't exist.
+    pub fn particle_poses<'a>(
+        &'a self,
+        key: RopeKey,
+        entity_set: &'a EntitySet,
+    ) -> impl 'a + Iterator<Item = PhysicsPose> {
+        self.get(key)
+            .into_iter()
+            .flat_map(|rope| rope.particles.iter())
+            .filter_map(|particle| entity_set.get_body(particle.body))
+            .map(|body| body.pose)
+    }
+
     /// Remove a Rope and all its particles from the physics world.
     #[inline]
     pub fn remove(&mut self, key: RopeKey, entity_set: &mut EntitySet) {
@@ -262,6 +314,7 @@ impl RopeSet {
                 queued_rope = Some(Rope {
                     particles: cut_particles,
                     params: rope.params,
+                    user_data: rope.user_data,
                 });
             }
             if let Some(q) = queued_rope.take() {
@@ -0,0 +1,263 @@
+//! Canonical scenes the solver should always handle well,
+//! with invariants checked every frame to catch regressions
+//! like drifting stacks or jittering resting bodies.
+
+use super::{
+    forcefield::Gravity, Body, BodyKey, Collider, ConstraintBuilder, Mass, PhysicsWorld, Rope,
+    RopeParameters,
+};
+use crate::math::{uv, PhysicsPose};
+
+const FRAME_DT: f64 = 1.0 / 60.0;
+const GRAVITY: f64 = 9.81;
+
+/// A physics world under gravity with helpers for building and checking scenes.
+struct Scene {
+    physics: PhysicsWorld,
+}
+
+impl Scene {
+    fn new() -> Self {
+        Self {
+            physics: PhysicsWorld::new(Default::default(), Default::default()),
+        }
+    }
+
+    /// Add a wide static ground whose top surface is at y = 0.
+    fn add_ground(&mut self) {
+        let pose = PhysicsPose::new(uv::DVec2::new(0.0, -0.5), uv::DRotor2::identity());
+        self.physics
+            .entity_set
+            .insert_collider(Collider::new_rect(20.0, 1.0).with_pose(pose));
+    }
+
+    fn add_box(&mut self, position: uv::DVec2) -> BodyKey {
+        let coll = Collider::new_square(1.0);
+        let pose = PhysicsPose::new(position, uv::DRotor2::identity());
+        let body = self
+            .physics
+            .entity_set
+            .insert_body(Body::new_dynamic(coll.info(), 1.0).with_pose(pose));
+        self.physics.entity_set.attach_collider(body, coll);
+        body
+    }
+
+    fn pose(&self, body: BodyKey) -> PhysicsPose {
+        self.physics.entity_set.get_body(body).unwrap().pose
+    }
+
+    /// Kinetic plus gravitational potential energy of every body.
+    fn energy(&self) -> f64 {
+        self.physics
+            .entity_set
+            .iter_bodies()
+            .map(|(_, body)| {
+                let Mass::Finite { mass, .. } = body.mass else {
+                    return 0.0;
+                };
+                let rotational = match body.moment_of_inertia {
+                    Mass::Finite { mass: inertia, .. } => {
+                        0.5 * inertia * body.velocity.angular.powi(2)
+                    }
+                    Mass::Infinite => 0.0,
+                };
+                0.5 * mass * body.velocity.linear.mag_sq()
+                    + rotational
+                    + mass * GRAVITY * body.pose.translation.y
+            })
+            .sum()
+    }
+
+    /// Run the scene for the given number of frames,
+    /// checking that energy never rises above where it started
+    /// and calling `check` after every frame.
+    ///
+    /// Energy is allowed to exceed the starting value by `energy_tolerance`
+    /// to account for the solver correcting small errors.
+    /// It can go up and down in between, e.g. when a stretched rope pulls back.
+    fn run(&mut self, frames: usize, energy_tolerance: f64, mut check: impl FnMut(&Self, usize)) {
+        let gravity = Gravity(uv::DVec2::new(0.0, -GRAVITY));
+        let start_energy = self.energy();
+        for frame in 0..frames {
+            self.physics.tick(FRAME_DT, None, &gravity);
+            let energy = self.energy();
+            assert!(
+                energy <= start_energy + energy_tolerance,
+                "energy rose from {start_energy} to {energy} on frame {frame}"
+            );
+            check(self, frame);
+        }
+    }
+}
+
+#[test]
+fn resting_box_stays_put() {
+    let mut scene = Scene::new();
+    scene.add_ground();
+    let start = uv::DVec2::new(0.0, 0.5);
+    let body = scene.add_box(start);
+
+    scene.run(300, 1e-3, |scene, frame| {
+        let pose = scene.pose(body);
+        assert!(
+            (pose.translation - start).mag() < 0.01,
+            "box moved to {pose:?} on frame {frame}"
+        );
+        assert!(
+            pose.rotation.s > 0.9999,
+            "box rotated to {pose:?} on frame {frame}"
+        );
+    });
+    assert_eq!(
+        scene.physics.stats().awake_bodies,
+        0,
+        "box never fell asleep"
+    );
+}
+
+#[test]
+fn five_box_stack_holds() {
+    let mut scene = Scene::new();
+    scene.add_ground();
+    let boxes: Vec<BodyKey> = (0..5)
+        .map(|i| scene.add_box(uv::DVec2::new(0.0, 0.5 + i as f64)))
+        .collect();
+
+    scene.run(600, 1e-3, |scene, frame| {
+        for (i, &body) in boxes.iter().enumerate() {
+            let pose = scene.pose(body);
+            let expected_y = 0.5 + i as f64;
+            assert!(
+                pose.translation.x.abs() < 0.01 && (pose.translation.y - expected_y).abs() < 0.05,
+                "box {i} moved to {pose:?} on frame {frame}"
+            );
+        }
+    });
+    assert_eq!(
+        scene.physics.stats().awake_bodies,
+        0,
+        "stack never fell asleep"
+    );
+}
+
+#[test]
+fn pendulum_keeps_length_and_loses_energy() {
+    let mut scene = Scene::new();
+    let length = 2.0;
+    let body = scene.add_box(uv::DVec2::new(length, 0.0));
+    // attached to the world origin
+    scene
+        .physics
+        .constraint_set
+        .insert(ConstraintBuilder::new(body).build_distance(length));
+
+    let mut lowest_y = 0.0_f64;
+    scene.run(300, 1e-6, |scene, frame| {
+        let pose = scene.pose(body);
+        let arm = pose.translation.mag();
+        assert!(
+            (arm - length).abs() < 0.01,
+            "arm length {arm} on frame {frame}"
+        );
+        assert!(
+            pose.translation.y < 1e-3,
+            "swung above its start to {pose:?} on frame {frame}"
+        );
+        lowest_y = lowest_y.min(pose.translation.y);
+    });
+    assert!(
+        lowest_y < -0.99 * length,
+        "never swung down, lowest {lowest_y}"
+    );
+}
+
+#[test]
+fn rope_swings_down_and_settles() {
+    let mut scene = Scene::new();
+    let length = 2.0;
+    let params = RopeParameters::default();
+    let rope = Rope::spawn_line(
+        params,
+        uv::DVec2::zero(),
+        uv::DVec2::new(length, 0.0),
+        &mut scene.physics.entity_set,
+    );
+    let spacing = rope.params.spacing;
+    let particles: Vec<BodyKey> = rope.particles.iter().map(|p| p.body).collect();
+    scene
+        .physics
+        .constraint_set
+        .insert(ConstraintBuilder::new(particles[0]).build_attachment());
+    scene.physics.rope_set.insert(rope);
+
+    scene.run(600, 1e-4, |scene, frame| {
+        let anchor = scene.pose(particles[0]).translation;
+        assert!(
+            anchor.mag() < 0.01,
+            "anchor moved to {anchor:?} on frame {frame}"
+        );
+        for (i, &particle) in particles.iter().enumerate().skip(1) {
+            let pos = scene.pose(particle).translation;
+            assert!(
+                pos.y < 0.01,
+                "particle {i} swung above the anchor to {pos:?} on frame {frame}"
+            );
+        }
+    });
+
+    for pair in particles.windows(2) {
+        let dist = (scene.pose(pair[1]).translation - scene.pose(pair[0]).translation).mag();
+        assert!(
+            (dist - spacing).abs() < 0.05 * spacing,
+            "rope segment stretched to {dist}"
+        );
+    }
+    // still swinging, but should have lost a good chunk of its energy by now
+    let total_mass = params.particle_mass * particles.len() as f64;
+    let hanging_energy = -total_mass * GRAVITY * length / 2.0;
+    let energy = scene.energy();
+    assert!(
+        energy < 0.5 * hanging_energy,
+        "energy {energy}, hanging straight down would be {hanging_energy}"
+    );
+}
+
+#[test]
+fn stiff_rope_holds_its_shape() {
+    let floppy = RopeParameters::default();
+    let stiff = RopeParameters {
+        bending_max_angle: 0.0,
+        bending_compliance: 0.0,
+        ..floppy
+    };
+    let tip_after_hanging = |params: RopeParameters| {
+        let mut scene = Scene::new();
+        let rope = Rope::spawn_line(
+            params,
+            uv::DVec2::zero(),
+            uv::DVec2::new(1.0, 0.0),
+            &mut scene.physics.entity_set,
+        );
+        let particles: Vec<BodyKey> = rope.particles.iter().map(|p| p.body).collect();
+        // pin down the first segment so there's something to bend against
+        for &particle in &particles[..2] {
+            let pinned_at = scene.pose(particle).translation;
+            scene.physics.constraint_set.insert(
+                ConstraintBuilder::new(particle)
+                    .with_target_origin(pinned_at)
+                    .build_attachment(),
+            );
+        }
+        scene.physics.rope_set.insert(rope);
+        scene.run(300, 1e-3, |_, _| {});
+        scene.pose(*particles.last().unwrap()).translation
+    };
+
+    let tip = tip_after_hanging(stiff);
+    assert!(
+        (tip - uv::DVec2::new(1.0, 0.0)).mag() < 0.01,
+        "stiff rope bent to {tip:?}"
+    );
+    let tip = tip_after_hanging(floppy);
+    assert!(tip.y < -0.7, "floppy rope only sagged to {tip:?}");
+}
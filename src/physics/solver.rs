@@ -13,6 +13,12 @@ pub struct DataView<'a> {
     pub dt: f64,
     pub inv_dt: f64,
     pub inv_dt_sq: f64,
+    /// number of times to repeat position solving every substep
+    pub iterations: usize,
+    /// whether to fall back to earlier contact forces for friction, see TuningConstants
+    pub warm_start: bool,
+    /// maximum distance to push contacts apart per iteration
+    pub max_depenetration: f64,
     /// index of the first body in the island in the global buffers
     pub island_offset: usize,
     /// map from the entity_set body storage to the sorted order
@@ -27,12 +33,22 @@ pub struct DataView<'a> {
     pub rope_next_particles: &'a [Option<usize>],
     pub rope_prev_particles: &'a [Option<usize>],
     pub rope_lateral_corrections: &'a mut [Option<uv::DVec2>],
+    /// rope lambdas accumulated over iterations, [distance to next, bending], per particle
+    pub rope_lambdas: &'a mut [[f64; 2]],
     pub constraints: &'a [Constraint],
     pub constraint_body_pairs: &'a [(usize, Option<usize>)],
     pub coll_pairs: &'a [[ColliderKey; 2]],
+    /// materials to use for a pair instead of the colliders' own, from the contact filter
+    pub material_overrides: &'a [Option<PhysicsMaterial>],
     pub contacts: &'a mut [ContactResult],
     pub last_contacts: &'a mut [ContactResult],
-    pub contact_lambdas: &'a mut [f64],
+    pub contact_lambdas: &'a mut [[f64; 2]],
+    /// last nonzero contact lambdas from earlier substeps or frames
+    pub warm_lambdas: &'a mut [[f64; 2]],
+    pub contact_errors: &'a mut [f64],
+    pub constraint_errors: &'a mut [f64],
+    /// constraint lambdas accumulated over iterations, [distance, angle]
+    pub constraint_lambdas: &'a mut [[f64; 2]],
 }
 
 // SAFETY: we only use these inside of the solver
@@ -60,11 +76,24 @@ pub fn solve(forcefield: &impl ForceField, data: &mut DataView<'_>, entity_set:
         &mut *data.old_velocities,
         &mut *data.ext_f_accelerations
     ) {
+        // forces applied to the body directly, see Body::apply_force
+        let mut accel = body.mass.inv() * body.force;
+        let mut angular_accel = body.moment_of_inertia.inv() * body.torque;
         if !body.ignores_gravity && matches!(body.mass, Mass::Finite { .. }) {
             // TODO: rename forcefield to accelerationfield or allow it to depend on mass
-            let ff_accel = forcefield.value_at(body.pose.translation);
-            body.velocity.linear += ff_accel * data.dt;
-            *ext_accel = ff_accel;
+            accel += forcefield.value_at(body.pose.translation);
+        }
+        if !body.ignores_gravity && matches!(body.moment_of_inertia, Mass::Finite { .. }) {
+            angular_accel += forcefield.torque_at(body.pose.translation);
+        }
+        body.velocity.linear += accel * data.dt;
+        body.velocity.angular += angular_accel * data.dt;
+        *ext_accel = accel;
+        if body.linear_damping > 0.0 {
+            body.velocity.linear /= 1.0 + body.linear_damping * data.dt;
+        }
+        if body.angular_damping > 0.0 {
+            body.velocity.angular /= 1.0 + body.angular_damping * data.dt;
         }
 
         // old_vel is velocity after external forces but before collisions
@@ -73,18 +102,24 @@ pub fn solve(forcefield: &impl ForceField, data: &mut DataView<'_>, entity_set:
         body.pose = body.velocity.apply_to_pose(data.dt, body.pose);
     }
 
-    if !data.ropes.is_empty() {
-        solve_ropes(data);
-    }
-    if !data.constraints.is_empty() {
-        solve_constraints(data);
-    }
+    // contact, constraint and rope lambdas accumulate over iterations
+    data.contact_lambdas.fill([0.0; 2]);
+    data.constraint_lambdas.fill([0.0; 2]);
+    data.rope_lambdas.fill([0.0; 2]);
+    for _iteration in 0..data.iterations {
+        if !data.ropes.is_empty() {
+            solve_ropes(data);
+        }
+        if !data.constraints.is_empty() {
+            solve_constraints(data);
+        }
 
-    for (body, pre_cont_pose) in izip!(&mut *data.bodies, &mut *data.pre_contact_poses) {
-        *pre_cont_pose = body.pose;
-    }
-    if !data.contacts.is_empty() {
-        solve_contacts(data, entity_set);
+        for (body, pre_cont_pose) in izip!(&mut *data.bodies, &mut *data.pre_contact_poses) {
+            *pre_cont_pose = body.pose;
+        }
+        if !data.contacts.is_empty() {
+            solve_contacts(data, entity_set);
+        }
     }
 
     // update velocities from pose differences
@@ -99,8 +134,17 @@ pub fn solve(forcefield: &impl ForceField, data: &mut DataView<'_>, entity_set:
     if !data.contacts.is_empty() {
         contact_velocity_step(data, entity_set);
     }
+    if data.warm_start {
+        for (lambdas, warm_lambdas) in izip!(&*data.contact_lambdas, &mut *data.warm_lambdas) {
+            for (lambda, warm) in izip!(lambdas, warm_lambdas) {
+                if *lambda != 0.0 {
+                    *warm = *lambda;
+                }
+            }
+        }
+    }
     if !data.constraints.is_empty() {
-        constraint_damping(data);
+        constraint_velocity_step(data);
     }
     if !data.ropes.is_empty() {
         rope_velocity_step(data);
@@ -125,17 +169,20 @@ fn solve_ropes(data: &mut DataView<'_>) {
             let dir = dist / dist_mag;
             let error = rope.params.spacing - dist_mag;
 
-            let lambda = -error
+            let compliance = rope.params.compliance * data.inv_dt_sq;
+            let lambda = &mut data.rope_lambdas[curr_particle][0];
+            let delta_lambda = (-error - compliance * *lambda)
                 / (data.bodies[curr_particle].mass.inv()
                     + data.bodies[next_particle].mass.inv()
-                    + rope.params.compliance * data.inv_dt_sq);
+                    + compliance);
+            *lambda += delta_lambda;
 
             data.bodies[curr_particle]
                 .pose
-                .append_translation(data.bodies[curr_particle].mass.inv() * lambda * dir);
+                .append_translation(data.bodies[curr_particle].mass.inv() * delta_lambda * dir);
             data.bodies[next_particle]
                 .pose
-                .append_translation(-data.bodies[next_particle].mass.inv() * lambda * dir);
+                .append_translation(-data.bodies[next_particle].mass.inv() * delta_lambda * dir);
 
             let particle_after_next = match data.rope_next_particles[next_particle] {
                 Some(next) => next,
@@ -154,14 +201,16 @@ fn solve_ropes(data: &mut DataView<'_>) {
                 .acos();
             let error = angle - rope.params.bending_max_angle;
             if error > 0.0 {
-                let lambda = -error
-                    / (data.bodies[particle_after_next].mass.inv()
-                        + rope.params.bending_compliance * data.inv_dt_sq);
+                let compliance = rope.params.bending_compliance * data.inv_dt_sq;
+                let lambda = &mut data.rope_lambdas[particle_after_next][1];
+                let delta_lambda = (-error - compliance * *lambda)
+                    / (data.bodies[particle_after_next].mass.inv() + compliance);
+                *lambda += delta_lambda;
 
                 let lambda_oriented = if left_normal(curr_to_next).dot(next_to_after) > 0.0 {
-                    lambda
+                    delta_lambda
                 } else {
-                    -lambda
+                    -delta_lambda
                 };
                 let correction = uv::DRotor2::from_angle(
                     lambda_oriented * data.bodies[particle_after_next].mass.inv(),
@@ -187,84 +236,131 @@ fn solve_ropes(data: &mut DataView<'_>) {
 fn solve_constraints(data: &mut DataView<'_>) {
     let _span = tracy_client::span!("solve constraints");
 
-    for (constraint, pair) in izip!(data.constraints, data.constraint_body_pairs) {
+    for (constraint, pair, remaining_error, lambdas) in izip!(
+        data.constraints,
+        data.constraint_body_pairs,
+        &mut *data.constraint_errors,
+        &mut *data.constraint_lambdas
+    ) {
+        // XPBD compliance term, scaled for the substep length
+        let compliance = constraint.compliance * data.inv_dt_sq;
         let inv_masses = map_semi_pair(*pair, |b| data.bodies[*b].mass.inv(), 0.0);
         let inv_mom_inertias =
             map_semi_pair(*pair, |b| data.bodies[*b].moment_of_inertia.inv(), 0.0);
 
-        match constraint.ty {
-            ConstraintType::Distance { distance } => {
-                let offsets_worldspace = [
-                    data.bodies[pair.0].pose * constraint.offsets[0],
-                    pair.1
-                        .map(|p1| data.bodies[p1].pose * constraint.offsets[1])
-                        .unwrap_or(constraint.offsets[1]),
-                ];
-                let actual_dist = offsets_worldspace[1] - offsets_worldspace[0];
-                let actual_dist_mag = actual_dist.mag();
-                let error = distance - actual_dist_mag;
-
-                if match constraint.limit {
-                    ConstraintLimit::Eq => true,
-                    ConstraintLimit::Lt if error < 0.0 => true,
-                    ConstraintLimit::Gt if error > 0.0 => true,
-                    _ => false,
-                } {
-                    let dir = if actual_dist_mag != 0.0 {
-                        actual_dist / actual_dist_mag
-                    } else {
-                        uv::DVec2::unit_y()
-                    };
-
-                    match pair.1 {
-                        Some(p1) => {
-                            let pair = [pair.0, p1];
-                            let offsets_rotated = map_pair(&[0, 1], |&i| {
-                                data.bodies[pair[i]].pose.rotation * constraint.offsets[i]
-                            });
-                            let offsets_wedge_dir =
-                                map_pair(&[0, 1], |&i| offsets_rotated[i].wedge(dir).xy);
-                            let eff_inv_masses = map_pair(&[0, 1], |&i| {
-                                inv_masses[i] + (offsets_wedge_dir[i].powi(2) * inv_mom_inertias[i])
-                            });
-
-                            let lambda = -error
-                                / (eff_inv_masses[0]
-                                    + eff_inv_masses[1]
-                                    + constraint.compliance * data.inv_dt_sq);
-
-                            let p0 = &mut data.bodies[pair[0]].pose;
-                            p0.append_translation(inv_masses[0] * lambda * dir);
-                            p0.prepend_rotation(uv::DRotor2::from_angle(
-                                inv_mom_inertias[0] * lambda * offsets_wedge_dir[0],
-                            ));
-                            let p1 = &mut data.bodies[pair[1]].pose;
-                            p1.append_translation(-inv_masses[1] * lambda * dir);
-                            p1.prepend_rotation(uv::DRotor2::from_angle(
-                                -inv_mom_inertias[1] * lambda * offsets_wedge_dir[1],
-                            ));
-                        }
-                        None => {
-                            // this is repetitive but kind of hard to abstract :thinking:
-                            let offset_rotated =
-                                data.bodies[pair.0].pose.rotation * constraint.offsets[0];
-                            let offset_wedge_dir = offset_rotated.wedge(dir).xy;
-                            let eff_inv_mass =
-                                inv_masses[0] + offset_wedge_dir.powi(2) * inv_mom_inertias[0];
-
-                            let lambda =
-                                -error / (eff_inv_mass + constraint.compliance * data.inv_dt_sq);
-
-                            let p0 = &mut data.bodies[pair.0].pose;
-                            p0.append_translation(inv_masses[0] * lambda * dir);
-                            p0.prepend_rotation(uv::DRotor2::from_angle(
-                                inv_mom_inertias[0] * lambda * offset_wedge_dir,
-                            ));
-                        }
-                    }
+        // welds are an attachment plus an angle constraint
+        let distance = match constraint.ty {
+            ConstraintType::Distance { distance } => distance,
+            ConstraintType::Weld { .. } => 0.0,
+            // motors are handled in the velocity step
+            ConstraintType::AngularMotor { .. } | ConstraintType::LinearMotor { .. } => {
+                *remaining_error = 0.0;
+                continue;
+            }
+        };
+
+        let offsets_worldspace = [
+            data.bodies[pair.0].pose * constraint.offsets[0],
+            pair.1
+                .map(|p1| data.bodies[p1].pose * constraint.offsets[1])
+                .unwrap_or(constraint.offsets[1]),
+        ];
+        let actual_dist = offsets_worldspace[1] - offsets_worldspace[0];
+        let actual_dist_mag = actual_dist.mag();
+        let error = distance - actual_dist_mag;
+
+        let is_active = match constraint.limit {
+            ConstraintLimit::Eq => true,
+            ConstraintLimit::Lt if error < 0.0 => true,
+            ConstraintLimit::Gt if error > 0.0 => true,
+            _ => false,
+        };
+        *remaining_error = if is_active { error.abs() } else { 0.0 };
+        if is_active {
+            let dir = if actual_dist_mag != 0.0 {
+                actual_dist / actual_dist_mag
+            } else {
+                uv::DVec2::unit_y()
+            };
+
+            match pair.1 {
+                Some(p1) => {
+                    let pair = [pair.0, p1];
+                    let offsets_rotated = map_pair(&[0, 1], |&i| {
+                        data.bodies[pair[i]].pose.rotation * constraint.offsets[i]
+                    });
+                    let offsets_wedge_dir =
+                        map_pair(&[0, 1], |&i| offsets_rotated[i].wedge(dir).xy);
+                    let eff_inv_masses = map_pair(&[0, 1], |&i| {
+                        inv_masses[i] + (offsets_wedge_dir[i].powi(2) * inv_mom_inertias[i])
+                    });
+
+                    let delta_lambda = (-error - compliance * lambdas[0])
+                        / (eff_inv_masses[0] + eff_inv_masses[1] + compliance);
+                    lambdas[0] += delta_lambda;
+
+                    let p0 = &mut data.bodies[pair[0]].pose;
+                    p0.append_translation(inv_masses[0] * delta_lambda * dir);
+                    p0.prepend_rotation(uv::DRotor2::from_angle(
+                        inv_mom_inertias[0] * delta_lambda * offsets_wedge_dir[0],
+                    ));
+                    let p1 = &mut data.bodies[pair[1]].pose;
+                    p1.append_translation(-inv_masses[1] * delta_lambda * dir);
+                    p1.prepend_rotation(uv::DRotor2::from_angle(
+                        -inv_mom_inertias[1] * delta_lambda * offsets_wedge_dir[1],
+                    ));
+                }
+                None => {
+                    // this is repetitive but kind of hard to abstract :thinking:
+                    let offset_rotated = data.bodies[pair.0].pose.rotation * constraint.offsets[0];
+                    let offset_wedge_dir = offset_rotated.wedge(dir).xy;
+                    let eff_inv_mass =
+                        inv_masses[0] + offset_wedge_dir.powi(2) * inv_mom_inertias[0];
+
+                    let delta_lambda =
+                        (-error - compliance * lambdas[0]) / (eff_inv_mass + compliance);
+                    lambdas[0] += delta_lambda;
+
+                    let p0 = &mut data.bodies[pair.0].pose;
+                    p0.append_translation(inv_masses[0] * delta_lambda * dir);
+                    p0.prepend_rotation(uv::DRotor2::from_angle(
+                        inv_mom_inertias[0] * delta_lambda * offset_wedge_dir,
+                    ));
                 }
             }
         }
+
+        if let ConstraintType::Weld { relative_angle } = constraint.ty {
+            let rel_rotation = match pair.1 {
+                Some(p1) => {
+                    data.bodies[pair.0].pose.rotation * data.bodies[p1].pose.rotation.reversed()
+                }
+                None => data.bodies[pair.0].pose.rotation,
+            };
+            let curr_angle = -rel_rotation.bv.xy.atan2(rel_rotation.s) * 2.0;
+            // wrap to [-pi, pi] to always rotate the short way around
+            let error = (curr_angle - relative_angle + std::f64::consts::PI)
+                .rem_euclid(std::f64::consts::TAU)
+                - std::f64::consts::PI;
+            *remaining_error = remaining_error.max(error.abs());
+
+            let inv_mom_inertia_sum = inv_mom_inertias[0] + inv_mom_inertias[1];
+            if inv_mom_inertia_sum == 0.0 {
+                // neither body can rotate, nothing to do
+                continue;
+            }
+            let delta_lambda =
+                (-error - compliance * lambdas[1]) / (inv_mom_inertia_sum + compliance);
+            lambdas[1] += delta_lambda;
+            data.bodies[pair.0]
+                .pose
+                .prepend_rotation(uv::DRotor2::from_angle(inv_mom_inertias[0] * delta_lambda));
+            if let Some(p1) = pair.1 {
+                data.bodies[p1]
+                    .pose
+                    .prepend_rotation(uv::DRotor2::from_angle(-inv_mom_inertias[1] * delta_lambda));
+            }
+        }
     }
 }
 
@@ -275,12 +371,25 @@ fn solve_constraints(data: &mut DataView<'_>) {
 fn solve_contacts(data: &mut DataView<'_>, entity_set: &EntitySet) {
     let _span = tracy_client::span!("solve contacts");
 
-    for (coll_keys, contact, last_contact, lambda_n) in izip!(
+    for (
+        coll_keys,
+        material_override,
+        contact,
+        last_contact,
+        lambda_n,
+        warm_lambda_n,
+        remaining_error,
+    ) in izip!(
         data.coll_pairs,
+        data.material_overrides,
         &mut *data.contacts,
         &mut *data.last_contacts,
-        &mut *data.contact_lambdas
+        &mut *data.contact_lambdas,
+        &*data.warm_lambdas,
+        &mut *data.contact_errors
     ) {
+        *remaining_error = 0.0;
+
         let bodies: [Option<usize>; 2] = map_pair(coll_keys, |c| {
             get_collider_body(data.global_body_order, data.island_offset, *c, entity_set)
         });
@@ -362,14 +471,18 @@ fn solve_contacts(data: &mut DataView<'_>, entity_set: &EntitySet) {
         }
 
         let materials = match (colls[0].ty, colls[1].ty) {
-            (ColliderType::Solid(m0), ColliderType::Solid(m1)) => [m0, m1],
+            (ColliderType::Solid(m0), ColliderType::Solid(m1)) => {
+                material_override.map_or([m0, m1], |m| [m, m])
+            }
             // one of the colliders was a trigger, no physics response
             _ => {
                 continue;
             }
         };
 
-        for contact in contact.iter() {
+        for (contact, lambda_n, warm_lambda_n) in
+            izip!(contact.iter(), lambda_n.iter_mut(), warm_lambda_n)
+        {
             // tangent for static friction
             let tangent = left_normal(*contact.normal);
 
@@ -426,28 +539,32 @@ fn solve_contacts(data: &mut DataView<'_>, entity_set: &EntitySet) {
                 (vars[0].offset_worldspace - vars[1].offset_worldspace).dot(*contact.normal);
 
             if depth <= 0.0 {
-                *lambda_n = 0.0;
                 continue;
             }
+            *remaining_error = remaining_error.max(depth);
+            // resolving a deep penetration all at once would launch the bodies apart
+            // at a velocity of depth / dt, so spread it out over multiple substeps instead
+            let correction = depth.min(data.max_depenetration);
 
-            *lambda_n = -depth / (vars[0].eff_inv_mass_n + vars[1].eff_inv_mass_n);
+            let delta_lambda = -correction / (vars[0].eff_inv_mass_n + vars[1].eff_inv_mass_n);
+            *lambda_n += delta_lambda;
 
             if let Some(bi) = bodies[0] {
                 let im = data.bodies[bi].mass.inv();
                 let imi = data.bodies[bi].moment_of_inertia.inv();
                 let p = &mut data.bodies[bi].pose;
-                p.append_translation(im * *lambda_n * *contact.normal);
+                p.append_translation(im * delta_lambda * *contact.normal);
                 p.prepend_rotation(uv::DRotor2::from_angle(
-                    imi * *lambda_n * vars[0].offset_wedge_normal,
+                    imi * delta_lambda * vars[0].offset_wedge_normal,
                 ));
             }
             if let Some(bi) = bodies[1] {
                 let im = data.bodies[bi].mass.inv();
                 let imi = data.bodies[bi].moment_of_inertia.inv();
                 let p = &mut data.bodies[bi].pose;
-                p.append_translation(-im * *lambda_n * *contact.normal);
+                p.append_translation(-im * delta_lambda * *contact.normal);
                 p.prepend_rotation(uv::DRotor2::from_angle(
-                    -imi * *lambda_n * vars[1].offset_wedge_normal,
+                    -imi * delta_lambda * vars[1].offset_wedge_normal,
                 ));
             }
 
@@ -459,7 +576,9 @@ fn solve_contacts(data: &mut DataView<'_>, entity_set: &EntitySet) {
                     - (vars[1].offset_worldspace - vars[1].offset_worldspace_old);
                 let motion_along_tan = offset_diff_motion.dot(tangent);
 
-                let max_coulomb_dx = *lambda_n * friction_coef;
+                let max_coulomb_dx =
+                    friction_lambda(*lambda_n, *warm_lambda_n, data.warm_start, depth)
+                        * friction_coef;
 
                 let lambda_t =
                     -motion_along_tan / (vars[0].eff_inv_mass_tan + vars[1].eff_inv_mass_tan);
@@ -489,6 +608,23 @@ fn solve_contacts(data: &mut DataView<'_>, entity_set: &EntitySet) {
     }
 }
 
+/// Distance between contact points below which a contact that isn't penetrating
+/// still counts as touching for the purposes of warm starting.
+const WARM_START_TOUCH_DISTANCE: f64 = 1e-3;
+
+/// The normal lambda to limit friction by.
+/// If the contact wasn't penetrating during this substep but is still touching,
+/// warm starting substitutes the last one where it was.
+/// Contacts that have come apart get no friction.
+#[inline]
+fn friction_lambda(lambda_n: f64, warm_lambda_n: f64, warm_start: bool, depth: f64) -> f64 {
+    if warm_start && lambda_n == 0.0 && depth > -WARM_START_TOUCH_DISTANCE {
+        warm_lambda_n
+    } else {
+        lambda_n
+    }
+}
+
 //
 // Contact velocity step
 //
@@ -496,13 +632,19 @@ fn solve_contacts(data: &mut DataView<'_>, entity_set: &EntitySet) {
 fn contact_velocity_step(data: &mut DataView<'_>, entity_set: &EntitySet) {
     let _span = tracy_client::span!("contact velocity step");
 
-    for (coll_keys, contact, lambda_n) in
-        izip!(data.coll_pairs, &*data.contacts, &*data.contact_lambdas)
-    {
+    for (coll_keys, material_override, contact, lambda_n, warm_lambda_n) in izip!(
+        data.coll_pairs,
+        data.material_overrides,
+        &*data.contacts,
+        &*data.contact_lambdas,
+        &*data.warm_lambdas
+    ) {
         let colls: [&Collider; 2] = map_pair(coll_keys, |c| entity_set.colliders.get(c.0).unwrap());
 
         let materials = match (colls[0].ty, colls[1].ty) {
-            (ColliderType::Solid(m0), ColliderType::Solid(m1)) => [m0, m1],
+            (ColliderType::Solid(m0), ColliderType::Solid(m1)) => {
+                material_override.map_or([m0, m1], |m| [m, m])
+            }
             _ => {
                 // one of the colliders was a sensor, no physics response
                 continue;
@@ -524,11 +666,12 @@ fn contact_velocity_step(data: &mut DataView<'_>, entity_set: &EntitySet) {
             continue;
         }
 
-        for contact in contact.iter() {
+        for (contact, lambda_n, warm_lambda_n) in izip!(contact.iter(), lambda_n, warm_lambda_n) {
             struct WorkingVars {
                 inv_mass: f64,
                 inv_mom_inertia: f64,
                 offset_rotated: uv::DVec2,
+                offset_worldspace: uv::DVec2,
                 point_vel: uv::DVec2,
                 old_point_vel: uv::DVec2,
                 ext_f_accel: uv::DVec2,
@@ -539,6 +682,7 @@ fn contact_velocity_step(data: &mut DataView<'_>, entity_set: &EntitySet) {
                     inv_mass: 0.0,
                     inv_mom_inertia: 0.0,
                     offset_rotated: colls[i].pose.rotation * contact.offsets[i],
+                    offset_worldspace: colls[i].pose * contact.offsets[i],
                     point_vel: uv::DVec2::zero(),
                     old_point_vel: uv::DVec2::zero(),
                     ext_f_accel: uv::DVec2::zero(),
@@ -551,6 +695,7 @@ fn contact_velocity_step(data: &mut DataView<'_>, entity_set: &EntitySet) {
                         inv_mass: data.bodies[bi].mass.inv(),
                         inv_mom_inertia: data.bodies[bi].moment_of_inertia.inv(),
                         offset_rotated,
+                        offset_worldspace: data.bodies[bi].pose * contact.offsets[i],
                         point_vel: data.bodies[bi].velocity.point_velocity(offset_rotated),
                         old_point_vel: data.old_velocities[bi].point_velocity(offset_rotated),
                         ext_f_accel: data.ext_f_accelerations[bi],
@@ -581,7 +726,11 @@ fn contact_velocity_step(data: &mut DataView<'_>, entity_set: &EntitySet) {
             let delta_tan_vel = match materials[0].dynamic_friction_with(&materials[1]) {
                 Some(friction_coef) => {
                     let tangent_vel = relative_vel_at_p.dot(tangent);
-                    let max_coulomb_dv = data.inv_dt * *lambda_n * friction_coef;
+                    let depth = (vars[0].offset_worldspace - vars[1].offset_worldspace)
+                        .dot(*contact.normal);
+                    let max_coulomb_dv = data.inv_dt
+                        * friction_lambda(*lambda_n, *warm_lambda_n, data.warm_start, depth)
+                        * friction_coef;
                     tangent_vel.abs().min(max_coulomb_dv.abs()) * -tangent_vel.signum()
                 }
                 None => 0.0,
@@ -621,14 +770,71 @@ fn contact_velocity_step(data: &mut DataView<'_>, entity_set: &EntitySet) {
 // Constraint damping
 //
 
-fn constraint_damping(data: &mut DataView<'_>) {
-    let _span = tracy_client::span!("constraint damping");
+fn constraint_velocity_step(data: &mut DataView<'_>) {
+    let _span = tracy_client::span!("constraint velocity step");
 
     for (constraint, pair) in izip!(data.constraints, data.constraint_body_pairs) {
         let inv_masses = map_semi_pair(*pair, |b| data.bodies[*b].mass.inv(), 0.0);
         let inv_mom_inertias =
             map_semi_pair(*pair, |b| data.bodies[*b].moment_of_inertia.inv(), 0.0);
 
+        // motors only act on velocity and don't get damped
+        if let ConstraintType::AngularMotor {
+            target_velocity,
+            max_torque,
+        } = constraint.ty
+        {
+            let inv_mom_inertia_sum = inv_mom_inertias[0] + inv_mom_inertias[1];
+            if inv_mom_inertia_sum == 0.0 {
+                continue;
+            }
+            let rel_angular_vel = data.bodies[pair.0].velocity.angular
+                - pair
+                    .1
+                    .map(|p1| data.bodies[p1].velocity.angular)
+                    .unwrap_or(0.0);
+            let max_impulse = max_torque * data.dt;
+            let angular_impulse = ((target_velocity - rel_angular_vel) / inv_mom_inertia_sum)
+                .clamp(-max_impulse, max_impulse);
+
+            data.bodies[pair.0].velocity.angular += inv_mom_inertias[0] * angular_impulse;
+            if let Some(p1) = pair.1 {
+                data.bodies[p1].velocity.angular -= inv_mom_inertias[1] * angular_impulse;
+            }
+            continue;
+        }
+        if let ConstraintType::LinearMotor {
+            axis,
+            target_speed,
+            max_force,
+        } = constraint.ty
+        {
+            let inv_mass_sum = inv_masses[0] + inv_masses[1];
+            if inv_mass_sum == 0.0 {
+                continue;
+            }
+            let rel_vel = data.bodies[pair.0].velocity.linear
+                - pair
+                    .1
+                    .map(|p1| data.bodies[p1].velocity.linear)
+                    .unwrap_or_else(uv::DVec2::zero);
+            let max_impulse = max_force * data.dt;
+            let impulse = ((target_speed - rel_vel.dot(*axis)) / inv_mass_sum)
+                .clamp(-max_impulse, max_impulse);
+
+            data.bodies[pair.0].velocity.linear += inv_masses[0] * impulse * *axis;
+            if let Some(p1) = pair.1 {
+                data.bodies[p1].velocity.linear -= inv_masses[1] * impulse * *axis;
+            }
+            continue;
+        }
+
+        if let ConstraintType::Distance { distance } = constraint.ty {
+            if constraint.limit_restitution > 0.0 && constraint.compliance == 0.0 {
+                limit_restitution_step(data, constraint, *pair, distance);
+            }
+        }
+
         match pair.1 {
             Some(p1) => {
                 let pair = [pair.0, p1];
@@ -708,6 +914,85 @@ fn constraint_damping(data: &mut DataView<'_>) {
     }
 }
 
+/// How far from a one-sided limit a constraint still counts as touching it
+/// for the purposes of restitution.
+const LIMIT_SLOP: f64 = 1e-3;
+
+/// Reflect the velocity towards the limit of a hard distance constraint
+/// if the limit was hit during this substep.
+fn limit_restitution_step(
+    data: &mut DataView<'_>,
+    constraint: &Constraint,
+    pair: (usize, Option<usize>),
+    distance: f64,
+) {
+    // direction in which the distance changes when moving further past the limit
+    let sign = match constraint.limit {
+        ConstraintLimit::Eq => return,
+        ConstraintLimit::Lt => 1.0,
+        ConstraintLimit::Gt => -1.0,
+    };
+
+    let offsets_rotated = [
+        data.bodies[pair.0].pose.rotation * constraint.offsets[0],
+        pair.1
+            .map(|p1| data.bodies[p1].pose.rotation * constraint.offsets[1])
+            .unwrap_or_else(uv::DVec2::zero),
+    ];
+    let points = [
+        data.bodies[pair.0].pose.translation + offsets_rotated[0],
+        pair.1
+            .map(|p1| data.bodies[p1].pose.translation + offsets_rotated[1])
+            .unwrap_or(constraint.offsets[1]),
+    ];
+    let actual_dist = points[1] - points[0];
+    let actual_dist_mag = actual_dist.mag();
+    if actual_dist_mag == 0.0 || sign * (actual_dist_mag - distance) < -LIMIT_SLOP {
+        return;
+    }
+    let dir = sign * actual_dist / actual_dist_mag;
+
+    // velocities towards the limit now and before the position solve
+    let approach_vel = |vels: [Velocity; 2]| {
+        (vels[1].point_velocity(offsets_rotated[1]) - vels[0].point_velocity(offsets_rotated[0]))
+            .dot(dir)
+    };
+    let vels = map_semi_pair(pair, |b| data.bodies[*b].velocity, Velocity::default());
+    let old_vels = map_semi_pair(pair, |b| data.old_velocities[*b], Velocity::default());
+    let curr_approach_vel = approach_vel(vels);
+    let old_approach_vel = approach_vel(old_vels);
+
+    // don't bounce if the velocity is only from external forces pulling against the limit
+    // to avoid jitter when resting on it
+    let ext_f_accels = map_semi_pair(pair, |b| data.ext_f_accelerations[*b], uv::DVec2::zero());
+    let ext_f_approach_vel = data.dt * (ext_f_accels[1] - ext_f_accels[0]).dot(dir).abs();
+    if old_approach_vel <= ext_f_approach_vel {
+        return;
+    }
+
+    let inv_masses = map_semi_pair(pair, |b| data.bodies[*b].mass.inv(), 0.0);
+    let inv_mom_inertias = map_semi_pair(pair, |b| data.bodies[*b].moment_of_inertia.inv(), 0.0);
+    let offsets_wedge_dir = map_pair(&[0, 1], |&i| offsets_rotated[i].wedge(dir).xy);
+    let eff_inv_mass_sum = (0..2)
+        .map(|i| inv_masses[i] + offsets_wedge_dir[i].powi(2) * inv_mom_inertias[i])
+        .sum::<f64>();
+    if eff_inv_mass_sum == 0.0 {
+        return;
+    }
+
+    let delta_vel = -curr_approach_vel - constraint.limit_restitution * old_approach_vel;
+    let impulse = delta_vel / eff_inv_mass_sum;
+
+    let b0 = &mut data.bodies[pair.0].velocity;
+    b0.linear -= inv_masses[0] * impulse * dir;
+    b0.angular -= inv_mom_inertias[0] * impulse * offsets_wedge_dir[0];
+    if let Some(p1) = pair.1 {
+        let b1 = &mut data.bodies[p1].velocity;
+        b1.linear += inv_masses[1] * impulse * dir;
+        b1.angular += inv_mom_inertias[1] * impulse * offsets_wedge_dir[1];
+    }
+}
+
 //
 // Rope velocity step
 //